@@ -8,6 +8,7 @@
 //! - Request/Response DTOs
 //! - Error response format
 //! - Generic response wrapper
+//! - WebSocket message protocol
 //! - Zero server dependencies (no Axum, Diesel, Tokio)
 //!
 //! ## Usage
@@ -20,6 +21,13 @@
 //!     title: "My Task".to_string(),
 //!     description: Some("Description".to_string()),
 //!     completed: false,
+//!     due_at: None,
+//!     priority: Default::default(),
+//!     project_id: None,
+//!     parent_id: None,
+//!     status: None,
+//!     recurrence: None,
+//!     recurrence_timezone: None,
 //! };
 //! ```
 
@@ -27,9 +35,27 @@ pub mod error;
 pub mod requests;
 pub mod responses;
 pub mod result;
+pub mod types;
+pub mod ws;
 
 // Re-exports for convenience
 pub use error::ErrorResponse;
-pub use requests::{CreateTaskRequest, UpdateTaskRequest};
-pub use responses::TaskResponse;
+pub use requests::{
+    CalendarQuery, ChildrenPolicy, CreateCalendarFeedRequest, CreateProjectRequest,
+    CreateStatusRequest, CreateTagRequest, CreateTaskRequest, CreateWebhookRequest,
+    DeleteTaskQuery, ExportTasksQuery, FieldsQuery, ImportTasksQuery, ListDeliveriesQuery,
+    ListProjectsQuery, ListTasksQuery, MoveTaskRequest, OccurrencesQuery, ScheduleQuery, TagMatch,
+    UpdateProjectRequest, UpdateStatusRequest, UpdateTagRequest, UpdateTaskRequest,
+    UpdateWebhookRequest,
+};
+pub use responses::{
+    CalendarFeedResponse, DeliveryAttemptResponse, ImportReport, ImportedRow, OccurrencesResponse,
+    ProjectResponse, RejectedRow, ScheduleResponse, SparseTask, StatusResponse, TagResponse,
+    TaskEvent, TaskResponse, TaskTreeNode, WebhookDeliveryResponse, WebhookEvent, WebhookResponse,
+};
 pub use result::{AppResponse, StatusCode};
+pub use types::{
+    CalendarComponent, DeliveryStatus, TaskEventKind, TaskField, TaskFields, TaskFileFormat,
+    TaskPriority, TaskRecord,
+};
+pub use ws::{ClientMessage, PresenceMember, ServerMessage};
@@ -1,16 +1,323 @@
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use uuid::Uuid;
 
-#[derive(Debug, Deserialize)]
+use crate::types::{CalendarComponent, DeliveryStatus, TaskFields, TaskFileFormat, TaskPriority};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateTaskRequest {
     pub title: String,
     pub description: Option<String>,
     #[serde(default)]
     pub completed: bool,
+    #[serde(default)]
+    pub due_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub priority: TaskPriority,
+    #[serde(default)]
+    pub project_id: Option<Uuid>,
+    /// Makes the task a subtask of this task
+    #[serde(default)]
+    pub parent_id: Option<Uuid>,
+    /// Status name in the project's workflow; defaults to the first column
+    /// (or the first done column when `completed` is true)
+    #[serde(default)]
+    pub status: Option<String>,
+    /// iCalendar RRULE; needs `due_at`, the first occurrence
+    #[serde(default)]
+    pub recurrence: Option<String>,
+    /// IANA time zone for the recurrence, `UTC` by default
+    #[serde(default)]
+    pub recurrence_timezone: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct UpdateTaskRequest {
     pub title: Option<String>,
     pub description: Option<String>,
     pub completed: Option<bool>,
+    /// Absent leaves the due date unchanged, `null` clears it
+    #[serde(
+        default,
+        deserialize_with = "double_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub due_at: Option<Option<DateTime<Utc>>>,
+    pub priority: Option<TaskPriority>,
+    /// Absent leaves the project unchanged, `null` moves the task out of its project
+    #[serde(
+        default,
+        deserialize_with = "double_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub project_id: Option<Option<Uuid>>,
+    /// Absent leaves the parent unchanged, `null` promotes the task to the top level
+    #[serde(
+        default,
+        deserialize_with = "double_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub parent_id: Option<Option<Uuid>>,
+    /// Status name; the move must be an allowed transition of the workflow
+    pub status: Option<String>,
+    /// Absent leaves the recurrence unchanged, `null` stops it; setting it
+    /// restarts the series at the task's due date
+    #[serde(
+        default,
+        deserialize_with = "double_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub recurrence: Option<Option<String>>,
+    pub recurrence_timezone: Option<String>,
+}
+
+/// Query parameters for `GET /tasks/{id}/occurrences`
+#[derive(Debug, Default, Deserialize)]
+pub struct OccurrencesQuery {
+    /// Number of occurrences to preview (default 5, at most 100)
+    pub count: Option<usize>,
+}
+
+/// Body of `POST /tasks/{id}/move`
+///
+/// Places the task right after `after`, right before `before`, or between
+/// both when both are given.
+#[derive(Debug, Deserialize)]
+pub struct MoveTaskRequest {
+    pub before: Option<Uuid>,
+    pub after: Option<Uuid>,
+}
+
+/// What happens to the subtasks of a deleted task
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChildrenPolicy {
+    /// Delete the whole subtree
+    Cascade,
+    /// Move the children up to the deleted task's parent
+    #[default]
+    Reparent,
+}
+
+/// Query parameters for `DELETE /tasks/{id}`
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteTaskQuery {
+    #[serde(default)]
+    pub children: ChildrenPolicy,
+}
+
+/// Distinguishes an absent field (`None`) from an explicit `null` (`Some(None)`)
+fn double_option<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// How multiple `tag` filters are combined when listing tasks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMatch {
+    /// Task has at least one of the tags
+    #[default]
+    Any,
+    /// Task has every one of the tags
+    All,
+}
+
+/// Query parameters for `GET /tasks`
+///
+/// Tags are passed as repeated keys: `?tag=a&tag=b&match=all`
+#[derive(Debug, Default, Deserialize)]
+pub struct ListTasksQuery {
+    #[serde(default)]
+    pub tag: Vec<String>,
+    #[serde(default, rename = "match")]
+    pub tag_match: TagMatch,
+    /// `true`: open tasks past their due date, `false`: everything else
+    pub overdue: Option<bool>,
+    /// Only tasks due strictly before this instant
+    pub due_before: Option<DateTime<Utc>>,
+    pub priority: Option<TaskPriority>,
+    pub project_id: Option<Uuid>,
+    /// Include tasks archived along with their project
+    #[serde(default)]
+    pub include_archived: bool,
+    /// Only tasks in the status with this name
+    pub status: Option<String>,
+    /// Fields to return, e.g. `id,title,completed`
+    #[serde(default)]
+    pub fields: TaskFields,
+}
+
+/// Query parameters for endpoints returning tasks, e.g. `?fields=id,title`
+#[derive(Debug, Default, Deserialize)]
+pub struct FieldsQuery {
+    #[serde(default)]
+    pub fields: TaskFields,
+}
+
+/// Query parameters for `GET /tasks/export`
+#[derive(Debug, Default, Deserialize)]
+pub struct ExportTasksQuery {
+    #[serde(default)]
+    pub format: TaskFileFormat,
+    pub project_id: Option<Uuid>,
+    /// Include tasks archived along with their project
+    #[serde(default)]
+    pub include_archived: bool,
+}
+
+/// Query parameters for `POST /tasks/import`
+#[derive(Debug, Default, Deserialize)]
+pub struct ImportTasksQuery {
+    #[serde(default)]
+    pub format: TaskFileFormat,
+    /// Validate every row and report the outcome without saving anything
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Query parameters for `GET /projects`
+#[derive(Debug, Default, Deserialize)]
+pub struct ListProjectsQuery {
+    #[serde(default)]
+    pub include_archived: bool,
+}
+
+/// Query parameters for `GET /tasks/schedule`
+///
+/// Task IDs are passed as repeated keys: `?id=...&id=...`. Without any ID the
+/// schedule covers every open task.
+#[derive(Debug, Default, Deserialize)]
+pub struct ScheduleQuery {
+    #[serde(default)]
+    pub id: Vec<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateProjectRequest {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateProjectRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTagRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTagRequest {
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateStatusRequest {
+    pub name: String,
+    /// Tasks in this status count as completed
+    #[serde(default)]
+    pub done: bool,
+    /// Column position; defaults to after the last column
+    pub position: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateStatusRequest {
+    pub name: Option<String>,
+    pub done: Option<bool>,
+    pub position: Option<i32>,
+}
+
+/// Query parameters for `GET /tasks.ics`
+#[derive(Debug, Default, Deserialize)]
+pub struct CalendarQuery {
+    pub project_id: Option<Uuid>,
+    #[serde(default)]
+    pub component: CalendarComponent,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCalendarFeedRequest {
+    /// Who or what the feed is for, e.g. the subscriber's name
+    pub name: String,
+    /// Limit the feed to one project
+    pub project_id: Option<Uuid>,
+    #[serde(default)]
+    pub component: CalendarComponent,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWebhookRequest {
+    /// `http` or `https` URL receiving the events
+    pub url: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default = "default_true")]
+    pub active: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateWebhookRequest {
+    pub url: Option<String>,
+    /// Absent leaves the description unchanged, `null` clears it
+    #[serde(default, deserialize_with = "double_option")]
+    pub description: Option<Option<String>>,
+    /// Inactive webhooks are not sent new events
+    pub active: Option<bool>,
+}
+
+/// Query parameters for `GET /webhooks/{id}/deliveries`
+#[derive(Debug, Default, Deserialize)]
+pub struct ListDeliveriesQuery {
+    pub status: Option<DeliveryStatus>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TaskField;
+
+    #[test]
+    fn test_update_due_at_absent_vs_null() {
+        let absent: UpdateTaskRequest = serde_json::from_str(r#"{}"#).unwrap();
+        assert_eq!(absent.due_at, None);
+
+        let cleared: UpdateTaskRequest = serde_json::from_str(r#"{"due_at": null}"#).unwrap();
+        assert_eq!(cleared.due_at, Some(None));
+
+        let set: UpdateTaskRequest =
+            serde_json::from_str(r#"{"due_at": "2026-03-01T09:00:00Z"}"#).unwrap();
+        assert!(matches!(set.due_at, Some(Some(_))));
+    }
+
+    #[test]
+    fn test_fields_query() {
+        let all: FieldsQuery = serde_json::from_str(r#"{}"#).unwrap();
+        assert_eq!(all.fields, TaskFields::all());
+
+        let some: FieldsQuery = serde_json::from_str(r#"{"fields": "title, completed"}"#).unwrap();
+        assert!(some.fields.contains(TaskField::Id));
+        assert!(some.fields.contains(TaskField::Completed));
+        assert!(!some.fields.contains(TaskField::Description));
+        assert_eq!(some.fields.len(), 3);
+
+        assert!(serde_json::from_str::<FieldsQuery>(r#"{"fields": "title,colour"}"#).is_err());
+    }
+
+    #[test]
+    fn test_create_priority_defaults_to_medium() {
+        let req: CreateTaskRequest = serde_json::from_str(r#"{"title": "Task"}"#).unwrap();
+        assert_eq!(req.priority, TaskPriority::Medium);
+    }
 }
@@ -1,8 +1,14 @@
-use chrono::{DateTime, Utc};
-use serde::Serialize;
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::ser::{SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Clone)]
+use crate::error::ErrorResponse;
+use crate::types::{
+    CalendarComponent, DeliveryStatus, TaskEventKind, TaskField, TaskFields, TaskPriority,
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TaskResponse {
     pub id: Uuid,
     pub title: String,
@@ -10,4 +16,297 @@ pub struct TaskResponse {
     pub completed: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub due_at: Option<DateTime<Utc>>,
+    pub priority: TaskPriority,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub project_id: Option<Uuid>,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub parent_id: Option<Uuid>,
+    /// Opaque rank: sorting by it gives the manual order
+    pub position: String,
+    /// Name of the task's status in its project's workflow
+    pub status: String,
+    /// iCalendar RRULE, e.g. `FREQ=DAILY;INTERVAL=90`
+    pub recurrence: Option<String>,
+    /// IANA time zone the recurrence is computed in
+    pub recurrence_timezone: Option<String>,
+    pub tags: Vec<TagResponse>,
+}
+
+#[cfg(any(test, feature = "test-fixtures"))]
+impl TaskResponse {
+    /// A new top-level `todo` task, for tests to adjust with `..fixture`
+    pub fn fixture(title: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            title: title.to_string(),
+            description: None,
+            completed: false,
+            created_at: now,
+            updated_at: now,
+            due_at: None,
+            priority: TaskPriority::Medium,
+            completed_at: None,
+            project_id: None,
+            archived_at: None,
+            parent_id: None,
+            position: "i".to_string(),
+            status: "todo".to_string(),
+            recurrence: None,
+            recurrence_timezone: None,
+            tags: Vec::new(),
+        }
+    }
+}
+
+/// A task change streamed by `GET /tasks/events`
+///
+/// `id` is also the SSE event id: send the last one back as `Last-Event-ID`
+/// to resume. Deleted events carry the task as it was before the delete.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaskEvent {
+    pub id: u64,
+    pub kind: TaskEventKind,
+    pub task: TaskResponse,
+}
+
+/// A task limited to the fields selected with `?fields=`
+///
+/// Serialises like `TaskResponse` with the other fields left out.
+#[derive(Debug, Clone)]
+pub struct SparseTask {
+    pub task: TaskResponse,
+    pub fields: TaskFields,
+}
+
+impl Serialize for SparseTask {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let task = &self.task;
+        let mut map = serializer.serialize_map(Some(self.fields.len()))?;
+        for field in TaskField::ALL {
+            if !self.fields.contains(field) {
+                continue;
+            }
+            let key = field.as_str();
+            match field {
+                TaskField::Id => map.serialize_entry(key, &task.id)?,
+                TaskField::Title => map.serialize_entry(key, &task.title)?,
+                TaskField::Description => map.serialize_entry(key, &task.description)?,
+                TaskField::Completed => map.serialize_entry(key, &task.completed)?,
+                TaskField::CreatedAt => map.serialize_entry(key, &task.created_at)?,
+                TaskField::UpdatedAt => map.serialize_entry(key, &task.updated_at)?,
+                TaskField::DueAt => map.serialize_entry(key, &task.due_at)?,
+                TaskField::Priority => map.serialize_entry(key, &task.priority)?,
+                TaskField::CompletedAt => map.serialize_entry(key, &task.completed_at)?,
+                TaskField::ProjectId => map.serialize_entry(key, &task.project_id)?,
+                TaskField::ArchivedAt => map.serialize_entry(key, &task.archived_at)?,
+                TaskField::ParentId => map.serialize_entry(key, &task.parent_id)?,
+                TaskField::Position => map.serialize_entry(key, &task.position)?,
+                TaskField::Status => map.serialize_entry(key, &task.status)?,
+                TaskField::Recurrence => map.serialize_entry(key, &task.recurrence)?,
+                TaskField::RecurrenceTimezone => {
+                    map.serialize_entry(key, &task.recurrence_timezone)?
+                }
+                TaskField::Tags => map.serialize_entry(key, &task.tags)?,
+            }
+        }
+        map.end()
+    }
+}
+
+/// A task within a subtask hierarchy
+#[derive(Debug, Serialize, Clone)]
+pub struct TaskTreeNode {
+    #[serde(flatten)]
+    pub task: TaskResponse,
+    /// Distance from the root of the requested tree (root is 0)
+    pub depth: i32,
+    /// Percentage of direct children that are completed, `None` for leaves
+    pub completion_percent: Option<f64>,
+    pub children: Vec<TaskTreeNode>,
+}
+
+/// Upcoming occurrences of a recurring task
+#[derive(Debug, Serialize, Clone)]
+pub struct OccurrencesResponse {
+    pub recurrence: String,
+    pub timezone: String,
+    /// Due dates after the current one, with the UTC offset of the time zone
+    pub occurrences: Vec<DateTime<FixedOffset>>,
+}
+
+/// Execution order for a set of tasks that respects their dependencies
+#[derive(Debug, Serialize, Clone)]
+pub struct ScheduleResponse {
+    /// Every task of the set, blockers before the tasks they block
+    pub order: Vec<TaskResponse>,
+    /// Longest chain of open tasks that depend on each other, first to last
+    pub critical_path: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TagResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ProjectResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub task_count: i64,
+    pub completed_task_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A column of a workflow with the statuses tasks may move to from it
+#[derive(Debug, Serialize, Clone)]
+pub struct StatusResponse {
+    pub id: Uuid,
+    /// `None` for the default workflow
+    pub project_id: Option<Uuid>,
+    pub name: String,
+    pub position: i32,
+    pub done: bool,
+    /// IDs of the statuses reachable from this one
+    pub transitions: Vec<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A secret calendar subscription URL
+#[derive(Debug, Serialize, Clone)]
+pub struct CalendarFeedResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub project_id: Option<Uuid>,
+    pub component: CalendarComponent,
+    /// URL of the feed; anyone with it can read it
+    ///
+    /// Only returned when the feed is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A URL notified of task events
+#[derive(Debug, Serialize, Clone)]
+pub struct WebhookResponse {
+    pub id: Uuid,
+    pub url: String,
+    pub description: Option<String>,
+    /// Key of the `X-Webhook-Signature` HMAC; anyone with it can forge deliveries
+    ///
+    /// Only returned when the webhook is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One event sent to one webhook, with its attempts
+#[derive(Debug, Serialize, Clone)]
+pub struct WebhookDeliveryResponse {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    /// ID of the event, also sent as `X-Webhook-Event-Id`
+    pub event_id: Uuid,
+    pub kind: TaskEventKind,
+    pub status: DeliveryStatus,
+    /// When the next attempt is due, while pending
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    /// Attempts so far, oldest first
+    pub attempts: Vec<DeliveryAttemptResponse>,
+}
+
+/// Log entry of one request sent to a webhook
+#[derive(Debug, Serialize, Clone)]
+pub struct DeliveryAttemptResponse {
+    /// HTTP status of the response, `null` when none was received
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub duration_ms: u32,
+    pub attempted_at: DateTime<Utc>,
+}
+
+/// Body of a webhook request
+///
+/// Verify it with the `X-Webhook-Signature` header: `sha256=` followed by the
+/// hex HMAC-SHA256 of `{X-Webhook-Timestamp}.{body}` keyed with the webhook's
+/// secret.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookEvent {
+    pub id: Uuid,
+    pub kind: TaskEventKind,
+    pub occurred_at: DateTime<Utc>,
+    /// The task right after the change, or right before a delete
+    pub task: TaskResponse,
+}
+
+/// Outcome of `POST /tasks/import`
+#[derive(Debug, Serialize, Clone)]
+pub struct ImportReport {
+    /// Nothing was saved; `accepted` lists the rows that would have been
+    pub dry_run: bool,
+    pub accepted: Vec<ImportedRow>,
+    pub rejected: Vec<RejectedRow>,
+}
+
+/// An imported row
+#[derive(Debug, Serialize, Clone)]
+pub struct ImportedRow {
+    /// Line number for NDJSON, record number after the header for CSV
+    pub row: usize,
+    /// ID of the created task, `None` on dry runs
+    pub id: Option<Uuid>,
+}
+
+/// A row that could not be imported
+#[derive(Debug, Serialize, Clone)]
+pub struct RejectedRow {
+    /// Line number for NDJSON, record number after the header for CSV
+    pub row: usize,
+    #[serde(flatten)]
+    pub error: ErrorResponse,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparse_task_serializes_selected_fields() {
+        let task = TaskResponse {
+            id: Uuid::nil(),
+            description: Some("A long description".to_string()),
+            ..TaskResponse::fixture("Task")
+        };
+
+        let sparse = SparseTask {
+            task: task.clone(),
+            fields: "title,completed".parse().unwrap(),
+        };
+        assert_eq!(
+            serde_json::to_value(&sparse).unwrap(),
+            serde_json::json!({"id": Uuid::nil(), "title": "Task", "completed": false})
+        );
+
+        let full = SparseTask {
+            task: task.clone(),
+            fields: TaskFields::all(),
+        };
+        assert_eq!(
+            serde_json::to_string(&full).unwrap(),
+            serde_json::to_string(&task).unwrap()
+        );
+    }
 }
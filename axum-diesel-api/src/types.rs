@@ -0,0 +1,295 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::responses::TaskResponse;
+
+/// Task priority
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskPriority {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Urgent,
+}
+
+/// How tasks are rendered in iCalendar feeds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CalendarComponent {
+    /// `VTODO` entries, due at `due_at`
+    #[default]
+    Todo,
+    /// `VEVENT` entries starting at `due_at`, for apps that ignore to-dos
+    Event,
+}
+
+impl CalendarComponent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CalendarComponent::Todo => "todo",
+            CalendarComponent::Event => "event",
+        }
+    }
+}
+
+/// A field of `TaskResponse`, named as in its JSON form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskField {
+    Id,
+    Title,
+    Description,
+    Completed,
+    CreatedAt,
+    UpdatedAt,
+    DueAt,
+    Priority,
+    CompletedAt,
+    ProjectId,
+    ArchivedAt,
+    ParentId,
+    Position,
+    Status,
+    Recurrence,
+    RecurrenceTimezone,
+    Tags,
+}
+
+impl TaskField {
+    /// Every field, in serialisation order
+    pub const ALL: [TaskField; 17] = [
+        TaskField::Id,
+        TaskField::Title,
+        TaskField::Description,
+        TaskField::Completed,
+        TaskField::CreatedAt,
+        TaskField::UpdatedAt,
+        TaskField::DueAt,
+        TaskField::Priority,
+        TaskField::CompletedAt,
+        TaskField::ProjectId,
+        TaskField::ArchivedAt,
+        TaskField::ParentId,
+        TaskField::Position,
+        TaskField::Status,
+        TaskField::Recurrence,
+        TaskField::RecurrenceTimezone,
+        TaskField::Tags,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TaskField::Id => "id",
+            TaskField::Title => "title",
+            TaskField::Description => "description",
+            TaskField::Completed => "completed",
+            TaskField::CreatedAt => "created_at",
+            TaskField::UpdatedAt => "updated_at",
+            TaskField::DueAt => "due_at",
+            TaskField::Priority => "priority",
+            TaskField::CompletedAt => "completed_at",
+            TaskField::ProjectId => "project_id",
+            TaskField::ArchivedAt => "archived_at",
+            TaskField::ParentId => "parent_id",
+            TaskField::Position => "position",
+            TaskField::Status => "status",
+            TaskField::Recurrence => "recurrence",
+            TaskField::RecurrenceTimezone => "recurrence_timezone",
+            TaskField::Tags => "tags",
+        }
+    }
+
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+impl FromStr for TaskField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TaskField::ALL
+            .into_iter()
+            .find(|field| field.as_str() == s)
+            .ok_or_else(|| format!("unknown task field '{}'", s))
+    }
+}
+
+/// Task fields selected with `?fields=id,title,completed`
+///
+/// `id` is always included. Without `fields`, every field is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskFields(u32);
+
+impl TaskFields {
+    pub fn all() -> Self {
+        TaskFields(TaskField::ALL.iter().fold(0, |bits, f| bits | f.bit()))
+    }
+
+    pub fn contains(self, field: TaskField) -> bool {
+        self.0 & field.bit() != 0
+    }
+
+    /// Number of selected fields
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl Default for TaskFields {
+    fn default() -> Self {
+        TaskFields::all()
+    }
+}
+
+impl FromStr for TaskFields {
+    type Err = String;
+
+    /// Parse a comma-separated list of field names
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .try_fold(TaskField::Id.bit(), |bits, name| {
+                name.parse::<TaskField>().map(|field| bits | field.bit())
+            })
+            .map(TaskFields)
+    }
+}
+
+impl<'de> Deserialize<'de> for TaskFields {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// File format of a bulk task import or export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskFileFormat {
+    /// Comma-separated values with a header row, tags joined by `,`
+    #[default]
+    Csv,
+    /// One JSON object per line
+    Ndjson,
+}
+
+impl TaskFileFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TaskFileFormat::Csv => "csv",
+            TaskFileFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+/// Kind of change streamed by `GET /tasks/events`, also used as the SSE event name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskEventKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl TaskEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TaskEventKind::Created => "created",
+            TaskEventKind::Updated => "updated",
+            TaskEventKind::Deleted => "deleted",
+        }
+    }
+}
+
+/// State of a webhook delivery
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// Waiting for its first or next attempt
+    Pending,
+    Delivered,
+    /// Every attempt failed; can be retried by hand
+    Dead,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Dead => "dead",
+        }
+    }
+}
+
+/// One task in a bulk import or export file
+///
+/// Exports fill every field. Imports only need `title`; `id` is used to
+/// resolve `parent_id` between rows of the same file, and the timestamps
+/// are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TaskRecord {
+    #[serde(default)]
+    pub id: Option<Uuid>,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Status name in the project's workflow
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub completed: bool,
+    #[serde(default)]
+    pub priority: TaskPriority,
+    #[serde(default)]
+    pub due_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub project_id: Option<Uuid>,
+    #[serde(default)]
+    pub parent_id: Option<Uuid>,
+    #[serde(default)]
+    pub recurrence: Option<String>,
+    #[serde(default)]
+    pub recurrence_timezone: Option<String>,
+    /// Tag names, created on import when missing
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl From<TaskResponse> for TaskRecord {
+    fn from(task: TaskResponse) -> Self {
+        TaskRecord {
+            id: Some(task.id),
+            title: task.title,
+            description: task.description,
+            status: Some(task.status),
+            completed: task.completed,
+            priority: task.priority,
+            due_at: task.due_at,
+            project_id: task.project_id,
+            parent_id: task.parent_id,
+            recurrence: task.recurrence,
+            recurrence_timezone: task.recurrence_timezone,
+            tags: task.tags.into_iter().map(|tag| tag.name).collect(),
+            created_at: Some(task.created_at),
+            updated_at: Some(task.updated_at),
+            completed_at: task.completed_at,
+        }
+    }
+}
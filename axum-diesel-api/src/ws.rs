@@ -0,0 +1,142 @@
+//! Messages of the `/ws` collaboration protocol
+//!
+//! Every message is a JSON text frame tagged with its `type`, e.g.
+//! `{"type": "join", "user": "alice", "project_id": null}`.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::ErrorResponse;
+use crate::requests::{ChildrenPolicy, CreateTaskRequest, UpdateTaskRequest};
+use crate::responses::{TaskEvent, TaskResponse};
+
+/// Message sent by a client
+///
+/// Mutations carry an optional `request_id`, echoed in their `result` or
+/// `error` reply.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Enter a project's room (`null` for tasks without a project), leaving the current one
+    Join {
+        user: String,
+        project_id: Option<Uuid>,
+    },
+    /// Tell the room the user started editing a task
+    StartEditing { task_id: Uuid },
+    /// Tell the room the user stopped editing a task
+    StopEditing { task_id: Uuid },
+    /// Create a task in the room's project
+    CreateTask {
+        #[serde(default)]
+        request_id: Option<String>,
+        task: CreateTaskRequest,
+    },
+    /// Update a task of the room's project
+    UpdateTask {
+        #[serde(default)]
+        request_id: Option<String>,
+        task_id: Uuid,
+        changes: UpdateTaskRequest,
+    },
+    /// Delete a task of the room's project
+    DeleteTask {
+        #[serde(default)]
+        request_id: Option<String>,
+        task_id: Uuid,
+        #[serde(default)]
+        children: ChildrenPolicy,
+    },
+}
+
+/// Message sent by the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Reply to `join`
+    Joined {
+        connection_id: Uuid,
+        project_id: Option<Uuid>,
+        members: Vec<PresenceMember>,
+    },
+    /// The room's members after someone joined, left or changed what they edit
+    Presence { members: Vec<PresenceMember> },
+    /// A task of the room was changed, over the socket or through the REST API
+    TaskChanged { event: TaskEvent },
+    /// Task changes were missed: reload the room's tasks
+    Reset,
+    /// Outcome of a mutation; `task` is `null` after a delete
+    Result {
+        request_id: Option<String>,
+        task: Option<TaskResponse>,
+    },
+    /// A message was rejected
+    Error {
+        request_id: Option<String>,
+        error: ErrorResponse,
+    },
+}
+
+/// A connection in a room
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresenceMember {
+    pub connection_id: Uuid,
+    pub user: String,
+    /// Tasks the user is editing
+    pub editing: Vec<Uuid>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_message_format() {
+        let task_id = Uuid::new_v4();
+        let message: ClientMessage = serde_json::from_str(&format!(
+            r#"{{"type": "update_task", "task_id": "{}", "changes": {{"due_at": null}}}}"#,
+            task_id
+        ))
+        .unwrap();
+
+        let ClientMessage::UpdateTask {
+            request_id,
+            task_id: id,
+            changes,
+        } = message
+        else {
+            panic!("expected update_task, got {:?}", message);
+        };
+        assert_eq!(request_id, None);
+        assert_eq!(id, task_id);
+        assert_eq!(changes.due_at, Some(None));
+        assert_eq!(changes.project_id, None);
+
+        // Absent fields stay absent when a client serializes its changes
+        let json = serde_json::to_value(ClientMessage::UpdateTask {
+            request_id: Some("1".to_string()),
+            task_id,
+            changes,
+        })
+        .unwrap();
+        assert_eq!(json["changes"]["due_at"], serde_json::Value::Null);
+        assert!(json["changes"].get("project_id").is_none());
+    }
+
+    #[test]
+    fn test_server_message_format() {
+        let json = serde_json::to_value(ServerMessage::Presence {
+            members: vec![PresenceMember {
+                connection_id: Uuid::nil(),
+                user: "alice".to_string(),
+                editing: Vec::new(),
+            }],
+        })
+        .unwrap();
+        assert_eq!(json["type"], "presence");
+        assert_eq!(json["members"][0]["user"], "alice");
+
+        let json = serde_json::to_value(ServerMessage::Reset).unwrap();
+        assert_eq!(json, serde_json::json!({"type": "reset"}));
+    }
+}
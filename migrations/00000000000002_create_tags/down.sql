@@ -0,0 +1,8 @@
+-- Drop join table first (references tags)
+DROP TABLE IF EXISTS task_tags;
+
+-- Drop trigger
+DROP TRIGGER IF EXISTS update_tags_updated_at ON tags;
+
+-- Drop table (indexes are dropped automatically)
+DROP TABLE IF EXISTS tags;
@@ -0,0 +1,14 @@
+-- Drop trigger first
+DROP TRIGGER IF EXISTS set_tasks_completed_at ON tasks;
+
+-- Drop function
+DROP FUNCTION IF EXISTS set_task_completed_at();
+
+-- Drop columns (indexes are dropped automatically)
+ALTER TABLE tasks
+    DROP COLUMN IF EXISTS completed_at,
+    DROP COLUMN IF EXISTS priority,
+    DROP COLUMN IF EXISTS due_at;
+
+-- Drop enum type
+DROP TYPE IF EXISTS task_priority;
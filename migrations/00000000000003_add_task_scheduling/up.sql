@@ -0,0 +1,36 @@
+-- Create priority enum
+CREATE TYPE task_priority AS ENUM ('low', 'medium', 'high', 'urgent');
+
+-- Add scheduling columns to tasks
+ALTER TABLE tasks
+    ADD COLUMN due_at TIMESTAMP WITH TIME ZONE,
+    ADD COLUMN priority task_priority NOT NULL DEFAULT 'medium',
+    ADD COLUMN completed_at TIMESTAMP WITH TIME ZONE;
+
+-- Backfill completion time for tasks that are already completed
+UPDATE tasks SET completed_at = updated_at WHERE completed;
+
+-- Create function to keep completed_at in sync with completed
+CREATE OR REPLACE FUNCTION set_task_completed_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NOT NEW.completed THEN
+        NEW.completed_at = NULL;
+    ELSIF TG_OP = 'INSERT' OR NOT OLD.completed THEN
+        NEW.completed_at = NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+-- Create trigger to set completed_at whenever completed flips
+CREATE TRIGGER set_tasks_completed_at
+    BEFORE INSERT OR UPDATE OF completed ON tasks
+    FOR EACH ROW
+    EXECUTE FUNCTION set_task_completed_at();
+
+-- Create partial index on due_at for overdue queries on open tasks
+CREATE INDEX idx_tasks_due_at_open ON tasks(due_at) WHERE NOT completed;
+
+-- Create index on priority for filtering
+CREATE INDEX idx_tasks_priority ON tasks(priority);
@@ -0,0 +1,10 @@
+-- Drop task columns first (references projects)
+ALTER TABLE tasks
+    DROP COLUMN IF EXISTS archived_at,
+    DROP COLUMN IF EXISTS project_id;
+
+-- Drop trigger
+DROP TRIGGER IF EXISTS update_projects_updated_at ON projects;
+
+-- Drop table (indexes are dropped automatically)
+DROP TABLE IF EXISTS projects;
@@ -0,0 +1,23 @@
+-- Create projects table
+CREATE TABLE projects (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    archived_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Reuse the updated_at trigger function from the tasks migration
+CREATE TRIGGER update_projects_updated_at
+    BEFORE UPDATE ON projects
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Attach tasks to projects (tasks without a project stay in the global list)
+ALTER TABLE tasks
+    ADD COLUMN project_id UUID REFERENCES projects(id) ON DELETE CASCADE,
+    ADD COLUMN archived_at TIMESTAMP WITH TIME ZONE;
+
+-- Create index on project_id for nested listings and counts
+CREATE INDEX idx_tasks_project_id ON tasks(project_id);
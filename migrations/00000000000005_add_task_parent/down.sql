@@ -0,0 +1,2 @@
+-- Drop column (constraint and index are dropped automatically)
+ALTER TABLE tasks DROP COLUMN IF EXISTS parent_id;
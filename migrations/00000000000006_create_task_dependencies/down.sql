@@ -0,0 +1,2 @@
+-- Drop table (indexes are dropped automatically)
+DROP TABLE IF EXISTS task_dependencies;
@@ -0,0 +1,2 @@
+-- Drop column (index is dropped automatically)
+ALTER TABLE tasks DROP COLUMN IF EXISTS position;
@@ -0,0 +1,19 @@
+-- Add lexicographic rank for manual ordering. The "C" collation makes the
+-- database compare ranks byte by byte, exactly like the application does.
+ALTER TABLE tasks ADD COLUMN position VARCHAR COLLATE "C";
+
+-- Backfill existing tasks in creation order with evenly spaced ranks.
+-- Ranks use the digits 0-9a-z and never end in '0', so there is always room
+-- for a rank between two neighbours.
+UPDATE tasks
+SET position = ranked.position
+FROM (
+    SELECT id, lpad(to_hex(row_number() OVER (ORDER BY created_at, id)), 8, '0') || 'i' AS position
+    FROM tasks
+) ranked
+WHERE tasks.id = ranked.id;
+
+ALTER TABLE tasks ALTER COLUMN position SET NOT NULL;
+
+-- Create index on position for ordered listings and neighbour lookups
+CREATE INDEX idx_tasks_position ON tasks(position);
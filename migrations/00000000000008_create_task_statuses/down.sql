@@ -0,0 +1,4 @@
+-- Drop task status column and workflow tables
+ALTER TABLE tasks DROP COLUMN status_id;
+DROP TABLE task_status_transitions;
+DROP TABLE task_statuses;
@@ -0,0 +1,77 @@
+-- Create workflow statuses (project_id NULL is the default workflow, used by
+-- tasks without a project and copied into every new project)
+CREATE TABLE task_statuses (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    project_id UUID REFERENCES projects(id) ON DELETE CASCADE,
+    name VARCHAR(50) NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0,
+    is_done BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE NULLS NOT DISTINCT (project_id, name)
+);
+
+-- Reuse the updated_at trigger function from the tasks migration
+CREATE TRIGGER update_task_statuses_updated_at
+    BEFORE UPDATE ON task_statuses
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Create allowed transitions between statuses of the same workflow
+CREATE TABLE task_status_transitions (
+    from_status_id UUID NOT NULL REFERENCES task_statuses(id) ON DELETE CASCADE,
+    to_status_id UUID NOT NULL REFERENCES task_statuses(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (from_status_id, to_status_id),
+    CHECK (from_status_id <> to_status_id)
+);
+
+-- Seed the default workflow
+INSERT INTO task_statuses (project_id, name, position, is_done) VALUES
+    (NULL, 'todo', 0, FALSE),
+    (NULL, 'in_progress', 1, FALSE),
+    (NULL, 'review', 2, FALSE),
+    (NULL, 'done', 3, TRUE);
+
+INSERT INTO task_status_transitions (from_status_id, to_status_id)
+SELECT f.id, t.id
+FROM (VALUES
+    ('todo', 'in_progress'),
+    ('todo', 'done'),
+    ('in_progress', 'todo'),
+    ('in_progress', 'review'),
+    ('in_progress', 'done'),
+    ('review', 'in_progress'),
+    ('review', 'done'),
+    ('done', 'todo')
+) AS edges(from_name, to_name)
+JOIN task_statuses f ON f.project_id IS NULL AND f.name = edges.from_name
+JOIN task_statuses t ON t.project_id IS NULL AND t.name = edges.to_name;
+
+-- Give every existing project a copy of the default workflow
+INSERT INTO task_statuses (project_id, name, position, is_done)
+SELECT p.id, s.name, s.position, s.is_done
+FROM projects p
+CROSS JOIN task_statuses s
+WHERE s.project_id IS NULL;
+
+INSERT INTO task_status_transitions (from_status_id, to_status_id)
+SELECT pf.id, pt.id
+FROM task_status_transitions tr
+JOIN task_statuses f ON f.id = tr.from_status_id AND f.project_id IS NULL
+JOIN task_statuses t ON t.id = tr.to_status_id AND t.project_id IS NULL
+JOIN task_statuses pf ON pf.project_id IS NOT NULL AND pf.name = f.name
+JOIN task_statuses pt ON pt.project_id = pf.project_id AND pt.name = t.name;
+
+-- Put every task in a status of its project's workflow
+ALTER TABLE tasks ADD COLUMN status_id UUID REFERENCES task_statuses(id);
+
+UPDATE tasks SET status_id = s.id
+FROM task_statuses s
+WHERE s.project_id IS NOT DISTINCT FROM tasks.project_id
+  AND s.name = CASE WHEN tasks.completed THEN 'done' ELSE 'todo' END;
+
+ALTER TABLE tasks ALTER COLUMN status_id SET NOT NULL;
+
+-- Create index on status_id for board queries and status deletion checks
+CREATE INDEX idx_tasks_status_id ON tasks(status_id);
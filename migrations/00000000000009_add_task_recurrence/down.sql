@@ -0,0 +1,6 @@
+-- Drop recurrence columns
+ALTER TABLE tasks
+    DROP CONSTRAINT tasks_recurrence_complete,
+    DROP COLUMN recurrence,
+    DROP COLUMN recurrence_timezone,
+    DROP COLUMN recurrence_start;
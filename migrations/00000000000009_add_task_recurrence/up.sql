@@ -0,0 +1,12 @@
+-- Add recurrence (an RRULE expanded in recurrence_timezone from recurrence_start)
+ALTER TABLE tasks
+    ADD COLUMN recurrence TEXT,
+    ADD COLUMN recurrence_timezone VARCHAR(64),
+    ADD COLUMN recurrence_start TIMESTAMP WITH TIME ZONE;
+
+-- A recurring task needs its whole schedule and a due date for the current occurrence
+ALTER TABLE tasks ADD CONSTRAINT tasks_recurrence_complete CHECK (
+    (recurrence IS NULL AND recurrence_timezone IS NULL AND recurrence_start IS NULL)
+    OR (recurrence IS NOT NULL AND recurrence_timezone IS NOT NULL
+        AND recurrence_start IS NOT NULL AND due_at IS NOT NULL)
+);
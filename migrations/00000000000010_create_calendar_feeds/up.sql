@@ -0,0 +1,9 @@
+-- Create calendar feeds: secret URLs for subscribing to tasks from calendar apps
+CREATE TABLE calendar_feeds (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    token VARCHAR(64) NOT NULL UNIQUE,
+    project_id UUID REFERENCES projects(id) ON DELETE CASCADE,
+    component VARCHAR(10) NOT NULL DEFAULT 'todo' CHECK (component IN ('todo', 'event')),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,8 @@
+-- Drop task change notifications
+DROP TRIGGER IF EXISTS notify_task_tags_changed ON task_tags;
+DROP TRIGGER IF EXISTS notify_tasks_deleted ON tasks;
+DROP TRIGGER IF EXISTS notify_tasks_written ON tasks;
+DROP FUNCTION IF EXISTS notify_task_tags_change();
+DROP FUNCTION IF EXISTS notify_task_change();
+DROP TABLE IF EXISTS task_deletions;
+DROP SEQUENCE IF EXISTS task_event_seq;
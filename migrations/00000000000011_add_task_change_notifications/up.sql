@@ -0,0 +1,75 @@
+-- Event ids are shared by every server instance, so a client can resume a
+-- stream on any of them with Last-Event-ID.
+CREATE SEQUENCE task_event_seq;
+
+-- Deleted tasks are gone by the time listeners react, so a snapshot is kept
+-- for them. NOTIFY payloads are limited to 8000 bytes, too small for a task.
+CREATE TABLE task_deletions (
+    event_id BIGINT PRIMARY KEY,
+    task_id UUID NOT NULL,
+    task JSONB NOT NULL,
+    deleted_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Create index on deleted_at for pruning old snapshots
+CREATE INDEX idx_task_deletions_deleted_at ON task_deletions(deleted_at);
+
+-- Create function to announce task changes on the task_changes channel
+CREATE OR REPLACE FUNCTION notify_task_change()
+RETURNS TRIGGER AS $$
+DECLARE
+    event_id BIGINT := nextval('task_event_seq');
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        -- Runs before the delete cascades, while the task still has its tags
+        INSERT INTO task_deletions (event_id, task_id, task)
+        SELECT event_id, OLD.id, to_jsonb(OLD) || jsonb_build_object(
+            'status', (SELECT name FROM task_statuses WHERE id = OLD.status_id),
+            'tags', COALESCE((
+                SELECT jsonb_agg(to_jsonb(tags) ORDER BY tags.name)
+                FROM tags JOIN task_tags ON task_tags.tag_id = tags.id
+                WHERE task_tags.task_id = OLD.id
+            ), '[]'::jsonb)
+        );
+        DELETE FROM task_deletions WHERE deleted_at < NOW() - INTERVAL '1 hour';
+        PERFORM pg_notify('task_changes', json_build_object(
+            'event_id', event_id, 'op', 'deleted', 'task_id', OLD.id)::text);
+        RETURN OLD;
+    END IF;
+
+    PERFORM pg_notify('task_changes', json_build_object(
+        'event_id', event_id,
+        'op', CASE TG_OP WHEN 'INSERT' THEN 'created' ELSE 'updated' END,
+        'task_id', NEW.id)::text);
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+-- Create function to announce tag changes as updates of their task
+CREATE OR REPLACE FUNCTION notify_task_tags_change()
+RETURNS TRIGGER AS $$
+DECLARE
+    changed task_tags := CASE TG_OP WHEN 'DELETE' THEN OLD ELSE NEW END;
+BEGIN
+    PERFORM pg_notify('task_changes', json_build_object(
+        'event_id', nextval('task_event_seq'), 'op', 'updated',
+        'task_id', changed.task_id)::text);
+    RETURN NULL;
+END;
+$$ language 'plpgsql';
+
+-- Create triggers to notify listeners of every task change
+CREATE TRIGGER notify_tasks_written
+    AFTER INSERT OR UPDATE ON tasks
+    FOR EACH ROW
+    EXECUTE FUNCTION notify_task_change();
+
+CREATE TRIGGER notify_tasks_deleted
+    BEFORE DELETE ON tasks
+    FOR EACH ROW
+    EXECUTE FUNCTION notify_task_change();
+
+CREATE TRIGGER notify_task_tags_changed
+    AFTER INSERT OR DELETE ON task_tags
+    FOR EACH ROW
+    EXECUTE FUNCTION notify_task_tags_change();
@@ -0,0 +1,18 @@
+-- Create tasks table. UUIDs are stored as lowercase hyphenated text and
+-- timestamps as fixed-width RFC 3339 text in UTC with microseconds, so text
+-- order is time order. updated_at is maintained by the application, in place
+-- of the PostgreSQL trigger.
+CREATE TABLE tasks (
+    id TEXT PRIMARY KEY NOT NULL,
+    title TEXT NOT NULL CHECK (length(title) <= 255),
+    description TEXT,
+    completed BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- Create index on completed field for faster filtering
+CREATE INDEX idx_tasks_completed ON tasks(completed);
+
+-- Create index on created_at for sorting
+CREATE INDEX idx_tasks_created_at ON tasks(created_at DESC);
@@ -0,0 +1,5 @@
+-- Drop join table first (references tags)
+DROP TABLE IF EXISTS task_tags;
+
+-- Drop table (indexes are dropped automatically)
+DROP TABLE IF EXISTS tags;
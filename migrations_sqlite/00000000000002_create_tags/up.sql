@@ -0,0 +1,18 @@
+-- Create tags table
+CREATE TABLE tags (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE CHECK (length(name) <= 50),
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- Create join table between tasks and tags
+CREATE TABLE task_tags (
+    task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    tag_id TEXT NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (task_id, tag_id)
+);
+
+-- Create index on tag_id for filtering tasks by tag
+CREATE INDEX idx_task_tags_tag_id ON task_tags(tag_id);
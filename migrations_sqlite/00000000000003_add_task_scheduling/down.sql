@@ -0,0 +1,8 @@
+-- Drop indexes first, SQLite keeps indexed columns
+DROP INDEX IF EXISTS idx_tasks_priority;
+DROP INDEX IF EXISTS idx_tasks_due_at_open;
+
+-- Drop columns
+ALTER TABLE tasks DROP COLUMN completed_at;
+ALTER TABLE tasks DROP COLUMN priority;
+ALTER TABLE tasks DROP COLUMN due_at;
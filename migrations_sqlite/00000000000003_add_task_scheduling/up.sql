@@ -0,0 +1,16 @@
+-- Add scheduling columns to tasks. The priority enum is a checked text
+-- column, and completed_at is maintained by the application, in place of the
+-- PostgreSQL trigger.
+ALTER TABLE tasks ADD COLUMN due_at TEXT;
+ALTER TABLE tasks ADD COLUMN priority TEXT NOT NULL DEFAULT 'medium'
+    CHECK (priority IN ('low', 'medium', 'high', 'urgent'));
+ALTER TABLE tasks ADD COLUMN completed_at TEXT;
+
+-- Backfill completion time for tasks that are already completed
+UPDATE tasks SET completed_at = updated_at WHERE completed;
+
+-- Create partial index on due_at for overdue queries on open tasks
+CREATE INDEX idx_tasks_due_at_open ON tasks(due_at) WHERE NOT completed;
+
+-- Create index on priority for filtering
+CREATE INDEX idx_tasks_priority ON tasks(priority);
@@ -0,0 +1,7 @@
+-- Drop task columns first (references projects)
+DROP INDEX IF EXISTS idx_tasks_project_id;
+ALTER TABLE tasks DROP COLUMN archived_at;
+ALTER TABLE tasks DROP COLUMN project_id;
+
+-- Drop table
+DROP TABLE IF EXISTS projects;
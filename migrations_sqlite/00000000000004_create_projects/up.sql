@@ -0,0 +1,16 @@
+-- Create projects table
+CREATE TABLE projects (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL CHECK (length(name) <= 255),
+    description TEXT,
+    archived_at TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- Attach tasks to projects (tasks without a project stay in the global list)
+ALTER TABLE tasks ADD COLUMN project_id TEXT REFERENCES projects(id) ON DELETE CASCADE;
+ALTER TABLE tasks ADD COLUMN archived_at TEXT;
+
+-- Create index on project_id for nested listings and counts
+CREATE INDEX idx_tasks_project_id ON tasks(project_id);
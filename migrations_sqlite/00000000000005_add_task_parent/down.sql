@@ -0,0 +1,3 @@
+-- Drop index, then the column and its constraint
+DROP INDEX IF EXISTS idx_tasks_parent_id;
+ALTER TABLE tasks DROP COLUMN parent_id;
@@ -0,0 +1,7 @@
+-- Add self-reference for subtasks (deleting a parent cascades unless the
+-- application re-parents the children first)
+ALTER TABLE tasks ADD COLUMN parent_id TEXT REFERENCES tasks(id) ON DELETE CASCADE
+    CONSTRAINT tasks_parent_not_self CHECK (parent_id <> id);
+
+-- Create index on parent_id for subtask lookups and tree traversal
+CREATE INDEX idx_tasks_parent_id ON tasks(parent_id);
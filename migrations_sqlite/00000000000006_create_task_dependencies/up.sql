@@ -0,0 +1,11 @@
+-- Create dependency edges: task_id is blocked by blocked_by_id
+CREATE TABLE task_dependencies (
+    task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    blocked_by_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (task_id, blocked_by_id),
+    CONSTRAINT task_dependencies_not_self CHECK (task_id <> blocked_by_id)
+);
+
+-- Create index on blocked_by_id for reverse lookups and cycle detection
+CREATE INDEX idx_task_dependencies_blocked_by_id ON task_dependencies(blocked_by_id);
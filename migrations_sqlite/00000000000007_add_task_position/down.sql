@@ -0,0 +1,3 @@
+-- Drop index, then the column
+DROP INDEX IF EXISTS idx_tasks_position;
+ALTER TABLE tasks DROP COLUMN position;
@@ -0,0 +1,18 @@
+-- Add lexicographic rank for manual ordering. The default BINARY collation
+-- compares ranks byte by byte, exactly like the application does. SQLite
+-- cannot add a NOT NULL column without a default; every insert sets one.
+ALTER TABLE tasks ADD COLUMN position TEXT NOT NULL DEFAULT '';
+
+-- Backfill existing tasks in creation order with evenly spaced ranks.
+-- Ranks use the digits 0-9a-z and never end in '0', so there is always room
+-- for a rank between two neighbours.
+UPDATE tasks
+SET position = ranked.position
+FROM (
+    SELECT id, printf('%08x', row_number() OVER (ORDER BY created_at, id)) || 'i' AS position
+    FROM tasks
+) ranked
+WHERE tasks.id = ranked.id;
+
+-- Create index on position for ordered listings and neighbour lookups
+CREATE INDEX idx_tasks_position ON tasks(position);
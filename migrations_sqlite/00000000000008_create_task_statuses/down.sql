@@ -0,0 +1,7 @@
+-- Drop task status column and workflow tables
+DROP TRIGGER IF EXISTS tasks_status_id_updated_not_null;
+DROP TRIGGER IF EXISTS tasks_status_id_inserted_not_null;
+DROP INDEX IF EXISTS idx_tasks_status_id;
+ALTER TABLE tasks DROP COLUMN status_id;
+DROP TABLE task_status_transitions;
+DROP TABLE task_statuses;
@@ -0,0 +1,89 @@
+-- Create workflow statuses (project_id NULL is the default workflow, used by
+-- tasks without a project and copied into every new project). IDs are random
+-- version 4 UUIDs, and updated_at is maintained by the application.
+CREATE TABLE task_statuses (
+    id TEXT PRIMARY KEY NOT NULL,
+    project_id TEXT REFERENCES projects(id) ON DELETE CASCADE,
+    name TEXT NOT NULL CHECK (length(name) <= 50),
+    position INTEGER NOT NULL DEFAULT 0,
+    is_done BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- Like UNIQUE NULLS NOT DISTINCT (project_id, name)
+CREATE UNIQUE INDEX idx_task_statuses_project_name
+    ON task_statuses(ifnull(project_id, ''), name);
+
+-- Create allowed transitions between statuses of the same workflow
+CREATE TABLE task_status_transitions (
+    from_status_id TEXT NOT NULL REFERENCES task_statuses(id) ON DELETE CASCADE,
+    to_status_id TEXT NOT NULL REFERENCES task_statuses(id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (from_status_id, to_status_id),
+    CHECK (from_status_id <> to_status_id)
+);
+
+-- Seed the default workflow, and give every existing project a copy of it
+INSERT INTO task_statuses (id, project_id, name, position, is_done, created_at, updated_at)
+SELECT
+    lower(hex(randomblob(4))) || '-' || lower(hex(randomblob(2))) || '-4'
+        || substr(lower(hex(randomblob(2))), 2) || '-'
+        || substr('89ab', 1 + (abs(random()) % 4), 1)
+        || substr(lower(hex(randomblob(2))), 2) || '-' || lower(hex(randomblob(6))),
+    workflows.project_id, statuses.name, statuses.position, statuses.is_done,
+    strftime('%Y-%m-%dT%H:%M:%f', 'now') || '000Z',
+    strftime('%Y-%m-%dT%H:%M:%f', 'now') || '000Z'
+FROM (
+    SELECT NULL AS project_id
+    UNION ALL SELECT id FROM projects
+) AS workflows
+CROSS JOIN (
+    SELECT 'todo' AS name, 0 AS position, FALSE AS is_done
+    UNION ALL SELECT 'in_progress', 1, FALSE
+    UNION ALL SELECT 'review', 2, FALSE
+    UNION ALL SELECT 'done', 3, TRUE
+) AS statuses;
+
+INSERT INTO task_status_transitions (from_status_id, to_status_id, created_at)
+SELECT f.id, t.id, strftime('%Y-%m-%dT%H:%M:%f', 'now') || '000Z'
+FROM (
+    SELECT 'todo' AS from_name, 'in_progress' AS to_name
+    UNION ALL SELECT 'todo', 'done'
+    UNION ALL SELECT 'in_progress', 'todo'
+    UNION ALL SELECT 'in_progress', 'review'
+    UNION ALL SELECT 'in_progress', 'done'
+    UNION ALL SELECT 'review', 'in_progress'
+    UNION ALL SELECT 'review', 'done'
+    UNION ALL SELECT 'done', 'todo'
+) AS edges
+JOIN task_statuses f ON f.name = edges.from_name
+JOIN task_statuses t ON t.project_id IS f.project_id AND t.name = edges.to_name;
+
+-- Put every task in a status of its project's workflow
+ALTER TABLE tasks ADD COLUMN status_id TEXT REFERENCES task_statuses(id);
+
+UPDATE tasks SET status_id = (
+    SELECT s.id FROM task_statuses s
+    WHERE s.project_id IS tasks.project_id
+      AND s.name = CASE WHEN tasks.completed THEN 'done' ELSE 'todo' END
+);
+
+-- SQLite cannot add a NOT NULL column with a foreign key, so triggers reject
+-- tasks without a status instead
+CREATE TRIGGER tasks_status_id_inserted_not_null
+    BEFORE INSERT ON tasks
+    FOR EACH ROW WHEN NEW.status_id IS NULL
+BEGIN
+    SELECT RAISE(ABORT, 'NOT NULL constraint failed: tasks.status_id');
+END;
+
+CREATE TRIGGER tasks_status_id_updated_not_null
+    BEFORE UPDATE OF status_id ON tasks
+    FOR EACH ROW WHEN NEW.status_id IS NULL
+BEGIN
+    SELECT RAISE(ABORT, 'NOT NULL constraint failed: tasks.status_id');
+END;
+
+-- Create index on status_id for board queries and status deletion checks
+CREATE INDEX idx_tasks_status_id ON tasks(status_id);
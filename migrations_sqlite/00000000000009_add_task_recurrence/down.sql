@@ -0,0 +1,4 @@
+-- Drop recurrence columns, the one holding the constraint first
+ALTER TABLE tasks DROP COLUMN recurrence_start;
+ALTER TABLE tasks DROP COLUMN recurrence_timezone;
+ALTER TABLE tasks DROP COLUMN recurrence;
@@ -0,0 +1,13 @@
+-- Add recurrence (an RRULE expanded in recurrence_timezone from recurrence_start)
+ALTER TABLE tasks ADD COLUMN recurrence TEXT;
+ALTER TABLE tasks ADD COLUMN recurrence_timezone TEXT
+    CHECK (length(recurrence_timezone) <= 64);
+
+-- A recurring task needs its whole schedule and a due date for the current
+-- occurrence. SQLite only adds constraints with a column, so this one comes
+-- with the last column.
+ALTER TABLE tasks ADD COLUMN recurrence_start TEXT CONSTRAINT tasks_recurrence_complete CHECK (
+    (recurrence IS NULL AND recurrence_timezone IS NULL AND recurrence_start IS NULL)
+    OR (recurrence IS NOT NULL AND recurrence_timezone IS NOT NULL
+        AND recurrence_start IS NOT NULL AND due_at IS NOT NULL)
+);
@@ -0,0 +1,2 @@
+-- Drop calendar feeds table
+DROP TABLE calendar_feeds;
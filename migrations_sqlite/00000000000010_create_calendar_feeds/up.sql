@@ -0,0 +1,9 @@
+-- Create calendar feeds: secret URLs for subscribing to tasks from calendar apps
+CREATE TABLE calendar_feeds (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL CHECK (length(name) <= 100),
+    token TEXT NOT NULL UNIQUE CHECK (length(token) <= 64),
+    project_id TEXT REFERENCES projects(id) ON DELETE CASCADE,
+    component TEXT NOT NULL DEFAULT 'todo' CHECK (component IN ('todo', 'event')),
+    created_at TEXT NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Nothing to drop
+SELECT 1;
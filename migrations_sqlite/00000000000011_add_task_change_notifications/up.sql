@@ -0,0 +1,4 @@
+-- Task change notifications are PostgreSQL triggers calling pg_notify, which
+-- SQLite has no equivalent of: live updates need PostgreSQL. This migration
+-- only keeps the numbering in step with the PostgreSQL migrations.
+SELECT 1;
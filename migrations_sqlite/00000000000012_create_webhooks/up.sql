@@ -0,0 +1,62 @@
+-- Create outbox: task events written in the same transaction as the change
+CREATE TABLE outbox (
+    id TEXT PRIMARY KEY NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('created', 'updated', 'deleted')),
+    task_id TEXT NOT NULL,
+    -- The task as returned by the API at the time of the change, as JSON text
+    payload TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    -- Set once the event has been queued for every webhook
+    dispatched_at TEXT
+);
+
+-- Create partial index for the dispatcher's queue of new events
+CREATE INDEX idx_outbox_pending ON outbox(created_at) WHERE dispatched_at IS NULL;
+
+-- Create webhooks: URLs notified of task events
+CREATE TABLE webhooks (
+    id TEXT PRIMARY KEY NOT NULL,
+    url TEXT NOT NULL,
+    description TEXT,
+    secret TEXT NOT NULL CHECK (length(secret) <= 64),
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- Create webhook deliveries: one event for one webhook, retried until
+-- delivered or dead
+CREATE TABLE webhook_deliveries (
+    id TEXT PRIMARY KEY NOT NULL,
+    webhook_id TEXT NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+    outbox_id TEXT NOT NULL REFERENCES outbox(id) ON DELETE CASCADE,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'delivered', 'dead')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TEXT NOT NULL,
+    delivered_at TEXT,
+    created_at TEXT NOT NULL,
+    UNIQUE (webhook_id, outbox_id)
+);
+
+-- Create partial index for the dispatcher's queue of due deliveries
+CREATE INDEX idx_webhook_deliveries_due ON webhook_deliveries(next_attempt_at)
+    WHERE status = 'pending';
+
+-- Create index for listing a webhook's deliveries
+CREATE INDEX idx_webhook_deliveries_webhook_id ON webhook_deliveries(webhook_id, created_at);
+
+-- Create delivery attempts: the log of every request sent
+CREATE TABLE webhook_delivery_attempts (
+    id TEXT PRIMARY KEY NOT NULL,
+    delivery_id TEXT NOT NULL REFERENCES webhook_deliveries(id) ON DELETE CASCADE,
+    -- HTTP status of the response, NULL when no response was received
+    status_code INTEGER,
+    error TEXT,
+    duration_ms INTEGER NOT NULL,
+    attempted_at TEXT NOT NULL
+);
+
+-- Create index on delivery_id for loading a delivery's log
+CREATE INDEX idx_webhook_delivery_attempts_delivery_id
+    ON webhook_delivery_attempts(delivery_id, attempted_at);
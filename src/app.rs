@@ -1,17 +1,193 @@
-use axum::{Router, routing::get};
+use axum::{
+    Router,
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{delete, get, post, put},
+};
 use tower_http::trace::TraceLayer;
 
+use crate::base_path::{BasePath, with_base_path};
+use crate::codec::negotiate;
+use crate::db::connection::read_your_writes;
+use crate::handlers::calendar::{
+    create_calendar_feed, delete_calendar_feed, export_feed_ics, export_tasks_ics,
+    list_calendar_feeds,
+};
+use crate::handlers::event::stream_task_events;
 use crate::handlers::health::health;
-use crate::handlers::task::{create_task, delete_task, get_task, list_tasks, update_task};
+use crate::handlers::project::{
+    archive_project, create_project, create_project_task, delete_project, get_project,
+    list_project_tasks, list_projects, unarchive_project, update_project,
+};
+use crate::handlers::status::{
+    add_status_transition, create_project_status, delete_project_status, list_default_statuses,
+    list_project_statuses, remove_status_transition, update_project_status,
+};
+use crate::handlers::tag::{create_tag, delete_tag, get_tag, list_tags, update_tag};
+use crate::handlers::task::{
+    add_dependency, add_task_tag, create_subtask, create_task, delete_task, get_schedule, get_task,
+    get_task_tree, list_dependencies, list_occurrences, list_subtasks, list_tasks, move_task,
+    remove_dependency, remove_task_tag, update_task,
+};
+use crate::handlers::transfer::{export_tasks, import_tasks};
+use crate::handlers::webhook::{
+    create_webhook, delete_webhook, get_webhook, list_deliveries, list_webhooks, retry_delivery,
+    update_webhook,
+};
+use crate::handlers::ws::open_socket;
+use crate::services::TaskService;
+use crate::services::transfer_service::MAX_IMPORT_BYTES;
 
-/// Build the complete application router
+/// Build the complete application router on the Postgres task store
+///
+/// Serves Lambda and the Lambda emulator, which cannot hold sockets open;
+/// the local server adds them with [`build_server_router`].
 pub fn build_router() -> Router {
+    finish(routes())
+}
+
+/// [`build_router`] plus the collaboration WebSocket, for the local server
+pub fn build_server_router() -> Router {
+    finish(routes().route("/ws", get(open_socket)))
+}
+
+/// Build a router of the task endpoints around the given task service
+///
+/// For the in-memory and SQLite modes, which have no PostgreSQL for the
+/// other endpoints: these are not mounted and answer `404`.
+pub fn build_task_router(tasks: TaskService) -> Router {
+    let app = task_routes()
+        .route_layer(middleware::from_fn(negotiate))
+        .with_state(tasks);
+
+    with_base_path(app, BasePath::from_env()).layer(TraceLayer::new_for_http())
+}
+
+/// The endpoints served by the task store alone
+fn task_routes() -> Router<TaskService> {
     Router::new()
         .route("/health", get(health))
         .route("/tasks", get(list_tasks).post(create_task))
         .route(
-            "/tasks/:id",
+            "/tasks/{id}",
             get(get_task).put(update_task).delete(delete_task),
         )
-        .layer(TraceLayer::new_for_http())
+        .route(
+            "/tasks/{id}/subtasks",
+            get(list_subtasks).post(create_subtask),
+        )
+        .route("/tasks/{id}/move", post(move_task))
+        .route("/tasks/{id}/occurrences", get(list_occurrences))
+        .route("/tasks/{id}/tree", get(get_task_tree))
+}
+
+/// Every HTTP route, waiting for its task service
+fn routes() -> Router<TaskService> {
+    task_routes()
+        .route("/tasks/schedule", get(get_schedule))
+        .route(
+            "/tasks/import",
+            post(import_tasks).layer(DefaultBodyLimit::max(MAX_IMPORT_BYTES)),
+        )
+        .route("/tasks/{id}/dependencies", get(list_dependencies))
+        .route(
+            "/tasks/{id}/dependencies/{blocked_by_id}",
+            put(add_dependency).delete(remove_dependency),
+        )
+        .route(
+            "/tasks/{id}/tags/{tag_id}",
+            put(add_task_tag).delete(remove_task_tag),
+        )
+        .route("/projects", get(list_projects).post(create_project))
+        .route(
+            "/projects/{id}",
+            get(get_project).put(update_project).delete(delete_project),
+        )
+        .route("/projects/{id}/archive", post(archive_project))
+        .route("/projects/{id}/unarchive", post(unarchive_project))
+        .route(
+            "/projects/{id}/tasks",
+            get(list_project_tasks).post(create_project_task),
+        )
+        .route(
+            "/projects/{id}/statuses",
+            get(list_project_statuses).post(create_project_status),
+        )
+        .route(
+            "/projects/{id}/statuses/{status_id}",
+            put(update_project_status).delete(delete_project_status),
+        )
+        .route(
+            "/projects/{id}/statuses/{status_id}/transitions/{to_status_id}",
+            put(add_status_transition).delete(remove_status_transition),
+        )
+        .route("/statuses", get(list_default_statuses))
+        .route(
+            "/calendar-feeds",
+            get(list_calendar_feeds).post(create_calendar_feed),
+        )
+        .route("/calendar-feeds/{id}", delete(delete_calendar_feed))
+        .route("/tags", get(list_tags).post(create_tag))
+        .route(
+            "/tags/{id}",
+            get(get_tag).put(update_tag).delete(delete_tag),
+        )
+        .route("/webhooks", get(list_webhooks).post(create_webhook))
+        .route(
+            "/webhooks/{id}",
+            get(get_webhook).put(update_webhook).delete(delete_webhook),
+        )
+        .route("/webhooks/{id}/deliveries", get(list_deliveries))
+        .route(
+            "/webhooks/{id}/deliveries/{delivery_id}/retry",
+            post(retry_delivery),
+        )
+        .route_layer(middleware::from_fn(negotiate))
+        // File downloads, event streams and sockets pick their own format, so they skip negotiation
+        .route("/tasks.ics", get(export_tasks_ics))
+        .route("/tasks/export", get(export_tasks))
+        .route("/tasks/events", get(stream_task_events))
+        .route("/feeds/{token}/tasks.ics", get(export_feed_ics))
+}
+
+/// Serve `routes` on the Postgres task store, below the base path
+fn finish(routes: Router<TaskService>) -> Router {
+    let app = routes
+        .with_state(TaskService::postgres())
+        .layer(middleware::from_fn(read_your_writes));
+
+    with_base_path(app, BasePath::from_env()).layer(TraceLayer::new_for_http())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_only_the_server_router_opens_sockets() {
+        let request = || Request::get("/ws").body(Body::empty()).unwrap();
+
+        let response = build_router().oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        // Mounted, but refuses a request that is not an upgrade
+        let response = build_server_router().oneshot(request()).await.unwrap();
+        assert_ne!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_task_router_only_serves_tasks() {
+        let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+        let app = build_task_router(TaskService::in_memory());
+
+        let response = app.clone().oneshot(get("/tasks")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        for uri in ["/projects", "/tags", "/webhooks", "/calendar-feeds", "/ws"] {
+            let response = app.clone().oneshot(get(uri)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "{}", uri);
+        }
+    }
 }
@@ -0,0 +1,289 @@
+//! Serving the API below a path prefix
+//!
+//! Behind API Gateway, requests arrive with the stage in their path
+//! (`/prod/tasks`), and custom domains may map the API below a base path.
+//! [`with_base_path`] strips both before routing, and [`BaseUrl`] rebuilds
+//! the external URL of the API root for `Location` headers and links.
+
+use axum::Router;
+use axum::extract::{FromRequestParts, Request, State};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, Uri, header};
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use lambda_http::request::RequestContext;
+use std::convert::Infallible;
+use std::env;
+
+/// Where the API is mounted
+#[derive(Debug, Clone, Default)]
+pub struct BasePath {
+    /// Prefix stripped from request paths after the stage, e.g. `/api`
+    pub prefix: Option<String>,
+    /// External URL of the API root, instead of the one derived from the request
+    pub public_url: Option<String>,
+}
+
+impl BasePath {
+    /// Read `BASE_PATH` and `PUBLIC_BASE_URL`
+    pub fn from_env() -> Self {
+        let prefix = env::var("BASE_PATH")
+            .ok()
+            .map(|prefix| format!("/{}", prefix.trim_matches('/')))
+            .filter(|prefix| prefix != "/");
+        let public_url = env::var("PUBLIC_BASE_URL")
+            .ok()
+            .map(|url| url.trim_end_matches('/').to_string())
+            .filter(|url| !url.is_empty());
+        Self { prefix, public_url }
+    }
+}
+
+/// External URL of the API root, e.g. `https://api.example.com/prod`
+///
+/// Falls back to a relative (empty) root outside [`with_base_path`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BaseUrl(String);
+
+impl BaseUrl {
+    /// Absolute URL of an API path
+    pub fn join(&self, path: &str) -> String {
+        format!("{}{}", self.0, path)
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for BaseUrl {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<BaseUrl>()
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+/// Route requests below the API Gateway stage and the configured prefix
+pub fn with_base_path(app: Router, base_path: BasePath) -> Router {
+    // Layers of a router run after its routing, so the rewrite wraps `app`
+    Router::new()
+        .fallback_service(app)
+        .layer(middleware::from_fn_with_state(base_path, strip_base_path))
+}
+
+async fn strip_base_path(
+    State(base_path): State<BasePath>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let mut stripped = String::new();
+
+    if let Some(stage) = stage(&req) {
+        stripped += &strip_prefix(req.uri_mut(), &format!("/{}", stage));
+    }
+    if let Some(ref prefix) = base_path.prefix {
+        stripped += &strip_prefix(req.uri_mut(), prefix);
+    }
+
+    let base_url = match base_path.public_url {
+        Some(url) => url,
+        None => derive_base_url(req.headers(), req.uri(), &stripped),
+    };
+    req.extensions_mut().insert(BaseUrl(base_url));
+
+    next.run(req).await
+}
+
+/// API Gateway stage of a Lambda request, if it is part of the path
+fn stage(req: &Request) -> Option<String> {
+    let stage = match req.extensions().get::<RequestContext>()? {
+        RequestContext::ApiGatewayV1(context) => context.stage.clone(),
+        RequestContext::ApiGatewayV2(context) => context.stage.clone(),
+        _ => None,
+    };
+    stage.filter(|stage| stage != "$default")
+}
+
+/// Remove `prefix` from the start of the path when it is a whole segment
+///
+/// Returns the removed prefix, or an empty string.
+fn strip_prefix(uri: &mut Uri, prefix: &str) -> String {
+    let path = uri.path();
+    let rest = match path.strip_prefix(prefix) {
+        Some("") => "/",
+        Some(rest) if rest.starts_with('/') => rest,
+        _ => return String::new(),
+    };
+
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{}?{}", rest, query),
+        None => rest.to_string(),
+    };
+    let mut parts = std::mem::take(uri).into_parts();
+    parts.path_and_query = Some(
+        path_and_query
+            .parse()
+            .expect("a suffix of a valid path is a valid path"),
+    );
+    *uri = Uri::from_parts(parts).expect("only the path changed");
+    prefix.to_string()
+}
+
+/// `{scheme}://{host}{stripped prefix}`, as seen by the client
+fn derive_base_url(headers: &HeaderMap, uri: &Uri, stripped: &str) -> String {
+    let forwarded = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .map(str::trim)
+    };
+    let scheme = forwarded("x-forwarded-proto")
+        .or(uri.scheme_str())
+        .unwrap_or("http");
+    let host = forwarded("x-forwarded-host")
+        .or_else(|| headers.get(header::HOST)?.to_str().ok())
+        .or(uri.host());
+
+    match host {
+        Some(host) => format!("{}://{}{}", scheme, host, stripped),
+        None => stripped.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::StatusCode;
+    use axum::routing::get;
+    use lambda_http::request::LambdaRequest;
+    use tower::ServiceExt;
+
+    /// Echo the routed path and the base URL
+    fn app(base_path: BasePath) -> Router {
+        let echo = |uri: Uri, base_url: BaseUrl| async move {
+            let location = base_url.join("/tasks/1");
+            let path = uri.path_and_query().map(ToString::to_string);
+            ([(header::LOCATION, location)], path.unwrap_or_default())
+        };
+        with_base_path(
+            Router::new()
+                .route("/health", get(echo))
+                .route("/tasks", get(echo).post(echo))
+                .route("/tasks/{id}", get(echo)),
+            base_path,
+        )
+    }
+
+    async fn send(base_path: BasePath, fixture: &str) -> (StatusCode, Option<String>, String) {
+        let request: LambdaRequest = serde_json::from_str(fixture).unwrap();
+        let request: lambda_http::Request = request.into();
+        let response = app(base_path).oneshot(request).await.unwrap();
+
+        let status = response.status();
+        let location = response
+            .headers()
+            .get(header::LOCATION)
+            .map(|value| value.to_str().unwrap().to_string());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, location, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_http_api_v2_stage_is_stripped() {
+        let (status, location, path) = send(
+            BasePath::default(),
+            include_str!("../tests/fixtures/lambda/apigw_v2_post_task_stage.json"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(path, "/tasks?dry_run=true");
+        assert_eq!(
+            location.unwrap(),
+            "https://abcdef1234.execute-api.us-east-1.amazonaws.com/prod/tasks/1"
+        );
+
+        // The $default stage is not part of the path
+        let (status, location, path) = send(
+            BasePath::default(),
+            include_str!("../tests/fixtures/lambda/apigw_v2_get_health.json"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(path, "/health");
+        assert_eq!(
+            location.unwrap(),
+            "https://abcdef1234.execute-api.us-east-1.amazonaws.com/tasks/1"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_api_v1_stage_is_stripped() {
+        let (status, location, path) = send(
+            BasePath::default(),
+            include_str!("../tests/fixtures/lambda/apigw_v1_get_task.json"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(path, "/tasks/943d888d-7c79-4139-a4ef-90caf5240eae");
+        assert_eq!(
+            location.unwrap(),
+            "https://wt6mne2s9k.execute-api.us-west-2.amazonaws.com/prod/tasks/1"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_alb_base_path_is_stripped() {
+        let fixture = include_str!("../tests/fixtures/lambda/alb_get_health.json");
+
+        let api = BasePath {
+            prefix: Some("/api".to_string()),
+            public_url: None,
+        };
+        let (status, location, path) = send(api, fixture).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(path, "/health");
+        assert_eq!(location.unwrap(), "https://tasks.example.com/api/tasks/1");
+
+        // Without the prefix configured, nothing matches
+        let (status, _, _) = send(BasePath::default(), fixture).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let public = BasePath {
+            prefix: Some("/api".to_string()),
+            public_url: Some("https://public.example.com/v1".to_string()),
+        };
+        let (_, location, _) = send(public, fixture).await;
+        assert_eq!(location.unwrap(), "https://public.example.com/v1/tasks/1");
+    }
+
+    #[tokio::test]
+    async fn test_local_requests_keep_their_path() {
+        let request = Request::builder()
+            .uri("/tasks")
+            .header(header::HOST, "localhost:3000")
+            .body(Body::empty())
+            .unwrap();
+        let response = app(BasePath::default()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::LOCATION],
+            "http://localhost:3000/tasks/1"
+        );
+    }
+
+    #[test]
+    fn test_strip_prefix_matches_whole_segments() {
+        let mut uri: Uri = "/prod".parse().unwrap();
+        assert_eq!(strip_prefix(&mut uri, "/prod"), "/prod");
+        assert_eq!(uri, "/");
+
+        let mut uri: Uri = "/production/tasks?page=2".parse().unwrap();
+        assert_eq!(strip_prefix(&mut uri, "/prod"), "");
+        assert_eq!(uri, "/production/tasks?page=2");
+    }
+}
@@ -0,0 +1,469 @@
+//! Body formats negotiated per request
+//!
+//! Responses are encoded in the format picked from the `Accept` header by the
+//! [`negotiate`] middleware; request bodies are decoded according to their
+//! `Content-Type` by the [`Payload`] extractor. JSON is always available,
+//! MessagePack and CBOR behind the `msgpack` and `cbor` features.
+
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::error::AppError;
+
+/// A supported body format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    #[cfg(feature = "msgpack")]
+    MessagePack,
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+/// Compiled-in formats, in server preference order
+const FORMATS: &[Format] = &[
+    Format::Json,
+    #[cfg(feature = "msgpack")]
+    Format::MessagePack,
+    #[cfg(feature = "cbor")]
+    Format::Cbor,
+];
+
+tokio::task_local! {
+    /// Response format of the request being handled
+    static RESPONSE_FORMAT: Format;
+}
+
+impl Format {
+    /// `Content-Type` of bodies in this format
+    pub fn content_type(self) -> &'static str {
+        self.media_types()[0]
+    }
+
+    /// Media types naming this format, canonical first
+    fn media_types(self) -> &'static [&'static str] {
+        match self {
+            Format::Json => &["application/json"],
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack => &[
+                "application/msgpack",
+                "application/x-msgpack",
+                "application/vnd.msgpack",
+            ],
+            #[cfg(feature = "cbor")]
+            Format::Cbor => &["application/cbor"],
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Format::Json => "JSON",
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack => "MessagePack",
+            #[cfg(feature = "cbor")]
+            Format::Cbor => "CBOR",
+        }
+    }
+
+    /// Format of a request body, from its `Content-Type` (parameters ignored)
+    pub fn from_content_type(value: &str) -> Option<Format> {
+        let media_type = media_type(value);
+        if media_type.ends_with("+json") {
+            return Some(Format::Json);
+        }
+        FORMATS
+            .iter()
+            .copied()
+            .find(|format| format.media_types().contains(&media_type.as_str()))
+    }
+
+    /// Response format for an `Accept` header
+    ///
+    /// Each format gets the quality of the most specific range matching it
+    /// (`*/*` and `application/*` match all of them). The highest quality
+    /// wins; ties go to the range listed first, then to JSON. Returns `None`
+    /// when no supported format is acceptable.
+    pub fn from_accept(accept: Option<&str>) -> Option<Format> {
+        let Some(accept) = accept.filter(|a| !a.trim().is_empty()) else {
+            return Some(Format::Json);
+        };
+
+        let ranges: Vec<(String, f32)> = accept
+            .split(',')
+            .map(|range| {
+                let q = range
+                    .split(';')
+                    .skip(1)
+                    .filter_map(|param| param.trim().strip_prefix("q="))
+                    .find_map(|q| q.trim().parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                (media_type(range), q)
+            })
+            .collect();
+
+        let mut best: Option<(Format, f32, usize)> = None;
+        for &format in FORMATS {
+            let specificity = |range: &str| {
+                if format.media_types().contains(&range) {
+                    Some(2)
+                } else if range == "application/*" {
+                    Some(1)
+                } else if range == "*/*" {
+                    Some(0)
+                } else {
+                    None
+                }
+            };
+
+            let Some((index, q)) = ranges
+                .iter()
+                .enumerate()
+                .filter_map(|(i, (range, q))| specificity(range).map(|s| (s, i, *q)))
+                .max_by_key(|&(s, i, _)| (s, std::cmp::Reverse(i)))
+                .map(|(_, i, q)| (i, q))
+            else {
+                continue;
+            };
+
+            let better = match best {
+                None => true,
+                Some((_, best_q, best_index)) => q > best_q || (q == best_q && index < best_index),
+            };
+            if q > 0.0 && better {
+                best = Some((format, q, index));
+            }
+        }
+
+        best.map(|(format, _, _)| format)
+    }
+
+    /// Serialize a value in this format
+    pub fn encode<T: Serialize>(self, value: &T) -> Result<Vec<u8>, String> {
+        match self {
+            Format::Json => serde_json::to_vec(value).map_err(|e| e.to_string()),
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack => rmp_serde::to_vec_named(value).map_err(|e| e.to_string()),
+            #[cfg(feature = "cbor")]
+            Format::Cbor => {
+                let mut out = Vec::new();
+                ciborium::into_writer(value, &mut out).map_err(|e| e.to_string())?;
+                Ok(out)
+            }
+        }
+    }
+
+    /// Deserialize a value in this format
+    pub fn decode<T: DeserializeOwned>(self, bytes: &[u8]) -> Result<T, String> {
+        match self {
+            Format::Json => serde_json::from_slice(bytes).map_err(|e| e.to_string()),
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack => rmp_serde::from_slice(bytes).map_err(|e| e.to_string()),
+            #[cfg(feature = "cbor")]
+            Format::Cbor => ciborium::from_reader(bytes).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Lowercased media type of a header value, without parameters
+fn media_type(value: &str) -> String {
+    value
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+fn supported_types() -> String {
+    FORMATS
+        .iter()
+        .map(|format| format.content_type())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Pick the response format from `Accept`, answering 406 when none fits
+///
+/// Runs before the handler, so a request that cannot be answered has no
+/// side effects.
+pub async fn negotiate(req: Request, next: Next) -> Response {
+    let accept = header_values(req.headers(), header::ACCEPT);
+    let Some(format) = Format::from_accept(accept.as_deref()) else {
+        return AppError::not_acceptable(format!(
+            "Supported response types: {}",
+            supported_types()
+        ))
+        .into_response();
+    };
+
+    let mut response = RESPONSE_FORMAT.scope(format, next.run(req)).await;
+    response
+        .headers_mut()
+        .append(header::VARY, HeaderValue::from_static("accept"));
+    response
+}
+
+/// All values of a header joined as one list
+fn header_values(headers: &HeaderMap, name: header::HeaderName) -> Option<String> {
+    let values: Vec<&str> = headers
+        .get_all(name)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .collect();
+    (!values.is_empty()).then(|| values.join(", "))
+}
+
+/// Encode a response body in the negotiated format (JSON outside [`negotiate`])
+pub fn respond<T: Serialize>(status: StatusCode, value: &T) -> Response {
+    let format = RESPONSE_FORMAT.try_with(|f| *f).unwrap_or(Format::Json);
+    match format.encode(value) {
+        Ok(body) => (
+            status,
+            [(header::CONTENT_TYPE, format.content_type())],
+            body,
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to encode {} response: {}", format.name(), e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Request body decoded according to its `Content-Type`
+///
+/// Drop-in replacement for `axum::Json` that also accepts the binary formats.
+pub struct Payload<T>(pub T);
+
+impl<T, S> FromRequest<S> for Payload<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let format = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(Format::from_content_type)
+            .ok_or_else(|| {
+                AppError::unsupported_media_type(format!(
+                    "Supported request types: {}",
+                    supported_types()
+                ))
+                .into_response()
+            })?;
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        format.decode(&bytes).map(Payload).map_err(|e| {
+            AppError::invalid_input(format!("Invalid {} body: {}", format.name(), e))
+                .into_response()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        Router,
+        body::Body,
+        middleware,
+        routing::{get, post},
+    };
+    use serde::Deserialize;
+    use tower::ServiceExt;
+
+    use crate::response::AppResponse;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Item {
+        id: uuid::Uuid,
+        name: String,
+        done: Option<bool>,
+    }
+
+    fn item() -> Item {
+        Item {
+            id: uuid::Uuid::from_u128(7),
+            name: "café".to_string(),
+            done: None,
+        }
+    }
+
+    #[test]
+    fn test_accept_defaults_to_json() {
+        assert_eq!(Format::from_accept(None), Some(Format::Json));
+        assert_eq!(Format::from_accept(Some("*/*")), Some(Format::Json));
+        assert_eq!(
+            Format::from_accept(Some("application/*")),
+            Some(Format::Json)
+        );
+        assert_eq!(
+            Format::from_accept(Some("text/html, application/json;q=0.9")),
+            Some(Format::Json)
+        );
+        assert_eq!(Format::from_accept(Some("text/html")), None);
+        assert_eq!(Format::from_accept(Some("application/json;q=0")), None);
+    }
+
+    #[cfg(all(feature = "msgpack", feature = "cbor"))]
+    #[test]
+    fn test_accept_picks_best_binary_format() {
+        assert_eq!(
+            Format::from_accept(Some("application/msgpack")),
+            Some(Format::MessagePack)
+        );
+        assert_eq!(
+            Format::from_accept(Some("application/json;q=0.5, application/cbor")),
+            Some(Format::Cbor)
+        );
+        assert_eq!(
+            Format::from_accept(Some("application/x-msgpack, */*")),
+            Some(Format::MessagePack)
+        );
+        // An explicit q=0 beats a wildcard match
+        assert_eq!(
+            Format::from_accept(Some("application/json;q=0, */*")),
+            Some(Format::MessagePack)
+        );
+    }
+
+    #[test]
+    fn test_content_type() {
+        assert_eq!(
+            Format::from_content_type("application/json; charset=utf-8"),
+            Some(Format::Json)
+        );
+        assert_eq!(
+            Format::from_content_type("application/merge-patch+json"),
+            Some(Format::Json)
+        );
+        assert_eq!(Format::from_content_type("text/plain"), None);
+    }
+
+    #[test]
+    fn test_round_trip_in_every_format() {
+        for &format in FORMATS {
+            let bytes = format.encode(&item()).unwrap();
+            assert_eq!(
+                format.decode::<Item>(&bytes).unwrap(),
+                item(),
+                "{:?}",
+                format
+            );
+        }
+    }
+
+    async fn echo(Payload(item): Payload<Item>) -> AppResponse<Item> {
+        AppResponse::created(item)
+    }
+
+    async fn fail() -> Result<AppResponse<Item>, AppError> {
+        Err(AppError::not_found("Item not found"))
+    }
+
+    fn router() -> Router {
+        Router::new()
+            .route("/items", post(echo))
+            .route("/missing", get(fail))
+            .layer(middleware::from_fn(negotiate))
+    }
+
+    fn request(method: &str, uri: &str, headers: &[(&str, &str)], body: Vec<u8>) -> Request {
+        let mut builder = Request::builder().method(method).uri(uri);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(Body::from(body)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_router_encodes_each_format() {
+        for &format in FORMATS {
+            let response = router()
+                .oneshot(request(
+                    "POST",
+                    "/items",
+                    &[
+                        ("content-type", format.content_type()),
+                        ("accept", format.content_type()),
+                    ],
+                    format.encode(&item()).unwrap(),
+                ))
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::CREATED);
+            assert_eq!(
+                response.headers()[header::CONTENT_TYPE],
+                format.content_type()
+            );
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert_eq!(format.decode::<Item>(&body).unwrap(), item());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_router_rejects_unsupported_types() {
+        let json = serde_json::to_vec(&item()).unwrap();
+
+        let response = router()
+            .oneshot(request(
+                "POST",
+                "/items",
+                &[("content-type", "text/plain")],
+                json.clone(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let response = router()
+            .oneshot(request(
+                "POST",
+                "/items",
+                &[("content-type", "application/json"), ("accept", "text/xml")],
+                json,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    }
+
+    #[cfg(feature = "cbor")]
+    #[tokio::test]
+    async fn test_router_encodes_errors() {
+        let response = router()
+            .oneshot(request(
+                "GET",
+                "/missing",
+                &[("accept", "application/cbor")],
+                Vec::new(),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()[header::VARY], "accept");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: serde_json::Value = Format::Cbor.decode(&body).unwrap();
+        assert_eq!(error["error"], "NOT_FOUND");
+    }
+}
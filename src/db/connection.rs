@@ -1,46 +1,417 @@
+//! Connection pools: the primary, and an optional read replica
+//!
+//! With `DATABASE_READ_URL` set, read-only task queries take their connection
+//! from a second pool on the replica. A request reads from the primary
+//! instead once it has written, from the start if its method is not safe
+//! (`POST`, `PUT`, `DELETE`...), or when it carries the [`READ_PRIMARY_HEADER`].
+//! Requests that write get a [`READ_PRIMARY_COOKIE`] that keeps the client's
+//! next requests on the primary while the replica catches up, so that clients
+//! see their own writes despite replication lag. Reads also fall back to the
+//! primary while the replica is unreachable.
+
 use anyhow::{Context, Result};
+use axum::extract::Request;
+use axum::http::{HeaderMap, HeaderValue, header};
+use axum::middleware::Next;
+use axum::response::Response;
 use diesel::pg::PgConnection;
 use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
-use std::sync::OnceLock;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::db::retry::with_backoff;
 
 pub type DbPool = Pool<ConnectionManager<PgConnection>>;
 pub type DbConnection = PooledConnection<ConnectionManager<PgConnection>>;
 
-static POOL: OnceLock<DbPool> = OnceLock::new();
+static POOL: RwLock<Option<DbPool>> = RwLock::new(None);
+/// Serializes pool creation, so concurrent first requests share one pool
+static INIT: Mutex<()> = Mutex::new(());
+/// Set by the Lambda emulator, which runs outside Lambda
+static LAMBDA: AtomicBool = AtomicBool::new(false);
+
+/// Attempts to reach the database when creating the pool
+const CONNECT_ATTEMPTS: u32 = 3;
+/// Delay after the first failed attempt, doubled after each following one
+const CONNECT_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// Request header sending every read of the request to the primary
+pub const READ_PRIMARY_HEADER: &str = "x-read-primary";
+/// Cookie holding the time (Unix milliseconds) until which the client's
+/// reads go to the primary
+pub const READ_PRIMARY_COOKIE: &str = "read_primary_until";
+
+/// How long reads skip the replica after failing to reach it
+const REPLICA_RETRY_AFTER: Duration = Duration::from_secs(30);
+/// Longest wait for a replica connection before falling back
+const REPLICA_CONNECTION_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long a client reads from the primary after writing, unless
+/// `DATABASE_READ_STICKY_SECS` says otherwise
+const DEFAULT_STICKY_WINDOW: Duration = Duration::from_secs(5);
+
+static READ_POOL: OnceLock<RwLock<Option<DbPool>>> = OnceLock::new();
+static HEALTH: Health = Health::new();
+
+tokio::task_local! {
+    /// Whether the current request reads from the primary
+    static PRIMARY_READS: Cell<bool>;
+}
+
+/// Pool sizing for an execution mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolProfile {
+    pub max_size: u32,
+    /// Idle connections kept open (`None`: as many as `max_size`)
+    pub min_idle: Option<u32>,
+    pub connection_timeout: Duration,
+}
+
+impl PoolProfile {
+    /// Long-running server: a full pool of warm connections
+    pub fn server() -> Self {
+        Self {
+            max_size: 15,
+            min_idle: None,
+            connection_timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Lambda: one request at a time per instance, and frozen between requests,
+    /// so keep at most two connections, none idle, and fail fast
+    pub fn lambda() -> Self {
+        Self {
+            max_size: 2,
+            min_idle: Some(0),
+            connection_timeout: Duration::from_secs(3),
+        }
+    }
+
+    /// Profile of the current execution mode, adjusted by `DB_POOL_MAX_SIZE`
+    /// and `DB_CONNECTION_TIMEOUT_SECS`
+    pub fn from_env() -> Self {
+        let lambda =
+            LAMBDA.load(Ordering::Relaxed) || std::env::var("AWS_LAMBDA_FUNCTION_NAME").is_ok();
+        let mut profile = if lambda {
+            Self::lambda()
+        } else {
+            Self::server()
+        };
+
+        let var = |name: &str| std::env::var(name).ok()?.parse::<u64>().ok();
+        if let Some(max_size) = var("DB_POOL_MAX_SIZE").filter(|&size| size > 0) {
+            profile.max_size = max_size.min(u32::MAX.into()) as u32;
+        }
+        if let Some(secs) = var("DB_CONNECTION_TIMEOUT_SECS").filter(|&secs| secs > 0) {
+            profile.connection_timeout = Duration::from_secs(secs);
+        }
+        profile
+    }
+
+    /// Pool of this size on `url`
+    ///
+    /// Unchecked: connections are opened by the first `get`.
+    fn build(&self, url: String) -> DbPool {
+        Pool::builder()
+            .max_size(self.max_size)
+            .min_idle(self.min_idle)
+            .connection_timeout(self.connection_timeout)
+            .build_unchecked(ConnectionManager::<PgConnection>::new(url))
+    }
+}
+
+/// Size the pool like on Lambda, whatever the environment
+///
+/// Only has an effect before the pool is created.
+pub fn use_lambda_profile() {
+    LAMBDA.store(true, Ordering::Relaxed);
+}
+
+/// Path of the SQLite database named by a `sqlite:` URL
+///
+/// Accepts `sqlite://path`, `sqlite:path` and `sqlite::memory:`. Returns
+/// `None` for any other URL.
+pub fn sqlite_path(url: &str) -> Option<&str> {
+    let path = url
+        .strip_prefix("sqlite://")
+        .or_else(|| url.strip_prefix("sqlite:"))?;
+    Some(if path == ":memory:" { ":memory:" } else { path })
+}
 
-/// Initialize the PostgreSQL connection pool using DATABASE_URL.
-/// This should be called once at application startup.
+/// Create the PostgreSQL connection pool from DATABASE_URL, unless it exists.
+///
+/// Called on first use by [`get_connection`]; calling it at startup only
+/// warms the pool up.
 pub fn init_pool() -> Result<()> {
+    connect().map(|_| ())
+}
+
+/// Use `pool` from now on, in place of any pool created so far
+///
+/// For callers that do not configure the database through the environment,
+/// such as the integration tests, which give each test its own schema.
+pub fn set_pool(pool: DbPool) {
+    *POOL
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(pool);
+}
+
+/// Get a handle on the pool, if it has been created.
+pub fn get_pool() -> Option<DbPool> {
+    POOL.read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+/// Get a connection from the pool, creating the pool on first use.
+pub fn get_connection() -> Result<DbConnection> {
+    match get_pool() {
+        Some(pool) => pool.get().context("Failed to get connection from pool"),
+        None => connect(),
+    }
+}
+
+/// Build the pool and check that the database is reachable, with retries
+///
+/// A pool is only kept once a connection succeeded, so a database that is
+/// down during a cold start fails requests instead of the whole instance,
+/// and the next request tries again.
+fn connect() -> Result<DbConnection> {
+    let _init = INIT.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(pool) = get_pool() {
+        return pool.get().context("Failed to get connection from pool");
+    }
+
     let url = std::env::var("DATABASE_URL").context("DATABASE_URL env var not set")?;
-    let manager = ConnectionManager::<PgConnection>::new(url);
-    let pool = Pool::builder()
-        .max_size(15)
-        .build(manager)
-        .context("failed to build r2d2 pool")?;
+    if sqlite_path(&url).is_some() {
+        anyhow::bail!(if cfg!(feature = "sqlite") {
+            "DATABASE_URL is a SQLite database, which only serves the task endpoints"
+        } else {
+            "DATABASE_URL is a SQLite database, but the `sqlite` feature is not enabled"
+        });
+    }
+    let profile = PoolProfile::from_env();
+    // The first `get` is retried
+    let pool = profile.build(url);
 
-    POOL.set(pool)
-        .map_err(|_| anyhow::anyhow!("Pool already initialized"))?;
+    let started = Instant::now();
+    let mut attempts = 0;
+    let conn = with_backoff(
+        "Database connection",
+        CONNECT_ATTEMPTS,
+        CONNECT_RETRY_DELAY,
+        |_| true,
+        || {
+            attempts += 1;
+            pool.get()
+        },
+    )
+    .context("Database unavailable")?;
+    tracing::info!(
+        "Database pool ready in {}ms after {} attempt(s) (max size {})",
+        started.elapsed().as_millis(),
+        attempts,
+        profile.max_size
+    );
 
-    Ok(())
+    // Only this thread creates a pool while holding INIT
+    set_pool(pool);
+    Ok(conn)
 }
 
-/// Get a reference to the initialized pool.
-/// Panics if the pool hasn't been initialized with init_pool().
-pub fn get_pool() -> &'static DbPool {
-    POOL.get()
-        .expect("DB pool not initialized. Call init_pool() first.")
+/// Replica pool, created from `DATABASE_READ_URL` on first use
+fn read_pool() -> &'static RwLock<Option<DbPool>> {
+    READ_POOL.get_or_init(|| {
+        let pool = std::env::var("DATABASE_READ_URL")
+            .ok()
+            .filter(|url| !url.is_empty())
+            .map(|url| {
+                // Sized like the primary, but quick to give up: a replica that
+                // is down must neither fail startup nor hold reads back
+                let mut profile = PoolProfile::from_env();
+                profile.connection_timeout =
+                    profile.connection_timeout.min(REPLICA_CONNECTION_TIMEOUT);
+                profile.build(url)
+            });
+        RwLock::new(pool)
+    })
 }
 
-/// Get a connection from the pool.
-pub fn get_connection() -> Result<DbConnection> {
-    get_pool()
-        .get()
-        .context("Failed to get connection from pool")
+/// Read from `pool` from now on (`None`: read from the primary)
+///
+/// For callers that do not configure the database through the environment,
+/// such as the integration tests.
+pub fn set_read_pool(pool: Option<DbPool>) {
+    *read_pool()
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = pool;
+    HEALTH.mark_up();
+}
+
+fn get_read_pool() -> Option<DbPool> {
+    read_pool()
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+/// Get a connection for read-only queries
+///
+/// Comes from the replica when one is configured and reachable, and the
+/// current request has not been pinned to the primary.
+pub fn get_read_connection() -> Result<DbConnection> {
+    if reads_use_primary() || !HEALTH.is_up(Instant::now()) {
+        return get_connection();
+    }
+    let Some(pool) = get_read_pool() else {
+        return get_connection();
+    };
+
+    match pool.get() {
+        Ok(conn) => Ok(conn),
+        Err(e) => {
+            tracing::warn!(
+                "Read replica unavailable, reading from the primary for {}s: {}",
+                REPLICA_RETRY_AFTER.as_secs(),
+                e
+            );
+            HEALTH.mark_down(Instant::now());
+            get_connection()
+        }
+    }
+}
+
+/// Send the remaining reads of the current request to the primary
+///
+/// Called after writes, so that the request reads what it wrote. Does
+/// nothing outside a request.
+pub fn stick_to_primary() {
+    let _ = PRIMARY_READS.try_with(|primary| primary.set(true));
+}
+
+/// Whether reads go to the primary
+///
+/// Outside a request, e.g. in background jobs, they always do.
+pub fn reads_use_primary() -> bool {
+    PRIMARY_READS.try_with(Cell::get).unwrap_or(true)
+}
+
+/// Run `f` with reads routed to the replica until it writes, or to the
+/// primary from the start if `primary`
+pub async fn route_reads<F: Future>(primary: bool, f: F) -> F::Output {
+    PRIMARY_READS.scope(Cell::new(primary), f).await
+}
+
+/// [`route_reads`] for blocking code, e.g. work the request moved to
+/// `spawn_blocking`
+pub fn route_reads_blocking<T>(primary: bool, f: impl FnOnce() -> T) -> T {
+    PRIMARY_READS.sync_scope(Cell::new(primary), f)
+}
+
+/// Middleware giving each request its own read routing
+///
+/// A request that may have written, because its method is not safe or it
+/// wrote through the task store, answers with a [`READ_PRIMARY_COOKIE`] so
+/// that the client's next requests read from the primary for the
+/// `DATABASE_READ_STICKY_SECS` window (default 5s).
+pub async fn read_your_writes(request: Request, next: Next) -> Response {
+    let now = unix_millis(SystemTime::now());
+    let unsafe_method = !request.method().is_safe();
+    let primary = unsafe_method
+        || request.headers().contains_key(READ_PRIMARY_HEADER)
+        || primary_until(request.headers()).is_some_and(|until| until > now);
+
+    let (mut response, wrote) = PRIMARY_READS
+        .scope(Cell::new(primary), async {
+            let response = next.run(request).await;
+            // Reads that started on the replica only move to the primary on a write
+            (response, !primary && reads_use_primary())
+        })
+        .await;
+
+    if (unsafe_method || wrote) && get_read_pool().is_some() {
+        let window = sticky_window();
+        let cookie = format!(
+            "{}={}; Max-Age={}; Path=/; HttpOnly; SameSite=Lax",
+            READ_PRIMARY_COOKIE,
+            now + window.as_millis() as u64,
+            window.as_secs().max(1)
+        );
+        if let Ok(value) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(header::SET_COOKIE, value);
+        }
+    }
+    response
+}
+
+/// Time until which the request's [`READ_PRIMARY_COOKIE`] pins reads to the primary
+fn primary_until(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| {
+            cookie
+                .trim()
+                .strip_prefix(READ_PRIMARY_COOKIE)?
+                .strip_prefix('=')
+        })
+        .find_map(|until| until.parse().ok())
+}
+
+/// Replication lag to cover after a write, from `DATABASE_READ_STICKY_SECS`
+fn sticky_window() -> Duration {
+    std::env::var("DATABASE_READ_STICKY_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_STICKY_WINDOW)
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// Replica state: skipped until `down_until` after a failure
+struct Health {
+    down_until: Mutex<Option<Instant>>,
+}
+
+impl Health {
+    const fn new() -> Self {
+        Self {
+            down_until: Mutex::new(None),
+        }
+    }
+
+    fn is_up(&self, now: Instant) -> bool {
+        let mut down_until = self.down_until.lock().unwrap_or_else(|e| e.into_inner());
+        match *down_until {
+            Some(until) if now < until => false,
+            Some(_) => {
+                *down_until = None;
+                true
+            }
+            None => true,
+        }
+    }
+
+    fn mark_down(&self, now: Instant) {
+        *self.down_until.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some(now + REPLICA_RETRY_AFTER);
+    }
+
+    fn mark_up(&self) {
+        *self.down_until.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
 }
 
 /// Legacy function for compatibility with existing code.
-/// Creates a new pool - use init_pool() and get_pool() instead for better performance.
-#[deprecated(note = "Use init_pool() at startup and get_pool() to access the pool")]
+/// Creates a new pool - use get_connection() instead for better performance.
+#[deprecated(note = "Use get_connection(), which shares one lazily created pool")]
 #[allow(dead_code)]
 pub fn create_pool() -> Result<DbPool> {
     let url = std::env::var("DATABASE_URL").context("DATABASE_URL env var not set")?;
@@ -50,3 +421,86 @@ pub fn create_pool() -> Result<DbPool> {
         .build(manager)
         .context("failed to build r2d2 pool")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connecting_gives_up() {
+        let mut calls = 0;
+        let result: Result<(), _> = with_backoff(
+            "Database connection",
+            CONNECT_ATTEMPTS,
+            Duration::from_millis(1),
+            |_| true,
+            || {
+                calls += 1;
+                Err("down")
+            },
+        );
+
+        assert_eq!(result, Err("down"));
+        assert_eq!(calls, CONNECT_ATTEMPTS);
+    }
+
+    #[test]
+    fn test_sqlite_path() {
+        assert_eq!(sqlite_path("sqlite://data/tasks.db"), Some("data/tasks.db"));
+        assert_eq!(sqlite_path("sqlite:/tmp/tasks.db"), Some("/tmp/tasks.db"));
+        assert_eq!(sqlite_path("sqlite::memory:"), Some(":memory:"));
+        assert_eq!(sqlite_path("postgres://localhost/app"), None);
+    }
+
+    #[tokio::test]
+    async fn test_requests_stick_to_primary_after_writing() {
+        assert!(reads_use_primary());
+
+        route_reads(false, async {
+            assert!(!reads_use_primary());
+            stick_to_primary();
+            assert!(reads_use_primary());
+        })
+        .await;
+        route_reads(false, async { assert!(!reads_use_primary()) }).await;
+        route_reads(true, async { assert!(reads_use_primary()) }).await;
+    }
+
+    #[test]
+    fn test_unhealthy_replica_is_skipped_for_a_while() {
+        let health = Health::new();
+        let now = Instant::now();
+        assert!(health.is_up(now));
+
+        health.mark_down(now);
+        assert!(!health.is_up(now + Duration::from_secs(1)));
+        assert!(health.is_up(now + REPLICA_RETRY_AFTER));
+        assert!(health.is_up(now));
+    }
+
+    #[test]
+    fn test_primary_until_reads_the_cookie() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(primary_until(&headers), None);
+
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("theme=dark; read_primary_until=1700000000000"),
+        );
+        assert_eq!(primary_until(&headers), Some(1_700_000_000_000));
+
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("read_primary_until_x=1; read_primary_until=soon"),
+        );
+        assert_eq!(primary_until(&headers), None);
+    }
+
+    #[test]
+    fn test_lambda_profile_stays_small() {
+        let profile = PoolProfile::lambda();
+        assert!((1..=2).contains(&profile.max_size));
+        assert_eq!(profile.min_idle, Some(0));
+        assert!(profile.connection_timeout < PoolProfile::server().connection_timeout);
+    }
+}
@@ -1,11 +1,45 @@
-use diesel::result::Error as DieselError;
+use diesel::result::{DatabaseErrorInformation, DatabaseErrorKind, Error as DieselError};
 use std::fmt;
 
 #[derive(Debug, Clone)]
 pub enum RepositoryError {
     NotFound,
     Duplicate,
+    /// A written row references a row that does not exist
+    InvalidReference(String),
+    /// A row cannot be changed or removed while other rows reference it
+    StillReferenced(String),
+    /// A written row breaks a check constraint
+    CheckViolation(String),
+    /// The transaction lost a serialization conflict or deadlock; retryable
+    Serialization(String),
+    /// The connection dropped while the statement ran; retryable
+    ConnectionLost(String),
     Database(String),
+    /// No connection to the database could be made
+    Unavailable(String),
+}
+
+impl RepositoryError {
+    /// Whether running the same operation again may succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            RepositoryError::Serialization(_) | RepositoryError::ConnectionLost(_)
+        )
+    }
+
+    /// Report a missing row as `not_found(msg)`, and any other error as usual
+    ///
+    /// For lookups of rows the request refers to, e.g.
+    /// `.map_err(|e| e.not_found_as("Project not found"))`, without turning
+    /// an outage or a retryable conflict into a 404.
+    pub fn not_found_as(self, msg: &str) -> crate::error::AppError {
+        match self {
+            RepositoryError::NotFound => crate::error::AppError::not_found(msg),
+            other => other.into(),
+        }
+    }
 }
 
 impl fmt::Display for RepositoryError {
@@ -13,7 +47,13 @@ impl fmt::Display for RepositoryError {
         match self {
             RepositoryError::NotFound => write!(f, "Resource not found"),
             RepositoryError::Duplicate => write!(f, "Resource already exists"),
+            RepositoryError::InvalidReference(msg) => write!(f, "Invalid reference: {}", msg),
+            RepositoryError::StillReferenced(msg) => write!(f, "Still referenced: {}", msg),
+            RepositoryError::CheckViolation(msg) => write!(f, "Check violation: {}", msg),
+            RepositoryError::Serialization(msg) => write!(f, "Serialization failure: {}", msg),
+            RepositoryError::ConnectionLost(msg) => write!(f, "Connection lost: {}", msg),
             RepositoryError::Database(msg) => write!(f, "Database error: {}", msg),
+            RepositoryError::Unavailable(msg) => write!(f, "Database unavailable: {}", msg),
         }
     }
 }
@@ -24,13 +64,41 @@ impl std::error::Error for RepositoryError {}
 pub fn map_diesel_error(err: DieselError) -> RepositoryError {
     match err {
         DieselError::NotFound => RepositoryError::NotFound,
-        DieselError::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _) => {
-            RepositoryError::Duplicate
-        }
+        DieselError::DatabaseError(kind, info) => map_database_error(kind, &*info),
         _ => RepositoryError::Database(err.to_string()),
     }
 }
 
+/// Classifies an error reported by the database by its kind
+fn map_database_error(
+    kind: DatabaseErrorKind,
+    info: &(dyn DatabaseErrorInformation + Send + Sync),
+) -> RepositoryError {
+    let message = info.message();
+    // Name the broken constraint where the backend reports it
+    let detail = || info.constraint_name().unwrap_or(message).to_string();
+
+    match kind {
+        DatabaseErrorKind::UniqueViolation => RepositoryError::Duplicate,
+        // Postgres reports both directions of a foreign key as the same kind
+        DatabaseErrorKind::ForeignKeyViolation if message.starts_with("update or delete on") => {
+            RepositoryError::StillReferenced(detail())
+        }
+        DatabaseErrorKind::ForeignKeyViolation => RepositoryError::InvalidReference(detail()),
+        DatabaseErrorKind::RestrictViolation => RepositoryError::StillReferenced(detail()),
+        DatabaseErrorKind::CheckViolation => RepositoryError::CheckViolation(detail()),
+        DatabaseErrorKind::SerializationFailure => {
+            RepositoryError::Serialization(message.to_string())
+        }
+        DatabaseErrorKind::ClosedConnection => RepositoryError::ConnectionLost(message.to_string()),
+        // Diesel has no kind for deadlocks (SQLSTATE 40P01)
+        _ if message.starts_with("deadlock detected") => {
+            RepositoryError::Serialization(message.to_string())
+        }
+        _ => RepositoryError::Database(message.to_string()),
+    }
+}
+
 impl From<DieselError> for RepositoryError {
     fn from(err: DieselError) -> Self {
         map_diesel_error(err)
@@ -39,12 +107,138 @@ impl From<DieselError> for RepositoryError {
 
 impl From<RepositoryError> for crate::error::AppError {
     fn from(err: RepositoryError) -> Self {
+        use crate::error::AppError;
+
         match err {
-            RepositoryError::NotFound => crate::error::AppError::not_found("Resource not found"),
-            RepositoryError::Duplicate => {
-                crate::error::AppError::duplicate("Resource already exists")
+            RepositoryError::NotFound => AppError::not_found("Resource not found"),
+            RepositoryError::Duplicate => AppError::duplicate("Resource already exists"),
+            RepositoryError::InvalidReference(constraint) => AppError::unprocessable(format!(
+                "A referenced resource does not exist ({})",
+                constraint
+            )),
+            RepositoryError::StillReferenced(constraint) => AppError::conflict(format!(
+                "The resource is still referenced by others ({})",
+                constraint
+            )),
+            RepositoryError::CheckViolation(constraint) => {
+                AppError::unprocessable(format!("The data breaks a constraint ({})", constraint))
+            }
+            RepositoryError::Serialization(msg) | RepositoryError::ConnectionLost(msg) => {
+                AppError::transient(msg)
             }
-            RepositoryError::Database(msg) => crate::error::AppError::database(msg),
+            RepositoryError::Database(msg) => AppError::database(msg),
+            RepositoryError::Unavailable(msg) => AppError::service_unavailable(msg),
+        }
+    }
+}
+
+impl From<DieselError> for crate::error::AppError {
+    fn from(err: DieselError) -> Self {
+        RepositoryError::from(err).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use axum::http::StatusCode;
+
+    struct Info {
+        message: &'static str,
+        constraint: Option<&'static str>,
+    }
+
+    impl DatabaseErrorInformation for Info {
+        fn message(&self) -> &str {
+            self.message
+        }
+        fn details(&self) -> Option<&str> {
+            None
+        }
+        fn hint(&self) -> Option<&str> {
+            None
+        }
+        fn table_name(&self) -> Option<&str> {
+            None
+        }
+        fn column_name(&self) -> Option<&str> {
+            None
+        }
+        fn constraint_name(&self) -> Option<&str> {
+            self.constraint
+        }
+        fn statement_position(&self) -> Option<i32> {
+            None
+        }
+    }
+
+    fn database_error(kind: DatabaseErrorKind, message: &'static str) -> RepositoryError {
+        let info = Info {
+            message,
+            constraint: Some("tasks_parent_id_fkey"),
+        };
+        map_diesel_error(DieselError::DatabaseError(kind, Box::new(info)))
+    }
+
+    #[test]
+    fn test_foreign_key_violations_by_direction() {
+        let insert = database_error(
+            DatabaseErrorKind::ForeignKeyViolation,
+            "insert or update on table \"tasks\" violates foreign key constraint",
+        );
+        assert!(
+            matches!(&insert, RepositoryError::InvalidReference(c) if c == "tasks_parent_id_fkey")
+        );
+        assert_eq!(
+            AppError::from(insert).status_code(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+
+        let delete = database_error(
+            DatabaseErrorKind::ForeignKeyViolation,
+            "update or delete on table \"task_statuses\" violates foreign key constraint",
+        );
+        assert!(matches!(delete, RepositoryError::StillReferenced(_)));
+        assert_eq!(AppError::from(delete).status_code(), StatusCode::CONFLICT);
+    }
+
+    #[test]
+    fn test_check_violation_is_unprocessable() {
+        let err = database_error(DatabaseErrorKind::CheckViolation, "violates check");
+        assert!(matches!(err, RepositoryError::CheckViolation(_)));
+        assert_eq!(
+            AppError::from(err).status_code(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    #[test]
+    fn test_only_missing_rows_become_not_found() {
+        let missing = RepositoryError::NotFound.not_found_as("Project not found");
+        assert!(matches!(missing, AppError::NotFound(ref msg) if msg == "Project not found"));
+
+        let outage = RepositoryError::Unavailable("down".to_string()).not_found_as("Project");
+        assert_eq!(outage.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        let conflict = database_error(DatabaseErrorKind::SerializationFailure, "conflict");
+        assert!(conflict.not_found_as("Project").is_retryable());
+    }
+
+    #[test]
+    fn test_transient_errors_are_retryable() {
+        let serialization = database_error(
+            DatabaseErrorKind::SerializationFailure,
+            "could not serialize access",
+        );
+        let deadlock = database_error(DatabaseErrorKind::Unknown, "deadlock detected");
+        let closed = database_error(DatabaseErrorKind::ClosedConnection, "server closed");
+        for err in [serialization, deadlock, closed] {
+            assert!(err.is_retryable(), "{}", err);
+            assert!(AppError::from(err).is_retryable());
         }
+
+        let other = database_error(DatabaseErrorKind::Unknown, "syntax error");
+        assert!(!other.is_retryable());
+        assert!(!RepositoryError::NotFound.is_retryable());
     }
 }
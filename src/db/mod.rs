@@ -2,4 +2,6 @@ pub mod connection;
 pub mod error;
 pub mod models;
 pub mod repositories;
+pub mod retry;
 pub mod schema;
+pub mod store;
@@ -0,0 +1,61 @@
+use axum_diesel_api::{CalendarComponent, CalendarFeedResponse};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::schema::calendar_feeds;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Identifiable)]
+#[diesel(table_name = calendar_feeds)]
+pub struct CalendarFeed {
+    pub id: Uuid,
+    pub name: String,
+    /// Secret part of the feed URL
+    pub token: String,
+    pub project_id: Option<Uuid>,
+    /// `todo` or `event`
+    pub component: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = calendar_feeds)]
+pub struct NewCalendarFeed {
+    pub name: String,
+    pub token: String,
+    pub project_id: Option<Uuid>,
+    pub component: String,
+}
+
+impl CalendarFeed {
+    pub fn component(&self) -> CalendarComponent {
+        match self.component.as_str() {
+            "event" => CalendarComponent::Event,
+            _ => CalendarComponent::Todo,
+        }
+    }
+
+    /// The response to the creation of the feed, the only one with its URL
+    pub fn into_created_response(self) -> CalendarFeedResponse {
+        let url = format!("/feeds/{}/tasks.ics", self.token);
+        CalendarFeedResponse {
+            url: Some(url),
+            ..self.into()
+        }
+    }
+}
+
+/// Leaves the URL out, see [`CalendarFeed::into_created_response`]
+impl From<CalendarFeed> for CalendarFeedResponse {
+    fn from(feed: CalendarFeed) -> Self {
+        Self {
+            id: feed.id,
+            component: feed.component(),
+            url: None,
+            name: feed.name,
+            project_id: feed.project_id,
+            created_at: feed.created_at,
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::db::schema::task_dependencies;
+
+/// Edge stating that `task_id` cannot be completed before `blocked_by_id`
+#[derive(Debug, Insertable)]
+#[diesel(table_name = task_dependencies)]
+pub struct NewTaskDependency {
+    pub task_id: Uuid,
+    pub blocked_by_id: Uuid,
+}
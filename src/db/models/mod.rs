@@ -1,3 +1,24 @@
+mod calendar_feed;
+mod dependency;
+mod outbox;
+mod priority;
+mod project;
+mod status;
+mod tag;
 mod task;
+mod task_deletion;
+mod webhook;
 
-pub use task::{NewTask, Task, UpdateTask};
+pub use calendar_feed::{CalendarFeed, NewCalendarFeed};
+pub use dependency::NewTaskDependency;
+pub use outbox::{NewOutboxEvent, OutboxEvent};
+pub use priority::Priority;
+pub use project::{NewProject, Project, ProjectTaskCounts, UpdateProject};
+pub use status::{NewTaskStatus, StatusTransition, TaskStatus, UpdateTaskStatus};
+pub use tag::{NewTag, NewTaskTag, Tag, TaskTag, UpdateTag};
+pub use task::{NewTask, Task, TaskNode, UpdateTask};
+pub use task_deletion::TaskDeletion;
+pub use webhook::{
+    DeliveryAttempt, NewDeliveryAttempt, NewWebhook, NewWebhookDelivery, UpdateWebhook, Webhook,
+    WebhookDelivery,
+};
@@ -0,0 +1,54 @@
+use axum_diesel_api::{TaskEventKind, TaskResponse, WebhookEvent};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::db::schema::outbox;
+
+/// A task event waiting to be sent to webhooks
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable)]
+#[diesel(table_name = outbox)]
+pub struct OutboxEvent {
+    pub id: Uuid,
+    /// `created`, `updated` or `deleted`
+    pub kind: String,
+    pub task_id: Uuid,
+    /// The `TaskResponse` at the time of the change
+    pub payload: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    /// Set once deliveries have been queued for every webhook
+    pub dispatched_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = outbox)]
+pub struct NewOutboxEvent {
+    pub kind: String,
+    pub task_id: Uuid,
+    pub payload: serde_json::Value,
+}
+
+/// Parse a stored event kind
+pub(crate) fn parse_event_kind(kind: &str) -> TaskEventKind {
+    match kind {
+        "created" => TaskEventKind::Created,
+        "deleted" => TaskEventKind::Deleted,
+        _ => TaskEventKind::Updated,
+    }
+}
+
+impl OutboxEvent {
+    pub fn kind(&self) -> TaskEventKind {
+        parse_event_kind(&self.kind)
+    }
+
+    /// Build the body sent to webhooks
+    pub fn to_webhook_event(&self) -> Result<WebhookEvent, serde_json::Error> {
+        Ok(WebhookEvent {
+            id: self.id,
+            kind: self.kind(),
+            occurred_at: self.created_at,
+            task: serde_json::from_value::<TaskResponse>(self.payload.clone())?,
+        })
+    }
+}
@@ -0,0 +1,71 @@
+use axum_diesel_api::TaskPriority;
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+use crate::db::schema::sql_types::TaskPriority as TaskPrioritySql;
+
+/// Task priority, stored as the `task_priority` Postgres enum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, AsExpression, FromSqlRow)]
+#[diesel(sql_type = TaskPrioritySql)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+
+impl ToSql<TaskPrioritySql, Pg> for Priority {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        let label: &[u8] = match self {
+            Priority::Low => b"low",
+            Priority::Medium => b"medium",
+            Priority::High => b"high",
+            Priority::Urgent => b"urgent",
+        };
+        out.write_all(label)?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<TaskPrioritySql, Pg> for Priority {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"low" => Ok(Priority::Low),
+            b"medium" => Ok(Priority::Medium),
+            b"high" => Ok(Priority::High),
+            b"urgent" => Ok(Priority::Urgent),
+            other => Err(format!(
+                "Unrecognized task_priority variant: {}",
+                String::from_utf8_lossy(other)
+            )
+            .into()),
+        }
+    }
+}
+
+impl From<TaskPriority> for Priority {
+    fn from(priority: TaskPriority) -> Self {
+        match priority {
+            TaskPriority::Low => Priority::Low,
+            TaskPriority::Medium => Priority::Medium,
+            TaskPriority::High => Priority::High,
+            TaskPriority::Urgent => Priority::Urgent,
+        }
+    }
+}
+
+impl From<Priority> for TaskPriority {
+    fn from(priority: Priority) -> Self {
+        match priority {
+            Priority::Low => TaskPriority::Low,
+            Priority::Medium => TaskPriority::Medium,
+            Priority::High => TaskPriority::High,
+            Priority::Urgent => TaskPriority::Urgent,
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use axum_diesel_api::ProjectResponse;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::schema::projects;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Identifiable)]
+#[diesel(table_name = projects)]
+pub struct Project {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = projects)]
+pub struct NewProject {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, AsChangeset)]
+#[diesel(table_name = projects)]
+pub struct UpdateProject {
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Task counts for a project, computed in SQL
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProjectTaskCounts {
+    pub total: i64,
+    pub completed: i64,
+}
+
+impl Project {
+    /// Build the API response for this project with its task counts
+    pub fn with_counts(self, counts: ProjectTaskCounts) -> ProjectResponse {
+        ProjectResponse {
+            id: self.id,
+            name: self.name,
+            description: self.description,
+            archived_at: self.archived_at,
+            task_count: counts.total,
+            completed_task_count: counts.completed,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+}
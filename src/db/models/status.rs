@@ -0,0 +1,62 @@
+use axum_diesel_api::StatusResponse;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::schema::{task_status_transitions, task_statuses};
+
+/// A workflow column; `project_id` is `None` for the default workflow
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Identifiable)]
+#[diesel(table_name = task_statuses)]
+pub struct TaskStatus {
+    pub id: Uuid,
+    pub project_id: Option<Uuid>,
+    pub name: String,
+    pub position: i32,
+    /// Tasks in this status are `completed`
+    pub is_done: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = task_statuses)]
+pub struct NewTaskStatus {
+    pub project_id: Option<Uuid>,
+    pub name: String,
+    pub position: i32,
+    pub is_done: bool,
+}
+
+#[derive(Debug, AsChangeset)]
+#[diesel(table_name = task_statuses)]
+pub struct UpdateTaskStatus {
+    pub name: Option<String>,
+    pub position: Option<i32>,
+    pub is_done: Option<bool>,
+}
+
+/// Edge allowing tasks to move from one status to another
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = task_status_transitions)]
+pub struct StatusTransition {
+    pub from_status_id: Uuid,
+    pub to_status_id: Uuid,
+}
+
+impl TaskStatus {
+    /// Build the API response with the statuses reachable from this one
+    pub fn with_transitions(self, transitions: Vec<Uuid>) -> StatusResponse {
+        StatusResponse {
+            id: self.id,
+            project_id: self.project_id,
+            name: self.name,
+            position: self.position,
+            done: self.is_done,
+            transitions,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+}
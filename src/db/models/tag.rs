@@ -0,0 +1,59 @@
+use axum_diesel_api::TagResponse;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::models::Task;
+use crate::db::schema::{tags, task_tags};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Identifiable)]
+#[diesel(table_name = tags)]
+pub struct Tag {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = tags)]
+pub struct NewTag {
+    pub name: String,
+}
+
+#[derive(Debug, AsChangeset)]
+#[diesel(table_name = tags)]
+pub struct UpdateTag {
+    pub name: Option<String>,
+}
+
+/// Join row linking a task to a tag
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Associations)]
+#[diesel(table_name = task_tags)]
+#[diesel(primary_key(task_id, tag_id))]
+#[diesel(belongs_to(Task))]
+#[diesel(belongs_to(Tag))]
+pub struct TaskTag {
+    pub task_id: Uuid,
+    pub tag_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = task_tags)]
+pub struct NewTaskTag {
+    pub task_id: Uuid,
+    pub tag_id: Uuid,
+}
+
+impl From<Tag> for TagResponse {
+    fn from(tag: Tag) -> Self {
+        Self {
+            id: tag.id,
+            name: tag.name,
+            created_at: tag.created_at,
+            updated_at: tag.updated_at,
+        }
+    }
+}
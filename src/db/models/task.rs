@@ -1,20 +1,58 @@
-use axum_diesel_api::TaskResponse;
+use axum_diesel_api::{TagResponse, TaskResponse};
 use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::db::models::{Priority, Project, Tag};
 use crate::db::schema::tasks;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Identifiable)]
+#[derive(
+    Debug,
+    Clone,
+    Serialize,
+    Deserialize,
+    Queryable,
+    QueryableByName,
+    Selectable,
+    Identifiable,
+    Associations,
+)]
 #[diesel(table_name = tasks)]
+#[diesel(belongs_to(Project))]
 pub struct Task {
     pub id: Uuid,
     pub title: String,
     pub description: Option<String>,
+    /// Derived from the status (`is_done`), kept for existing consumers
     pub completed: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub due_at: Option<DateTime<Utc>>,
+    pub priority: Priority,
+    /// Maintained by the `set_tasks_completed_at` trigger
+    pub completed_at: Option<DateTime<Utc>>,
+    pub project_id: Option<Uuid>,
+    /// Set when the parent project is archived
+    pub archived_at: Option<DateTime<Utc>>,
+    pub parent_id: Option<Uuid>,
+    /// Lexicographic rank for manual ordering (see `services::rank`)
+    pub position: String,
+    pub status_id: Uuid,
+    /// RRULE (see `services::recurrence`), expanded from `recurrence_start`
+    pub recurrence: Option<String>,
+    /// IANA time zone the rule is expanded in
+    pub recurrence_timezone: Option<String>,
+    pub recurrence_start: Option<DateTime<Utc>>,
+}
+
+/// A task loaded as part of a hierarchy, with its distance from the root
+#[derive(Debug, Clone, QueryableByName)]
+pub struct TaskNode {
+    #[diesel(embed)]
+    pub task: Task,
+    #[diesel(sql_type = diesel::sql_types::Integer)]
+    pub depth: i32,
 }
 
 #[derive(Debug, Insertable)]
@@ -23,25 +61,58 @@ pub struct NewTask {
     pub title: String,
     pub description: Option<String>,
     pub completed: bool,
+    pub due_at: Option<DateTime<Utc>>,
+    pub priority: Priority,
+    pub project_id: Option<Uuid>,
+    pub parent_id: Option<Uuid>,
+    pub position: String,
+    pub status_id: Uuid,
+    pub recurrence: Option<String>,
+    pub recurrence_timezone: Option<String>,
+    pub recurrence_start: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, AsChangeset)]
+#[derive(Debug, Default, AsChangeset)]
 #[diesel(table_name = tasks)]
 pub struct UpdateTask {
     pub title: Option<String>,
     pub description: Option<String>,
     pub completed: Option<bool>,
+    /// `Some(None)` clears the due date
+    pub due_at: Option<Option<DateTime<Utc>>>,
+    pub priority: Option<Priority>,
+    /// `Some(None)` moves the task out of its project
+    pub project_id: Option<Option<Uuid>>,
+    /// `Some(None)` promotes the task to the top level
+    pub parent_id: Option<Option<Uuid>>,
+    pub status_id: Option<Uuid>,
+    /// `Some(None)` stops the task from recurring
+    pub recurrence: Option<Option<String>>,
+    pub recurrence_timezone: Option<Option<String>>,
+    pub recurrence_start: Option<Option<DateTime<Utc>>>,
 }
 
-impl From<Task> for TaskResponse {
-    fn from(task: Task) -> Self {
-        Self {
-            id: task.id,
-            title: task.title,
-            description: task.description,
-            completed: task.completed,
-            created_at: task.created_at,
-            updated_at: task.updated_at,
+impl Task {
+    /// Build the API response for this task with its status name and tags
+    pub fn into_response(self, status: String, tags: Vec<Tag>) -> TaskResponse {
+        TaskResponse {
+            id: self.id,
+            title: self.title,
+            description: self.description,
+            completed: self.completed,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            due_at: self.due_at,
+            priority: self.priority.into(),
+            completed_at: self.completed_at,
+            project_id: self.project_id,
+            archived_at: self.archived_at,
+            parent_id: self.parent_id,
+            position: self.position,
+            status,
+            recurrence: self.recurrence,
+            recurrence_timezone: self.recurrence_timezone,
+            tags: tags.into_iter().map(TagResponse::from).collect(),
         }
     }
 }
@@ -0,0 +1,78 @@
+use axum_diesel_api::TaskResponse;
+use diesel::prelude::*;
+use serde::Deserialize;
+
+use crate::db::models::{Tag, Task};
+use crate::db::schema::task_deletions;
+
+/// Snapshot of a deleted task, written by the `notify_tasks_deleted` trigger
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = task_deletions)]
+pub struct TaskDeletion {
+    pub event_id: i64,
+    /// The task row with its `status` name and `tags` added
+    pub task: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct Snapshot {
+    #[serde(flatten)]
+    task: Task,
+    status: String,
+    tags: Vec<Tag>,
+}
+
+impl TaskDeletion {
+    /// Build the API response of the task as it was before the delete
+    pub fn into_response(self) -> Result<TaskResponse, serde_json::Error> {
+        let snapshot: Snapshot = serde_json::from_value(self.task)?;
+        Ok(snapshot.task.into_response(snapshot.status, snapshot.tags))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_into_response_from_trigger_snapshot() {
+        let task_id = Uuid::new_v4();
+        let deletion = TaskDeletion {
+            event_id: 7,
+            task: json!({
+                "id": task_id,
+                "title": "Gone",
+                "description": null,
+                "completed": false,
+                "created_at": "2026-10-18T20:24:00.435332+00:00",
+                "updated_at": "2026-10-18T20:24:00.435332+00:00",
+                "due_at": null,
+                "priority": "high",
+                "completed_at": null,
+                "project_id": null,
+                "archived_at": null,
+                "parent_id": null,
+                "position": "zz",
+                "status_id": Uuid::new_v4(),
+                "recurrence": null,
+                "recurrence_timezone": null,
+                "recurrence_start": null,
+                "status": "todo",
+                "tags": [{
+                    "id": Uuid::new_v4(),
+                    "name": "home",
+                    "created_at": "2026-10-18T20:24:00.37939+00:00",
+                    "updated_at": "2026-10-18T20:24:00.37939+00:00"
+                }]
+            }),
+        };
+
+        let response = deletion.into_response().unwrap();
+        assert_eq!(response.id, task_id);
+        assert_eq!(response.title, "Gone");
+        assert_eq!(response.status, "todo");
+        assert_eq!(response.tags[0].name, "home");
+    }
+}
@@ -0,0 +1,150 @@
+use axum_diesel_api::{
+    DeliveryAttemptResponse, DeliveryStatus, WebhookDeliveryResponse, WebhookResponse,
+};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::db::models::outbox::parse_event_kind;
+use crate::db::schema::{webhook_deliveries, webhook_delivery_attempts, webhooks};
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable)]
+#[diesel(table_name = webhooks)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub url: String,
+    pub description: Option<String>,
+    /// Key of the request signatures
+    pub secret: String,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = webhooks)]
+pub struct NewWebhook {
+    pub url: String,
+    pub description: Option<String>,
+    pub secret: String,
+    pub active: bool,
+}
+
+#[derive(Debug, AsChangeset)]
+#[diesel(table_name = webhooks)]
+pub struct UpdateWebhook {
+    pub url: Option<String>,
+    /// `Some(None)` clears the description
+    pub description: Option<Option<String>>,
+    pub active: Option<bool>,
+}
+
+/// Leaves the secret out, see [`Webhook::into_created_response`]
+impl From<Webhook> for WebhookResponse {
+    fn from(webhook: Webhook) -> Self {
+        Self {
+            id: webhook.id,
+            url: webhook.url,
+            description: webhook.description,
+            secret: None,
+            active: webhook.active,
+            created_at: webhook.created_at,
+            updated_at: webhook.updated_at,
+        }
+    }
+}
+
+impl Webhook {
+    /// The response to the creation of the webhook, the only one with its secret
+    pub fn into_created_response(self) -> WebhookResponse {
+        let secret = self.secret.clone();
+        WebhookResponse {
+            secret: Some(secret),
+            ..self.into()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Associations)]
+#[diesel(table_name = webhook_deliveries)]
+#[diesel(belongs_to(Webhook))]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub outbox_id: Uuid,
+    /// `pending`, `delivered` or `dead`
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = webhook_deliveries)]
+pub struct NewWebhookDelivery {
+    pub webhook_id: Uuid,
+    pub outbox_id: Uuid,
+}
+
+impl WebhookDelivery {
+    pub fn status(&self) -> DeliveryStatus {
+        match self.status.as_str() {
+            "delivered" => DeliveryStatus::Delivered,
+            "dead" => DeliveryStatus::Dead,
+            _ => DeliveryStatus::Pending,
+        }
+    }
+
+    /// Build the API response with the event kind and the attempt log
+    pub fn into_response(
+        self,
+        kind: &str,
+        attempts: Vec<DeliveryAttempt>,
+    ) -> WebhookDeliveryResponse {
+        let status = self.status();
+        WebhookDeliveryResponse {
+            id: self.id,
+            webhook_id: self.webhook_id,
+            event_id: self.outbox_id,
+            kind: parse_event_kind(kind),
+            status,
+            next_attempt_at: (status == DeliveryStatus::Pending).then_some(self.next_attempt_at),
+            delivered_at: self.delivered_at,
+            created_at: self.created_at,
+            attempts: attempts.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Associations)]
+#[diesel(table_name = webhook_delivery_attempts)]
+#[diesel(belongs_to(WebhookDelivery, foreign_key = delivery_id))]
+pub struct DeliveryAttempt {
+    pub id: Uuid,
+    pub delivery_id: Uuid,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: i32,
+    pub attempted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = webhook_delivery_attempts)]
+pub struct NewDeliveryAttempt {
+    pub delivery_id: Uuid,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: i32,
+}
+
+impl From<DeliveryAttempt> for DeliveryAttemptResponse {
+    fn from(attempt: DeliveryAttempt) -> Self {
+        Self {
+            status_code: attempt.status_code.map(|code| code as u16),
+            error: attempt.error,
+            duration_ms: attempt.duration_ms as u32,
+            attempted_at: attempt.attempted_at,
+        }
+    }
+}
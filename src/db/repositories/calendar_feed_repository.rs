@@ -0,0 +1,54 @@
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::db::connection::DbConnection;
+use crate::db::error::RepositoryError;
+use crate::db::models::{CalendarFeed, NewCalendarFeed};
+use crate::db::schema::calendar_feeds;
+
+pub struct CalendarFeedRepository;
+
+impl CalendarFeedRepository {
+    /// Find all feeds, oldest first
+    pub fn find_all(conn: &mut DbConnection) -> Result<Vec<CalendarFeed>, RepositoryError> {
+        calendar_feeds::table
+            .select(CalendarFeed::as_select())
+            .order(calendar_feeds::created_at.asc())
+            .load(conn)
+            .map_err(Into::into)
+    }
+
+    /// Find a feed by its secret token
+    pub fn find_by_token(
+        conn: &mut DbConnection,
+        token: &str,
+    ) -> Result<CalendarFeed, RepositoryError> {
+        calendar_feeds::table
+            .filter(calendar_feeds::token.eq(token))
+            .select(CalendarFeed::as_select())
+            .first(conn)
+            .map_err(Into::into)
+    }
+
+    /// Create a new feed
+    pub fn create(
+        conn: &mut DbConnection,
+        new_feed: NewCalendarFeed,
+    ) -> Result<CalendarFeed, RepositoryError> {
+        diesel::insert_into(calendar_feeds::table)
+            .values(&new_feed)
+            .returning(CalendarFeed::as_returning())
+            .get_result(conn)
+            .map_err(Into::into)
+    }
+
+    /// Delete a feed, revoking its URL
+    ///
+    /// Fails with `NotFound` if the feed does not exist.
+    pub fn delete(conn: &mut DbConnection, feed_id: Uuid) -> Result<(), RepositoryError> {
+        match diesel::delete(calendar_feeds::table.find(feed_id)).execute(conn)? {
+            0 => Err(RepositoryError::NotFound),
+            _ => Ok(()),
+        }
+    }
+}
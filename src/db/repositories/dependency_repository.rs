@@ -0,0 +1,112 @@
+use diesel::dsl::count;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::db::connection::DbConnection;
+use crate::db::error::RepositoryError;
+use crate::db::models::{NewTaskDependency, Task};
+use crate::db::schema::{task_dependencies, tasks};
+
+pub struct DependencyRepository;
+
+impl DependencyRepository {
+    /// Find the tasks blocking a task
+    pub fn find_blockers(
+        conn: &mut DbConnection,
+        task_id: Uuid,
+    ) -> Result<Vec<Task>, RepositoryError> {
+        tasks::table
+            .inner_join(task_dependencies::table.on(task_dependencies::blocked_by_id.eq(tasks::id)))
+            .filter(task_dependencies::task_id.eq(task_id))
+            .select(Task::as_select())
+            .order(tasks::created_at.asc())
+            .load(conn)
+            .map_err(Into::into)
+    }
+
+    /// Count the blockers of a task that are not completed yet
+    pub fn count_open_blockers(
+        conn: &mut DbConnection,
+        task_id: Uuid,
+    ) -> Result<i64, RepositoryError> {
+        tasks::table
+            .inner_join(task_dependencies::table.on(task_dependencies::blocked_by_id.eq(tasks::id)))
+            .filter(task_dependencies::task_id.eq(task_id))
+            .filter(tasks::completed.eq(false))
+            .select(count(tasks::id))
+            .first(conn)
+            .map_err(Into::into)
+    }
+
+    /// Find the dependency edges `(task_id, blocked_by_id)` between the given tasks
+    pub fn find_edges_among(
+        conn: &mut DbConnection,
+        task_ids: &[Uuid],
+    ) -> Result<Vec<(Uuid, Uuid)>, RepositoryError> {
+        task_dependencies::table
+            .filter(task_dependencies::task_id.eq_any(task_ids))
+            .filter(task_dependencies::blocked_by_id.eq_any(task_ids))
+            .select((task_dependencies::task_id, task_dependencies::blocked_by_id))
+            .load(conn)
+            .map_err(Into::into)
+    }
+
+    /// Record that `task_id` is blocked by `blocked_by_id` (no-op if already recorded)
+    pub fn add(
+        conn: &mut DbConnection,
+        task_id: Uuid,
+        blocked_by_id: Uuid,
+    ) -> Result<(), RepositoryError> {
+        diesel::insert_into(task_dependencies::table)
+            .values(&NewTaskDependency {
+                task_id,
+                blocked_by_id,
+            })
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    /// Remove a dependency edge
+    pub fn remove(
+        conn: &mut DbConnection,
+        task_id: Uuid,
+        blocked_by_id: Uuid,
+    ) -> Result<(), RepositoryError> {
+        diesel::delete(task_dependencies::table.find((task_id, blocked_by_id)))
+            .execute(conn)
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    /// Whether adding "`task_id` is blocked by `blocked_by_id`" would close a cycle,
+    /// i.e. `blocked_by_id` already depends (transitively) on `task_id`
+    pub fn would_create_cycle(
+        conn: &mut DbConnection,
+        task_id: Uuid,
+        blocked_by_id: Uuid,
+    ) -> Result<bool, RepositoryError> {
+        #[derive(QueryableByName)]
+        struct Found {
+            #[diesel(sql_type = diesel::sql_types::Bool)]
+            found: bool,
+        }
+
+        // UNION (not UNION ALL) terminates even on a graph that already has a cycle
+        diesel::sql_query(
+            "WITH RECURSIVE upstream AS ( \
+                 SELECT blocked_by_id AS id FROM task_dependencies WHERE task_id = $1 \
+                 UNION \
+                 SELECT dep.blocked_by_id \
+                 FROM task_dependencies dep JOIN upstream ON dep.task_id = upstream.id \
+             ) \
+             SELECT EXISTS (SELECT 1 FROM upstream WHERE id = $2) AS found",
+        )
+        .bind::<diesel::sql_types::Uuid, _>(blocked_by_id)
+        .bind::<diesel::sql_types::Uuid, _>(task_id)
+        .get_result::<Found>(conn)
+        .map(|row| row.found)
+        .map_err(Into::into)
+    }
+}
@@ -1,3 +1,17 @@
+pub mod calendar_feed_repository;
+pub mod dependency_repository;
+pub mod outbox_repository;
+pub mod project_repository;
+pub mod status_repository;
+pub mod tag_repository;
 pub mod task_repository;
+pub mod webhook_repository;
 
-pub use task_repository::TaskRepository;
+pub use calendar_feed_repository::CalendarFeedRepository;
+pub use dependency_repository::DependencyRepository;
+pub use outbox_repository::OutboxRepository;
+pub use project_repository::ProjectRepository;
+pub use status_repository::StatusRepository;
+pub use tag_repository::TagRepository;
+pub use task_repository::{TaskFilter, TaskRepository};
+pub use webhook_repository::WebhookRepository;
@@ -0,0 +1,121 @@
+use axum_diesel_api::{DeliveryStatus, TaskEventKind};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::db::connection::DbConnection;
+use crate::db::error::RepositoryError;
+use crate::db::models::{NewOutboxEvent, OutboxEvent, Task};
+use crate::db::repositories::TagRepository;
+use crate::db::schema::{outbox, task_statuses, webhook_deliveries};
+
+pub struct OutboxRepository;
+
+impl OutboxRepository {
+    /// Write one event per task, with the task as the API returns it
+    ///
+    /// Call it inside the transaction of the change, so that events are
+    /// written if and only if the change is committed.
+    pub fn record(
+        conn: &mut DbConnection,
+        kind: TaskEventKind,
+        tasks: &[Task],
+    ) -> Result<(), RepositoryError> {
+        if tasks.is_empty() {
+            return Ok(());
+        }
+
+        let tags = TagRepository::find_by_tasks(conn, tasks)?;
+        let status_ids: Vec<Uuid> = tasks.iter().map(|task| task.status_id).collect();
+        let status_names: HashMap<Uuid, String> = task_statuses::table
+            .filter(task_statuses::id.eq_any(&status_ids))
+            .select((task_statuses::id, task_statuses::name))
+            .load(conn)?
+            .into_iter()
+            .collect();
+
+        let events = tasks
+            .iter()
+            .zip(tags)
+            .map(|(task, tags)| {
+                let status = status_names
+                    .get(&task.status_id)
+                    .cloned()
+                    .unwrap_or_default();
+                let payload = serde_json::to_value(task.clone().into_response(status, tags))
+                    .map_err(|e| RepositoryError::Database(e.to_string()))?;
+                Ok(NewOutboxEvent {
+                    kind: kind.as_str().to_string(),
+                    task_id: task.id,
+                    payload,
+                })
+            })
+            .collect::<Result<Vec<_>, RepositoryError>>()?;
+
+        diesel::insert_into(outbox::table)
+            .values(&events)
+            .execute(conn)
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    /// Lock the oldest events not yet queued for delivery
+    ///
+    /// Rows locked by another dispatcher are skipped.
+    pub fn lock_undispatched(
+        conn: &mut DbConnection,
+        limit: i64,
+    ) -> Result<Vec<OutboxEvent>, RepositoryError> {
+        outbox::table
+            .filter(outbox::dispatched_at.is_null())
+            .order(outbox::created_at.asc())
+            .limit(limit)
+            .select(OutboxEvent::as_select())
+            .for_update()
+            .skip_locked()
+            .load(conn)
+            .map_err(Into::into)
+    }
+
+    /// Mark events as queued for delivery
+    pub fn mark_dispatched(
+        conn: &mut DbConnection,
+        event_ids: &[Uuid],
+    ) -> Result<(), RepositoryError> {
+        diesel::update(outbox::table.filter(outbox::id.eq_any(event_ids)))
+            .set(outbox::dispatched_at.eq(diesel::dsl::now))
+            .execute(conn)
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    /// Delete events dispatched before `cutoff` whose deliveries are all settled
+    ///
+    /// Their deliveries and attempt logs go with them.
+    pub fn prune(conn: &mut DbConnection, cutoff: DateTime<Utc>) -> Result<usize, RepositoryError> {
+        let pending = webhook_deliveries::table
+            .filter(webhook_deliveries::status.eq(DeliveryStatus::Pending.as_str()))
+            .select(webhook_deliveries::outbox_id);
+
+        diesel::delete(
+            outbox::table
+                .filter(outbox::dispatched_at.lt(cutoff))
+                .filter(outbox::id.ne_all(pending)),
+        )
+        .execute(conn)
+        .map_err(Into::into)
+    }
+
+    /// Find an event by ID
+    pub fn find_by_id(
+        conn: &mut DbConnection,
+        event_id: Uuid,
+    ) -> Result<OutboxEvent, RepositoryError> {
+        outbox::table
+            .find(event_id)
+            .select(OutboxEvent::as_select())
+            .first(conn)
+            .map_err(Into::into)
+    }
+}
@@ -0,0 +1,186 @@
+use axum_diesel_api::TaskEventKind;
+use chrono::{DateTime, Utc};
+use diesel::dsl::{count, now};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::db::connection::DbConnection;
+use crate::db::error::RepositoryError;
+use crate::db::models::{NewProject, Project, ProjectTaskCounts, Task, UpdateProject};
+use crate::db::repositories::{OutboxRepository, StatusRepository};
+use crate::db::schema::{projects, tasks};
+
+pub struct ProjectRepository;
+
+impl ProjectRepository {
+    /// Find all projects with their task counts, ordered by name
+    pub fn find_all(
+        conn: &mut DbConnection,
+        include_archived: bool,
+    ) -> Result<Vec<(Project, ProjectTaskCounts)>, RepositoryError> {
+        let mut query = projects::table
+            .left_join(tasks::table)
+            .group_by(projects::id)
+            .select((
+                Project::as_select(),
+                count(tasks::id.nullable()),
+                // A NULL predicate (no task joined) is not counted, like FALSE
+                count(tasks::id.nullable())
+                    .aggregate_filter(tasks::completed.nullable().eq(true).assume_not_null()),
+            ))
+            .order(projects::name.asc())
+            .into_boxed();
+
+        if !include_archived {
+            query = query.filter(projects::archived_at.is_null());
+        }
+
+        let rows: Vec<(Project, i64, i64)> = query.load(conn)?;
+        Ok(rows
+            .into_iter()
+            .map(|(project, total, completed)| (project, ProjectTaskCounts { total, completed }))
+            .collect())
+    }
+
+    /// Find a project by ID
+    pub fn find_by_id(
+        conn: &mut DbConnection,
+        project_id: Uuid,
+    ) -> Result<Project, RepositoryError> {
+        projects::table
+            .find(project_id)
+            .select(Project::as_select())
+            .first(conn)
+            .map_err(Into::into)
+    }
+
+    /// Count the tasks of a project
+    pub fn count_tasks(
+        conn: &mut DbConnection,
+        project_id: Uuid,
+    ) -> Result<ProjectTaskCounts, RepositoryError> {
+        let (total, completed) = tasks::table
+            .filter(tasks::project_id.eq(project_id))
+            .select((
+                count(tasks::id),
+                count(tasks::id).aggregate_filter(tasks::completed.eq(true)),
+            ))
+            .first(conn)?;
+
+        Ok(ProjectTaskCounts { total, completed })
+    }
+
+    /// Create a new project with a copy of the default workflow
+    pub fn create(
+        conn: &mut DbConnection,
+        new_project: NewProject,
+    ) -> Result<Project, RepositoryError> {
+        conn.transaction(|conn| {
+            let project: Project = diesel::insert_into(projects::table)
+                .values(&new_project)
+                .returning(Project::as_returning())
+                .get_result(conn)?;
+
+            StatusRepository::copy_default_workflow(conn, project.id)?;
+            Ok(project)
+        })
+    }
+
+    /// Update an existing project
+    pub fn update(
+        conn: &mut DbConnection,
+        project_id: Uuid,
+        update_project: UpdateProject,
+    ) -> Result<Project, RepositoryError> {
+        diesel::update(projects::table.find(project_id))
+            .set(&update_project)
+            .returning(Project::as_returning())
+            .get_result(conn)
+            .map_err(Into::into)
+    }
+
+    /// Delete a project (its tasks and statuses are removed by the foreign key cascade)
+    pub fn delete(conn: &mut DbConnection, project_id: Uuid) -> Result<(), RepositoryError> {
+        conn.transaction(|conn| {
+            let tasks: Vec<Task> = tasks::table
+                .filter(tasks::project_id.eq(project_id))
+                .select(Task::as_select())
+                .load(conn)?;
+            // Recorded first, while the tags are still attached
+            OutboxRepository::record(conn, TaskEventKind::Deleted, &tasks)?;
+
+            diesel::delete(projects::table.find(project_id))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(Into::into)
+        })
+    }
+
+    /// Find the IDs of the projects archived before `cutoff`
+    pub fn find_archived_before(
+        conn: &mut DbConnection,
+        cutoff: DateTime<Utc>,
+    ) -> Result<Vec<Uuid>, RepositoryError> {
+        projects::table
+            .filter(projects::archived_at.lt(cutoff))
+            .select(projects::id)
+            .load(conn)
+            .map_err(Into::into)
+    }
+
+    /// Archive a project and, in the same transaction, every task it contains
+    /// (with an outbox event per task)
+    ///
+    /// Tasks receive the project's `archived_at` so that unarchiving only
+    /// restores the tasks archived by this cascade.
+    pub fn archive(conn: &mut DbConnection, project_id: Uuid) -> Result<Project, RepositoryError> {
+        conn.transaction(|conn| {
+            let project: Project = diesel::update(
+                projects::table
+                    .find(project_id)
+                    .filter(projects::archived_at.is_null()),
+            )
+            .set(projects::archived_at.eq(now))
+            .returning(Project::as_returning())
+            .get_result(conn)?;
+
+            let archived: Vec<Task> = diesel::update(
+                tasks::table
+                    .filter(tasks::project_id.eq(project_id))
+                    .filter(tasks::archived_at.is_null()),
+            )
+            .set(tasks::archived_at.eq(project.archived_at))
+            .returning(Task::as_returning())
+            .get_results(conn)?;
+            OutboxRepository::record(conn, TaskEventKind::Updated, &archived)?;
+
+            Ok(project)
+        })
+    }
+
+    /// Unarchive a project and the tasks archived along with it
+    pub fn unarchive(
+        conn: &mut DbConnection,
+        project_id: Uuid,
+    ) -> Result<Project, RepositoryError> {
+        conn.transaction(|conn| {
+            let archived_at = Self::find_by_id(conn, project_id)?.archived_at;
+
+            let restored: Vec<Task> = diesel::update(
+                tasks::table
+                    .filter(tasks::project_id.eq(project_id))
+                    .filter(tasks::archived_at.eq(archived_at)),
+            )
+            .set(tasks::archived_at.eq(None::<chrono::DateTime<chrono::Utc>>))
+            .returning(Task::as_returning())
+            .get_results(conn)?;
+            OutboxRepository::record(conn, TaskEventKind::Updated, &restored)?;
+
+            diesel::update(projects::table.find(project_id))
+                .set(projects::archived_at.eq(None::<chrono::DateTime<chrono::Utc>>))
+                .returning(Project::as_returning())
+                .get_result(conn)
+                .map_err(Into::into)
+        })
+    }
+}
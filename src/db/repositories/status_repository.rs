@@ -0,0 +1,212 @@
+use axum_diesel_api::TaskEventKind;
+use diesel::dsl::{count, exists, max};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::db::connection::DbConnection;
+use crate::db::error::RepositoryError;
+use crate::db::models::{NewTaskStatus, StatusTransition, Task, TaskStatus, UpdateTaskStatus};
+use crate::db::repositories::OutboxRepository;
+use crate::db::schema::{task_status_transitions, task_statuses, tasks};
+
+pub struct StatusRepository;
+
+impl StatusRepository {
+    /// Find the statuses of a workflow in column order (`None` is the default workflow)
+    pub fn find_workflow(
+        conn: &mut DbConnection,
+        project_id: Option<Uuid>,
+    ) -> Result<Vec<TaskStatus>, RepositoryError> {
+        task_statuses::table
+            .filter(task_statuses::project_id.is_not_distinct_from(project_id))
+            .select(TaskStatus::as_select())
+            .order((task_statuses::position.asc(), task_statuses::name.asc()))
+            .load(conn)
+            .map_err(Into::into)
+    }
+
+    /// Find a status by ID
+    pub fn find_by_id(
+        conn: &mut DbConnection,
+        status_id: Uuid,
+    ) -> Result<TaskStatus, RepositoryError> {
+        task_statuses::table
+            .find(status_id)
+            .select(TaskStatus::as_select())
+            .first(conn)
+            .map_err(Into::into)
+    }
+
+    /// Find several statuses by ID
+    pub fn find_by_ids(
+        conn: &mut DbConnection,
+        status_ids: &[Uuid],
+    ) -> Result<Vec<TaskStatus>, RepositoryError> {
+        task_statuses::table
+            .filter(task_statuses::id.eq_any(status_ids))
+            .select(TaskStatus::as_select())
+            .load(conn)
+            .map_err(Into::into)
+    }
+
+    /// Find a status of a workflow by name
+    pub fn find_by_name(
+        conn: &mut DbConnection,
+        project_id: Option<Uuid>,
+        name: &str,
+    ) -> Result<TaskStatus, RepositoryError> {
+        task_statuses::table
+            .filter(task_statuses::project_id.is_not_distinct_from(project_id))
+            .filter(task_statuses::name.eq(name))
+            .select(TaskStatus::as_select())
+            .first(conn)
+            .map_err(Into::into)
+    }
+
+    /// Highest column position of a workflow, if it has any status
+    pub fn max_position(
+        conn: &mut DbConnection,
+        project_id: Option<Uuid>,
+    ) -> Result<Option<i32>, RepositoryError> {
+        task_statuses::table
+            .filter(task_statuses::project_id.is_not_distinct_from(project_id))
+            .select(max(task_statuses::position))
+            .first(conn)
+            .map_err(Into::into)
+    }
+
+    /// Count the tasks currently in a status
+    pub fn count_tasks(conn: &mut DbConnection, status_id: Uuid) -> Result<i64, RepositoryError> {
+        tasks::table
+            .filter(tasks::status_id.eq(status_id))
+            .select(count(tasks::id))
+            .first(conn)
+            .map_err(Into::into)
+    }
+
+    /// Create a new status
+    pub fn create(
+        conn: &mut DbConnection,
+        new_status: NewTaskStatus,
+    ) -> Result<TaskStatus, RepositoryError> {
+        diesel::insert_into(task_statuses::table)
+            .values(&new_status)
+            .returning(TaskStatus::as_returning())
+            .get_result(conn)
+            .map_err(Into::into)
+    }
+
+    /// Update a status and, in the same transaction, the `completed` flag of
+    /// its tasks when `is_done` changes (with an outbox event per changed task)
+    pub fn update(
+        conn: &mut DbConnection,
+        status_id: Uuid,
+        update_status: UpdateTaskStatus,
+    ) -> Result<TaskStatus, RepositoryError> {
+        conn.transaction(|conn| {
+            let status: TaskStatus = diesel::update(task_statuses::table.find(status_id))
+                .set(&update_status)
+                .returning(TaskStatus::as_returning())
+                .get_result(conn)?;
+
+            if let Some(is_done) = update_status.is_done {
+                let changed: Vec<Task> = diesel::update(
+                    tasks::table
+                        .filter(tasks::status_id.eq(status_id))
+                        .filter(tasks::completed.ne(is_done)),
+                )
+                .set(tasks::completed.eq(is_done))
+                .returning(Task::as_returning())
+                .get_results(conn)?;
+                OutboxRepository::record(conn, TaskEventKind::Updated, &changed)?;
+            }
+
+            Ok(status)
+        })
+    }
+
+    /// Delete a status (its transitions are removed by the foreign key cascade)
+    pub fn delete(conn: &mut DbConnection, status_id: Uuid) -> Result<(), RepositoryError> {
+        diesel::delete(task_statuses::table.find(status_id))
+            .execute(conn)
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    /// Find the transitions leaving any of the given statuses
+    pub fn find_transitions(
+        conn: &mut DbConnection,
+        status_ids: &[Uuid],
+    ) -> Result<Vec<StatusTransition>, RepositoryError> {
+        task_status_transitions::table
+            .filter(task_status_transitions::from_status_id.eq_any(status_ids))
+            .select(StatusTransition::as_select())
+            .load(conn)
+            .map_err(Into::into)
+    }
+
+    /// Whether tasks may move from one status to another
+    pub fn is_transition_allowed(
+        conn: &mut DbConnection,
+        from_status_id: Uuid,
+        to_status_id: Uuid,
+    ) -> Result<bool, RepositoryError> {
+        diesel::select(exists(
+            task_status_transitions::table.find((from_status_id, to_status_id)),
+        ))
+        .get_result(conn)
+        .map_err(Into::into)
+    }
+
+    /// Allow a transition (no-op if already allowed)
+    pub fn add_transition(
+        conn: &mut DbConnection,
+        transition: StatusTransition,
+    ) -> Result<(), RepositoryError> {
+        diesel::insert_into(task_status_transitions::table)
+            .values(&transition)
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    /// Forbid a transition (no-op if not allowed)
+    pub fn remove_transition(
+        conn: &mut DbConnection,
+        from_status_id: Uuid,
+        to_status_id: Uuid,
+    ) -> Result<(), RepositoryError> {
+        diesel::delete(task_status_transitions::table.find((from_status_id, to_status_id)))
+            .execute(conn)
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    /// Copy the default workflow, statuses and transitions, into a project
+    pub fn copy_default_workflow(
+        conn: &mut DbConnection,
+        project_id: Uuid,
+    ) -> Result<(), RepositoryError> {
+        diesel::sql_query(
+            "WITH defaults AS ( \
+                 SELECT * FROM task_statuses WHERE project_id IS NULL \
+             ), copies AS ( \
+                 INSERT INTO task_statuses (project_id, name, position, is_done) \
+                 SELECT $1, name, position, is_done FROM defaults \
+                 RETURNING id, name \
+             ) \
+             INSERT INTO task_status_transitions (from_status_id, to_status_id) \
+             SELECT cf.id, ct.id \
+             FROM task_status_transitions tr \
+             JOIN defaults f ON f.id = tr.from_status_id \
+             JOIN defaults t ON t.id = tr.to_status_id \
+             JOIN copies cf ON cf.name = f.name \
+             JOIN copies ct ON ct.name = t.name",
+        )
+        .bind::<diesel::sql_types::Uuid, _>(project_id)
+        .execute(conn)
+        .map(|_| ())
+        .map_err(Into::into)
+    }
+}
@@ -0,0 +1,153 @@
+use axum_diesel_api::TaskEventKind;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::db::connection::DbConnection;
+use crate::db::error::RepositoryError;
+use crate::db::models::{NewTag, NewTaskTag, Tag, Task, TaskTag, UpdateTag};
+use crate::db::repositories::OutboxRepository;
+use crate::db::schema::{tags, task_tags, tasks};
+
+pub struct TagRepository;
+
+impl TagRepository {
+    /// Find all tags, ordered by name
+    pub fn find_all(conn: &mut DbConnection) -> Result<Vec<Tag>, RepositoryError> {
+        tags::table
+            .select(Tag::as_select())
+            .order(tags::name.asc())
+            .load(conn)
+            .map_err(Into::into)
+    }
+
+    /// Find a tag by ID
+    pub fn find_by_id(conn: &mut DbConnection, tag_id: Uuid) -> Result<Tag, RepositoryError> {
+        tags::table
+            .find(tag_id)
+            .select(Tag::as_select())
+            .first(conn)
+            .map_err(Into::into)
+    }
+
+    /// Create a new tag
+    pub fn create(conn: &mut DbConnection, new_tag: NewTag) -> Result<Tag, RepositoryError> {
+        diesel::insert_into(tags::table)
+            .values(&new_tag)
+            .returning(Tag::as_returning())
+            .get_result(conn)
+            .map_err(Into::into)
+    }
+
+    /// Find a tag by name, creating it if missing
+    pub fn find_or_create(conn: &mut DbConnection, name: &str) -> Result<Tag, RepositoryError> {
+        diesel::insert_into(tags::table)
+            .values(&NewTag {
+                name: name.to_string(),
+            })
+            .on_conflict(tags::name)
+            .do_nothing()
+            .execute(conn)?;
+
+        tags::table
+            .filter(tags::name.eq(name))
+            .select(Tag::as_select())
+            .first(conn)
+            .map_err(Into::into)
+    }
+
+    /// Update an existing tag
+    pub fn update(
+        conn: &mut DbConnection,
+        tag_id: Uuid,
+        update_tag: UpdateTag,
+    ) -> Result<Tag, RepositoryError> {
+        diesel::update(tags::table.find(tag_id))
+            .set(&update_tag)
+            .returning(Tag::as_returning())
+            .get_result(conn)
+            .map_err(Into::into)
+    }
+
+    /// Delete a tag (task links are removed by the foreign key cascade)
+    pub fn delete(conn: &mut DbConnection, tag_id: Uuid) -> Result<(), RepositoryError> {
+        diesel::delete(tags::table.find(tag_id))
+            .execute(conn)
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    /// Load the tags of several tasks with a single query
+    ///
+    /// The result is aligned with `tasks`: entry `i` holds the tags of `tasks[i]`.
+    pub fn find_by_tasks(
+        conn: &mut DbConnection,
+        tasks: &[Task],
+    ) -> Result<Vec<Vec<Tag>>, RepositoryError> {
+        let links: Vec<(TaskTag, Tag)> = TaskTag::belonging_to(tasks)
+            .inner_join(tags::table)
+            .select((TaskTag::as_select(), Tag::as_select()))
+            .order(tags::name.asc())
+            .load(conn)?;
+
+        Ok(links
+            .grouped_by(tasks)
+            .into_iter()
+            .map(|group| group.into_iter().map(|(_, tag)| tag).collect())
+            .collect())
+    }
+
+    /// Attach a tag to a task (no-op if already attached)
+    ///
+    /// Fails with `NotFound` if the task or the tag does not exist.
+    pub fn attach(
+        conn: &mut DbConnection,
+        task_id: Uuid,
+        tag_id: Uuid,
+    ) -> Result<(), RepositoryError> {
+        conn.transaction(|conn| {
+            let attached = diesel::insert_into(task_tags::table)
+                .values(&NewTaskTag { task_id, tag_id })
+                .on_conflict_do_nothing()
+                .execute(conn)
+                .map_err(|e| match RepositoryError::from(e) {
+                    RepositoryError::InvalidReference(_) => RepositoryError::NotFound,
+                    other => other,
+                })?;
+            Self::record_task_update(conn, task_id, attached)
+        })
+    }
+
+    /// Detach a tag from a task
+    ///
+    /// Fails with `NotFound` if the tag was not attached to the task.
+    pub fn detach(
+        conn: &mut DbConnection,
+        task_id: Uuid,
+        tag_id: Uuid,
+    ) -> Result<(), RepositoryError> {
+        conn.transaction(|conn| {
+            let detached =
+                diesel::delete(task_tags::table.find((task_id, tag_id))).execute(conn)?;
+            if detached == 0 {
+                return Err(RepositoryError::NotFound);
+            }
+            Self::record_task_update(conn, task_id, detached)
+        })
+    }
+
+    /// Write the outbox event of a task whose tags changed
+    fn record_task_update(
+        conn: &mut DbConnection,
+        task_id: Uuid,
+        changed_rows: usize,
+    ) -> Result<(), RepositoryError> {
+        if changed_rows == 0 {
+            return Ok(());
+        }
+        let task = tasks::table
+            .find(task_id)
+            .select(Task::as_select())
+            .first(conn)?;
+        OutboxRepository::record(conn, TaskEventKind::Updated, &[task])
+    }
+}
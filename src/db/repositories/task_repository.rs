@@ -1,22 +1,217 @@
+use axum_diesel_api::TaskEventKind;
+use chrono::{DateTime, Utc};
+use diesel::dsl::{count, now, sql};
+use diesel::expression::SqlLiteral;
+use diesel::pg::Pg;
 use diesel::prelude::*;
+use diesel::sql_types::{Nullable, Text};
 use uuid::Uuid;
 
 use crate::db::connection::DbConnection;
 use crate::db::error::RepositoryError;
-use crate::db::models::{NewTask, Task, UpdateTask};
-use crate::db::schema::tasks;
+use crate::db::models::{NewTask, Priority, Task, TaskDeletion, TaskNode, UpdateTask};
+use crate::db::repositories::OutboxRepository;
+use crate::db::schema::{tags, task_deletions, task_statuses, task_tags, tasks};
+
+/// Filters applied when listing tasks
+#[derive(Debug, Default)]
+pub struct TaskFilter {
+    /// Tag names to filter on (empty means no tag filter)
+    pub tags: Vec<String>,
+    /// Require every tag instead of at least one
+    pub match_all_tags: bool,
+    /// Open tasks past their due date (`Some(true)`) or every other task (`Some(false)`)
+    pub overdue: Option<bool>,
+    pub due_before: Option<DateTime<Utc>>,
+    pub priority: Option<Priority>,
+    pub project_id: Option<Uuid>,
+    /// Include tasks archived along with their project
+    pub include_archived: bool,
+    pub completed: Option<bool>,
+    /// Status name (matched in every workflow)
+    pub status: Option<String>,
+    /// Only tasks with a due date
+    pub has_due_at: bool,
+}
+
+/// Task columns with `description` replaced by `NULL`, in `Task` field order
+type ColumnsWithoutDescription = (
+    tasks::id,
+    tasks::title,
+    SqlLiteral<Nullable<Text>>,
+    tasks::completed,
+    tasks::created_at,
+    tasks::updated_at,
+    tasks::due_at,
+    tasks::priority,
+    tasks::completed_at,
+    tasks::project_id,
+    tasks::archived_at,
+    tasks::parent_id,
+    tasks::position,
+    tasks::status_id,
+    tasks::recurrence,
+    tasks::recurrence_timezone,
+    tasks::recurrence_start,
+);
+
+/// Select list loading tasks without their (possibly large) description
+fn columns_without_description() -> ColumnsWithoutDescription {
+    (
+        tasks::id,
+        tasks::title,
+        sql::<Nullable<Text>>("NULL"),
+        tasks::completed,
+        tasks::created_at,
+        tasks::updated_at,
+        tasks::due_at,
+        tasks::priority,
+        tasks::completed_at,
+        tasks::project_id,
+        tasks::archived_at,
+        tasks::parent_id,
+        tasks::position,
+        tasks::status_id,
+        tasks::recurrence,
+        tasks::recurrence_timezone,
+        tasks::recurrence_start,
+    )
+}
+
+#[derive(QueryableByName)]
+struct PositionLen {
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Integer>)]
+    len: Option<i32>,
+}
 
 pub struct TaskRepository;
 
 impl TaskRepository {
-    /// Find all tasks
-    pub fn find_all(conn: &mut DbConnection) -> Result<Vec<Task>, RepositoryError> {
-        tasks::table
+    /// Find tasks matching a filter
+    ///
+    /// Without `with_description`, descriptions are not read and come back as `None`.
+    pub fn find_filtered(
+        conn: &mut DbConnection,
+        filter: &TaskFilter,
+        with_description: bool,
+    ) -> Result<Vec<Task>, RepositoryError> {
+        let query = Self::filtered(filter).order((tasks::position.asc(), tasks::created_at.asc()));
+
+        if with_description {
+            query.select(Task::as_select()).load(conn)
+        } else {
+            query.select(columns_without_description()).load(conn)
+        }
+        .map_err(Into::into)
+    }
+
+    /// Find the next page of tasks matching a filter, in `(position, id)` order
+    ///
+    /// `after` is the `(position, id)` of the last task of the previous page.
+    /// Unlike an offset, the cursor stays valid while tasks are added.
+    pub fn find_page(
+        conn: &mut DbConnection,
+        filter: &TaskFilter,
+        after: Option<(&str, Uuid)>,
+        limit: i64,
+    ) -> Result<Vec<Task>, RepositoryError> {
+        let mut query = Self::filtered(filter);
+
+        if let Some((position, id)) = after {
+            query = query.filter(
+                tasks::position
+                    .gt(position)
+                    .or(tasks::position.eq(position).and(tasks::id.gt(id))),
+            );
+        }
+
+        query
             .select(Task::as_select())
+            .order((tasks::position.asc(), tasks::id.asc()))
+            .limit(limit)
             .load(conn)
             .map_err(Into::into)
     }
 
+    /// Boxed query over the tasks matching a filter
+    fn filtered(filter: &TaskFilter) -> tasks::BoxedQuery<'_, Pg> {
+        let mut query = tasks::table.into_boxed();
+
+        if !filter.tags.is_empty() {
+            let tagged = task_tags::table
+                .inner_join(tags::table)
+                .filter(tags::name.eq_any(&filter.tags))
+                .select(task_tags::task_id);
+
+            query = if filter.match_all_tags {
+                // (task_id, tag_id) is the primary key, so each tag counts once per task
+                let required = filter.tags.len() as i64;
+                query.filter(
+                    tasks::id.eq_any(
+                        tagged
+                            .group_by(task_tags::task_id)
+                            .having(count(task_tags::tag_id).eq(required)),
+                    ),
+                )
+            } else {
+                query.filter(tasks::id.eq_any(tagged))
+            };
+        }
+
+        match filter.overdue {
+            Some(true) => {
+                query = query
+                    .filter(tasks::completed.eq(false))
+                    .filter(tasks::due_at.lt(now));
+            }
+            Some(false) => {
+                query = query.filter(
+                    tasks::completed
+                        .eq(true)
+                        .or(tasks::due_at.is_null())
+                        .or(tasks::due_at.ge(now)),
+                );
+            }
+            None => {}
+        }
+
+        if filter.has_due_at {
+            query = query.filter(tasks::due_at.is_not_null());
+        }
+
+        if let Some(due_before) = filter.due_before {
+            query = query.filter(tasks::due_at.lt(due_before));
+        }
+
+        if let Some(priority) = filter.priority {
+            query = query.filter(tasks::priority.eq(priority));
+        }
+
+        if let Some(project_id) = filter.project_id {
+            query = query.filter(tasks::project_id.eq(project_id));
+        }
+
+        if !filter.include_archived {
+            query = query.filter(tasks::archived_at.is_null());
+        }
+
+        if let Some(completed) = filter.completed {
+            query = query.filter(tasks::completed.eq(completed));
+        }
+
+        if let Some(ref status) = filter.status {
+            query = query.filter(
+                tasks::status_id.eq_any(
+                    task_statuses::table
+                        .filter(task_statuses::name.eq(status))
+                        .select(task_statuses::id),
+                ),
+            );
+        }
+
+        query
+    }
+
     /// Find a task by ID
     pub fn find_by_id(conn: &mut DbConnection, task_id: Uuid) -> Result<Task, RepositoryError> {
         tasks::table
@@ -26,13 +221,118 @@ impl TaskRepository {
             .map_err(Into::into)
     }
 
+    /// Highest rank in use, if any task exists
+    pub fn max_position(conn: &mut DbConnection) -> Result<Option<String>, RepositoryError> {
+        tasks::table
+            .select(diesel::dsl::max(tasks::position))
+            .first(conn)
+            .map_err(Into::into)
+    }
+
+    /// Rank of the first task ranked after `position`, ignoring `exclude`
+    pub fn next_position(
+        conn: &mut DbConnection,
+        position: &str,
+        exclude: Uuid,
+    ) -> Result<Option<String>, RepositoryError> {
+        tasks::table
+            .filter(tasks::position.gt(position))
+            .filter(tasks::id.ne(exclude))
+            .select(diesel::dsl::min(tasks::position))
+            .first(conn)
+            .map_err(Into::into)
+    }
+
+    /// Rank of the last task ranked before `position`, ignoring `exclude`
+    pub fn previous_position(
+        conn: &mut DbConnection,
+        position: &str,
+        exclude: Uuid,
+    ) -> Result<Option<String>, RepositoryError> {
+        tasks::table
+            .filter(tasks::position.lt(position))
+            .filter(tasks::id.ne(exclude))
+            .select(diesel::dsl::max(tasks::position))
+            .first(conn)
+            .map_err(Into::into)
+    }
+
+    /// Length of the longest rank, used to decide whether to rebalance
+    pub fn max_position_len(conn: &mut DbConnection) -> Result<Option<i32>, RepositoryError> {
+        diesel::sql_query("SELECT max(length(position)) AS len FROM tasks")
+            .get_result::<PositionLen>(conn)
+            .map(|row| row.len)
+            .map_err(Into::into)
+    }
+
+    /// Set the rank of a single task
+    pub fn set_position(
+        conn: &mut DbConnection,
+        task_id: Uuid,
+        position: &str,
+    ) -> Result<Task, RepositoryError> {
+        conn.transaction(|conn| {
+            let task = diesel::update(tasks::table.find(task_id))
+                .set(tasks::position.eq(position))
+                .returning(Task::as_returning())
+                .get_result(conn)?;
+            OutboxRepository::record(conn, TaskEventKind::Updated, std::slice::from_ref(&task))?;
+            Ok(task)
+        })
+    }
+
+    /// Rewrite every rank evenly spaced, keeping the current order
+    ///
+    /// Uses the same scheme as the migration backfill. Returns the number of
+    /// tasks updated. The order does not change, so no outbox events are written.
+    pub fn rebalance_positions(conn: &mut DbConnection) -> Result<usize, RepositoryError> {
+        diesel::sql_query(
+            "UPDATE tasks SET position = ranked.position \
+             FROM ( \
+                 SELECT id, lpad(to_hex(row_number() OVER ( \
+                     ORDER BY position, created_at, id)), 8, '0') || 'i' AS position \
+                 FROM tasks \
+             ) ranked \
+             WHERE tasks.id = ranked.id AND tasks.position <> ranked.position",
+        )
+        .execute(conn)
+        .map_err(Into::into)
+    }
+
+    /// Find several tasks by ID (missing IDs are skipped)
+    pub fn find_by_ids(
+        conn: &mut DbConnection,
+        task_ids: &[Uuid],
+    ) -> Result<Vec<Task>, RepositoryError> {
+        tasks::table
+            .filter(tasks::id.eq_any(task_ids))
+            .select(Task::as_select())
+            .load(conn)
+            .map_err(Into::into)
+    }
+
+    /// Find the snapshots of deleted tasks by change event ID
+    pub fn find_deletions(
+        conn: &mut DbConnection,
+        event_ids: &[i64],
+    ) -> Result<Vec<TaskDeletion>, RepositoryError> {
+        task_deletions::table
+            .filter(task_deletions::event_id.eq_any(event_ids))
+            .select(TaskDeletion::as_select())
+            .load(conn)
+            .map_err(Into::into)
+    }
+
     /// Create a new task
     pub fn create(conn: &mut DbConnection, new_task: NewTask) -> Result<Task, RepositoryError> {
-        diesel::insert_into(tasks::table)
-            .values(&new_task)
-            .returning(Task::as_returning())
-            .get_result(conn)
-            .map_err(Into::into)
+        conn.transaction(|conn| {
+            let task = diesel::insert_into(tasks::table)
+                .values(&new_task)
+                .returning(Task::as_returning())
+                .get_result(conn)?;
+            OutboxRepository::record(conn, TaskEventKind::Created, std::slice::from_ref(&task))?;
+            Ok(task)
+        })
     }
 
     /// Update an existing task
@@ -41,18 +341,130 @@ impl TaskRepository {
         task_id: Uuid,
         update_task: UpdateTask,
     ) -> Result<Task, RepositoryError> {
-        diesel::update(tasks::table.find(task_id))
-            .set(&update_task)
-            .returning(Task::as_returning())
-            .get_result(conn)
-            .map_err(Into::into)
+        conn.transaction(|conn| {
+            let task = diesel::update(tasks::table.find(task_id))
+                .set(&update_task)
+                .returning(Task::as_returning())
+                .get_result(conn)?;
+            OutboxRepository::record(conn, TaskEventKind::Updated, std::slice::from_ref(&task))?;
+            Ok(task)
+        })
     }
 
-    /// Delete a task
+    /// Delete a task (its subtasks are removed by the foreign key cascade)
+    ///
+    /// An outbox event is written for every deleted task, subtasks included.
+    /// Fails with `NotFound`, writing nothing, if the task does not exist.
     pub fn delete(conn: &mut DbConnection, task_id: Uuid) -> Result<(), RepositoryError> {
-        diesel::delete(tasks::table.find(task_id))
-            .execute(conn)
-            .map(|_| ())
-            .map_err(Into::into)
+        conn.transaction(|conn| {
+            let subtree: Vec<Task> = Self::find_subtree(conn, task_id)?
+                .into_iter()
+                .map(|node| node.task)
+                .collect();
+            // Recorded first, while the tags are still attached
+            OutboxRepository::record(conn, TaskEventKind::Deleted, &subtree)?;
+
+            match diesel::delete(tasks::table.find(task_id)).execute(conn)? {
+                0 => Err(RepositoryError::NotFound),
+                _ => Ok(()),
+            }
+        })
+    }
+
+    /// Delete a task after moving its children up to its own parent
+    pub fn delete_reparenting(
+        conn: &mut DbConnection,
+        task_id: Uuid,
+    ) -> Result<(), RepositoryError> {
+        conn.transaction(|conn| {
+            let parent_id = tasks::table
+                .find(task_id)
+                .select(tasks::parent_id)
+                .for_update()
+                .first::<Option<Uuid>>(conn)?;
+
+            let children = diesel::update(tasks::table.filter(tasks::parent_id.eq(task_id)))
+                .set(tasks::parent_id.eq(parent_id))
+                .returning(Task::as_returning())
+                .get_results(conn)?;
+            OutboxRepository::record(conn, TaskEventKind::Updated, &children)?;
+
+            Self::delete(conn, task_id)
+        })
+    }
+
+    /// Find the direct subtasks of a task
+    ///
+    /// Without `with_description`, descriptions are not read and come back as `None`.
+    pub fn find_children(
+        conn: &mut DbConnection,
+        parent_id: Uuid,
+        with_description: bool,
+    ) -> Result<Vec<Task>, RepositoryError> {
+        let query = tasks::table
+            .filter(tasks::parent_id.eq(parent_id))
+            .order((tasks::position.asc(), tasks::created_at.asc()));
+
+        if with_description {
+            query.select(Task::as_select()).load(conn)
+        } else {
+            query.select(columns_without_description()).load(conn)
+        }
+        .map_err(Into::into)
+    }
+
+    /// Load a task and all of its descendants with a recursive CTE
+    ///
+    /// Rows are ordered by depth, so every parent precedes its children. The
+    /// walk skips tasks already on the path, so it ends even on a cycle.
+    pub fn find_subtree(
+        conn: &mut DbConnection,
+        root_id: Uuid,
+    ) -> Result<Vec<TaskNode>, RepositoryError> {
+        diesel::sql_query(
+            "WITH RECURSIVE tree AS ( \
+                 SELECT id, 0 AS depth, ARRAY[id] AS path FROM tasks WHERE id = $1 \
+                 UNION ALL \
+                 SELECT child.id, tree.depth + 1, tree.path || child.id \
+                 FROM tasks child JOIN tree ON child.parent_id = tree.id \
+                 WHERE child.id <> ALL (tree.path) \
+             ) \
+             SELECT tasks.*, tree.depth FROM tree JOIN tasks ON tasks.id = tree.id \
+             ORDER BY tree.depth, tasks.position, tasks.created_at",
+        )
+        .bind::<diesel::sql_types::Uuid, _>(root_id)
+        .load(conn)
+        .map_err(Into::into)
+    }
+
+    /// Whether `task_id` is `node_id` itself or one of its ancestors
+    ///
+    /// Used to reject parent assignments that would create a cycle.
+    pub fn is_self_or_ancestor(
+        conn: &mut DbConnection,
+        task_id: Uuid,
+        node_id: Uuid,
+    ) -> Result<bool, RepositoryError> {
+        #[derive(QueryableByName)]
+        struct Found {
+            #[diesel(sql_type = diesel::sql_types::Bool)]
+            found: bool,
+        }
+
+        // UNION (not UNION ALL) stops the walk even if a cycle already exists
+        diesel::sql_query(
+            "WITH RECURSIVE ancestors AS ( \
+                 SELECT id, parent_id FROM tasks WHERE id = $1 \
+                 UNION \
+                 SELECT parent.id, parent.parent_id \
+                 FROM tasks parent JOIN ancestors ON parent.id = ancestors.parent_id \
+             ) \
+             SELECT EXISTS (SELECT 1 FROM ancestors WHERE id = $2) AS found",
+        )
+        .bind::<diesel::sql_types::Uuid, _>(node_id)
+        .bind::<diesel::sql_types::Uuid, _>(task_id)
+        .get_result::<Found>(conn)
+        .map(|row| row.found)
+        .map_err(Into::into)
     }
 }
@@ -0,0 +1,226 @@
+use axum_diesel_api::DeliveryStatus;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::db::connection::DbConnection;
+use crate::db::error::RepositoryError;
+use crate::db::models::{
+    DeliveryAttempt, NewDeliveryAttempt, NewWebhook, NewWebhookDelivery, UpdateWebhook, Webhook,
+    WebhookDelivery,
+};
+use crate::db::schema::{outbox, webhook_deliveries, webhook_delivery_attempts, webhooks};
+
+/// Most deliveries listed at once
+const DELIVERY_PAGE_SIZE: i64 = 100;
+
+pub struct WebhookRepository;
+
+impl WebhookRepository {
+    /// Find all webhooks, oldest first
+    pub fn find_all(conn: &mut DbConnection) -> Result<Vec<Webhook>, RepositoryError> {
+        webhooks::table
+            .select(Webhook::as_select())
+            .order(webhooks::created_at.asc())
+            .load(conn)
+            .map_err(Into::into)
+    }
+
+    /// Find the webhooks new events are sent to
+    pub fn find_active(conn: &mut DbConnection) -> Result<Vec<Webhook>, RepositoryError> {
+        webhooks::table
+            .filter(webhooks::active.eq(true))
+            .select(Webhook::as_select())
+            .load(conn)
+            .map_err(Into::into)
+    }
+
+    /// Find a webhook by ID
+    pub fn find_by_id(
+        conn: &mut DbConnection,
+        webhook_id: Uuid,
+    ) -> Result<Webhook, RepositoryError> {
+        webhooks::table
+            .find(webhook_id)
+            .select(Webhook::as_select())
+            .first(conn)
+            .map_err(Into::into)
+    }
+
+    /// Create a new webhook
+    pub fn create(
+        conn: &mut DbConnection,
+        new_webhook: NewWebhook,
+    ) -> Result<Webhook, RepositoryError> {
+        diesel::insert_into(webhooks::table)
+            .values(&new_webhook)
+            .returning(Webhook::as_returning())
+            .get_result(conn)
+            .map_err(Into::into)
+    }
+
+    /// Update an existing webhook
+    pub fn update(
+        conn: &mut DbConnection,
+        webhook_id: Uuid,
+        update_webhook: UpdateWebhook,
+    ) -> Result<Webhook, RepositoryError> {
+        diesel::update(webhooks::table.find(webhook_id))
+            .set(&update_webhook)
+            .returning(Webhook::as_returning())
+            .get_result(conn)
+            .map_err(Into::into)
+    }
+
+    /// Delete a webhook with its deliveries
+    pub fn delete(conn: &mut DbConnection, webhook_id: Uuid) -> Result<(), RepositoryError> {
+        diesel::delete(webhooks::table.find(webhook_id))
+            .execute(conn)
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    /// Queue deliveries, skipping those already queued
+    pub fn queue_deliveries(
+        conn: &mut DbConnection,
+        deliveries: &[NewWebhookDelivery],
+    ) -> Result<(), RepositoryError> {
+        diesel::insert_into(webhook_deliveries::table)
+            .values(deliveries)
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    /// Claim the pending deliveries of active webhooks that are due
+    ///
+    /// Claimed deliveries are postponed until `lease_until`, so that other
+    /// dispatchers leave them alone while they are being sent.
+    pub fn claim_due(
+        conn: &mut DbConnection,
+        limit: i64,
+        lease_until: DateTime<Utc>,
+    ) -> Result<Vec<WebhookDelivery>, RepositoryError> {
+        conn.transaction(|conn| {
+            let ids: Vec<Uuid> = webhook_deliveries::table
+                .filter(webhook_deliveries::status.eq(DeliveryStatus::Pending.as_str()))
+                .filter(webhook_deliveries::next_attempt_at.le(diesel::dsl::now))
+                .filter(
+                    webhook_deliveries::webhook_id.eq_any(
+                        webhooks::table
+                            .filter(webhooks::active.eq(true))
+                            .select(webhooks::id),
+                    ),
+                )
+                .order(webhook_deliveries::next_attempt_at.asc())
+                .limit(limit)
+                .select(webhook_deliveries::id)
+                .for_update()
+                .skip_locked()
+                .load(conn)?;
+
+            diesel::update(webhook_deliveries::table.filter(webhook_deliveries::id.eq_any(&ids)))
+                .set(webhook_deliveries::next_attempt_at.eq(lease_until))
+                .returning(WebhookDelivery::as_returning())
+                .get_results(conn)
+        })
+        .map_err(Into::into)
+    }
+
+    /// Log an attempt and move the delivery to its next state
+    ///
+    /// `next_attempt_at` is only used while the delivery stays pending.
+    pub fn record_attempt(
+        conn: &mut DbConnection,
+        attempt: NewDeliveryAttempt,
+        status: DeliveryStatus,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        conn.transaction(|conn| {
+            let delivery_id = attempt.delivery_id;
+            diesel::insert_into(webhook_delivery_attempts::table)
+                .values(&attempt)
+                .execute(conn)?;
+
+            let delivered_at = (status == DeliveryStatus::Delivered).then(Utc::now);
+            diesel::update(webhook_deliveries::table.find(delivery_id))
+                .set((
+                    webhook_deliveries::status.eq(status.as_str()),
+                    webhook_deliveries::attempts.eq(webhook_deliveries::attempts + 1),
+                    webhook_deliveries::next_attempt_at.eq(next_attempt_at),
+                    webhook_deliveries::delivered_at.eq(delivered_at),
+                ))
+                .execute(conn)
+                .map(|_| ())
+        })
+        .map_err(Into::into)
+    }
+
+    /// Find a webhook's latest deliveries with their event kinds, newest first
+    pub fn find_deliveries(
+        conn: &mut DbConnection,
+        webhook_id: Uuid,
+        status: Option<DeliveryStatus>,
+    ) -> Result<Vec<(WebhookDelivery, String)>, RepositoryError> {
+        let mut query = webhook_deliveries::table
+            .inner_join(outbox::table)
+            .filter(webhook_deliveries::webhook_id.eq(webhook_id))
+            .select((WebhookDelivery::as_select(), outbox::kind))
+            .order((
+                webhook_deliveries::created_at.desc(),
+                webhook_deliveries::id.desc(),
+            ))
+            .limit(DELIVERY_PAGE_SIZE)
+            .into_boxed();
+
+        if let Some(status) = status {
+            query = query.filter(webhook_deliveries::status.eq(status.as_str()));
+        }
+
+        query.load(conn).map_err(Into::into)
+    }
+
+    /// Find a delivery by ID with its event kind
+    pub fn find_delivery(
+        conn: &mut DbConnection,
+        delivery_id: Uuid,
+    ) -> Result<(WebhookDelivery, String), RepositoryError> {
+        webhook_deliveries::table
+            .inner_join(outbox::table)
+            .filter(webhook_deliveries::id.eq(delivery_id))
+            .select((WebhookDelivery::as_select(), outbox::kind))
+            .first(conn)
+            .map_err(Into::into)
+    }
+
+    /// Load the attempt logs of a batch of deliveries, oldest attempt first
+    pub fn find_attempts(
+        conn: &mut DbConnection,
+        deliveries: &[WebhookDelivery],
+    ) -> Result<Vec<Vec<DeliveryAttempt>>, RepositoryError> {
+        let attempts: Vec<DeliveryAttempt> = DeliveryAttempt::belonging_to(deliveries)
+            .select(DeliveryAttempt::as_select())
+            .order(webhook_delivery_attempts::attempted_at.asc())
+            .load(conn)?;
+
+        Ok(attempts.grouped_by(deliveries))
+    }
+
+    /// Send a delivery again with a fresh set of attempts
+    pub fn requeue(
+        conn: &mut DbConnection,
+        delivery_id: Uuid,
+    ) -> Result<WebhookDelivery, RepositoryError> {
+        diesel::update(webhook_deliveries::table.find(delivery_id))
+            .set((
+                webhook_deliveries::status.eq(DeliveryStatus::Pending.as_str()),
+                webhook_deliveries::attempts.eq(0),
+                webhook_deliveries::next_attempt_at.eq(diesel::dsl::now),
+                webhook_deliveries::delivered_at.eq(None::<DateTime<Utc>>),
+            ))
+            .returning(WebhookDelivery::as_returning())
+            .get_result(conn)
+            .map_err(Into::into)
+    }
+}
@@ -0,0 +1,156 @@
+//! Retrying operations that failed on a transient database error
+//!
+//! Serialization failures, deadlocks and dropped connections
+//! ([`AppError::is_retryable`]) usually pass when the operation runs again.
+//! Only wrap operations that are safe to repeat: reads, idempotent writes,
+//! and transactions, which roll back as a whole before the next attempt.
+//!
+//! The pauses between attempts block the thread, like the database calls
+//! around them, so async code runs retried work on the blocking pool (see
+//! [`handlers::blocking`](crate::handlers::blocking)).
+
+use diesel::{Connection, RunQueryDsl};
+use std::fmt::Display;
+use std::time::Duration;
+
+use crate::db::connection::{self, DbConnection};
+use crate::db::store::IsolationLevel;
+use crate::error::AppError;
+
+/// Runs of an operation before its transient error is returned
+pub const MAX_ATTEMPTS: u32 = 3;
+/// Pause before the second run, doubled before each further one
+const FIRST_DELAY: Duration = Duration::from_millis(20);
+
+/// Run `f`, running it again on a transient database error
+pub fn retry_transient<T>(f: impl FnMut() -> Result<T, AppError>) -> Result<T, AppError> {
+    retry_with(MAX_ATTEMPTS, FIRST_DELAY, f)
+}
+
+/// Run `f` in a SERIALIZABLE transaction, retried on serialization failures
+///
+/// Each attempt takes a fresh connection, so that a dropped connection is
+/// retried as well.
+pub fn serializable<T>(
+    mut f: impl FnMut(&mut DbConnection) -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    retry_transient(|| {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+        conn.transaction(|conn| {
+            diesel::sql_query(IsolationLevel::Serializable.statement()).execute(conn)?;
+            f(conn)
+        })
+    })
+}
+
+fn retry_with<T>(
+    attempts: u32,
+    first_delay: Duration,
+    f: impl FnMut() -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    with_backoff(
+        "Database operation",
+        attempts,
+        first_delay,
+        AppError::is_retryable,
+        f,
+    )
+}
+
+/// Run `f` up to `attempts` times while its error is `retryable`, sleeping
+/// `first_delay` before the second run and twice as long before each further one
+pub(crate) fn with_backoff<T, E: Display>(
+    what: &str,
+    attempts: u32,
+    first_delay: Duration,
+    retryable: impl Fn(&E) -> bool,
+    mut f: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    let mut delay = first_delay;
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Err(e) if retryable(&e) && attempt < attempts => {
+                tracing::warn!(
+                    "{} failed on attempt {}/{}, retrying: {}",
+                    what,
+                    attempt,
+                    attempts,
+                    e
+                );
+                std::thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transient_errors_are_retried() {
+        let mut runs = 0;
+        let result = retry_with(3, Duration::ZERO, || {
+            runs += 1;
+            if runs < 3 {
+                Err(AppError::transient("could not serialize access"))
+            } else {
+                Ok(runs)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[test]
+    fn test_retries_are_bounded() {
+        let mut runs = 0;
+        let result: Result<(), _> = retry_with(3, Duration::ZERO, || {
+            runs += 1;
+            Err(AppError::transient("deadlock detected"))
+        });
+        assert!(matches!(result, Err(AppError::Transient(_))));
+        assert_eq!(runs, 3);
+    }
+
+    #[test]
+    fn test_backoff_doubles_the_delay() {
+        let mut calls = Vec::new();
+        let started = std::time::Instant::now();
+        let result = with_backoff(
+            "test",
+            3,
+            Duration::from_millis(5),
+            |_| true,
+            || {
+                calls.push(started.elapsed());
+                if calls.len() < 3 {
+                    Err("down")
+                } else {
+                    Ok(calls.len())
+                }
+            },
+        );
+
+        assert_eq!(result, Ok(3));
+        // 5ms then 10ms between attempts
+        assert!(calls[1] >= Duration::from_millis(5));
+        assert!(calls[2] - calls[1] >= Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_other_errors_are_not_retried() {
+        let mut runs = 0;
+        let result: Result<(), _> = retry_with(3, Duration::ZERO, || {
+            runs += 1;
+            Err(AppError::not_found("Task"))
+        });
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        assert_eq!(runs, 1);
+    }
+}
@@ -1,12 +1,192 @@
 // @generated automatically by Diesel CLI.
 
+pub mod sql_types {
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "task_priority"))]
+    pub struct TaskPriority;
+}
+
+diesel::table! {
+    calendar_feeds (id) {
+        id -> Uuid,
+        #[max_length = 100]
+        name -> Varchar,
+        #[max_length = 64]
+        token -> Varchar,
+        project_id -> Nullable<Uuid>,
+        #[max_length = 10]
+        component -> Varchar,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    outbox (id) {
+        id -> Uuid,
+        #[max_length = 10]
+        kind -> Varchar,
+        task_id -> Uuid,
+        payload -> Jsonb,
+        created_at -> Timestamptz,
+        dispatched_at -> Nullable<Timestamptz>,
+    }
+}
+
 diesel::table! {
+    projects (id) {
+        id -> Uuid,
+        #[max_length = 255]
+        name -> Varchar,
+        description -> Nullable<Text>,
+        archived_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    tags (id) {
+        id -> Uuid,
+        #[max_length = 50]
+        name -> Varchar,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    task_deletions (event_id) {
+        event_id -> Int8,
+        task_id -> Uuid,
+        task -> Jsonb,
+        deleted_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    task_dependencies (task_id, blocked_by_id) {
+        task_id -> Uuid,
+        blocked_by_id -> Uuid,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    task_status_transitions (from_status_id, to_status_id) {
+        from_status_id -> Uuid,
+        to_status_id -> Uuid,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    task_statuses (id) {
+        id -> Uuid,
+        project_id -> Nullable<Uuid>,
+        #[max_length = 50]
+        name -> Varchar,
+        position -> Int4,
+        is_done -> Bool,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    task_tags (task_id, tag_id) {
+        task_id -> Uuid,
+        tag_id -> Uuid,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::TaskPriority;
+
     tasks (id) {
         id -> Uuid,
+        #[max_length = 255]
         title -> Varchar,
         description -> Nullable<Text>,
         completed -> Bool,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        due_at -> Nullable<Timestamptz>,
+        priority -> TaskPriority,
+        completed_at -> Nullable<Timestamptz>,
+        project_id -> Nullable<Uuid>,
+        archived_at -> Nullable<Timestamptz>,
+        parent_id -> Nullable<Uuid>,
+        position -> Varchar,
+        status_id -> Uuid,
+        recurrence -> Nullable<Text>,
+        #[max_length = 64]
+        recurrence_timezone -> Nullable<Varchar>,
+        recurrence_start -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    webhook_deliveries (id) {
+        id -> Uuid,
+        webhook_id -> Uuid,
+        outbox_id -> Uuid,
+        #[max_length = 10]
+        status -> Varchar,
+        attempts -> Int4,
+        next_attempt_at -> Timestamptz,
+        delivered_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    webhook_delivery_attempts (id) {
+        id -> Uuid,
+        delivery_id -> Uuid,
+        status_code -> Nullable<Int4>,
+        error -> Nullable<Text>,
+        duration_ms -> Int4,
+        attempted_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    webhooks (id) {
+        id -> Uuid,
+        url -> Text,
+        description -> Nullable<Text>,
+        #[max_length = 64]
+        secret -> Varchar,
+        active -> Bool,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
     }
 }
+
+diesel::joinable!(calendar_feeds -> projects (project_id));
+diesel::joinable!(task_statuses -> projects (project_id));
+diesel::joinable!(task_tags -> tags (tag_id));
+diesel::joinable!(task_tags -> tasks (task_id));
+diesel::joinable!(tasks -> projects (project_id));
+diesel::joinable!(tasks -> task_statuses (status_id));
+diesel::joinable!(webhook_deliveries -> outbox (outbox_id));
+diesel::joinable!(webhook_deliveries -> webhooks (webhook_id));
+diesel::joinable!(webhook_delivery_attempts -> webhook_deliveries (delivery_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    calendar_feeds,
+    outbox,
+    projects,
+    tags,
+    task_deletions,
+    task_dependencies,
+    task_status_transitions,
+    task_statuses,
+    task_tags,
+    tasks,
+    webhook_deliveries,
+    webhook_delivery_attempts,
+    webhooks,
+);
@@ -0,0 +1,359 @@
+//! Behaviour every [`TaskStore`] shares, checked on each store that runs
+//! without a database server
+//!
+//! The SQLite store cannot share the Diesel queries of the Postgres
+//! repositories, so these tests are what keeps the stores from drifting
+//! apart: the same calls must give the same results on every store.
+
+use axum_diesel_api::ChildrenPolicy;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::{MemoryTaskStore, TaskStore};
+use crate::db::error::RepositoryError;
+use crate::db::models::{NewTask, Priority, Project, Tag, UpdateTask};
+use crate::db::repositories::TaskFilter;
+
+/// A store under test, with the rows the task endpoints cannot create
+trait Fixture: TaskStore {
+    fn insert_tag(&self, name: &str) -> Tag;
+    fn insert_project(&self, name: &str) -> Project;
+    fn insert_dependency(&self, task_id: Uuid, blocked_by_id: Uuid);
+}
+
+impl Fixture for MemoryTaskStore<'_> {
+    fn insert_tag(&self, name: &str) -> Tag {
+        MemoryTaskStore::insert_tag(self, name)
+    }
+
+    fn insert_project(&self, name: &str) -> Project {
+        MemoryTaskStore::insert_project(self, name, false)
+    }
+
+    fn insert_dependency(&self, task_id: Uuid, blocked_by_id: Uuid) {
+        MemoryTaskStore::insert_dependency(self, task_id, blocked_by_id)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Fixture for super::SqliteTaskStore<'_> {
+    fn insert_tag(&self, name: &str) -> Tag {
+        super::SqliteTaskStore::insert_tag(self, name)
+    }
+
+    fn insert_project(&self, name: &str) -> Project {
+        super::SqliteTaskStore::insert_project(self, name, false)
+    }
+
+    fn insert_dependency(&self, task_id: Uuid, blocked_by_id: Uuid) {
+        super::SqliteTaskStore::insert_dependency(self, task_id, blocked_by_id)
+    }
+}
+
+/// A fresh, empty instance of every store, by name
+fn stores() -> Vec<(&'static str, Box<dyn Fixture>)> {
+    #[cfg_attr(not(feature = "sqlite"), allow(unused_mut))]
+    let mut stores: Vec<(&'static str, Box<dyn Fixture>)> =
+        vec![("memory", Box::new(MemoryTaskStore::new()))];
+    #[cfg(feature = "sqlite")]
+    stores.push((
+        "sqlite",
+        Box::new(super::SqliteTaskStore::open(":memory:").expect("open an in-memory database")),
+    ));
+    stores
+}
+
+fn new_task(store: &dyn Fixture, title: &str, position: &str) -> NewTask {
+    NewTask {
+        title: title.to_string(),
+        description: None,
+        completed: false,
+        due_at: None,
+        priority: Priority::Medium,
+        project_id: None,
+        parent_id: None,
+        position: position.to_string(),
+        status_id: store.find_workflow(None).unwrap()[0].id,
+        recurrence: None,
+        recurrence_timezone: None,
+        recurrence_start: None,
+    }
+}
+
+fn titles(store: &dyn Fixture, filter: TaskFilter) -> Vec<String> {
+    store
+        .find_tasks(&filter, false)
+        .unwrap()
+        .into_iter()
+        .map(|task| task.title)
+        .collect()
+}
+
+#[test]
+fn test_writes_to_missing_rows_are_not_found() {
+    for (name, store) in stores() {
+        let store = &*store;
+        let task = store.create_task(new_task(store, "task", "a")).unwrap();
+        let tag = store.insert_tag("home");
+        let missing = Uuid::new_v4();
+        let not_found = |result: Result<(), RepositoryError>| {
+            assert!(
+                matches!(result, Err(RepositoryError::NotFound)),
+                "{}: {:?}",
+                name,
+                result
+            );
+        };
+
+        not_found(
+            store
+                .update_task(missing, UpdateTask::default())
+                .map(|_| ()),
+        );
+        not_found(store.set_position(missing, "b").map(|_| ()));
+        not_found(store.delete_task(missing, ChildrenPolicy::Cascade));
+        not_found(store.find_task(missing).map(|_| ()));
+        not_found(store.find_project(missing).map(|_| ()));
+        not_found(store.attach_tag(missing, tag.id));
+        not_found(store.attach_tag(task.id, missing));
+        not_found(store.detach_tag(task.id, tag.id));
+
+        // Attaching twice is a no-op, detaching twice is not
+        store.attach_tag(task.id, tag.id).unwrap();
+        store.attach_tag(task.id, tag.id).unwrap();
+        store.detach_tag(task.id, tag.id).unwrap();
+        not_found(store.detach_tag(task.id, tag.id));
+    }
+}
+
+#[test]
+fn test_updates_maintain_timestamps() {
+    let due: DateTime<Utc> = "2030-01-01T00:00:00Z".parse().unwrap();
+    for (name, store) in stores() {
+        let store = &*store;
+        let task = store
+            .create_task(NewTask {
+                due_at: Some(due),
+                ..new_task(store, "task", "a")
+            })
+            .unwrap();
+        assert_eq!(task.completed_at, None, "{}", name);
+
+        let done = store
+            .update_task(
+                task.id,
+                UpdateTask {
+                    completed: Some(true),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(done.completed_at.is_some(), "{}", name);
+        assert!(done.updated_at >= task.updated_at, "{}", name);
+
+        // Other changes keep the completion time
+        let renamed = store
+            .update_task(
+                task.id,
+                UpdateTask {
+                    title: Some("renamed".to_string()),
+                    due_at: Some(None),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(renamed.completed_at, done.completed_at, "{}", name);
+        assert_eq!(renamed.due_at, None, "{}", name);
+        assert_eq!(store.find_task(task.id).unwrap().title, "renamed");
+
+        let reopened = store
+            .update_task(
+                task.id,
+                UpdateTask {
+                    completed: Some(false),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(reopened.completed_at, None, "{}", name);
+    }
+}
+
+#[test]
+fn test_positions_and_rebalance() {
+    for (name, store) in stores() {
+        let store = &*store;
+        let [a, b, c] = ["a", "b", "c"].map(|position| {
+            store
+                .create_task(new_task(store, position, position))
+                .unwrap()
+        });
+
+        assert_eq!(store.max_position().unwrap().as_deref(), Some("c"));
+        assert_eq!(store.max_position_len().unwrap(), Some(1));
+        assert_eq!(
+            store.next_position("a", b.id).unwrap().as_deref(),
+            Some("c"),
+            "{}",
+            name
+        );
+        assert_eq!(
+            store.previous_position("c", b.id).unwrap().as_deref(),
+            Some("a"),
+            "{}",
+            name
+        );
+        assert_eq!(store.previous_position("a", c.id).unwrap(), None);
+        assert_eq!(store.next_position("c", a.id).unwrap(), None);
+
+        store.set_position(c.id, "0").unwrap();
+        assert_eq!(titles(store, TaskFilter::default()), ["c", "a", "b"]);
+
+        assert_eq!(store.rebalance_positions().unwrap(), 3, "{}", name);
+        assert_eq!(store.rebalance_positions().unwrap(), 0, "{}", name);
+        assert_eq!(titles(store, TaskFilter::default()), ["c", "a", "b"]);
+        assert_eq!(
+            store.find_task(c.id).unwrap().position,
+            "00000001i",
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn test_children_and_filters() {
+    let soon: DateTime<Utc> = "2030-01-01T00:00:00Z".parse().unwrap();
+    let later: DateTime<Utc> = "2040-01-01T00:00:00Z".parse().unwrap();
+    for (name, store) in stores() {
+        let store = &*store;
+        let project = store.insert_project("Home");
+        let parent = store
+            .create_task(NewTask {
+                project_id: Some(project.id),
+                due_at: Some(soon),
+                ..new_task(store, "parent", "a")
+            })
+            .unwrap();
+        for (title, position, priority) in [
+            ("second", "c", Priority::High),
+            ("first", "b", Priority::Low),
+        ] {
+            store
+                .create_task(NewTask {
+                    parent_id: Some(parent.id),
+                    priority,
+                    due_at: Some(later),
+                    ..new_task(store, title, position)
+                })
+                .unwrap();
+        }
+        store.create_task(new_task(store, "loose", "d")).unwrap();
+
+        let children: Vec<String> = store
+            .find_children(parent.id, false)
+            .unwrap()
+            .into_iter()
+            .map(|task| task.title)
+            .collect();
+        assert_eq!(children, ["first", "second"], "{}", name);
+
+        let filtered = [
+            TaskFilter {
+                project_id: Some(project.id),
+                ..Default::default()
+            },
+            TaskFilter {
+                priority: Some(Priority::High),
+                ..Default::default()
+            },
+            TaskFilter {
+                due_before: Some(later),
+                ..Default::default()
+            },
+            TaskFilter {
+                has_due_at: true,
+                ..Default::default()
+            },
+            TaskFilter {
+                completed: Some(false),
+                overdue: Some(false),
+                ..Default::default()
+            },
+        ]
+        .map(|filter| titles(store, filter));
+        assert_eq!(
+            filtered,
+            [
+                vec!["parent"],
+                vec!["second"],
+                vec!["parent"],
+                vec!["parent", "first", "second"],
+                vec!["parent", "first", "second", "loose"],
+            ],
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn test_workflow_tags_and_blockers() {
+    for (name, store) in stores() {
+        let store = &*store;
+        let workflow = store.find_workflow(None).unwrap();
+        let done = store.find_status_by_name(None, "done").unwrap();
+        assert_eq!(done.id, workflow[3].id, "{}", name);
+        assert_eq!(store.find_status(done.id).unwrap().name, "done");
+        assert_eq!(
+            store
+                .find_statuses(&[workflow[1].id, workflow[0].id])
+                .unwrap()
+                .len(),
+            2,
+            "{}",
+            name
+        );
+        assert!(matches!(
+            store.find_status_by_name(None, "shipped"),
+            Err(RepositoryError::NotFound)
+        ));
+
+        let blocked = store.create_task(new_task(store, "blocked", "a")).unwrap();
+        let blocker = store.create_task(new_task(store, "blocker", "b")).unwrap();
+        store.insert_dependency(blocked.id, blocker.id);
+        assert_eq!(
+            store.count_open_blockers(blocked.id).unwrap(),
+            1,
+            "{}",
+            name
+        );
+        store
+            .update_task(
+                blocker.id,
+                UpdateTask {
+                    completed: Some(true),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            store.count_open_blockers(blocked.id).unwrap(),
+            0,
+            "{}",
+            name
+        );
+
+        let [work, home] = ["work", "home"].map(|tag| store.insert_tag(tag));
+        for tag in [&work, &home] {
+            store.attach_tag(blocked.id, tag.id).unwrap();
+        }
+        let tags: Vec<Vec<String>> = store
+            .find_tags(&[blocker, blocked])
+            .unwrap()
+            .into_iter()
+            .map(|tags| tags.into_iter().map(|tag| tag.name).collect())
+            .collect();
+        assert_eq!(tags, [vec![], vec!["home", "work"]], "{}", name);
+    }
+}
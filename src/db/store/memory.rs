@@ -0,0 +1,675 @@
+use axum_diesel_api::ChildrenPolicy;
+use chrono::Utc;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+use uuid::Uuid;
+
+use super::{IsolationLevel, TaskStore, TransactionWork, apply_update, inserted};
+use crate::db::error::RepositoryError;
+use crate::db::models::{NewTask, Project, Tag, Task, TaskNode, TaskStatus, UpdateTask};
+use crate::db::repositories::TaskFilter;
+use crate::error::AppError;
+
+/// Default workflow, as seeded by the statuses migration
+const DEFAULT_WORKFLOW: [(&str, bool); 4] = [
+    ("todo", false),
+    ("in_progress", false),
+    ("review", false),
+    ("done", true),
+];
+const DEFAULT_TRANSITIONS: [(&str, &str); 8] = [
+    ("todo", "in_progress"),
+    ("todo", "done"),
+    ("in_progress", "todo"),
+    ("in_progress", "review"),
+    ("in_progress", "done"),
+    ("review", "in_progress"),
+    ("review", "done"),
+    ("done", "todo"),
+];
+
+#[derive(Debug, Clone, Default)]
+struct State {
+    tasks: HashMap<Uuid, Task>,
+    statuses: Vec<TaskStatus>,
+    /// `(from_status_id, to_status_id)`
+    transitions: HashSet<(Uuid, Uuid)>,
+    projects: HashMap<Uuid, Project>,
+    tags: HashMap<Uuid, Tag>,
+    /// `(task_id, tag_id)`
+    task_tags: HashSet<(Uuid, Uuid)>,
+    /// `(task_id, blocked_by_id)`
+    dependencies: HashSet<(Uuid, Uuid)>,
+}
+
+impl State {
+    fn task(&self, task_id: Uuid) -> Result<&Task, RepositoryError> {
+        self.tasks.get(&task_id).ok_or(RepositoryError::NotFound)
+    }
+
+    fn task_mut(&mut self, task_id: Uuid) -> Result<&mut Task, RepositoryError> {
+        self.tasks
+            .get_mut(&task_id)
+            .ok_or(RepositoryError::NotFound)
+    }
+
+    /// Tasks in manual order, like `ORDER BY position, created_at`
+    fn sorted<'a>(&self, tasks: impl Iterator<Item = &'a Task>) -> Vec<Task> {
+        let mut tasks: Vec<Task> = tasks.cloned().collect();
+        tasks.sort_by(|a, b| (&a.position, a.created_at).cmp(&(&b.position, b.created_at)));
+        tasks
+    }
+
+    fn tag_names(&self, task_id: Uuid) -> HashSet<&str> {
+        self.task_tags
+            .iter()
+            .filter(|(task, _)| *task == task_id)
+            .filter_map(|(_, tag)| self.tags.get(tag))
+            .map(|tag| tag.name.as_str())
+            .collect()
+    }
+
+    fn matches(&self, task: &Task, filter: &TaskFilter) -> bool {
+        let now = Utc::now();
+
+        if !filter.tags.is_empty() {
+            let names = self.tag_names(task.id);
+            let mut wanted = filter.tags.iter().map(|tag| names.contains(tag.as_str()));
+            let tagged = if filter.match_all_tags {
+                wanted.all(|found| found)
+            } else {
+                wanted.any(|found| found)
+            };
+            if !tagged {
+                return false;
+            }
+        }
+
+        let overdue = !task.completed && task.due_at.is_some_and(|due_at| due_at < now);
+        let status = self.statuses.iter().find(|s| s.id == task.status_id);
+
+        filter.overdue.is_none_or(|wanted| wanted == overdue)
+            && (!filter.has_due_at || task.due_at.is_some())
+            && filter
+                .due_before
+                .is_none_or(|before| task.due_at.is_some_and(|due_at| due_at < before))
+            && filter.priority.is_none_or(|p| p == task.priority)
+            && filter.project_id.is_none_or(|p| Some(p) == task.project_id)
+            && (filter.include_archived || task.archived_at.is_none())
+            && filter.completed.is_none_or(|c| c == task.completed)
+            && filter
+                .status
+                .as_ref()
+                .is_none_or(|name| status.is_some_and(|s| &s.name == name))
+    }
+
+    /// Fail like a foreign key violation when a referenced row is missing
+    fn check_references(&self, task: &Task) -> Result<(), RepositoryError> {
+        let missing = |column: &str| {
+            Err(RepositoryError::InvalidReference(format!(
+                "tasks_{}_fkey",
+                column
+            )))
+        };
+        if !self.statuses.iter().any(|s| s.id == task.status_id) {
+            return missing("status_id");
+        }
+        if task
+            .project_id
+            .is_some_and(|id| !self.projects.contains_key(&id))
+        {
+            return missing("project_id");
+        }
+        if task
+            .parent_id
+            .is_some_and(|id| !self.tasks.contains_key(&id))
+        {
+            return missing("parent_id");
+        }
+        Ok(())
+    }
+
+    /// Remove tasks with their tag links and dependencies, returning the
+    /// number of tasks removed
+    fn remove_tasks(&mut self, task_ids: &HashSet<Uuid>) -> usize {
+        let before = self.tasks.len();
+        self.tasks.retain(|id, _| !task_ids.contains(id));
+        self.task_tags.retain(|(task, _)| !task_ids.contains(task));
+        self.dependencies
+            .retain(|(task, blocker)| !task_ids.contains(task) && !task_ids.contains(blocker));
+        before - self.tasks.len()
+    }
+
+    fn subtree(&self, root_id: Uuid) -> Vec<TaskNode> {
+        let Some(root) = self.tasks.get(&root_id) else {
+            return Vec::new();
+        };
+
+        let mut nodes = vec![TaskNode {
+            task: root.clone(),
+            depth: 0,
+        }];
+        let mut level: HashSet<Uuid> = HashSet::from([root_id]);
+        // Ends the walk even if the parents form a cycle
+        let mut seen = level.clone();
+        let mut depth = 0;
+        while !level.is_empty() {
+            depth += 1;
+            let children = self.sorted(self.tasks.values().filter(|task| {
+                task.parent_id.is_some_and(|p| level.contains(&p)) && !seen.contains(&task.id)
+            }));
+            level = children.iter().map(|task| task.id).collect();
+            seen.extend(&level);
+            nodes.extend(children.into_iter().map(|task| TaskNode { task, depth }));
+        }
+        nodes
+    }
+}
+
+enum Backing<'s> {
+    Owned(Box<Mutex<State>>),
+    Borrowed(Mutex<&'s mut State>),
+}
+
+/// Thread-safe [`TaskStore`] keeping everything in memory
+///
+/// Starts with the default workflow and no tasks. Transactions lock the
+/// whole store, and a failed one restores the state it started from.
+/// Nothing is persisted, and no outbox events are written.
+pub struct MemoryTaskStore<'s> {
+    state: Backing<'s>,
+}
+
+impl Default for MemoryTaskStore<'static> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryTaskStore<'static> {
+    /// Empty store with the default workflow
+    pub fn new() -> Self {
+        let now = Utc::now();
+        let statuses: Vec<TaskStatus> = DEFAULT_WORKFLOW
+            .iter()
+            .zip(0..)
+            .map(|(&(name, is_done), position)| TaskStatus {
+                id: Uuid::new_v4(),
+                project_id: None,
+                name: name.to_string(),
+                position,
+                is_done,
+                created_at: now,
+                updated_at: now,
+            })
+            .collect();
+        let id = |name: &str| statuses.iter().find(|s| s.name == name).unwrap().id;
+        let transitions = DEFAULT_TRANSITIONS
+            .iter()
+            .map(|&(from, to)| (id(from), id(to)))
+            .collect();
+
+        Self {
+            state: Backing::Owned(Box::new(Mutex::new(State {
+                statuses,
+                transitions,
+                ..Default::default()
+            }))),
+        }
+    }
+}
+
+impl MemoryTaskStore<'_> {
+    fn with<T>(&self, f: impl FnOnce(&mut State) -> T) -> T {
+        fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+            mutex.lock().unwrap_or_else(|e| e.into_inner())
+        }
+        match self.state {
+            Backing::Owned(ref state) => f(&mut lock(state)),
+            Backing::Borrowed(ref state) => f(&mut lock(state)),
+        }
+    }
+}
+
+#[cfg(test)]
+impl MemoryTaskStore<'_> {
+    /// Add a tag, as the tag endpoints would
+    pub fn insert_tag(&self, name: &str) -> Tag {
+        let now = Utc::now();
+        let tag = Tag {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        self.with(|state| state.tags.insert(tag.id, tag.clone()));
+        tag
+    }
+
+    /// Add a project, optionally archived, without a workflow of its own
+    pub fn insert_project(&self, name: &str, archived: bool) -> Project {
+        let now = Utc::now();
+        let project = Project {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            description: None,
+            archived_at: archived.then_some(now),
+            created_at: now,
+            updated_at: now,
+        };
+        self.with(|state| state.projects.insert(project.id, project.clone()));
+        project
+    }
+
+    /// Mark `task_id` as blocked by `blocked_by_id`
+    pub fn insert_dependency(&self, task_id: Uuid, blocked_by_id: Uuid) {
+        self.with(|state| state.dependencies.insert((task_id, blocked_by_id)));
+    }
+}
+
+impl TaskStore for MemoryTaskStore<'_> {
+    fn find_tasks(
+        &self,
+        filter: &TaskFilter,
+        with_description: bool,
+    ) -> Result<Vec<Task>, RepositoryError> {
+        self.with(|state| {
+            let mut tasks = state.sorted(
+                state
+                    .tasks
+                    .values()
+                    .filter(|task| state.matches(task, filter)),
+            );
+            if !with_description {
+                tasks.iter_mut().for_each(|task| task.description = None);
+            }
+            Ok(tasks)
+        })
+    }
+
+    fn find_task(&self, task_id: Uuid) -> Result<Task, RepositoryError> {
+        self.with(|state| state.task(task_id).cloned())
+    }
+
+    fn find_children(
+        &self,
+        parent_id: Uuid,
+        with_description: bool,
+    ) -> Result<Vec<Task>, RepositoryError> {
+        self.with(|state| {
+            let mut tasks = state.sorted(
+                state
+                    .tasks
+                    .values()
+                    .filter(|task| task.parent_id == Some(parent_id)),
+            );
+            if !with_description {
+                tasks.iter_mut().for_each(|task| task.description = None);
+            }
+            Ok(tasks)
+        })
+    }
+
+    fn find_subtree(&self, root_id: Uuid) -> Result<Vec<TaskNode>, RepositoryError> {
+        self.with(|state| Ok(state.subtree(root_id)))
+    }
+
+    fn is_self_or_ancestor(&self, task_id: Uuid, node_id: Uuid) -> Result<bool, RepositoryError> {
+        self.with(|state| {
+            let mut seen = HashSet::new();
+            let mut current = Some(node_id);
+            while let Some(id) = current.filter(|id| seen.insert(*id)) {
+                if id == task_id {
+                    return Ok(true);
+                }
+                current = state.tasks.get(&id).and_then(|task| task.parent_id);
+            }
+            Ok(false)
+        })
+    }
+
+    fn create_task(&self, new_task: NewTask) -> Result<Task, RepositoryError> {
+        let task = inserted(new_task, Utc::now());
+        self.with(|state| {
+            state.check_references(&task)?;
+            state.tasks.insert(task.id, task.clone());
+            Ok(task)
+        })
+    }
+
+    fn update_task(&self, task_id: Uuid, update_task: UpdateTask) -> Result<Task, RepositoryError> {
+        self.with(|state| {
+            let task = apply_update(state.task(task_id)?.clone(), update_task, Utc::now());
+            state.check_references(&task)?;
+            state.tasks.insert(task_id, task.clone());
+            Ok(task)
+        })
+    }
+
+    fn delete_task(&self, task_id: Uuid, children: ChildrenPolicy) -> Result<(), RepositoryError> {
+        self.with(|state| {
+            let removed = match children {
+                ChildrenPolicy::Cascade => state
+                    .subtree(task_id)
+                    .into_iter()
+                    .map(|node| node.task.id)
+                    .collect(),
+                ChildrenPolicy::Reparent => {
+                    let parent_id = state.task(task_id)?.parent_id;
+                    let now = Utc::now();
+                    for child in state.tasks.values_mut() {
+                        if child.parent_id == Some(task_id) {
+                            child.parent_id = parent_id;
+                            child.updated_at = now;
+                        }
+                    }
+                    HashSet::from([task_id])
+                }
+            };
+            if state.remove_tasks(&removed) == 0 {
+                return Err(RepositoryError::NotFound);
+            }
+            Ok(())
+        })
+    }
+
+    fn max_position(&self) -> Result<Option<String>, RepositoryError> {
+        self.with(|state| Ok(state.tasks.values().map(|t| t.position.clone()).max()))
+    }
+
+    fn next_position(
+        &self,
+        position: &str,
+        exclude: Uuid,
+    ) -> Result<Option<String>, RepositoryError> {
+        self.with(|state| {
+            Ok(state
+                .tasks
+                .values()
+                .filter(|task| task.id != exclude && task.position.as_str() > position)
+                .map(|task| task.position.clone())
+                .min())
+        })
+    }
+
+    fn previous_position(
+        &self,
+        position: &str,
+        exclude: Uuid,
+    ) -> Result<Option<String>, RepositoryError> {
+        self.with(|state| {
+            Ok(state
+                .tasks
+                .values()
+                .filter(|task| task.id != exclude && task.position.as_str() < position)
+                .map(|task| task.position.clone())
+                .max())
+        })
+    }
+
+    fn max_position_len(&self) -> Result<Option<i32>, RepositoryError> {
+        self.with(|state| {
+            Ok(state
+                .tasks
+                .values()
+                .map(|task| task.position.chars().count() as i32)
+                .max())
+        })
+    }
+
+    fn set_position(&self, task_id: Uuid, position: &str) -> Result<Task, RepositoryError> {
+        self.with(|state| {
+            let task = state.task_mut(task_id)?;
+            task.position = position.to_string();
+            task.updated_at = Utc::now();
+            Ok(task.clone())
+        })
+    }
+
+    fn rebalance_positions(&self) -> Result<usize, RepositoryError> {
+        self.with(|state| {
+            // Same ranks as the SQL rebalance: 00000001i, 00000002i, ...
+            let mut order: Vec<&Task> = state.tasks.values().collect();
+            order.sort_by(|a, b| {
+                (&a.position, a.created_at, a.id).cmp(&(&b.position, b.created_at, b.id))
+            });
+            let ranks: Vec<(Uuid, String)> = order
+                .iter()
+                .zip(1..)
+                .map(|(task, n)| (task.id, format!("{:08x}i", n)))
+                .filter(|(id, rank)| state.tasks[id].position != *rank)
+                .collect();
+
+            let now = Utc::now();
+            for (id, rank) in &ranks {
+                let task = state.task_mut(*id)?;
+                task.position = rank.clone();
+                task.updated_at = now;
+            }
+            Ok(ranks.len())
+        })
+    }
+
+    fn find_project(&self, project_id: Uuid) -> Result<Project, RepositoryError> {
+        self.with(|state| {
+            state
+                .projects
+                .get(&project_id)
+                .cloned()
+                .ok_or(RepositoryError::NotFound)
+        })
+    }
+
+    fn find_workflow(&self, project_id: Option<Uuid>) -> Result<Vec<TaskStatus>, RepositoryError> {
+        self.with(|state| {
+            let mut workflow: Vec<TaskStatus> = state
+                .statuses
+                .iter()
+                .filter(|status| status.project_id == project_id)
+                .cloned()
+                .collect();
+            workflow.sort_by(|a, b| (a.position, &a.name).cmp(&(b.position, &b.name)));
+            Ok(workflow)
+        })
+    }
+
+    fn find_status(&self, status_id: Uuid) -> Result<TaskStatus, RepositoryError> {
+        self.with(|state| {
+            state
+                .statuses
+                .iter()
+                .find(|status| status.id == status_id)
+                .cloned()
+                .ok_or(RepositoryError::NotFound)
+        })
+    }
+
+    fn find_statuses(&self, status_ids: &[Uuid]) -> Result<Vec<TaskStatus>, RepositoryError> {
+        self.with(|state| {
+            Ok(state
+                .statuses
+                .iter()
+                .filter(|status| status_ids.contains(&status.id))
+                .cloned()
+                .collect())
+        })
+    }
+
+    fn find_status_by_name(
+        &self,
+        project_id: Option<Uuid>,
+        name: &str,
+    ) -> Result<TaskStatus, RepositoryError> {
+        self.with(|state| {
+            state
+                .statuses
+                .iter()
+                .find(|status| status.project_id == project_id && status.name == name)
+                .cloned()
+                .ok_or(RepositoryError::NotFound)
+        })
+    }
+
+    fn is_transition_allowed(&self, from: Uuid, to: Uuid) -> Result<bool, RepositoryError> {
+        self.with(|state| Ok(state.transitions.contains(&(from, to))))
+    }
+
+    fn count_open_blockers(&self, task_id: Uuid) -> Result<i64, RepositoryError> {
+        self.with(|state| {
+            Ok(state
+                .dependencies
+                .iter()
+                .filter(|(task, _)| *task == task_id)
+                .filter(|(_, blocker)| state.tasks.get(blocker).is_some_and(|t| !t.completed))
+                .count() as i64)
+        })
+    }
+
+    fn find_tag(&self, tag_id: Uuid) -> Result<Tag, RepositoryError> {
+        self.with(|state| {
+            state
+                .tags
+                .get(&tag_id)
+                .cloned()
+                .ok_or(RepositoryError::NotFound)
+        })
+    }
+
+    fn find_tags(&self, tasks: &[Task]) -> Result<Vec<Vec<Tag>>, RepositoryError> {
+        self.with(|state| {
+            Ok(tasks
+                .iter()
+                .map(|task| {
+                    let mut tags: Vec<Tag> = state
+                        .task_tags
+                        .iter()
+                        .filter(|(id, _)| *id == task.id)
+                        .filter_map(|(_, tag_id)| state.tags.get(tag_id).cloned())
+                        .collect();
+                    tags.sort_by(|a, b| a.name.cmp(&b.name));
+                    tags
+                })
+                .collect())
+        })
+    }
+
+    fn attach_tag(&self, task_id: Uuid, tag_id: Uuid) -> Result<(), RepositoryError> {
+        self.with(|state| {
+            if !state.tasks.contains_key(&task_id) || !state.tags.contains_key(&tag_id) {
+                return Err(RepositoryError::NotFound);
+            }
+            state.task_tags.insert((task_id, tag_id));
+            Ok(())
+        })
+    }
+
+    fn detach_tag(&self, task_id: Uuid, tag_id: Uuid) -> Result<(), RepositoryError> {
+        self.with(|state| {
+            if !state.task_tags.remove(&(task_id, tag_id)) {
+                return Err(RepositoryError::NotFound);
+            }
+            Ok(())
+        })
+    }
+
+    fn transaction(
+        &self,
+        _isolation: IsolationLevel,
+        work: &mut TransactionWork<'_>,
+    ) -> Result<(), AppError> {
+        self.with(|state| {
+            let snapshot = state.clone();
+            let result = work(&MemoryTaskStore {
+                state: Backing::Borrowed(Mutex::new(&mut *state)),
+            });
+            if result.is_err() {
+                *state = snapshot;
+            }
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::Priority;
+
+    fn new_task(store: &dyn TaskStore, title: &str, position: &str) -> NewTask {
+        NewTask {
+            title: title.to_string(),
+            description: None,
+            completed: false,
+            due_at: None,
+            priority: Priority::Medium,
+            project_id: None,
+            parent_id: None,
+            position: position.to_string(),
+            status_id: store.find_workflow(None).unwrap()[0].id,
+            recurrence: None,
+            recurrence_timezone: None,
+            recurrence_start: None,
+        }
+    }
+
+    #[test]
+    fn test_failed_transactions_roll_back() {
+        let store = MemoryTaskStore::new();
+        let kept = store.create_task(new_task(&store, "kept", "a")).unwrap();
+
+        let store: &dyn TaskStore = &store;
+        let result: Result<(), AppError> = store.in_transaction(|tx| {
+            tx.create_task(new_task(tx, "discarded", "b"))?;
+            tx.delete_task(kept.id, ChildrenPolicy::Cascade)?;
+            Err(AppError::conflict("stop"))
+        });
+        assert!(result.is_err());
+
+        let tasks = store.find_tasks(&TaskFilter::default(), true).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, kept.id);
+    }
+
+    #[test]
+    fn test_subtrees_and_deletes_follow_the_hierarchy() {
+        let store = MemoryTaskStore::new();
+        let root = store.create_task(new_task(&store, "root", "a")).unwrap();
+        let child = store
+            .create_task(NewTask {
+                parent_id: Some(root.id),
+                ..new_task(&store, "child", "b")
+            })
+            .unwrap();
+        let grandchild = store
+            .create_task(NewTask {
+                parent_id: Some(child.id),
+                ..new_task(&store, "grandchild", "c")
+            })
+            .unwrap();
+
+        let depths: Vec<i32> = store
+            .find_subtree(root.id)
+            .unwrap()
+            .iter()
+            .map(|node| node.depth)
+            .collect();
+        assert_eq!(depths, [0, 1, 2]);
+        assert!(store.is_self_or_ancestor(root.id, grandchild.id).unwrap());
+        assert!(!store.is_self_or_ancestor(grandchild.id, root.id).unwrap());
+
+        store
+            .delete_task(child.id, ChildrenPolicy::Reparent)
+            .unwrap();
+        assert_eq!(
+            store.find_task(grandchild.id).unwrap().parent_id,
+            Some(root.id)
+        );
+
+        store.delete_task(root.id, ChildrenPolicy::Cascade).unwrap();
+        assert!(matches!(
+            store.delete_task(root.id, ChildrenPolicy::Cascade),
+            Err(RepositoryError::NotFound)
+        ));
+        assert!(store.find_task(grandchild.id).is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! Storage behind [`TaskService`](crate::services::TaskService)
+//!
+//! [`TaskStore`] is everything the task endpoints read and write: tasks and
+//! the statuses, projects, tags and dependencies they are checked against.
+//! [`PgTaskStore`] runs on PostgreSQL through the repositories, and
+//! [`MemoryTaskStore`] keeps everything in memory for tests and demos. With
+//! the `sqlite` feature, `SqliteTaskStore` runs on a SQLite file.
+
+#[cfg(test)]
+mod conformance;
+mod memory;
+mod postgres;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use memory::MemoryTaskStore;
+pub use postgres::PgTaskStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteTaskStore;
+
+use axum_diesel_api::ChildrenPolicy;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::db::error::RepositoryError;
+use crate::db::models::{NewTask, Project, Tag, Task, TaskNode, TaskStatus, UpdateTask};
+use crate::db::repositories::TaskFilter;
+use crate::error::AppError;
+
+/// Work run by [`TaskStore::transaction`]
+pub type TransactionWork<'a> = dyn FnMut(&dyn TaskStore) -> Result<(), AppError> + 'a;
+
+/// Isolation level of a [`TaskStore::transaction`]
+///
+/// The Postgres store sets it on top-level transactions; a nested transaction
+/// runs at the level of the one around it. The SQLite and in-memory stores
+/// run one transaction at a time, which is always serializable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IsolationLevel {
+    #[default]
+    ReadCommitted,
+    /// Rows read stay as they were; a concurrent change to them fails the
+    /// transaction with a retryable error
+    RepeatableRead,
+    Serializable,
+}
+
+impl IsolationLevel {
+    /// Statement choosing the level, run first in a transaction
+    pub(crate) fn statement(self) -> &'static str {
+        match self {
+            IsolationLevel::ReadCommitted => "SET TRANSACTION ISOLATION LEVEL READ COMMITTED",
+            IsolationLevel::RepeatableRead => "SET TRANSACTION ISOLATION LEVEL REPEATABLE READ",
+            IsolationLevel::Serializable => "SET TRANSACTION ISOLATION LEVEL SERIALIZABLE",
+        }
+    }
+}
+
+/// Task storage, see the [module documentation](self)
+///
+/// Methods behave like the [`TaskRepository`](crate::db::repositories::TaskRepository)
+/// methods of the same name. The Postgres store also records the outbox events of
+/// its writes.
+pub trait TaskStore: Send + Sync {
+    /// Tasks matching a filter, in manual order
+    fn find_tasks(
+        &self,
+        filter: &TaskFilter,
+        with_description: bool,
+    ) -> Result<Vec<Task>, RepositoryError>;
+
+    fn find_task(&self, task_id: Uuid) -> Result<Task, RepositoryError>;
+
+    /// Direct subtasks of a task, in manual order
+    fn find_children(
+        &self,
+        parent_id: Uuid,
+        with_description: bool,
+    ) -> Result<Vec<Task>, RepositoryError>;
+
+    /// A task and its descendants, parents before children
+    fn find_subtree(&self, root_id: Uuid) -> Result<Vec<TaskNode>, RepositoryError>;
+
+    /// Whether `task_id` is `node_id` itself or one of its ancestors
+    fn is_self_or_ancestor(&self, task_id: Uuid, node_id: Uuid) -> Result<bool, RepositoryError>;
+
+    fn create_task(&self, new_task: NewTask) -> Result<Task, RepositoryError>;
+
+    /// Fails with `NotFound` if no task was updated
+    fn update_task(&self, task_id: Uuid, update_task: UpdateTask) -> Result<Task, RepositoryError>;
+
+    /// Delete a task, cascading to or re-parenting its subtasks
+    ///
+    /// Fails with `NotFound` if no task was deleted.
+    fn delete_task(&self, task_id: Uuid, children: ChildrenPolicy) -> Result<(), RepositoryError>;
+
+    /// Highest rank in use
+    fn max_position(&self) -> Result<Option<String>, RepositoryError>;
+
+    /// First rank after `position`, ignoring `exclude`
+    fn next_position(
+        &self,
+        position: &str,
+        exclude: Uuid,
+    ) -> Result<Option<String>, RepositoryError>;
+
+    /// Last rank before `position`, ignoring `exclude`
+    fn previous_position(
+        &self,
+        position: &str,
+        exclude: Uuid,
+    ) -> Result<Option<String>, RepositoryError>;
+
+    /// Length of the longest rank
+    fn max_position_len(&self) -> Result<Option<i32>, RepositoryError>;
+
+    /// Fails with `NotFound` if no task was updated
+    fn set_position(&self, task_id: Uuid, position: &str) -> Result<Task, RepositoryError>;
+
+    /// Respread every rank evenly, returning the number of tasks updated
+    fn rebalance_positions(&self) -> Result<usize, RepositoryError>;
+
+    fn find_project(&self, project_id: Uuid) -> Result<Project, RepositoryError>;
+
+    /// Statuses of a workflow in column order (`None` is the default workflow)
+    fn find_workflow(&self, project_id: Option<Uuid>) -> Result<Vec<TaskStatus>, RepositoryError>;
+
+    fn find_status(&self, status_id: Uuid) -> Result<TaskStatus, RepositoryError>;
+
+    fn find_statuses(&self, status_ids: &[Uuid]) -> Result<Vec<TaskStatus>, RepositoryError>;
+
+    fn find_status_by_name(
+        &self,
+        project_id: Option<Uuid>,
+        name: &str,
+    ) -> Result<TaskStatus, RepositoryError>;
+
+    fn is_transition_allowed(&self, from: Uuid, to: Uuid) -> Result<bool, RepositoryError>;
+
+    /// Number of blockers of a task that are not completed
+    fn count_open_blockers(&self, task_id: Uuid) -> Result<i64, RepositoryError>;
+
+    fn find_tag(&self, tag_id: Uuid) -> Result<Tag, RepositoryError>;
+
+    /// Tags of several tasks by name, entry `i` holding the tags of `tasks[i]`
+    fn find_tags(&self, tasks: &[Task]) -> Result<Vec<Vec<Tag>>, RepositoryError>;
+
+    /// Attach a tag to a task (no-op if already attached)
+    ///
+    /// Fails with `NotFound` if the task or the tag does not exist.
+    fn attach_tag(&self, task_id: Uuid, tag_id: Uuid) -> Result<(), RepositoryError>;
+
+    /// Fails with `NotFound` if the tag was not attached to the task
+    fn detach_tag(&self, task_id: Uuid, tag_id: Uuid) -> Result<(), RepositoryError>;
+
+    /// Run `work` so that its writes are all kept or all discarded
+    ///
+    /// Above `ReadCommitted`, `work` may run again after a serialization
+    /// failure. Prefer [`in_transaction`](Self::in_transaction), which returns
+    /// a value.
+    fn transaction(
+        &self,
+        isolation: IsolationLevel,
+        work: &mut TransactionWork<'_>,
+    ) -> Result<(), AppError>;
+}
+
+impl dyn TaskStore + '_ {
+    /// Run `work` in a transaction and return its result
+    pub fn in_transaction<T>(
+        &self,
+        work: impl FnMut(&dyn TaskStore) -> Result<T, AppError>,
+    ) -> Result<T, AppError> {
+        self.in_transaction_at(IsolationLevel::default(), work)
+    }
+
+    /// Run `work` in a transaction at `isolation` and return its result
+    pub fn in_transaction_at<T>(
+        &self,
+        isolation: IsolationLevel,
+        mut work: impl FnMut(&dyn TaskStore) -> Result<T, AppError>,
+    ) -> Result<T, AppError> {
+        let mut result = None;
+        self.transaction(isolation, &mut |store| {
+            result = Some(work(store)?);
+            Ok(())
+        })?;
+        Ok(result.expect("a committed transaction ran its work"))
+    }
+}
+
+/// The task an `INSERT` of `new_task` at `now` creates, for stores without
+/// the Postgres defaults and triggers
+fn inserted(new_task: NewTask, now: DateTime<Utc>) -> Task {
+    Task {
+        id: Uuid::new_v4(),
+        title: new_task.title,
+        description: new_task.description,
+        completed: new_task.completed,
+        created_at: now,
+        updated_at: now,
+        due_at: new_task.due_at,
+        priority: new_task.priority,
+        completed_at: new_task.completed.then_some(now),
+        project_id: new_task.project_id,
+        archived_at: None,
+        parent_id: new_task.parent_id,
+        position: new_task.position,
+        status_id: new_task.status_id,
+        recurrence: new_task.recurrence,
+        recurrence_timezone: new_task.recurrence_timezone,
+        recurrence_start: new_task.recurrence_start,
+    }
+}
+
+/// The task an `UPDATE` of `task` at `now` leaves, including what the
+/// `updated_at` and `completed_at` triggers do
+fn apply_update(mut task: Task, update_task: UpdateTask, now: DateTime<Utc>) -> Task {
+    let was_completed = task.completed;
+
+    let UpdateTask {
+        title,
+        description,
+        completed,
+        due_at,
+        priority,
+        project_id,
+        parent_id,
+        status_id,
+        recurrence,
+        recurrence_timezone,
+        recurrence_start,
+    } = update_task;
+    task.title = title.unwrap_or(task.title);
+    task.description = description.or(task.description);
+    task.completed = completed.unwrap_or(task.completed);
+    task.due_at = due_at.unwrap_or(task.due_at);
+    task.priority = priority.unwrap_or(task.priority);
+    task.project_id = project_id.unwrap_or(task.project_id);
+    task.parent_id = parent_id.unwrap_or(task.parent_id);
+    task.status_id = status_id.unwrap_or(task.status_id);
+    task.recurrence = recurrence.unwrap_or(task.recurrence);
+    task.recurrence_timezone = recurrence_timezone.unwrap_or(task.recurrence_timezone);
+    task.recurrence_start = recurrence_start.unwrap_or(task.recurrence_start);
+
+    task.updated_at = now;
+    if !task.completed {
+        task.completed_at = None;
+    } else if !was_completed {
+        task.completed_at = Some(now);
+    }
+    task
+}
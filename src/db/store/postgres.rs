@@ -0,0 +1,236 @@
+use axum_diesel_api::ChildrenPolicy;
+use diesel::{Connection, RunQueryDsl};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use super::{IsolationLevel, TaskStore, TransactionWork};
+use crate::db::connection::{self, DbConnection};
+use crate::db::error::RepositoryError;
+use crate::db::models::{NewTask, Project, Tag, Task, TaskNode, TaskStatus, UpdateTask};
+use crate::db::repositories::{
+    DependencyRepository, ProjectRepository, StatusRepository, TagRepository, TaskFilter,
+    TaskRepository,
+};
+use crate::db::retry::retry_transient;
+use crate::error::AppError;
+
+/// [`TaskStore`] on PostgreSQL
+///
+/// Takes a connection from the pool for every call, or uses the one it was
+/// given, e.g. inside a transaction. Task reads go to the read replica when
+/// there is one (see [`connection`]), and writes send the rest of the request's
+/// reads to the primary.
+#[derive(Default)]
+pub struct PgTaskStore<'c> {
+    conn: Option<Mutex<&'c mut DbConnection>>,
+}
+
+impl PgTaskStore<'static> {
+    /// Store using the shared pool
+    pub fn new() -> Self {
+        Self { conn: None }
+    }
+}
+
+impl<'c> PgTaskStore<'c> {
+    /// Store running every call on `conn`
+    pub fn with_connection(conn: &'c mut DbConnection) -> Self {
+        Self {
+            conn: Some(Mutex::new(conn)),
+        }
+    }
+
+    fn run<T>(
+        &self,
+        f: impl FnOnce(&mut DbConnection) -> Result<T, RepositoryError>,
+    ) -> Result<T, RepositoryError> {
+        self.run_on(connection::get_connection, f)
+    }
+
+    /// Run a read-only task query, on the replica if the request may use it
+    fn read<T>(
+        &self,
+        f: impl FnOnce(&mut DbConnection) -> Result<T, RepositoryError>,
+    ) -> Result<T, RepositoryError> {
+        self.run_on(connection::get_read_connection, f)
+    }
+
+    /// Run a write, then keep the request reading from the primary
+    fn write<T>(
+        &self,
+        f: impl FnOnce(&mut DbConnection) -> Result<T, RepositoryError>,
+    ) -> Result<T, RepositoryError> {
+        let result = self.run(f);
+        connection::stick_to_primary();
+        result
+    }
+
+    fn run_on<T>(
+        &self,
+        connect: impl FnOnce() -> anyhow::Result<DbConnection>,
+        f: impl FnOnce(&mut DbConnection) -> Result<T, RepositoryError>,
+    ) -> Result<T, RepositoryError> {
+        match self.conn {
+            Some(ref conn) => f(&mut conn.lock().unwrap_or_else(|e| e.into_inner())),
+            None => {
+                let mut conn = connect().map_err(|e| {
+                    RepositoryError::Unavailable(format!("Failed to get connection: {}", e))
+                })?;
+                f(&mut conn)
+            }
+        }
+    }
+}
+
+impl TaskStore for PgTaskStore<'_> {
+    fn find_tasks(
+        &self,
+        filter: &TaskFilter,
+        with_description: bool,
+    ) -> Result<Vec<Task>, RepositoryError> {
+        self.read(|conn| TaskRepository::find_filtered(conn, filter, with_description))
+    }
+
+    fn find_task(&self, task_id: Uuid) -> Result<Task, RepositoryError> {
+        self.read(|conn| TaskRepository::find_by_id(conn, task_id))
+    }
+
+    fn find_children(
+        &self,
+        parent_id: Uuid,
+        with_description: bool,
+    ) -> Result<Vec<Task>, RepositoryError> {
+        self.read(|conn| TaskRepository::find_children(conn, parent_id, with_description))
+    }
+
+    fn find_subtree(&self, root_id: Uuid) -> Result<Vec<TaskNode>, RepositoryError> {
+        self.read(|conn| TaskRepository::find_subtree(conn, root_id))
+    }
+
+    fn is_self_or_ancestor(&self, task_id: Uuid, node_id: Uuid) -> Result<bool, RepositoryError> {
+        self.run(|conn| TaskRepository::is_self_or_ancestor(conn, task_id, node_id))
+    }
+
+    fn create_task(&self, new_task: NewTask) -> Result<Task, RepositoryError> {
+        self.write(|conn| TaskRepository::create(conn, new_task))
+    }
+
+    fn update_task(&self, task_id: Uuid, update_task: UpdateTask) -> Result<Task, RepositoryError> {
+        self.write(|conn| TaskRepository::update(conn, task_id, update_task))
+    }
+
+    fn delete_task(&self, task_id: Uuid, children: ChildrenPolicy) -> Result<(), RepositoryError> {
+        self.write(|conn| match children {
+            ChildrenPolicy::Cascade => TaskRepository::delete(conn, task_id),
+            ChildrenPolicy::Reparent => TaskRepository::delete_reparenting(conn, task_id),
+        })
+    }
+
+    fn max_position(&self) -> Result<Option<String>, RepositoryError> {
+        self.run(TaskRepository::max_position)
+    }
+
+    fn next_position(
+        &self,
+        position: &str,
+        exclude: Uuid,
+    ) -> Result<Option<String>, RepositoryError> {
+        self.run(|conn| TaskRepository::next_position(conn, position, exclude))
+    }
+
+    fn previous_position(
+        &self,
+        position: &str,
+        exclude: Uuid,
+    ) -> Result<Option<String>, RepositoryError> {
+        self.run(|conn| TaskRepository::previous_position(conn, position, exclude))
+    }
+
+    fn max_position_len(&self) -> Result<Option<i32>, RepositoryError> {
+        self.run(TaskRepository::max_position_len)
+    }
+
+    fn set_position(&self, task_id: Uuid, position: &str) -> Result<Task, RepositoryError> {
+        self.write(|conn| TaskRepository::set_position(conn, task_id, position))
+    }
+
+    fn rebalance_positions(&self) -> Result<usize, RepositoryError> {
+        self.write(TaskRepository::rebalance_positions)
+    }
+
+    fn find_project(&self, project_id: Uuid) -> Result<Project, RepositoryError> {
+        self.run(|conn| ProjectRepository::find_by_id(conn, project_id))
+    }
+
+    fn find_workflow(&self, project_id: Option<Uuid>) -> Result<Vec<TaskStatus>, RepositoryError> {
+        self.run(|conn| StatusRepository::find_workflow(conn, project_id))
+    }
+
+    fn find_status(&self, status_id: Uuid) -> Result<TaskStatus, RepositoryError> {
+        self.run(|conn| StatusRepository::find_by_id(conn, status_id))
+    }
+
+    fn find_statuses(&self, status_ids: &[Uuid]) -> Result<Vec<TaskStatus>, RepositoryError> {
+        self.run(|conn| StatusRepository::find_by_ids(conn, status_ids))
+    }
+
+    fn find_status_by_name(
+        &self,
+        project_id: Option<Uuid>,
+        name: &str,
+    ) -> Result<TaskStatus, RepositoryError> {
+        self.run(|conn| StatusRepository::find_by_name(conn, project_id, name))
+    }
+
+    fn is_transition_allowed(&self, from: Uuid, to: Uuid) -> Result<bool, RepositoryError> {
+        self.run(|conn| StatusRepository::is_transition_allowed(conn, from, to))
+    }
+
+    fn count_open_blockers(&self, task_id: Uuid) -> Result<i64, RepositoryError> {
+        self.run(|conn| DependencyRepository::count_open_blockers(conn, task_id))
+    }
+
+    fn find_tag(&self, tag_id: Uuid) -> Result<Tag, RepositoryError> {
+        self.run(|conn| TagRepository::find_by_id(conn, tag_id))
+    }
+
+    fn find_tags(&self, tasks: &[Task]) -> Result<Vec<Vec<Tag>>, RepositoryError> {
+        self.run(|conn| TagRepository::find_by_tasks(conn, tasks))
+    }
+
+    fn attach_tag(&self, task_id: Uuid, tag_id: Uuid) -> Result<(), RepositoryError> {
+        self.write(|conn| TagRepository::attach(conn, task_id, tag_id))
+    }
+
+    fn detach_tag(&self, task_id: Uuid, tag_id: Uuid) -> Result<(), RepositoryError> {
+        self.write(|conn| TagRepository::detach(conn, task_id, tag_id))
+    }
+
+    fn transaction(
+        &self,
+        isolation: IsolationLevel,
+        work: &mut TransactionWork<'_>,
+    ) -> Result<(), AppError> {
+        // Nested in another transaction: a savepoint at the outer level
+        let top_level = self.conn.is_none();
+        let mut attempt = || {
+            let mut result = Ok(());
+            self.write(|conn| {
+                result = conn.transaction(|conn| {
+                    if top_level {
+                        diesel::sql_query(isolation.statement()).execute(conn)?;
+                    }
+                    work(&PgTaskStore::with_connection(conn))
+                });
+                Ok(())
+            })?;
+            result
+        };
+
+        if top_level && isolation != IsolationLevel::ReadCommitted {
+            retry_transient(attempt)
+        } else {
+            attempt()
+        }
+    }
+}
@@ -0,0 +1,1172 @@
+use axum_diesel_api::ChildrenPolicy;
+use chrono::{DateTime, SubsecRound, Utc};
+use diesel::connection::SimpleConnection;
+use diesel::dsl::{count, exists};
+use diesel::prelude::*;
+use diesel::r2d2::{self, ConnectionManager, CustomizeConnection, Pool};
+use diesel::sql_types::{Integer, Nullable, Text};
+use diesel::sqlite::{Sqlite, SqliteConnection};
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use super::{IsolationLevel, TaskStore, TransactionWork, apply_update, inserted};
+use crate::db::error::RepositoryError;
+use crate::db::models::{NewTask, Priority, Project, Tag, Task, TaskNode, TaskStatus, UpdateTask};
+use crate::db::repositories::TaskFilter;
+use crate::error::AppError;
+
+use schema::{
+    projects, tags, task_dependencies, task_status_transitions, task_statuses, task_tags, tasks,
+};
+
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations_sqlite");
+
+/// Fixed-width RFC 3339, so that comparing the text compares the times
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.6fZ";
+
+type SqlitePool = Pool<ConnectionManager<SqliteConnection>>;
+
+/// Tables of `migrations_sqlite`: UUIDs and timestamps are text
+mod schema {
+    diesel::table! {
+        projects (id) {
+            id -> Text,
+            name -> Text,
+            description -> Nullable<Text>,
+            archived_at -> Nullable<Text>,
+            created_at -> Text,
+            updated_at -> Text,
+        }
+    }
+
+    diesel::table! {
+        tags (id) {
+            id -> Text,
+            name -> Text,
+            created_at -> Text,
+            updated_at -> Text,
+        }
+    }
+
+    diesel::table! {
+        task_statuses (id) {
+            id -> Text,
+            project_id -> Nullable<Text>,
+            name -> Text,
+            position -> Integer,
+            is_done -> Bool,
+            created_at -> Text,
+            updated_at -> Text,
+        }
+    }
+
+    diesel::table! {
+        task_status_transitions (from_status_id, to_status_id) {
+            from_status_id -> Text,
+            to_status_id -> Text,
+            created_at -> Text,
+        }
+    }
+
+    diesel::table! {
+        tasks (id) {
+            id -> Text,
+            title -> Text,
+            description -> Nullable<Text>,
+            completed -> Bool,
+            created_at -> Text,
+            updated_at -> Text,
+            due_at -> Nullable<Text>,
+            priority -> Text,
+            completed_at -> Nullable<Text>,
+            project_id -> Nullable<Text>,
+            archived_at -> Nullable<Text>,
+            parent_id -> Nullable<Text>,
+            position -> Text,
+            status_id -> Text,
+            recurrence -> Nullable<Text>,
+            recurrence_timezone -> Nullable<Text>,
+            recurrence_start -> Nullable<Text>,
+        }
+    }
+
+    diesel::table! {
+        task_tags (task_id, tag_id) {
+            task_id -> Text,
+            tag_id -> Text,
+            created_at -> Text,
+        }
+    }
+
+    diesel::table! {
+        task_dependencies (task_id, blocked_by_id) {
+            task_id -> Text,
+            blocked_by_id -> Text,
+            created_at -> Text,
+        }
+    }
+
+    diesel::joinable!(task_tags -> tags (tag_id));
+
+    diesel::allow_tables_to_appear_in_same_query!(
+        projects,
+        tags,
+        task_statuses,
+        task_status_transitions,
+        tasks,
+        task_tags,
+        task_dependencies,
+    );
+}
+
+/// Current time at the precision PostgreSQL keeps (microseconds)
+fn now() -> DateTime<Utc> {
+    Utc::now().trunc_subsecs(6)
+}
+
+fn to_text(time: DateTime<Utc>) -> String {
+    // Rounded to microseconds like PostgreSQL input
+    time.round_subsecs(6).format(TIMESTAMP_FORMAT).to_string()
+}
+
+fn to_time(text: &str) -> Result<DateTime<Utc>, RepositoryError> {
+    DateTime::parse_from_rfc3339(text)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| RepositoryError::Database(format!("Invalid timestamp {:?}: {}", text, e)))
+}
+
+fn to_uuid(text: &str) -> Result<Uuid, RepositoryError> {
+    text.parse()
+        .map_err(|e| RepositoryError::Database(format!("Invalid UUID {:?}: {}", text, e)))
+}
+
+fn priority_text(priority: Priority) -> &'static str {
+    match priority {
+        Priority::Low => "low",
+        Priority::Medium => "medium",
+        Priority::High => "high",
+        Priority::Urgent => "urgent",
+    }
+}
+
+fn to_priority(text: &str) -> Result<Priority, RepositoryError> {
+    match text {
+        "low" => Ok(Priority::Low),
+        "medium" => Ok(Priority::Medium),
+        "high" => Ok(Priority::High),
+        "urgent" => Ok(Priority::Urgent),
+        other => Err(RepositoryError::Database(format!(
+            "Unrecognized task priority: {}",
+            other
+        ))),
+    }
+}
+
+#[derive(Queryable, QueryableByName, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = tasks, check_for_backend(Sqlite), treat_none_as_null = true)]
+struct TaskRow {
+    id: String,
+    title: String,
+    description: Option<String>,
+    completed: bool,
+    created_at: String,
+    updated_at: String,
+    due_at: Option<String>,
+    priority: String,
+    completed_at: Option<String>,
+    project_id: Option<String>,
+    archived_at: Option<String>,
+    parent_id: Option<String>,
+    position: String,
+    status_id: String,
+    recurrence: Option<String>,
+    recurrence_timezone: Option<String>,
+    recurrence_start: Option<String>,
+}
+
+impl From<&Task> for TaskRow {
+    fn from(task: &Task) -> Self {
+        Self {
+            id: task.id.to_string(),
+            title: task.title.clone(),
+            description: task.description.clone(),
+            completed: task.completed,
+            created_at: to_text(task.created_at),
+            updated_at: to_text(task.updated_at),
+            due_at: task.due_at.map(to_text),
+            priority: priority_text(task.priority).to_string(),
+            completed_at: task.completed_at.map(to_text),
+            project_id: task.project_id.map(|id| id.to_string()),
+            archived_at: task.archived_at.map(to_text),
+            parent_id: task.parent_id.map(|id| id.to_string()),
+            position: task.position.clone(),
+            status_id: task.status_id.to_string(),
+            recurrence: task.recurrence.clone(),
+            recurrence_timezone: task.recurrence_timezone.clone(),
+            recurrence_start: task.recurrence_start.map(to_text),
+        }
+    }
+}
+
+impl TryFrom<TaskRow> for Task {
+    type Error = RepositoryError;
+
+    fn try_from(row: TaskRow) -> Result<Self, Self::Error> {
+        Ok(Task {
+            id: to_uuid(&row.id)?,
+            title: row.title,
+            description: row.description,
+            completed: row.completed,
+            created_at: to_time(&row.created_at)?,
+            updated_at: to_time(&row.updated_at)?,
+            due_at: row.due_at.as_deref().map(to_time).transpose()?,
+            priority: to_priority(&row.priority)?,
+            completed_at: row.completed_at.as_deref().map(to_time).transpose()?,
+            project_id: row.project_id.as_deref().map(to_uuid).transpose()?,
+            archived_at: row.archived_at.as_deref().map(to_time).transpose()?,
+            parent_id: row.parent_id.as_deref().map(to_uuid).transpose()?,
+            position: row.position,
+            status_id: to_uuid(&row.status_id)?,
+            recurrence: row.recurrence,
+            recurrence_timezone: row.recurrence_timezone,
+            recurrence_start: row.recurrence_start.as_deref().map(to_time).transpose()?,
+        })
+    }
+}
+
+#[derive(QueryableByName)]
+struct TaskNodeRow {
+    #[diesel(embed)]
+    task: TaskRow,
+    #[diesel(sql_type = Integer)]
+    depth: i32,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = task_statuses, check_for_backend(Sqlite))]
+struct StatusRow {
+    id: String,
+    project_id: Option<String>,
+    name: String,
+    position: i32,
+    is_done: bool,
+    created_at: String,
+    updated_at: String,
+}
+
+impl TryFrom<StatusRow> for TaskStatus {
+    type Error = RepositoryError;
+
+    fn try_from(row: StatusRow) -> Result<Self, Self::Error> {
+        Ok(TaskStatus {
+            id: to_uuid(&row.id)?,
+            project_id: row.project_id.as_deref().map(to_uuid).transpose()?,
+            name: row.name,
+            position: row.position,
+            is_done: row.is_done,
+            created_at: to_time(&row.created_at)?,
+            updated_at: to_time(&row.updated_at)?,
+        })
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = projects, check_for_backend(Sqlite))]
+struct ProjectRow {
+    id: String,
+    name: String,
+    description: Option<String>,
+    archived_at: Option<String>,
+    created_at: String,
+    updated_at: String,
+}
+
+impl TryFrom<ProjectRow> for Project {
+    type Error = RepositoryError;
+
+    fn try_from(row: ProjectRow) -> Result<Self, Self::Error> {
+        Ok(Project {
+            id: to_uuid(&row.id)?,
+            name: row.name,
+            description: row.description,
+            archived_at: row.archived_at.as_deref().map(to_time).transpose()?,
+            created_at: to_time(&row.created_at)?,
+            updated_at: to_time(&row.updated_at)?,
+        })
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = tags, check_for_backend(Sqlite))]
+struct TagRow {
+    id: String,
+    name: String,
+    created_at: String,
+    updated_at: String,
+}
+
+impl TryFrom<TagRow> for Tag {
+    type Error = RepositoryError;
+
+    fn try_from(row: TagRow) -> Result<Self, Self::Error> {
+        Ok(Tag {
+            id: to_uuid(&row.id)?,
+            name: row.name,
+            created_at: to_time(&row.created_at)?,
+            updated_at: to_time(&row.updated_at)?,
+        })
+    }
+}
+
+/// Convert loaded rows into models
+fn convert<R, T>(rows: Vec<R>) -> Result<Vec<T>, RepositoryError>
+where
+    T: TryFrom<R, Error = RepositoryError>,
+{
+    rows.into_iter().map(T::try_from).collect()
+}
+
+/// Turns on foreign keys (off by default in SQLite) and waits for locks
+#[derive(Debug)]
+struct Pragmas;
+
+impl CustomizeConnection<SqliteConnection, r2d2::Error> for Pragmas {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), r2d2::Error> {
+        conn.batch_execute("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;")
+            .map_err(r2d2::Error::QueryError)
+    }
+}
+
+enum Backing<'c> {
+    Pool(SqlitePool),
+    Borrowed(Mutex<&'c mut SqliteConnection>),
+}
+
+/// [`TaskStore`] on SQLite, for local runs and tests without PostgreSQL
+///
+/// Only the task endpoints run on it: the repositories behind every other
+/// service are written for PostgreSQL. Projects, tags and dependencies are
+/// read but never created outside tests.
+///
+/// Uses the tables of `migrations_sqlite`, one migration per PostgreSQL
+/// migration, with UUIDs and timestamps stored as text. Timestamps keep microseconds
+/// like PostgreSQL, so tasks read back the same on both. The
+/// `updated_at` and `completed_at` triggers are done in code, and no outbox
+/// events are written.
+pub struct SqliteTaskStore<'c> {
+    conn: Backing<'c>,
+}
+
+impl SqliteTaskStore<'static> {
+    /// Open (or create) the database at `path` and apply the migrations
+    ///
+    /// `:memory:` gives a private database, kept on a single connection.
+    pub fn open(path: &str) -> Result<Self, RepositoryError> {
+        let in_memory = path == ":memory:";
+        let pool = Pool::builder()
+            .max_size(if in_memory { 1 } else { 8 })
+            // An in-memory database lives as long as its connection
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connection_customizer(Box::new(Pragmas))
+            .build(ConnectionManager::new(path))
+            .map_err(|e| RepositoryError::Unavailable(e.to_string()))?;
+
+        let mut conn = pool
+            .get()
+            .map_err(|e| RepositoryError::Unavailable(e.to_string()))?;
+        if !in_memory {
+            // Readers do not wait for writers
+            conn.batch_execute("PRAGMA journal_mode = WAL;")?;
+        }
+        conn.run_pending_migrations(MIGRATIONS)
+            .map_err(|e| RepositoryError::Database(format!("Migrations failed: {}", e)))?;
+
+        Ok(Self {
+            conn: Backing::Pool(pool),
+        })
+    }
+}
+
+impl<'c> SqliteTaskStore<'c> {
+    /// Store running every call on `conn`
+    pub fn with_connection(conn: &'c mut SqliteConnection) -> Self {
+        Self {
+            conn: Backing::Borrowed(Mutex::new(conn)),
+        }
+    }
+
+    fn run<T>(
+        &self,
+        f: impl FnOnce(&mut SqliteConnection) -> Result<T, RepositoryError>,
+    ) -> Result<T, RepositoryError> {
+        match self.conn {
+            Backing::Borrowed(ref conn) => f(&mut conn.lock().unwrap_or_else(|e| e.into_inner())),
+            Backing::Pool(ref pool) => {
+                let mut conn = pool.get().map_err(|e| {
+                    RepositoryError::Unavailable(format!("Failed to get connection: {}", e))
+                })?;
+                f(&mut conn)
+            }
+        }
+    }
+
+    /// Run `f` in a transaction, taking the write lock up front
+    ///
+    /// A deferred transaction that reads before writing can fail with
+    /// `SQLITE_BUSY` instead of waiting when another connection writes.
+    fn write<T>(
+        &self,
+        f: impl FnOnce(&mut SqliteConnection) -> Result<T, RepositoryError>,
+    ) -> Result<T, RepositoryError> {
+        match self.conn {
+            // Already in a transaction: a savepoint
+            Backing::Borrowed(ref conn) => conn
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .transaction(f),
+            Backing::Pool(_) => self.run(|conn| conn.immediate_transaction(f)),
+        }
+    }
+
+    /// Boxed query over the tasks matching a filter
+    fn filtered(filter: &TaskFilter) -> tasks::BoxedQuery<'_, Sqlite> {
+        let mut query = tasks::table.into_boxed();
+
+        if !filter.tags.is_empty() {
+            let tagged = task_tags::table
+                .inner_join(tags::table)
+                .filter(tags::name.eq_any(&filter.tags))
+                .select(task_tags::task_id);
+
+            query = if filter.match_all_tags {
+                let required = filter.tags.len() as i64;
+                query.filter(
+                    tasks::id.eq_any(
+                        tagged
+                            .group_by(task_tags::task_id)
+                            .having(count(task_tags::tag_id).eq(required)),
+                    ),
+                )
+            } else {
+                query.filter(tasks::id.eq_any(tagged))
+            };
+        }
+
+        let now = to_text(now());
+        match filter.overdue {
+            Some(true) => {
+                query = query
+                    .filter(tasks::completed.eq(false))
+                    .filter(tasks::due_at.lt(now));
+            }
+            Some(false) => {
+                query = query.filter(
+                    tasks::completed
+                        .eq(true)
+                        .or(tasks::due_at.is_null())
+                        .or(tasks::due_at.ge(now)),
+                );
+            }
+            None => {}
+        }
+
+        if filter.has_due_at {
+            query = query.filter(tasks::due_at.is_not_null());
+        }
+
+        if let Some(due_before) = filter.due_before {
+            query = query.filter(tasks::due_at.lt(to_text(due_before)));
+        }
+
+        if let Some(priority) = filter.priority {
+            query = query.filter(tasks::priority.eq(priority_text(priority)));
+        }
+
+        if let Some(project_id) = filter.project_id {
+            query = query.filter(tasks::project_id.eq(project_id.to_string()));
+        }
+
+        if !filter.include_archived {
+            query = query.filter(tasks::archived_at.is_null());
+        }
+
+        if let Some(completed) = filter.completed {
+            query = query.filter(tasks::completed.eq(completed));
+        }
+
+        if let Some(ref status) = filter.status {
+            query = query.filter(
+                tasks::status_id.eq_any(
+                    task_statuses::table
+                        .filter(task_statuses::name.eq(status))
+                        .select(task_statuses::id),
+                ),
+            );
+        }
+
+        query
+    }
+
+    /// Statuses of a workflow (`None` is the default workflow)
+    fn workflow(project_id: Option<Uuid>) -> task_statuses::BoxedQuery<'static, Sqlite> {
+        let query = task_statuses::table.into_boxed();
+        match project_id {
+            Some(id) => query.filter(task_statuses::project_id.eq(id.to_string())),
+            None => query.filter(task_statuses::project_id.is_null()),
+        }
+    }
+
+    fn load_task(conn: &mut SqliteConnection, task_id: Uuid) -> Result<Task, RepositoryError> {
+        tasks::table
+            .find(task_id.to_string())
+            .select(TaskRow::as_select())
+            .first(conn)?
+            .try_into()
+    }
+}
+
+/// Drop descriptions that were not asked for
+fn without_description(mut tasks: Vec<Task>, with_description: bool) -> Vec<Task> {
+    if !with_description {
+        tasks.iter_mut().for_each(|task| task.description = None);
+    }
+    tasks
+}
+
+#[cfg(test)]
+impl SqliteTaskStore<'_> {
+    /// Add a tag, as the tag endpoints would
+    pub fn insert_tag(&self, name: &str) -> Tag {
+        let time = to_text(now());
+        let row = TagRow {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            created_at: time.clone(),
+            updated_at: time,
+        };
+        self.run(|conn| {
+            diesel::insert_into(tags::table)
+                .values(&row)
+                .execute(conn)?;
+            row.try_into()
+        })
+        .expect("insert tag")
+    }
+
+    /// Add a project, optionally archived, without a workflow of its own
+    pub fn insert_project(&self, name: &str, archived: bool) -> Project {
+        let time = to_text(now());
+        let row = ProjectRow {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            description: None,
+            archived_at: archived.then(|| time.clone()),
+            created_at: time.clone(),
+            updated_at: time,
+        };
+        self.run(|conn| {
+            diesel::insert_into(projects::table)
+                .values(&row)
+                .execute(conn)?;
+            row.try_into()
+        })
+        .expect("insert project")
+    }
+
+    /// Mark `task_id` as blocked by `blocked_by_id`
+    pub fn insert_dependency(&self, task_id: Uuid, blocked_by_id: Uuid) {
+        self.run(|conn| {
+            diesel::insert_into(task_dependencies::table)
+                .values((
+                    task_dependencies::task_id.eq(task_id.to_string()),
+                    task_dependencies::blocked_by_id.eq(blocked_by_id.to_string()),
+                    task_dependencies::created_at.eq(to_text(now())),
+                ))
+                .execute(conn)
+                .map_err(Into::into)
+        })
+        .expect("insert dependency");
+    }
+}
+
+impl TaskStore for SqliteTaskStore<'_> {
+    fn find_tasks(
+        &self,
+        filter: &TaskFilter,
+        with_description: bool,
+    ) -> Result<Vec<Task>, RepositoryError> {
+        self.run(|conn| {
+            let rows = Self::filtered(filter)
+                .order((tasks::position.asc(), tasks::created_at.asc()))
+                .select(TaskRow::as_select())
+                .load(conn)?;
+            Ok(without_description(convert(rows)?, with_description))
+        })
+    }
+
+    fn find_task(&self, task_id: Uuid) -> Result<Task, RepositoryError> {
+        self.run(|conn| Self::load_task(conn, task_id))
+    }
+
+    fn find_children(
+        &self,
+        parent_id: Uuid,
+        with_description: bool,
+    ) -> Result<Vec<Task>, RepositoryError> {
+        self.run(|conn| {
+            let rows = tasks::table
+                .filter(tasks::parent_id.eq(parent_id.to_string()))
+                .order((tasks::position.asc(), tasks::created_at.asc()))
+                .select(TaskRow::as_select())
+                .load(conn)?;
+            Ok(without_description(convert(rows)?, with_description))
+        })
+    }
+
+    fn find_subtree(&self, root_id: Uuid) -> Result<Vec<TaskNode>, RepositoryError> {
+        self.run(|conn| {
+            diesel::sql_query(
+                "WITH RECURSIVE tree AS ( \
+                     SELECT id, 0 AS depth, '/' || id || '/' AS path FROM tasks WHERE id = ? \
+                     UNION ALL \
+                     SELECT child.id, tree.depth + 1, tree.path || child.id || '/' \
+                     FROM tasks child JOIN tree ON child.parent_id = tree.id \
+                     WHERE instr(tree.path, '/' || child.id || '/') = 0 \
+                 ) \
+                 SELECT tasks.*, tree.depth FROM tree JOIN tasks ON tasks.id = tree.id \
+                 ORDER BY tree.depth, tasks.position, tasks.created_at",
+            )
+            .bind::<Text, _>(root_id.to_string())
+            .load::<TaskNodeRow>(conn)?
+            .into_iter()
+            .map(|row| {
+                Ok(TaskNode {
+                    task: row.task.try_into()?,
+                    depth: row.depth,
+                })
+            })
+            .collect()
+        })
+    }
+
+    fn is_self_or_ancestor(&self, task_id: Uuid, node_id: Uuid) -> Result<bool, RepositoryError> {
+        #[derive(QueryableByName)]
+        struct Found {
+            #[diesel(sql_type = diesel::sql_types::Bool)]
+            found: bool,
+        }
+
+        // UNION (not UNION ALL) stops the walk even if a cycle already exists
+        self.run(|conn| {
+            diesel::sql_query(
+                "WITH RECURSIVE ancestors AS ( \
+                     SELECT id, parent_id FROM tasks WHERE id = ? \
+                     UNION \
+                     SELECT parent.id, parent.parent_id \
+                     FROM tasks parent JOIN ancestors ON parent.id = ancestors.parent_id \
+                 ) \
+                 SELECT EXISTS (SELECT 1 FROM ancestors WHERE id = ?) AS found",
+            )
+            .bind::<Text, _>(node_id.to_string())
+            .bind::<Text, _>(task_id.to_string())
+            .get_result::<Found>(conn)
+            .map(|row| row.found)
+            .map_err(Into::into)
+        })
+    }
+
+    fn create_task(&self, new_task: NewTask) -> Result<Task, RepositoryError> {
+        let task = inserted(new_task, now());
+        self.write(|conn| {
+            diesel::insert_into(tasks::table)
+                .values(TaskRow::from(&task))
+                .execute(conn)?;
+            // Read back at the stored precision, like `RETURNING`
+            Self::load_task(conn, task.id)
+        })
+    }
+
+    fn update_task(&self, task_id: Uuid, update_task: UpdateTask) -> Result<Task, RepositoryError> {
+        self.write(|conn| {
+            let task = apply_update(Self::load_task(conn, task_id)?, update_task, now());
+            diesel::update(tasks::table.find(task_id.to_string()))
+                .set(TaskRow::from(&task))
+                .execute(conn)?;
+            Self::load_task(conn, task_id)
+        })
+    }
+
+    fn delete_task(&self, task_id: Uuid, children: ChildrenPolicy) -> Result<(), RepositoryError> {
+        let id = task_id.to_string();
+        self.write(|conn| {
+            if children == ChildrenPolicy::Reparent {
+                let parent_id = tasks::table
+                    .find(&id)
+                    .select(tasks::parent_id)
+                    .first::<Option<String>>(conn)?;
+                diesel::update(tasks::table.filter(tasks::parent_id.eq(&id)))
+                    .set((
+                        tasks::parent_id.eq(parent_id),
+                        tasks::updated_at.eq(to_text(now())),
+                    ))
+                    .execute(conn)?;
+            }
+            // Subtasks, tag links and dependencies go through ON DELETE CASCADE
+            match diesel::delete(tasks::table.find(&id)).execute(conn)? {
+                0 => Err(RepositoryError::NotFound),
+                _ => Ok(()),
+            }
+        })
+    }
+
+    fn max_position(&self) -> Result<Option<String>, RepositoryError> {
+        self.run(|conn| {
+            tasks::table
+                .select(diesel::dsl::max(tasks::position))
+                .first(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    fn next_position(
+        &self,
+        position: &str,
+        exclude: Uuid,
+    ) -> Result<Option<String>, RepositoryError> {
+        self.run(|conn| {
+            tasks::table
+                .filter(tasks::position.gt(position))
+                .filter(tasks::id.ne(exclude.to_string()))
+                .select(diesel::dsl::min(tasks::position))
+                .first(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    fn previous_position(
+        &self,
+        position: &str,
+        exclude: Uuid,
+    ) -> Result<Option<String>, RepositoryError> {
+        self.run(|conn| {
+            tasks::table
+                .filter(tasks::position.lt(position))
+                .filter(tasks::id.ne(exclude.to_string()))
+                .select(diesel::dsl::max(tasks::position))
+                .first(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    fn max_position_len(&self) -> Result<Option<i32>, RepositoryError> {
+        #[derive(QueryableByName)]
+        struct PositionLen {
+            #[diesel(sql_type = Nullable<Integer>)]
+            len: Option<i32>,
+        }
+
+        self.run(|conn| {
+            diesel::sql_query("SELECT max(length(position)) AS len FROM tasks")
+                .get_result::<PositionLen>(conn)
+                .map(|row| row.len)
+                .map_err(Into::into)
+        })
+    }
+
+    fn set_position(&self, task_id: Uuid, position: &str) -> Result<Task, RepositoryError> {
+        self.write(|conn| {
+            let updated = diesel::update(tasks::table.find(task_id.to_string()))
+                .set((
+                    tasks::position.eq(position),
+                    tasks::updated_at.eq(to_text(now())),
+                ))
+                .execute(conn)?;
+            if updated == 0 {
+                return Err(RepositoryError::NotFound);
+            }
+            Self::load_task(conn, task_id)
+        })
+    }
+
+    fn rebalance_positions(&self) -> Result<usize, RepositoryError> {
+        // Same ranks as the PostgreSQL rebalance: 00000001i, 00000002i, ...
+        self.write(|conn| {
+            diesel::sql_query(
+                "UPDATE tasks SET position = ranked.position, updated_at = ? \
+                 FROM ( \
+                     SELECT id, printf('%08xi', row_number() OVER ( \
+                         ORDER BY position, created_at, id)) AS position \
+                     FROM tasks \
+                 ) ranked \
+                 WHERE tasks.id = ranked.id AND tasks.position <> ranked.position",
+            )
+            .bind::<Text, _>(to_text(now()))
+            .execute(conn)
+            .map_err(Into::into)
+        })
+    }
+
+    fn find_project(&self, project_id: Uuid) -> Result<Project, RepositoryError> {
+        self.run(|conn| {
+            projects::table
+                .find(project_id.to_string())
+                .select(ProjectRow::as_select())
+                .first(conn)?
+                .try_into()
+        })
+    }
+
+    fn find_workflow(&self, project_id: Option<Uuid>) -> Result<Vec<TaskStatus>, RepositoryError> {
+        self.run(|conn| {
+            let rows = Self::workflow(project_id)
+                .select(StatusRow::as_select())
+                .order((task_statuses::position.asc(), task_statuses::name.asc()))
+                .load(conn)?;
+            convert(rows)
+        })
+    }
+
+    fn find_status(&self, status_id: Uuid) -> Result<TaskStatus, RepositoryError> {
+        self.run(|conn| {
+            task_statuses::table
+                .find(status_id.to_string())
+                .select(StatusRow::as_select())
+                .first(conn)?
+                .try_into()
+        })
+    }
+
+    fn find_statuses(&self, status_ids: &[Uuid]) -> Result<Vec<TaskStatus>, RepositoryError> {
+        let ids: Vec<String> = status_ids.iter().map(Uuid::to_string).collect();
+        self.run(|conn| {
+            let rows = task_statuses::table
+                .filter(task_statuses::id.eq_any(&ids))
+                .select(StatusRow::as_select())
+                .load(conn)?;
+            convert(rows)
+        })
+    }
+
+    fn find_status_by_name(
+        &self,
+        project_id: Option<Uuid>,
+        name: &str,
+    ) -> Result<TaskStatus, RepositoryError> {
+        self.run(|conn| {
+            Self::workflow(project_id)
+                .filter(task_statuses::name.eq(name))
+                .select(StatusRow::as_select())
+                .first(conn)?
+                .try_into()
+        })
+    }
+
+    fn is_transition_allowed(&self, from: Uuid, to: Uuid) -> Result<bool, RepositoryError> {
+        self.run(|conn| {
+            diesel::select(exists(
+                task_status_transitions::table.find((from.to_string(), to.to_string())),
+            ))
+            .get_result(conn)
+            .map_err(Into::into)
+        })
+    }
+
+    fn count_open_blockers(&self, task_id: Uuid) -> Result<i64, RepositoryError> {
+        self.run(|conn| {
+            tasks::table
+                .filter(
+                    tasks::id.eq_any(
+                        task_dependencies::table
+                            .filter(task_dependencies::task_id.eq(task_id.to_string()))
+                            .select(task_dependencies::blocked_by_id),
+                    ),
+                )
+                .filter(tasks::completed.eq(false))
+                .count()
+                .get_result(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    fn find_tag(&self, tag_id: Uuid) -> Result<Tag, RepositoryError> {
+        self.run(|conn| {
+            tags::table
+                .find(tag_id.to_string())
+                .select(TagRow::as_select())
+                .first(conn)?
+                .try_into()
+        })
+    }
+
+    fn find_tags(&self, tasks: &[Task]) -> Result<Vec<Vec<Tag>>, RepositoryError> {
+        let ids: Vec<String> = tasks.iter().map(|task| task.id.to_string()).collect();
+        self.run(|conn| {
+            let rows: Vec<(String, TagRow)> = task_tags::table
+                .inner_join(tags::table)
+                .filter(task_tags::task_id.eq_any(&ids))
+                .order(tags::name.asc())
+                .select((task_tags::task_id, TagRow::as_select()))
+                .load(conn)?;
+
+            let mut grouped = vec![Vec::new(); ids.len()];
+            for (task_id, row) in rows {
+                let tag = Tag::try_from(row)?;
+                for (i, _) in ids.iter().enumerate().filter(|(_, id)| **id == task_id) {
+                    grouped[i].push(tag.clone());
+                }
+            }
+            Ok(grouped)
+        })
+    }
+
+    fn attach_tag(&self, task_id: Uuid, tag_id: Uuid) -> Result<(), RepositoryError> {
+        self.write(|conn| {
+            diesel::insert_into(task_tags::table)
+                .values((
+                    task_tags::task_id.eq(task_id.to_string()),
+                    task_tags::tag_id.eq(tag_id.to_string()),
+                    task_tags::created_at.eq(to_text(now())),
+                ))
+                .on_conflict_do_nothing()
+                .execute(conn)
+                .map(|_| ())
+                .map_err(|e| match RepositoryError::from(e) {
+                    RepositoryError::InvalidReference(_) => RepositoryError::NotFound,
+                    other => other,
+                })
+        })
+    }
+
+    fn detach_tag(&self, task_id: Uuid, tag_id: Uuid) -> Result<(), RepositoryError> {
+        self.write(|conn| {
+            match diesel::delete(task_tags::table.find((task_id.to_string(), tag_id.to_string())))
+                .execute(conn)?
+            {
+                0 => Err(RepositoryError::NotFound),
+                _ => Ok(()),
+            }
+        })
+    }
+
+    fn transaction(
+        &self,
+        _isolation: IsolationLevel,
+        work: &mut TransactionWork<'_>,
+    ) -> Result<(), AppError> {
+        let mut result = Ok(());
+        let nested = matches!(self.conn, Backing::Borrowed(_));
+        self.run(|conn| {
+            result = if nested {
+                conn.transaction(|conn| work(&SqliteTaskStore::with_connection(conn)))
+            } else {
+                conn.immediate_transaction(|conn| work(&SqliteTaskStore::with_connection(conn)))
+            };
+            Ok(())
+        })?;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::store::MemoryTaskStore;
+
+    fn store() -> SqliteTaskStore<'static> {
+        SqliteTaskStore::open(":memory:").expect("open an in-memory database")
+    }
+
+    fn new_task(store: &dyn TaskStore, title: &str, position: &str) -> NewTask {
+        NewTask {
+            title: title.to_string(),
+            description: Some("details".to_string()),
+            completed: false,
+            due_at: None,
+            priority: Priority::High,
+            project_id: None,
+            parent_id: None,
+            position: position.to_string(),
+            status_id: store.find_workflow(None).unwrap()[0].id,
+            recurrence: None,
+            recurrence_timezone: None,
+            recurrence_start: None,
+        }
+    }
+
+    #[test]
+    fn test_tasks_read_back_like_postgres() {
+        let store = store();
+        let workflow = store.find_workflow(None).unwrap();
+        let names: Vec<&str> = workflow.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["todo", "in_progress", "review", "done"]);
+        assert!(
+            store
+                .is_transition_allowed(workflow[0].id, workflow[3].id)
+                .unwrap()
+        );
+
+        let created = store.create_task(new_task(&store, "Write", "a")).unwrap();
+        let found = store.find_task(created.id).unwrap();
+        assert_eq!(created.created_at.timestamp_subsec_nanos() % 1000, 0);
+        assert_eq!(
+            serde_json::to_value(created.into_response("todo".into(), vec![])).unwrap(),
+            serde_json::to_value(found.clone().into_response("todo".into(), vec![])).unwrap()
+        );
+
+        let done = store
+            .update_task(
+                found.id,
+                UpdateTask {
+                    completed: Some(true),
+                    status_id: Some(workflow[3].id),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(done.completed_at.is_some());
+        assert!(done.updated_at >= done.created_at);
+        assert_eq!(
+            store.find_task(done.id).unwrap().completed_at,
+            done.completed_at
+        );
+    }
+
+    #[test]
+    fn test_filters_match_the_memory_store() {
+        let sqlite = store();
+        let memory = MemoryTaskStore::new();
+        let stores: [&dyn TaskStore; 2] = [&sqlite, &memory];
+        let home = (sqlite.insert_tag("home"), memory.insert_tag("home"));
+        let past = "2000-01-01T00:00:00Z".parse().unwrap();
+
+        let mut titles = Vec::new();
+        for (store, tag) in stores.into_iter().zip([home.0.id, home.1.id]) {
+            let late = store
+                .create_task(NewTask {
+                    due_at: Some(past),
+                    ..new_task(store, "late", "b")
+                })
+                .unwrap();
+            store.create_task(new_task(store, "first", "a")).unwrap();
+            store.attach_tag(late.id, tag).unwrap();
+            store.attach_tag(late.id, tag).unwrap();
+
+            let list = |filter: TaskFilter| -> Vec<String> {
+                store
+                    .find_tasks(&filter, false)
+                    .unwrap()
+                    .into_iter()
+                    .map(|task| task.title)
+                    .collect()
+            };
+            titles.push([
+                list(TaskFilter::default()),
+                list(TaskFilter {
+                    overdue: Some(true),
+                    ..Default::default()
+                }),
+                list(TaskFilter {
+                    tags: vec!["home".to_string()],
+                    match_all_tags: true,
+                    ..Default::default()
+                }),
+                list(TaskFilter {
+                    status: Some("todo".to_string()),
+                    ..Default::default()
+                }),
+            ]);
+        }
+        assert_eq!(titles[0], titles[1]);
+        assert_eq!(titles[0][1], ["late"]);
+    }
+
+    #[test]
+    fn test_hierarchy_and_foreign_keys() {
+        let store = store();
+        let root = store.create_task(new_task(&store, "root", "a")).unwrap();
+        let child = store
+            .create_task(NewTask {
+                parent_id: Some(root.id),
+                ..new_task(&store, "child", "b")
+            })
+            .unwrap();
+        let grandchild = store
+            .create_task(NewTask {
+                parent_id: Some(child.id),
+                ..new_task(&store, "grandchild", "c")
+            })
+            .unwrap();
+
+        let depths: Vec<i32> = store
+            .find_subtree(root.id)
+            .unwrap()
+            .iter()
+            .map(|node| node.depth)
+            .collect();
+        assert_eq!(depths, [0, 1, 2]);
+        assert!(store.is_self_or_ancestor(root.id, grandchild.id).unwrap());
+
+        let orphan = store.create_task(NewTask {
+            parent_id: Some(Uuid::new_v4()),
+            ..new_task(&store, "orphan", "d")
+        });
+        assert!(matches!(orphan, Err(RepositoryError::InvalidReference(_))));
+
+        store
+            .delete_task(child.id, ChildrenPolicy::Reparent)
+            .unwrap();
+        assert_eq!(
+            store.find_task(grandchild.id).unwrap().parent_id,
+            Some(root.id)
+        );
+        store.delete_task(root.id, ChildrenPolicy::Cascade).unwrap();
+        assert!(matches!(
+            store.delete_task(root.id, ChildrenPolicy::Cascade),
+            Err(RepositoryError::NotFound)
+        ));
+        assert!(matches!(
+            store.find_task(grandchild.id),
+            Err(RepositoryError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn test_failed_transactions_roll_back() {
+        let store = store();
+        let kept = store.create_task(new_task(&store, "kept", "a")).unwrap();
+
+        let store: &dyn TaskStore = &store;
+        let result: Result<(), AppError> = store.in_transaction(|tx| {
+            tx.create_task(new_task(tx, "discarded", "b"))?;
+            tx.delete_task(kept.id, ChildrenPolicy::Cascade)?;
+            Err(AppError::conflict("stop"))
+        });
+        assert!(result.is_err());
+
+        let tasks = store.find_tasks(&TaskFilter::default(), true).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, kept.id);
+    }
+
+    #[test]
+    fn test_rebalance_positions() {
+        let store = store();
+        for position in ["b", "a", "azz"] {
+            store
+                .create_task(new_task(&store, position, position))
+                .unwrap();
+        }
+        assert_eq!(store.rebalance_positions().unwrap(), 3);
+        let positions: Vec<String> = store
+            .find_tasks(&TaskFilter::default(), false)
+            .unwrap()
+            .into_iter()
+            .map(|task| format!("{}:{}", task.title, task.position))
+            .collect();
+        assert_eq!(positions, ["a:00000001i", "azz:00000002i", "b:00000003i"]);
+        assert_eq!(store.max_position_len().unwrap(), Some(9));
+        assert_eq!(store.rebalance_positions().unwrap(), 0);
+    }
+}
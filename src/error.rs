@@ -1,11 +1,12 @@
 use axum::{
-    Json,
     http::StatusCode,
     response::{IntoResponse, Response},
 };
 use axum_diesel_api::ErrorResponse;
 use std::fmt;
 
+use crate::codec;
+
 #[derive(Debug, Clone)]
 pub enum AppError {
     // === Repository Errors ===
@@ -16,6 +17,20 @@ pub enum AppError {
     // === Validation Errors ===
     ValidationError(String),
     InvalidInput(String),
+    /// Well-formed, but refers to missing data or breaks a constraint
+    Unprocessable(String),
+
+    // === State Errors ===
+    Conflict(String),
+
+    // === Content Negotiation Errors ===
+    NotAcceptable(String),
+    UnsupportedMediaType(String),
+
+    // === Availability Errors ===
+    ServiceUnavailable(String),
+    /// A conflict or dropped connection that may pass on a retry
+    Transient(String),
 
     // === Internal Errors ===
     InternalServerError(String),
@@ -29,6 +44,12 @@ impl fmt::Display for AppError {
             AppError::DatabaseError(msg) => write!(f, "Database error: {}", msg),
             AppError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
             AppError::InvalidInput(msg) => write!(f, "Invalid input: {}", msg),
+            AppError::Unprocessable(msg) => write!(f, "Unprocessable: {}", msg),
+            AppError::Conflict(msg) => write!(f, "Conflict: {}", msg),
+            AppError::NotAcceptable(msg) => write!(f, "Not acceptable: {}", msg),
+            AppError::UnsupportedMediaType(msg) => write!(f, "Unsupported media type: {}", msg),
+            AppError::ServiceUnavailable(msg) => write!(f, "Service unavailable: {}", msg),
+            AppError::Transient(msg) => write!(f, "Transient error: {}", msg),
             AppError::InternalServerError(msg) => write!(f, "Internal server error: {}", msg),
         }
     }
@@ -38,19 +59,22 @@ impl std::error::Error for AppError {}
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, error_code, message, details) = self.get_error_info();
+        codec::respond(self.status_code(), &self.to_error_response())
+    }
+}
+
+impl AppError {
+    /// Body of the error response, also used to report errors inside other payloads
+    pub fn to_error_response(&self) -> ErrorResponse {
+        let (_, error_code, message, details) = self.get_error_info();
 
-        let body = Json(ErrorResponse {
+        ErrorResponse {
             error: error_code.to_string(),
             message,
             details,
-        });
-
-        (status, body).into_response()
+        }
     }
-}
 
-impl AppError {
     /// Retrieves formatted error information for HTTP response
     fn get_error_info(&self) -> (StatusCode, &'static str, String, Option<String>) {
         match self {
@@ -61,6 +85,7 @@ impl AppError {
             AppError::Duplicate(msg) => {
                 (StatusCode::CONFLICT, "DUPLICATE_ENTRY", msg.clone(), None)
             }
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, "CONFLICT", msg.clone(), None),
 
             // 400 Bad Request
             AppError::ValidationError(msg) => (
@@ -73,6 +98,42 @@ impl AppError {
                 (StatusCode::BAD_REQUEST, "INVALID_INPUT", msg.clone(), None)
             }
 
+            // 422 Unprocessable Entity
+            AppError::Unprocessable(msg) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "UNPROCESSABLE_ENTITY",
+                msg.clone(),
+                None,
+            ),
+
+            // 406 Not Acceptable / 415 Unsupported Media Type
+            AppError::NotAcceptable(msg) => (
+                StatusCode::NOT_ACCEPTABLE,
+                "NOT_ACCEPTABLE",
+                msg.clone(),
+                None,
+            ),
+            AppError::UnsupportedMediaType(msg) => (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "UNSUPPORTED_MEDIA_TYPE",
+                msg.clone(),
+                None,
+            ),
+
+            // 503 Service Unavailable
+            AppError::ServiceUnavailable(msg) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "SERVICE_UNAVAILABLE",
+                msg.clone(),
+                None,
+            ),
+            AppError::Transient(msg) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "TRANSIENT_ERROR",
+                "The request conflicted with another one, please retry".to_string(),
+                Some(msg.clone()),
+            ),
+
             // 500 Internal Server Error
             AppError::DatabaseError(msg) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -114,8 +175,42 @@ impl AppError {
         AppError::InvalidInput(msg.into())
     }
 
+    pub fn unprocessable(msg: impl Into<String>) -> Self {
+        AppError::Unprocessable(msg.into())
+    }
+
+    pub fn conflict(msg: impl Into<String>) -> Self {
+        AppError::Conflict(msg.into())
+    }
+
+    pub fn not_acceptable(msg: impl Into<String>) -> Self {
+        AppError::NotAcceptable(msg.into())
+    }
+
+    pub fn unsupported_media_type(msg: impl Into<String>) -> Self {
+        AppError::UnsupportedMediaType(msg.into())
+    }
+
+    pub fn service_unavailable(msg: impl Into<String>) -> Self {
+        AppError::ServiceUnavailable(msg.into())
+    }
+
+    pub fn transient(msg: impl Into<String>) -> Self {
+        AppError::Transient(msg.into())
+    }
+
+    /// Whether running the same operation again may succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, AppError::Transient(_))
+    }
+
+    /// Whether the operation may succeed later, once the database is back:
+    /// worth retrying from a queue, unlike invalid input
+    pub fn may_succeed_later(&self) -> bool {
+        self.is_retryable() || matches!(self, AppError::ServiceUnavailable(_))
+    }
+
     /// Returns the HTTP status code
-    #[allow(dead_code)]
     pub fn status_code(&self) -> StatusCode {
         self.get_error_info().0
     }
@@ -185,6 +280,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_conflict_status() {
+        assert_eq!(
+            AppError::conflict("test").status_code(),
+            StatusCode::CONFLICT
+        );
+    }
+
+    #[test]
+    fn test_unprocessable_status() {
+        assert_eq!(
+            AppError::unprocessable("test").status_code(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    #[test]
+    fn test_transient_status() {
+        let err = AppError::transient("could not serialize access");
+        assert_eq!(err.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(err.is_retryable());
+        assert!(!AppError::service_unavailable("test").is_retryable());
+    }
+
     #[test]
     fn test_internal_status() {
         assert_eq!(
@@ -0,0 +1,50 @@
+use axum::extract::Path;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum_diesel_api::{CalendarFeedResponse, CalendarQuery, CreateCalendarFeedRequest};
+use axum_extra::extract::Query;
+use uuid::Uuid;
+
+use crate::base_path::BaseUrl;
+use crate::codec::Payload;
+use crate::error::AppError;
+use crate::response::AppResponse;
+use crate::services::CalendarService;
+
+const ICS_CONTENT_TYPE: &str = "text/calendar; charset=utf-8";
+
+/// Export tasks with a due date as an iCalendar file
+pub async fn export_tasks_ics(
+    Query(query): Query<CalendarQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let ics = CalendarService::export(query)?;
+    Ok(([(header::CONTENT_TYPE, ICS_CONTENT_TYPE)], ics))
+}
+
+/// Serve a tokenised calendar feed
+pub async fn export_feed_ics(Path(token): Path<String>) -> Result<impl IntoResponse, AppError> {
+    let ics = CalendarService::export_feed(&token)?;
+    Ok(([(header::CONTENT_TYPE, ICS_CONTENT_TYPE)], ics))
+}
+
+/// List all calendar feeds
+pub async fn list_calendar_feeds() -> Result<AppResponse<Vec<CalendarFeedResponse>>, AppError> {
+    let feeds = CalendarService::get_feeds()?;
+    Ok(AppResponse::ok(feeds))
+}
+
+/// Create a calendar feed with a secret URL
+pub async fn create_calendar_feed(
+    base_url: BaseUrl,
+    Payload(req): Payload<CreateCalendarFeedRequest>,
+) -> Result<AppResponse<CalendarFeedResponse>, AppError> {
+    let mut feed = CalendarService::create_feed(req)?;
+    feed.url = feed.url.map(|url| base_url.join(&url));
+    Ok(AppResponse::created(feed))
+}
+
+/// Delete a calendar feed, revoking its URL
+pub async fn delete_calendar_feed(Path(id): Path<Uuid>) -> Result<AppResponse<()>, AppError> {
+    CalendarService::delete_feed(id)?;
+    Ok(AppResponse::no_content())
+}
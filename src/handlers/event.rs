@@ -0,0 +1,42 @@
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use std::convert::Infallible;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+
+use crate::error::AppError;
+use crate::services::EventService;
+use crate::services::event_service::{Signal, Subscription};
+
+/// Stream task changes as server-sent events
+///
+/// Resumes after the `Last-Event-ID` header while that event is still
+/// buffered; otherwise the stream starts with a `reset` event.
+pub async fn stream_task_events(
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
+
+    let Subscription { backlog, receiver } = EventService::subscribe(last_event_id)?;
+    // A lagging receiver has missed events, just like an unknown resume point
+    let live = BroadcastStream::new(receiver).map(|signal| signal.unwrap_or(Signal::Reset));
+    let stream = tokio_stream::iter(backlog)
+        .chain(live)
+        .map(|signal| Ok(to_sse(signal)));
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+fn to_sse(signal: Signal) -> Event {
+    match signal {
+        Signal::Event(event) => Event::default()
+            .id(event.id.to_string())
+            .event(event.kind.as_str())
+            .json_data(&*event)
+            .expect("task events serialize to JSON"),
+        Signal::Reset => Event::default().event("reset").data("{}"),
+    }
+}
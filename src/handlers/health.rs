@@ -1,12 +1,30 @@
-use axum::Json;
-use axum::response::IntoResponse;
 use serde::Serialize;
 
+use crate::response::AppResponse;
+
 #[derive(Serialize)]
 pub struct HealthResponse {
     pub status: &'static str,
 }
 
-pub async fn health() -> impl IntoResponse {
-    Json(HealthResponse { status: "ok" })
+/// Liveness check; does not touch the database, so it answers during outages
+pub async fn health() -> AppResponse<HealthResponse> {
+    AppResponse::ok(HealthResponse { status: "ok" })
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    use crate::app::build_router;
+
+    #[tokio::test]
+    async fn test_health_does_not_need_the_database() {
+        // No pool is ever created in unit tests
+        let request = Request::get("/health").body(Body::empty()).unwrap();
+        let response = build_router().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
 }
@@ -1,2 +1,26 @@
+pub mod calendar;
+pub mod event;
 pub mod health;
+pub mod project;
+pub mod status;
+pub mod tag;
 pub mod task;
+pub mod transfer;
+pub mod webhook;
+pub mod ws;
+
+use crate::db::connection;
+use crate::error::AppError;
+
+/// Run service work on the blocking pool, with the request's read routing
+///
+/// For work that may pause between retries (see [`crate::db::retry`]), so
+/// that it does not hold up the async workers.
+pub async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    let read_primary = connection::reads_use_primary();
+    tokio::task::spawn_blocking(move || connection::route_reads_blocking(read_primary, work))
+        .await
+        .map_err(|e| AppError::internal(format!("Blocking task panicked: {}", e)))?
+}
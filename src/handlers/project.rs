@@ -0,0 +1,91 @@
+use axum::extract::{Path, State};
+use axum_diesel_api::{
+    CreateProjectRequest, CreateTaskRequest, ListProjectsQuery, ListTasksQuery, ProjectResponse,
+    SparseTask, TaskResponse, UpdateProjectRequest,
+};
+use axum_extra::extract::Query;
+use uuid::Uuid;
+
+use crate::base_path::BaseUrl;
+use crate::codec::Payload;
+use crate::error::AppError;
+use crate::handlers::blocking;
+use crate::response::AppResponse;
+use crate::services::{ProjectService, TaskService};
+
+/// List all projects, `?include_archived=true` to include archived ones
+pub async fn list_projects(
+    Query(query): Query<ListProjectsQuery>,
+) -> Result<AppResponse<Vec<ProjectResponse>>, AppError> {
+    let projects = ProjectService::get_all(query.include_archived)?;
+    Ok(AppResponse::ok(projects))
+}
+
+/// Get a single project by ID
+pub async fn get_project(Path(id): Path<Uuid>) -> Result<AppResponse<ProjectResponse>, AppError> {
+    let project = ProjectService::get_by_id(id)?;
+    Ok(AppResponse::ok(project))
+}
+
+/// Create a new project
+pub async fn create_project(
+    base_url: BaseUrl,
+    Payload(req): Payload<CreateProjectRequest>,
+) -> Result<AppResponse<ProjectResponse>, AppError> {
+    let project = ProjectService::create(req)?;
+    let location = base_url.join(&format!("/projects/{}", project.id));
+    Ok(AppResponse::created(project).with_location(location))
+}
+
+/// Update an existing project
+pub async fn update_project(
+    Path(id): Path<Uuid>,
+    Payload(req): Payload<UpdateProjectRequest>,
+) -> Result<AppResponse<ProjectResponse>, AppError> {
+    let project = ProjectService::update(id, req)?;
+    Ok(AppResponse::ok(project))
+}
+
+/// Delete a project and its tasks
+pub async fn delete_project(Path(id): Path<Uuid>) -> Result<AppResponse<()>, AppError> {
+    ProjectService::delete(id)?;
+    Ok(AppResponse::no_content())
+}
+
+/// Archive a project and its tasks
+pub async fn archive_project(
+    Path(id): Path<Uuid>,
+) -> Result<AppResponse<ProjectResponse>, AppError> {
+    let project = ProjectService::archive(id)?;
+    Ok(AppResponse::ok(project))
+}
+
+/// Unarchive a project and its tasks
+pub async fn unarchive_project(
+    Path(id): Path<Uuid>,
+) -> Result<AppResponse<ProjectResponse>, AppError> {
+    let project = ProjectService::unarchive(id)?;
+    Ok(AppResponse::ok(project))
+}
+
+/// List the tasks of a project (same filters as `GET /tasks`)
+pub async fn list_project_tasks(
+    State(tasks): State<TaskService>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ListTasksQuery>,
+) -> Result<AppResponse<Vec<SparseTask>>, AppError> {
+    let tasks = blocking(move || ProjectService::get_tasks(&tasks, id, query)).await?;
+    Ok(AppResponse::ok(tasks))
+}
+
+/// Create a task inside a project
+pub async fn create_project_task(
+    State(tasks): State<TaskService>,
+    Path(id): Path<Uuid>,
+    base_url: BaseUrl,
+    Payload(req): Payload<CreateTaskRequest>,
+) -> Result<AppResponse<TaskResponse>, AppError> {
+    let task = ProjectService::create_task(&tasks, id, req)?;
+    let location = base_url.join(&format!("/tasks/{}", task.id));
+    Ok(AppResponse::created(task).with_location(location))
+}
@@ -0,0 +1,64 @@
+use axum::extract::Path;
+use axum_diesel_api::{CreateStatusRequest, StatusResponse, UpdateStatusRequest};
+use uuid::Uuid;
+
+use crate::codec::Payload;
+use crate::error::AppError;
+use crate::response::AppResponse;
+use crate::services::StatusService;
+
+/// List the default workflow used by tasks without a project
+pub async fn list_default_statuses() -> Result<AppResponse<Vec<StatusResponse>>, AppError> {
+    let statuses = StatusService::get_default()?;
+    Ok(AppResponse::ok(statuses))
+}
+
+/// List the workflow of a project
+pub async fn list_project_statuses(
+    Path(id): Path<Uuid>,
+) -> Result<AppResponse<Vec<StatusResponse>>, AppError> {
+    let statuses = StatusService::get_all(id)?;
+    Ok(AppResponse::ok(statuses))
+}
+
+/// Add a status to a project's workflow
+pub async fn create_project_status(
+    Path(id): Path<Uuid>,
+    Payload(req): Payload<CreateStatusRequest>,
+) -> Result<AppResponse<StatusResponse>, AppError> {
+    let status = StatusService::create(id, req)?;
+    Ok(AppResponse::created(status))
+}
+
+/// Update a status of a project's workflow
+pub async fn update_project_status(
+    Path((id, status_id)): Path<(Uuid, Uuid)>,
+    Payload(req): Payload<UpdateStatusRequest>,
+) -> Result<AppResponse<StatusResponse>, AppError> {
+    let status = StatusService::update(id, status_id, req)?;
+    Ok(AppResponse::ok(status))
+}
+
+/// Remove an unused status from a project's workflow
+pub async fn delete_project_status(
+    Path((id, status_id)): Path<(Uuid, Uuid)>,
+) -> Result<AppResponse<()>, AppError> {
+    StatusService::delete(id, status_id)?;
+    Ok(AppResponse::no_content())
+}
+
+/// Allow tasks to move from one status to another
+pub async fn add_status_transition(
+    Path((id, status_id, to_status_id)): Path<(Uuid, Uuid, Uuid)>,
+) -> Result<AppResponse<StatusResponse>, AppError> {
+    let status = StatusService::add_transition(id, status_id, to_status_id)?;
+    Ok(AppResponse::ok(status))
+}
+
+/// Forbid tasks to move from one status to another
+pub async fn remove_status_transition(
+    Path((id, status_id, to_status_id)): Path<(Uuid, Uuid, Uuid)>,
+) -> Result<AppResponse<StatusResponse>, AppError> {
+    let status = StatusService::remove_transition(id, status_id, to_status_id)?;
+    Ok(AppResponse::ok(status))
+}
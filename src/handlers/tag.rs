@@ -0,0 +1,46 @@
+use axum::extract::Path;
+use axum_diesel_api::{CreateTagRequest, TagResponse, UpdateTagRequest};
+use uuid::Uuid;
+
+use crate::base_path::BaseUrl;
+use crate::codec::Payload;
+use crate::error::AppError;
+use crate::response::AppResponse;
+use crate::services::TagService;
+
+/// List all tags
+pub async fn list_tags() -> Result<AppResponse<Vec<TagResponse>>, AppError> {
+    let tags = TagService::get_all()?;
+    Ok(AppResponse::ok(tags))
+}
+
+/// Get a single tag by ID
+pub async fn get_tag(Path(id): Path<Uuid>) -> Result<AppResponse<TagResponse>, AppError> {
+    let tag = TagService::get_by_id(id)?;
+    Ok(AppResponse::ok(tag))
+}
+
+/// Create a new tag
+pub async fn create_tag(
+    base_url: BaseUrl,
+    Payload(req): Payload<CreateTagRequest>,
+) -> Result<AppResponse<TagResponse>, AppError> {
+    let tag = TagService::create(req)?;
+    let location = base_url.join(&format!("/tags/{}", tag.id));
+    Ok(AppResponse::created(tag).with_location(location))
+}
+
+/// Rename an existing tag
+pub async fn update_tag(
+    Path(id): Path<Uuid>,
+    Payload(req): Payload<UpdateTagRequest>,
+) -> Result<AppResponse<TagResponse>, AppError> {
+    let tag = TagService::update(id, req)?;
+    Ok(AppResponse::ok(tag))
+}
+
+/// Delete a tag
+pub async fn delete_tag(Path(id): Path<Uuid>) -> Result<AppResponse<()>, AppError> {
+    TagService::delete(id)?;
+    Ok(AppResponse::no_content())
+}
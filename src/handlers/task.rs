@@ -1,42 +1,166 @@
-use axum::extract::{Json, Path};
-use axum_diesel_api::{CreateTaskRequest, TaskResponse, UpdateTaskRequest};
+use axum::extract::{Path, State};
+use axum_diesel_api::{
+    CreateTaskRequest, DeleteTaskQuery, FieldsQuery, ListTasksQuery, MoveTaskRequest,
+    OccurrencesQuery, OccurrencesResponse, ScheduleQuery, ScheduleResponse, SparseTask,
+    TaskResponse, TaskTreeNode, UpdateTaskRequest,
+};
+use axum_extra::extract::Query;
 use uuid::Uuid;
 
+use crate::base_path::BaseUrl;
+use crate::codec::Payload;
 use crate::error::AppError;
+use crate::handlers::blocking;
 use crate::response::AppResponse;
-use crate::services::TaskService;
+use crate::services::{DependencyService, TaskService};
 
-/// List all tasks
-pub async fn list_tasks() -> Result<AppResponse<Vec<TaskResponse>>, AppError> {
-    let tasks = TaskService::get_all()?;
+/// List all tasks, optionally filtered by `?tag=a&tag=b&match=any|all`
+pub async fn list_tasks(
+    State(service): State<TaskService>,
+    Query(query): Query<ListTasksQuery>,
+) -> Result<AppResponse<Vec<SparseTask>>, AppError> {
+    let tasks = blocking(move || service.get_all(query)).await?;
     Ok(AppResponse::ok(tasks))
 }
 
 /// Get a single task by ID
-pub async fn get_task(Path(id): Path<Uuid>) -> Result<AppResponse<TaskResponse>, AppError> {
-    let task = TaskService::get_by_id(id)?;
+pub async fn get_task(
+    State(service): State<TaskService>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<FieldsQuery>,
+) -> Result<AppResponse<SparseTask>, AppError> {
+    let task = blocking(move || service.get_by_id(id, query.fields)).await?;
     Ok(AppResponse::ok(task))
 }
 
 /// Create a new task
 pub async fn create_task(
-    Json(req): Json<CreateTaskRequest>,
+    State(service): State<TaskService>,
+    base_url: BaseUrl,
+    Payload(req): Payload<CreateTaskRequest>,
 ) -> Result<AppResponse<TaskResponse>, AppError> {
-    let task = TaskService::create(req)?;
-    Ok(AppResponse::created(task))
+    let task = service.create(req)?;
+    let location = base_url.join(&format!("/tasks/{}", task.id));
+    Ok(AppResponse::created(task).with_location(location))
 }
 
 /// Update an existing task
 pub async fn update_task(
+    State(service): State<TaskService>,
     Path(id): Path<Uuid>,
-    Json(req): Json<UpdateTaskRequest>,
+    Payload(req): Payload<UpdateTaskRequest>,
 ) -> Result<AppResponse<TaskResponse>, AppError> {
-    let task = TaskService::update(id, req)?;
+    let task = blocking(move || service.update(id, req)).await?;
     Ok(AppResponse::ok(task))
 }
 
-/// Delete a task
-pub async fn delete_task(Path(id): Path<Uuid>) -> Result<AppResponse<()>, AppError> {
-    TaskService::delete(id)?;
+/// Delete a task, `?children=cascade|reparent` decides the fate of its subtasks
+pub async fn delete_task(
+    State(service): State<TaskService>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DeleteTaskQuery>,
+) -> Result<AppResponse<()>, AppError> {
+    service.delete(id, query.children)?;
     Ok(AppResponse::no_content())
 }
+
+/// Move a task in the manual order
+pub async fn move_task(
+    State(service): State<TaskService>,
+    Path(id): Path<Uuid>,
+    Payload(payload): Payload<MoveTaskRequest>,
+) -> Result<AppResponse<TaskResponse>, AppError> {
+    let task = blocking(move || service.move_task(id, payload)).await?;
+    Ok(AppResponse::ok(task))
+}
+
+/// Preview the upcoming occurrences of a recurring task
+pub async fn list_occurrences(
+    State(service): State<TaskService>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<OccurrencesQuery>,
+) -> Result<AppResponse<OccurrencesResponse>, AppError> {
+    let occurrences = service.get_occurrences(id, query.count)?;
+    Ok(AppResponse::ok(occurrences))
+}
+
+/// List the direct subtasks of a task
+pub async fn list_subtasks(
+    State(service): State<TaskService>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<FieldsQuery>,
+) -> Result<AppResponse<Vec<SparseTask>>, AppError> {
+    let tasks = blocking(move || service.get_subtasks(id, query.fields)).await?;
+    Ok(AppResponse::ok(tasks))
+}
+
+/// Create a subtask under a task
+pub async fn create_subtask(
+    State(service): State<TaskService>,
+    Path(id): Path<Uuid>,
+    base_url: BaseUrl,
+    Payload(req): Payload<CreateTaskRequest>,
+) -> Result<AppResponse<TaskResponse>, AppError> {
+    let task = service.create_subtask(id, req)?;
+    let location = base_url.join(&format!("/tasks/{}", task.id));
+    Ok(AppResponse::created(task).with_location(location))
+}
+
+/// Get a task with its whole subtask hierarchy
+pub async fn get_task_tree(
+    State(service): State<TaskService>,
+    Path(id): Path<Uuid>,
+) -> Result<AppResponse<TaskTreeNode>, AppError> {
+    let tree = blocking(move || service.get_tree(id)).await?;
+    Ok(AppResponse::ok(tree))
+}
+
+/// Attach a tag to a task
+pub async fn add_task_tag(
+    State(service): State<TaskService>,
+    Path((id, tag_id)): Path<(Uuid, Uuid)>,
+) -> Result<AppResponse<TaskResponse>, AppError> {
+    let task = service.add_tag(id, tag_id)?;
+    Ok(AppResponse::ok(task))
+}
+
+/// Detach a tag from a task
+pub async fn remove_task_tag(
+    State(service): State<TaskService>,
+    Path((id, tag_id)): Path<(Uuid, Uuid)>,
+) -> Result<AppResponse<TaskResponse>, AppError> {
+    let task = service.remove_tag(id, tag_id)?;
+    Ok(AppResponse::ok(task))
+}
+
+/// List the tasks blocking a task
+pub async fn list_dependencies(
+    Path(id): Path<Uuid>,
+) -> Result<AppResponse<Vec<TaskResponse>>, AppError> {
+    let tasks = DependencyService::get_blockers(id)?;
+    Ok(AppResponse::ok(tasks))
+}
+
+/// Mark a task as blocked by another one
+pub async fn add_dependency(
+    Path((id, blocked_by_id)): Path<(Uuid, Uuid)>,
+) -> Result<AppResponse<Vec<TaskResponse>>, AppError> {
+    let tasks = blocking(move || DependencyService::add(id, blocked_by_id)).await?;
+    Ok(AppResponse::ok(tasks))
+}
+
+/// Remove a dependency between two tasks
+pub async fn remove_dependency(
+    Path((id, blocked_by_id)): Path<(Uuid, Uuid)>,
+) -> Result<AppResponse<Vec<TaskResponse>>, AppError> {
+    let tasks = DependencyService::remove(id, blocked_by_id)?;
+    Ok(AppResponse::ok(tasks))
+}
+
+/// Topological order and critical path for `?id=...&id=...` (all open tasks by default)
+pub async fn get_schedule(
+    Query(query): Query<ScheduleQuery>,
+) -> Result<AppResponse<ScheduleResponse>, AppError> {
+    let schedule = DependencyService::schedule(query.id)?;
+    Ok(AppResponse::ok(schedule))
+}
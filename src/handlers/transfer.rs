@@ -0,0 +1,60 @@
+use axum::body::{Body, Bytes};
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum_diesel_api::{ExportTasksQuery, ImportReport, ImportTasksQuery, TaskFileFormat};
+use axum_extra::extract::Query;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::db::connection;
+use crate::error::AppError;
+use crate::response::AppResponse;
+use crate::services::TransferService;
+
+/// Export chunks buffered ahead of a slow client
+const EXPORT_BUFFER: usize = 4;
+
+/// Stream every task as CSV or NDJSON, `?format=csv|ndjson`
+pub async fn export_tasks(
+    Query(query): Query<ExportTasksQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let export = TransferService::prepare_export(query)?;
+    let format = export.format();
+
+    let (tx, rx) = mpsc::channel::<Result<Bytes, std::io::Error>>(EXPORT_BUFFER);
+    let read_primary = connection::reads_use_primary();
+    tokio::task::spawn_blocking(move || {
+        let result = connection::route_reads_blocking(read_primary, || {
+            export.run(|chunk| tx.blocking_send(Ok(Bytes::from(chunk))).is_ok())
+        });
+        if let Err(e) = result {
+            // Headers are gone already: abort the body so the client sees a truncated file
+            tracing::error!("Task export failed: {}", e);
+            let _ = tx.blocking_send(Err(std::io::Error::other(e)));
+        }
+    });
+
+    let (content_type, filename) = match format {
+        TaskFileFormat::Csv => ("text/csv; charset=utf-8", "tasks.csv"),
+        TaskFileFormat::Ndjson => ("application/x-ndjson", "tasks.ndjson"),
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        Body::from_stream(ReceiverStream::new(rx)),
+    ))
+}
+
+/// Import tasks from CSV or NDJSON, `?format=csv|ndjson&dry_run=true`
+pub async fn import_tasks(
+    Query(query): Query<ImportTasksQuery>,
+    body: Bytes,
+) -> Result<AppResponse<ImportReport>, AppError> {
+    let report = TransferService::import(query.format, query.dry_run, &body)?;
+    Ok(AppResponse::ok(report))
+}
@@ -0,0 +1,67 @@
+use axum::extract::Path;
+use axum_diesel_api::{
+    CreateWebhookRequest, ListDeliveriesQuery, UpdateWebhookRequest, WebhookDeliveryResponse,
+    WebhookResponse,
+};
+use axum_extra::extract::Query;
+use uuid::Uuid;
+
+use crate::base_path::BaseUrl;
+use crate::codec::Payload;
+use crate::error::AppError;
+use crate::response::AppResponse;
+use crate::services::WebhookService;
+
+/// List all webhooks
+pub async fn list_webhooks() -> Result<AppResponse<Vec<WebhookResponse>>, AppError> {
+    let webhooks = WebhookService::get_all()?;
+    Ok(AppResponse::ok(webhooks))
+}
+
+/// Get a single webhook by ID
+pub async fn get_webhook(Path(id): Path<Uuid>) -> Result<AppResponse<WebhookResponse>, AppError> {
+    let webhook = WebhookService::get_by_id(id)?;
+    Ok(AppResponse::ok(webhook))
+}
+
+/// Register a webhook for task events
+pub async fn create_webhook(
+    base_url: BaseUrl,
+    Payload(req): Payload<CreateWebhookRequest>,
+) -> Result<AppResponse<WebhookResponse>, AppError> {
+    let webhook = WebhookService::create(req)?;
+    let location = base_url.join(&format!("/webhooks/{}", webhook.id));
+    Ok(AppResponse::created(webhook).with_location(location))
+}
+
+/// Update an existing webhook
+pub async fn update_webhook(
+    Path(id): Path<Uuid>,
+    Payload(req): Payload<UpdateWebhookRequest>,
+) -> Result<AppResponse<WebhookResponse>, AppError> {
+    let webhook = WebhookService::update(id, req)?;
+    Ok(AppResponse::ok(webhook))
+}
+
+/// Delete a webhook and its delivery logs
+pub async fn delete_webhook(Path(id): Path<Uuid>) -> Result<AppResponse<()>, AppError> {
+    WebhookService::delete(id)?;
+    Ok(AppResponse::no_content())
+}
+
+/// List a webhook's latest deliveries, `?status=dead` for the dead letters
+pub async fn list_deliveries(
+    Path(id): Path<Uuid>,
+    Query(query): Query<ListDeliveriesQuery>,
+) -> Result<AppResponse<Vec<WebhookDeliveryResponse>>, AppError> {
+    let deliveries = WebhookService::get_deliveries(id, query)?;
+    Ok(AppResponse::ok(deliveries))
+}
+
+/// Send a delivered or dead delivery again
+pub async fn retry_delivery(
+    Path((id, delivery_id)): Path<(Uuid, Uuid)>,
+) -> Result<AppResponse<WebhookDeliveryResponse>, AppError> {
+    let delivery = WebhookService::retry_delivery(id, delivery_id)?;
+    Ok(AppResponse::ok(delivery))
+}
@@ -0,0 +1,188 @@
+use axum::extract::State;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::response::Response;
+use axum_diesel_api::{ClientMessage, ServerMessage, TaskResponse};
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::handlers::blocking;
+use crate::services::event_service::Signal;
+use crate::services::room_service::RoomKey;
+use crate::services::{EventService, RoomService, TaskService};
+
+/// Open a collaboration socket
+///
+/// See `axum_diesel_api::ws` for the message protocol.
+pub async fn open_socket(
+    State(tasks): State<TaskService>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    // Fails early where task events are not streamed (Lambda)
+    let events = EventService::subscribe(None)?.receiver;
+    Ok(ws.on_upgrade(move |socket| Session::new(socket, tasks, events).run()))
+}
+
+struct Session {
+    socket: WebSocket,
+    tasks: TaskService,
+    connection_id: Uuid,
+    events: broadcast::Receiver<Signal>,
+    room: Option<(RoomKey, broadcast::Receiver<ServerMessage>)>,
+}
+
+impl Session {
+    fn new(socket: WebSocket, tasks: TaskService, events: broadcast::Receiver<Signal>) -> Self {
+        Self {
+            socket,
+            tasks,
+            connection_id: Uuid::new_v4(),
+            events,
+            room: None,
+        }
+    }
+
+    async fn run(mut self) {
+        loop {
+            let outgoing = tokio::select! {
+                incoming = self.socket.recv() => match incoming {
+                    Some(Ok(Message::Text(text))) => self.handle(&text).await,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    // Pings are answered by axum, binary frames are not part of the protocol
+                    Some(Ok(_)) => None,
+                },
+                presence = recv_room(&mut self.room) => match presence {
+                    Ok(message) => Some(message),
+                    // The next presence message carries the whole roster again
+                    Err(RecvError::Lagged(_)) => None,
+                    Err(RecvError::Closed) => break,
+                },
+                signal = self.events.recv() => self.room_event(signal),
+            };
+
+            if let Some(message) = outgoing {
+                let json = serde_json::to_string(&message).expect("server messages serialize");
+                if self.socket.send(Message::Text(json.into())).await.is_err() {
+                    break;
+                }
+            }
+        }
+
+        if let Some((key, _)) = self.room.take() {
+            RoomService::leave(key, self.connection_id);
+        }
+    }
+
+    /// Apply a client message and build the reply, if any
+    async fn handle(&mut self, text: &str) -> Option<ServerMessage> {
+        let message = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(message) => message,
+            Err(e) => {
+                let e = AppError::invalid_input(format!("Invalid message: {}", e));
+                return Some(error(None, e));
+            }
+        };
+
+        if let ClientMessage::Join { user, project_id } = message {
+            return Some(
+                match RoomService::join(project_id, self.connection_id, &user) {
+                    Ok((members, receiver)) => {
+                        if let Some((key, _)) = self.room.replace((project_id, receiver)) {
+                            RoomService::leave(key, self.connection_id);
+                        }
+                        ServerMessage::Joined {
+                            connection_id: self.connection_id,
+                            project_id,
+                            members,
+                        }
+                    }
+                    Err(e) => error(None, e),
+                },
+            );
+        }
+
+        let Some((key, _)) = self.room else {
+            return Some(error(None, AppError::validation("Join a project first")));
+        };
+
+        // Editing changes are answered by the room's presence broadcast
+        let tasks = self.tasks.clone();
+        let reply = match message {
+            ClientMessage::Join { .. } => unreachable!("handled above"),
+            ClientMessage::StartEditing { task_id } => {
+                RoomService::set_editing(key, self.connection_id, task_id, true);
+                return None;
+            }
+            ClientMessage::StopEditing { task_id } => {
+                RoomService::set_editing(key, self.connection_id, task_id, false);
+                return None;
+            }
+            ClientMessage::CreateTask { request_id, task } => reply(
+                request_id,
+                blocking(move || RoomService::create_task(&tasks, key, task).map(Some)).await,
+            ),
+            ClientMessage::UpdateTask {
+                request_id,
+                task_id,
+                changes,
+            } => reply(
+                request_id,
+                blocking(move || RoomService::update_task(&tasks, key, task_id, changes).map(Some))
+                    .await,
+            ),
+            ClientMessage::DeleteTask {
+                request_id,
+                task_id,
+                children,
+            } => reply(
+                request_id,
+                blocking(move || {
+                    RoomService::delete_task(&tasks, key, task_id, children).map(|_| None)
+                })
+                .await,
+            ),
+        };
+        Some(reply)
+    }
+
+    /// Forward task events of the joined room's project
+    fn room_event(&self, signal: Result<Signal, RecvError>) -> Option<ServerMessage> {
+        let (key, _) = self.room.as_ref()?;
+        match signal {
+            Ok(Signal::Event(event)) => {
+                (event.task.project_id == *key).then(|| ServerMessage::TaskChanged {
+                    event: (*event).clone(),
+                })
+            }
+            Ok(Signal::Reset) | Err(RecvError::Lagged(_)) => Some(ServerMessage::Reset),
+            Err(RecvError::Closed) => None,
+        }
+    }
+}
+
+/// Receive from the joined room, or wait forever before joining one
+async fn recv_room(
+    room: &mut Option<(RoomKey, broadcast::Receiver<ServerMessage>)>,
+) -> Result<ServerMessage, RecvError> {
+    match room {
+        Some((_, receiver)) => receiver.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+fn reply(
+    request_id: Option<String>,
+    result: Result<Option<TaskResponse>, AppError>,
+) -> ServerMessage {
+    match result {
+        Ok(task) => ServerMessage::Result { request_id, task },
+        Err(e) => error(request_id, e),
+    }
+}
+
+fn error(request_id: Option<String>, e: AppError) -> ServerMessage {
+    ServerMessage::Error {
+        request_id,
+        error: e.to_error_response(),
+    }
+}
@@ -0,0 +1,409 @@
+//! Local Lambda emulation (`--lambda-emulate`)
+//!
+//! Serves plain HTTP by wrapping each request in an API Gateway HTTP API
+//! (v2) event, running it through the Lambda handler and unwrapping the
+//! response, so every request takes the exact path it takes on Lambda.
+//! Raw events (SQS, schedules, jobs, recorded requests) can be posted to
+//! the invoke path of the Lambda Runtime Interface Emulator:
+//!
+//! ```bash
+//! curl -d @tests/fixtures/lambda/sqs_batch.json \
+//!     localhost:3000/2015-03-31/functions/function/invocations
+//! ```
+
+use axum::body::{Body, Bytes};
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::Utc;
+use lambda_http::{Context, Error, LambdaEvent};
+use serde_json::{Map, Value, json};
+use tokio::runtime::Handle;
+use uuid::Uuid;
+
+/// Invoke path of the Lambda Runtime Interface Emulator
+pub const INVOKE_PATH: &str = "/2015-03-31/functions/function/invocations";
+
+/// Largest request body accepted, like API Gateway
+const MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// Router serving `app` the way Lambda would
+pub fn router(app: Router) -> Router {
+    Router::new()
+        .route(INVOKE_PATH, post(invoke))
+        .fallback(proxy)
+        .with_state(app)
+}
+
+/// Run a raw event and return the function's response payload
+///
+/// Errors are reported like the Invoke API: a `200` with the
+/// `X-Amz-Function-Error` header and an error payload.
+async fn invoke(State(app): State<Router>, body: Bytes) -> Response {
+    // Like the Invoke API, any content type is accepted
+    let payload = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+    match call(app, payload).await {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => (
+            [("x-amz-function-error", "Unhandled")],
+            Json(json!({ "errorType": "Error", "errorMessage": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+/// Serve a plain HTTP request through an API Gateway v2 event
+async fn proxy(State(app): State<Router>, request: Request) -> Response {
+    let (parts, body) = request.into_parts();
+    let body = match axum::body::to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+    };
+
+    let event = to_event(&parts.method, &parts.uri, &parts.headers, &body);
+    match call(app, event).await {
+        Ok(response) => from_response(&response).unwrap_or_else(|e| {
+            tracing::error!("Invalid Lambda response: {}", e);
+            StatusCode::BAD_GATEWAY.into_response()
+        }),
+        Err(e) => {
+            tracing::error!("Lambda invocation failed: {}", e);
+            // What API Gateway answers when the function fails
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "message": "Internal Server Error" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Run the Lambda handler on an event
+///
+/// The handler future is not `Send` (the adapter holds the request body
+/// across awaits), so it runs on a thread of its own instead of a task.
+async fn call(app: Router, payload: Value) -> Result<Value, Error> {
+    let runtime = Handle::current();
+    tokio::task::spawn_blocking(move || {
+        runtime.block_on(super::handle(app, LambdaEvent::new(payload, context())))
+    })
+    .await?
+}
+
+fn context() -> Context {
+    let mut context = Context::default();
+    context.request_id = Uuid::new_v4().to_string();
+    context
+}
+
+/// The API Gateway HTTP API (v2) event of a request
+///
+/// Cookies move to `cookies`, repeated headers are joined with commas,
+/// `X-Forwarded-*` headers are added and bodies that are not UTF-8 are
+/// base64 encoded, as API Gateway does.
+pub fn to_event(
+    method: &axum::http::Method,
+    uri: &axum::http::Uri,
+    headers: &HeaderMap,
+    body: &Bytes,
+) -> Value {
+    let mut cookies = Vec::new();
+    let mut joined: Map<String, Value> = Map::new();
+    for (name, value) in headers {
+        let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+        if name == header::COOKIE {
+            cookies.extend(value.split(';').map(|cookie| cookie.trim().to_string()));
+            continue;
+        }
+        match joined.get_mut(name.as_str()) {
+            Some(Value::String(existing)) => *existing = format!("{},{}", existing, value),
+            _ => {
+                joined.insert(name.to_string(), Value::String(value));
+            }
+        }
+    }
+
+    // Added by API Gateway; the emulator is reached over plain HTTP
+    let forwarded = [
+        ("x-forwarded-for", "127.0.0.1"),
+        ("x-forwarded-proto", "http"),
+    ];
+    for (name, value) in forwarded {
+        joined
+            .entry(name)
+            .or_insert_with(|| Value::String(value.to_string()));
+    }
+
+    let header = |name: HeaderName| headers.get(name).and_then(|value| value.to_str().ok());
+    let host = header(header::HOST).unwrap_or("localhost");
+    let now = Utc::now();
+
+    let mut event = json!({
+        "version": "2.0",
+        "routeKey": "$default",
+        "rawPath": uri.path(),
+        "rawQueryString": uri.query().unwrap_or(""),
+        "headers": joined,
+        "requestContext": {
+            "accountId": "123456789012",
+            "apiId": "emulator",
+            "domainName": host,
+            "domainPrefix": host.split('.').next().unwrap_or(host),
+            "http": {
+                "method": method.as_str(),
+                "path": uri.path(),
+                "protocol": "HTTP/1.1",
+                "sourceIp": "127.0.0.1",
+                "userAgent": header(header::USER_AGENT).unwrap_or(""),
+            },
+            "requestId": Uuid::new_v4().to_string(),
+            "routeKey": "$default",
+            "stage": "$default",
+            "time": now.format("%d/%b/%Y:%H:%M:%S %z").to_string(),
+            "timeEpoch": now.timestamp_millis(),
+        },
+        "isBase64Encoded": false,
+    });
+    if !cookies.is_empty() {
+        event["cookies"] = json!(cookies);
+    }
+    if !body.is_empty() {
+        match std::str::from_utf8(body) {
+            Ok(text) => event["body"] = json!(text),
+            Err(_) => {
+                event["body"] = json!(BASE64.encode(body));
+                event["isBase64Encoded"] = json!(true);
+            }
+        }
+    }
+    event
+}
+
+/// The HTTP response of an API Gateway v2 response payload
+pub fn from_response(payload: &Value) -> Result<Response, String> {
+    let status = payload["statusCode"]
+        .as_u64()
+        .and_then(|code| StatusCode::from_u16(code.try_into().ok()?).ok())
+        .ok_or("missing or invalid statusCode")?;
+
+    let mut response = Response::builder().status(status);
+    let headers = response.headers_mut().ok_or("invalid response")?;
+    let value = |value: &Value| {
+        value
+            .as_str()
+            .and_then(|value| HeaderValue::from_str(value).ok())
+            .ok_or_else(|| format!("invalid header value {}", value))
+    };
+    for (name, values) in payload["multiValueHeaders"]
+        .as_object()
+        .into_iter()
+        .flatten()
+    {
+        let name = HeaderName::try_from(name).map_err(|e| e.to_string())?;
+        for v in values.as_array().into_iter().flatten() {
+            headers.append(&name, value(v)?);
+        }
+    }
+    for (name, v) in payload["headers"].as_object().into_iter().flatten() {
+        let name = HeaderName::try_from(name).map_err(|e| e.to_string())?;
+        if !headers.contains_key(&name) {
+            headers.insert(name, value(v)?);
+        }
+    }
+    for cookie in payload["cookies"].as_array().into_iter().flatten() {
+        headers.append(header::SET_COOKIE, value(cookie)?);
+    }
+
+    let body = match payload["body"].as_str() {
+        None => Vec::new(),
+        Some(body) if payload["isBase64Encoded"] == true => {
+            BASE64.decode(body).map_err(|e| e.to_string())?
+        }
+        Some(body) => body.as_bytes().to_vec(),
+    };
+    response.body(Body::from(body)).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Method;
+    use axum::response::AppendHeaders;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    /// Echo the request body and cookies, and set two cookies
+    fn app() -> Router {
+        Router::new().route(
+            "/echo",
+            post(|headers: HeaderMap, body: Bytes| async move {
+                let cookie = headers
+                    .get(header::COOKIE)
+                    .map(|value| value.to_str().unwrap().to_string())
+                    .unwrap_or_default();
+                (
+                    [
+                        (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+                        (HeaderName::from_static("x-cookie"), cookie),
+                    ],
+                    AppendHeaders([
+                        (header::SET_COOKIE, "a=1; Path=/"),
+                        (header::SET_COOKIE, "b=2; HttpOnly"),
+                    ]),
+                    body,
+                )
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_proxy_round_trips_binary_bodies_and_cookies() {
+        let request = Request::post("/echo")
+            .header(header::COOKIE, "session=abc; theme=dark")
+            .body(Body::from(vec![0u8, 159, 146, 150]))
+            .unwrap();
+
+        let response = router(app()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-cookie"], "session=abc; theme=dark");
+        let cookies: Vec<_> = response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .collect();
+        assert_eq!(cookies, ["a=1; Path=/", "b=2; HttpOnly"]);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], [0u8, 159, 146, 150]);
+    }
+
+    #[tokio::test]
+    async fn test_invoke_returns_the_raw_payload() {
+        let app = Router::new().route("/health", get(|| async { "ok" }));
+        let request = Request::post(INVOKE_PATH)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(include_str!(
+                "../../tests/fixtures/lambda/apigw_v2_get_health.json"
+            )))
+            .unwrap();
+
+        let response = router(app.clone()).oneshot(request).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let payload: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["statusCode"], 200);
+        assert_eq!(payload["body"], "ok");
+        assert_eq!(payload["isBase64Encoded"], false);
+
+        // Events the function rejects are function errors
+        let request = Request::post(INVOKE_PATH)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"job": "reboot"}"#))
+            .unwrap();
+        let response = router(app).oneshot(request).await.unwrap();
+        assert_eq!(response.headers()["x-amz-function-error"], "Unhandled");
+    }
+
+    async fn invoke_app(app: Router, event: Value) -> Value {
+        call(app, event).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_http_api_payload_encodes_binary_bodies_and_cookies() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, HeaderValue::from_static("session=abc"));
+        let body = Bytes::from_static(&[0, 159, 146, 150]);
+        let event = to_event(&Method::POST, &"/echo".parse().unwrap(), &headers, &body);
+        assert_eq!(event["body"], "AJ+Slg==");
+        assert_eq!(event["isBase64Encoded"], true);
+
+        let payload = invoke_app(app(), event).await;
+        assert_eq!(
+            payload,
+            json!({
+                "statusCode": 200,
+                "headers": {
+                    "content-length": "4",
+                    "content-type": "application/octet-stream",
+                    "x-cookie": "session=abc",
+                },
+                "multiValueHeaders": {},
+                "body": "AJ+Slg==",
+                "isBase64Encoded": true,
+                "cookies": ["a=1; Path=/", "b=2; HttpOnly"],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_api_payload_has_multi_value_headers() {
+        let app = Router::new().route(
+            "/prod/tasks/{id}",
+            get(|| async {
+                (
+                    [(header::CONTENT_TYPE, "application/json")],
+                    AppendHeaders([(header::SET_COOKIE, "a=1"), (header::SET_COOKIE, "b=2")]),
+                    r#"{"ok":true}"#,
+                )
+            }),
+        );
+        let event = serde_json::from_str(include_str!(
+            "../../tests/fixtures/lambda/apigw_v1_get_task.json"
+        ))
+        .unwrap();
+
+        let payload = invoke_app(app, event).await;
+        assert_eq!(
+            payload,
+            json!({
+                "statusCode": 200,
+                "headers": {},
+                "multiValueHeaders": {
+                    "content-length": ["11"],
+                    "content-type": ["application/json"],
+                    "set-cookie": ["a=1", "b=2"],
+                },
+                "body": r#"{"ok":true}"#,
+                "isBase64Encoded": false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_to_event_matches_api_gateway() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, HeaderValue::from_static("localhost:3000"));
+        headers.append("x-tag", HeaderValue::from_static("a"));
+        headers.append("x-tag", HeaderValue::from_static("b"));
+        headers.insert(header::COOKIE, HeaderValue::from_static("a=1; b=2"));
+
+        let event = to_event(
+            &Method::GET,
+            &"/tasks?tag=a&tag=b".parse().unwrap(),
+            &headers,
+            &Bytes::new(),
+        );
+        assert_eq!(event["rawPath"], "/tasks");
+        assert_eq!(event["rawQueryString"], "tag=a&tag=b");
+        assert_eq!(event["headers"]["x-tag"], "a,b");
+        assert_eq!(event["headers"]["x-forwarded-proto"], "http");
+        assert!(event["headers"].get("cookie").is_none());
+        assert_eq!(event["cookies"], json!(["a=1", "b=2"]));
+        assert!(event.get("body").is_none());
+
+        // The event is one lambda_http accepts
+        let request: lambda_http::request::LambdaRequest = serde_json::from_value(event).unwrap();
+        let request: lambda_http::Request = request.into();
+        assert_eq!(request.uri().path(), "/tasks");
+        assert_eq!(request.headers()[header::COOKIE], "a=1; b=2");
+    }
+}
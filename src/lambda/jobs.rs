@@ -0,0 +1,141 @@
+use aws_lambda_events::eventbridge::EventBridgeEvent;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::env;
+
+use crate::error::AppError;
+use crate::services::webhook_service::{self, WebhookService};
+use crate::services::{ProjectService, TaskService};
+
+/// Maintenance work run by a schedule or a direct invocation
+///
+/// Invoked directly as e.g. `{"job": "purge_archived", "older_than_days": 7}`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "job", rename_all = "snake_case")]
+pub enum Job {
+    /// Delete projects archived for longer than `older_than_days`
+    /// (default `PURGE_ARCHIVED_AFTER_DAYS`, or 30)
+    PurgeArchived {
+        #[serde(default)]
+        older_than_days: Option<u32>,
+    },
+    /// Respread task ranks that grew too long
+    RebalancePositions,
+    /// Queue and send pending webhook deliveries
+    DispatchWebhooks,
+}
+
+impl Job {
+    /// Pick the job of a scheduled event from its rule or schedule name
+    ///
+    /// The name must end with the job's kebab-case name, e.g.
+    /// `tasks-api-purge-archived` runs `purge_archived` with its defaults.
+    pub fn for_schedule(event: &EventBridgeEvent) -> Result<Self, AppError> {
+        if event.detail_type != "Scheduled Event" {
+            return Err(AppError::validation(format!(
+                "Unsupported EventBridge event '{}' from '{}'",
+                event.detail_type, event.source
+            )));
+        }
+
+        let name = event
+            .resources
+            .iter()
+            .flatten()
+            .filter_map(|arn| arn.rsplit('/').next())
+            .next()
+            .ok_or_else(|| AppError::validation("Scheduled event has no rule"))?;
+
+        let jobs = [
+            (
+                "purge-archived",
+                Job::PurgeArchived {
+                    older_than_days: None,
+                },
+            ),
+            ("rebalance-positions", Job::RebalancePositions),
+            ("dispatch-webhooks", Job::DispatchWebhooks),
+        ];
+        jobs.into_iter()
+            .find(|(suffix, _)| name.ends_with(suffix))
+            .map(|(_, job)| job)
+            .ok_or_else(|| AppError::validation(format!("No job for schedule '{}'", name)))
+    }
+
+    /// Run the job and describe what it did
+    pub fn run(self) -> Result<Value, AppError> {
+        match self {
+            Job::PurgeArchived { older_than_days } => {
+                let days = older_than_days.unwrap_or_else(|| {
+                    env::var("PURGE_ARCHIVED_AFTER_DAYS")
+                        .ok()
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(30)
+                });
+                let purged = ProjectService::purge_archived(days)?;
+                tracing::info!(
+                    "Purged {} project(s) archived over {} days ago",
+                    purged,
+                    days
+                );
+                Ok(json!({ "purged_projects": purged }))
+            }
+            Job::RebalancePositions => {
+                let rebalanced = TaskService::postgres().rebalance_positions_if_needed()?;
+                Ok(json!({ "rebalanced_tasks": rebalanced }))
+            }
+            Job::DispatchWebhooks => {
+                let stats = WebhookService::dispatch(&webhook_service::http_agent())?;
+                Ok(serde_json::to_value(stats)?)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_for_schedule() {
+        let mut event: EventBridgeEvent = serde_json::from_str(include_str!(
+            "../../tests/fixtures/lambda/eventbridge_schedule.json"
+        ))
+        .unwrap();
+        assert_eq!(
+            Job::for_schedule(&event).unwrap(),
+            Job::PurgeArchived {
+                older_than_days: None
+            }
+        );
+
+        event.resources = Some(vec![
+            "arn:aws:scheduler:us-east-1:123456789012:schedule/default/dispatch-webhooks"
+                .to_string(),
+        ]);
+        assert_eq!(Job::for_schedule(&event).unwrap(), Job::DispatchWebhooks);
+
+        event.resources = Some(vec!["arn:aws:events:us-east-1:1:rule/backup".to_string()]);
+        assert!(Job::for_schedule(&event).is_err());
+
+        event.detail_type = "EC2 Instance State-change Notification".to_string();
+        assert!(Job::for_schedule(&event).is_err());
+    }
+
+    #[test]
+    fn test_direct_invocation_payload() {
+        let job: Job = serde_json::from_str(include_str!(
+            "../../tests/fixtures/lambda/invoke_purge.json"
+        ))
+        .unwrap();
+        assert_eq!(
+            job,
+            Job::PurgeArchived {
+                older_than_days: Some(7)
+            }
+        );
+
+        let job: Job = serde_json::from_str(r#"{"job": "rebalance_positions"}"#).unwrap();
+        assert_eq!(job, Job::RebalancePositions);
+    }
+}
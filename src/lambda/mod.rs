@@ -0,0 +1,157 @@
+//! Lambda entry point
+//!
+//! One function serves API Gateway and ALB requests through the router,
+//! SQS batches, EventBridge schedules and direct invocations.
+
+pub mod emulator;
+pub mod jobs;
+pub mod sqs;
+
+use aws_lambda_events::eventbridge::EventBridgeEvent;
+use aws_lambda_events::sqs::SqsEvent;
+use axum::Router;
+use lambda_http::request::LambdaRequest;
+use lambda_http::tower::ServiceExt;
+use lambda_http::{Adapter, Error, LambdaEvent, lambda_runtime, service_fn};
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use crate::error::AppError;
+use jobs::Job;
+
+/// An invocation payload, by event shape
+#[derive(Debug)]
+pub enum Invocation {
+    Http(Box<LambdaRequest>),
+    Sqs(SqsEvent),
+    Schedule(Box<EventBridgeEvent>),
+    Direct(Job),
+}
+
+impl Invocation {
+    /// Tell the event sources apart by their distinguishing fields
+    pub fn classify(payload: Value) -> Result<Self, serde_json::Error> {
+        let is_sqs = payload["Records"][0]["eventSource"] == "aws:sqs";
+        let is_eventbridge =
+            payload.get("detail-type").is_some() && payload.get("source").is_some();
+        let is_http =
+            payload.get("requestContext").is_some() || payload.get("httpMethod").is_some();
+
+        if is_sqs {
+            serde_json::from_value(payload).map(Invocation::Sqs)
+        } else if is_eventbridge {
+            serde_json::from_value(payload).map(|event| Invocation::Schedule(Box::new(event)))
+        } else if is_http {
+            serde_json::from_value(payload).map(|request| Invocation::Http(Box::new(request)))
+        } else {
+            serde_json::from_value(payload).map(Invocation::Direct)
+        }
+    }
+}
+
+/// Whether the next invocation is the first one of this instance
+static COLD: AtomicBool = AtomicBool::new(true);
+
+/// Run the Lambda runtime until it shuts down
+///
+/// `started` is when the process started, to log the cold start duration.
+pub async fn run(app: Router, started: Instant) -> Result<(), Error> {
+    tracing::info!("Initialized in {}ms", started.elapsed().as_millis());
+    lambda_runtime::run(service_fn(move |event| {
+        let app = app.clone();
+        async move {
+            let invoked = Instant::now();
+            let result = handle(app, event).await;
+            if COLD.swap(false, Ordering::Relaxed) {
+                tracing::info!(
+                    "Cold start: first invocation took {}ms, {}ms after process start",
+                    invoked.elapsed().as_millis(),
+                    started.elapsed().as_millis()
+                );
+            }
+            result
+        }
+    }))
+    .await
+}
+
+async fn handle(app: Router, event: LambdaEvent<Value>) -> Result<Value, Error> {
+    let LambdaEvent { payload, context } = event;
+    match Invocation::classify(payload)? {
+        Invocation::Http(request) => {
+            let Ok(response) = Adapter::from(app)
+                .oneshot(LambdaEvent::new(*request, context))
+                .await;
+            Ok(serde_json::to_value(response)?)
+        }
+        Invocation::Sqs(event) => {
+            let response = blocking(move || Ok(sqs::handle_batch(event, sqs::process))).await?;
+            Ok(serde_json::to_value(response)?)
+        }
+        Invocation::Schedule(event) => {
+            let job = Job::for_schedule(&event)?;
+            Ok(blocking(move || job.run()).await?)
+        }
+        Invocation::Direct(job) => Ok(blocking(move || job.run()).await?),
+    }
+}
+
+/// Run database work off the async runtime
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| AppError::internal(format!("Lambda job panicked: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(fixture: &str) -> Invocation {
+        Invocation::classify(serde_json::from_str(fixture).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_classify_fixtures() {
+        assert!(matches!(
+            classify(include_str!("../../tests/fixtures/lambda/apigw_v2_get_health.json")),
+            Invocation::Http(request) if matches!(*request, LambdaRequest::ApiGatewayV2(_))
+        ));
+        assert!(matches!(
+            classify(include_str!("../../tests/fixtures/lambda/sqs_batch.json")),
+            Invocation::Sqs(event) if event.records.len() == 3
+        ));
+        assert!(matches!(
+            classify(include_str!(
+                "../../tests/fixtures/lambda/eventbridge_schedule.json"
+            )),
+            Invocation::Schedule(_)
+        ));
+        assert!(matches!(
+            classify(include_str!(
+                "../../tests/fixtures/lambda/invoke_purge.json"
+            )),
+            Invocation::Direct(Job::PurgeArchived { .. })
+        ));
+
+        assert!(Invocation::classify(serde_json::json!({"job": "reboot"})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_http_events_reach_the_router() {
+        let app = Router::new().route("/health", axum::routing::get(|| async { "ok" }));
+        let payload = serde_json::from_str(include_str!(
+            "../../tests/fixtures/lambda/apigw_v2_get_health.json"
+        ))
+        .unwrap();
+
+        let response = handle(app, LambdaEvent::new(payload, Default::default()))
+            .await
+            .unwrap();
+        assert_eq!(response["statusCode"], 200);
+        assert_eq!(response["body"], "ok");
+    }
+}
@@ -0,0 +1,125 @@
+use aws_lambda_events::sqs::{BatchItemFailure, SqsBatchResponse, SqsEvent};
+use axum_diesel_api::{CreateTaskRequest, TaskFileFormat};
+use serde::Deserialize;
+
+use crate::error::AppError;
+use crate::services::{TaskService, TransferService};
+
+/// Work item sent through the task queue, as the JSON body of a message
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QueueMessage {
+    /// Import a CSV or NDJSON file, like `POST /tasks/import`
+    ImportTasks {
+        format: TaskFileFormat,
+        content: String,
+    },
+    /// Create a task, like `POST /tasks`
+    CreateTask { task: CreateTaskRequest },
+}
+
+/// Apply a queue message
+///
+/// Rejected import rows are logged: retrying the message would reject them again.
+pub fn process(message: QueueMessage) -> Result<(), AppError> {
+    match message {
+        QueueMessage::ImportTasks { format, content } => {
+            let report = TransferService::import(format, false, content.as_bytes())?;
+            if !report.rejected.is_empty() {
+                tracing::warn!(
+                    "Queued import rejected {} row(s), first: {:?}",
+                    report.rejected.len(),
+                    report.rejected[0]
+                );
+            }
+            Ok(())
+        }
+        QueueMessage::CreateTask { task } => TaskService::postgres().create(task).map(|_| ()),
+    }
+}
+
+/// Process a batch record by record, reporting the records to retry
+///
+/// Only records that failed on an error that may pass later (the database
+/// being unavailable or a transient conflict) are returned to the queue, so
+/// the event source mapping must enable `ReportBatchItemFailures`. Malformed
+/// or invalid messages would fail again on every delivery: they are logged
+/// and acknowledged.
+pub fn handle_batch(
+    event: SqsEvent,
+    mut process: impl FnMut(QueueMessage) -> Result<(), AppError>,
+) -> SqsBatchResponse {
+    let mut response = SqsBatchResponse::default();
+
+    for record in event.records {
+        let message_id = record.message_id.unwrap_or_default();
+        let result = serde_json::from_str::<QueueMessage>(record.body.as_deref().unwrap_or(""))
+            .map_err(|e| AppError::invalid_input(format!("Invalid message: {}", e)))
+            .and_then(&mut process);
+
+        match result {
+            Ok(()) => {}
+            Err(e) if e.may_succeed_later() => {
+                tracing::warn!("Queue message {} failed, to retry: {}", message_id, e);
+                let mut failure = BatchItemFailure::default();
+                failure.item_identifier = message_id;
+                response.batch_item_failures.push(failure);
+            }
+            Err(e) => tracing::error!("Queue message {} dropped: {}", message_id, e),
+        }
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handle_batch_reports_failed_records() {
+        let event: SqsEvent =
+            serde_json::from_str(include_str!("../../tests/fixtures/lambda/sqs_batch.json"))
+                .unwrap();
+
+        let mut processed = Vec::new();
+        let response = handle_batch(event, |message| {
+            processed.push(message.clone());
+            match message {
+                QueueMessage::ImportTasks { format, content } => {
+                    assert_eq!(format, TaskFileFormat::Ndjson);
+                    assert_eq!(content.lines().count(), 2);
+                    Ok(())
+                }
+                QueueMessage::CreateTask { .. } => {
+                    Err(AppError::service_unavailable("database is down"))
+                }
+            }
+        });
+
+        assert_eq!(processed.len(), 2);
+        let failed: Vec<&str> = response
+            .batch_item_failures
+            .iter()
+            .map(|failure| failure.item_identifier.as_str())
+            .collect();
+        // The create that may pass later, but not the unparseable body
+        assert_eq!(failed, ["2e1424d4-f796-459a-8184-9c92662be6da"]);
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            json["batchItemFailures"][0]["itemIdentifier"],
+            "2e1424d4-f796-459a-8184-9c92662be6da"
+        );
+    }
+
+    #[test]
+    fn test_rejected_messages_are_not_retried() {
+        let event: SqsEvent =
+            serde_json::from_str(include_str!("../../tests/fixtures/lambda/sqs_batch.json"))
+                .unwrap();
+
+        let response = handle_batch(event, |_| Err(AppError::validation("Title is required")));
+        assert!(response.batch_item_failures.is_empty());
+    }
+}
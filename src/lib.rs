@@ -0,0 +1,13 @@
+//! Task API: the router, its services and data access
+//!
+//! The server binary and the integration tests build the application from here.
+
+pub mod app;
+pub mod base_path;
+pub mod codec;
+pub mod db;
+pub mod error;
+pub mod handlers;
+pub mod lambda;
+pub mod response;
+pub mod services;
@@ -1,13 +1,8 @@
 use std::env;
 
-mod app;
-mod db;
-mod error;
-mod handlers;
-mod response;
-mod services;
-
-use app::build_router;
+use axum_diesel_project::app::{build_router, build_server_router, build_task_router};
+use axum_diesel_project::services::{self, TaskService};
+use axum_diesel_project::{db, lambda};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 /// Setup logging for all execution modes
@@ -21,37 +16,179 @@ fn setup_logging() {
         .init();
 }
 
+/// Periodically respread task ranks once they grow too long
+///
+/// Runs every `POSITION_REBALANCE_INTERVAL_SECS` seconds (default 3600);
+/// `0` disables it.
+fn spawn_position_rebalancer(tasks: TaskService) {
+    let secs = env::var("POSITION_REBALANCE_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(3600);
+    if secs == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(secs));
+        loop {
+            interval.tick().await;
+            let tasks = tasks.clone();
+            let result =
+                tokio::task::spawn_blocking(move || tasks.rebalance_positions_if_needed()).await;
+            match result {
+                Ok(Ok(0)) => {}
+                Ok(Ok(n)) => tracing::info!("Rebalanced positions of {} task(s)", n),
+                Ok(Err(e)) => tracing::warn!("Position rebalance failed: {}", e),
+                Err(e) => tracing::warn!("Position rebalance task panicked: {}", e),
+            }
+        }
+    });
+}
+
+/// Periodically send due webhook deliveries and prune old outbox events
+///
+/// Runs every `WEBHOOK_DISPATCH_INTERVAL_SECS` seconds (default 5);
+/// `0` disables it.
+fn spawn_webhook_dispatcher() {
+    let secs = env::var("WEBHOOK_DISPATCH_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(5);
+    if secs == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let agent = services::webhook_service::http_agent();
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(secs));
+        loop {
+            interval.tick().await;
+            let agent = agent.clone();
+            let result =
+                tokio::task::spawn_blocking(move || services::WebhookService::dispatch(&agent))
+                    .await;
+            match result {
+                Ok(Ok(stats)) if stats == Default::default() => {}
+                Ok(Ok(stats)) => tracing::info!(
+                    "Webhooks: {} event(s) queued, {} delivered, {} to retry, {} dead",
+                    stats.queued,
+                    stats.delivered,
+                    stats.retried,
+                    stats.dead
+                ),
+                Ok(Err(e)) => tracing::warn!("Webhook dispatch failed: {}", e),
+                Err(e) => tracing::warn!("Webhook dispatch task panicked: {}", e),
+            }
+        }
+    });
+}
+
+/// `SERVER_HOST:SERVER_PORT` of the local modes
+fn server_addr() -> String {
+    let host = env::var("SERVER_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
+    let port = env::var("SERVER_PORT").unwrap_or_else(|_| "3000".to_string());
+    format!("{}:{}", host, port)
+}
+
+/// Serve the task endpoints on `tasks` for the modes without PostgreSQL
+///
+/// There are no projects, tags, webhooks or change events.
+async fn serve_tasks_only(tasks: TaskService, label: &str) {
+    spawn_position_rebalancer(tasks.clone());
+
+    let listener = tokio::net::TcpListener::bind(server_addr())
+        .await
+        .expect("Failed to bind to address");
+    tracing::info!(
+        "Server listening on http://{} ({})",
+        listener.local_addr().expect("bound address"),
+        label
+    );
+
+    if let Err(e) = axum::serve(listener, build_task_router(tasks)).await {
+        tracing::error!("Server error: {}", e);
+        std::process::exit(1);
+    }
+}
+
 #[tokio::main]
 async fn main() {
+    let started = std::time::Instant::now();
+
     // Initialize logging early for all modes
     setup_logging();
 
-    // Initialize database connection pool
-    db::connection::init_pool().expect("Failed to initialize database pool");
-    tracing::info!("Database connection pool initialized");
+    if env::args().any(|arg| arg == "--in-memory") {
+        // Demo mode: tasks live in process memory, nothing touches the database
+        tracing::info!("Starting in in-memory mode");
+        serve_tasks_only(TaskService::in_memory(), "in-memory tasks").await;
+        return;
+    }
 
-    let app = build_router();
+    #[cfg(feature = "sqlite")]
+    if let Some(path) = env::var("DATABASE_URL")
+        .ok()
+        .as_deref()
+        .and_then(db::connection::sqlite_path)
+    {
+        // Local development on a SQLite file instead of PostgreSQL
+        tracing::info!("Starting in SQLite mode on {}", path);
+        let tasks = TaskService::sqlite(path).unwrap_or_else(|e| {
+            tracing::error!("Failed to open the SQLite database: {}", e);
+            std::process::exit(1);
+        });
+        serve_tasks_only(tasks, "SQLite tasks").await;
+        return;
+    }
 
     if env::var("AWS_LAMBDA_FUNCTION_NAME").is_ok() {
-        // Lambda execution mode
+        // Lambda execution mode: the pool is created by the first request that needs it
         tracing::info!("Starting in Lambda mode");
-        if let Err(e) = lambda_http::run(app).await {
+        if let Err(e) = lambda::run(build_router(), started).await {
             tracing::error!("Lambda runtime error: {}", e);
             std::process::exit(1);
         }
+    } else if env::args().any(|arg| arg == "--lambda-emulate") {
+        // Local Lambda emulation: requests go through API Gateway events and
+        // the Lambda handler, without background jobs
+        tracing::info!("Starting in Lambda emulation mode");
+        db::connection::use_lambda_profile();
+        let listener = tokio::net::TcpListener::bind(server_addr())
+            .await
+            .expect("Failed to bind to address");
+        tracing::info!(
+            "Emulating Lambda on http://{} (raw events: POST {})",
+            listener.local_addr().expect("bound address"),
+            lambda::emulator::INVOKE_PATH
+        );
+
+        if let Err(e) = axum::serve(listener, lambda::emulator::router(build_router())).await {
+            tracing::error!("Server error: {}", e);
+            std::process::exit(1);
+        }
     } else {
         // Local HTTP server mode
-        let host = env::var("SERVER_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
-        let port = env::var("SERVER_PORT").unwrap_or_else(|_| "3000".to_string());
-        let addr = format!("{}:{}", host, port);
+        let addr = server_addr();
 
         tracing::info!("Starting in local HTTP server mode");
+        // Warm the pool up in the background; requests retry if the database is not there yet
+        tokio::task::spawn_blocking(|| match db::connection::init_pool() {
+            Ok(()) => tracing::info!("Database connection pool initialized"),
+            Err(e) => tracing::warn!("{:#}", e),
+        });
+        spawn_position_rebalancer(TaskService::postgres());
+        spawn_webhook_dispatcher();
+        if let Err(e) = services::EventService::spawn_listener() {
+            tracing::warn!("Task events are disabled: {}", e);
+        }
+
         let listener = tokio::net::TcpListener::bind(&addr)
             .await
             .expect("Failed to bind to address");
         tracing::info!("Server listening on http://{}", addr);
 
-        if let Err(e) = axum::serve(listener, app).await {
+        if let Err(e) = axum::serve(listener, build_server_router()).await {
             tracing::error!("Server error: {}", e);
             std::process::exit(1);
         }
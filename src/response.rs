@@ -1,19 +1,21 @@
 use axum::{
-    Json,
-    http::{HeaderMap, StatusCode},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
     response::{IntoResponse, Response},
 };
 use axum_diesel_api::{AppResponse as ApiResponse, StatusCode as ApiStatusCode};
 use serde::Serialize;
 
+use crate::codec;
+
 /// Backend wrapper for axum-diesel-api's AppResponse with Axum integration
 ///
 /// This wrapper adds Axum's IntoResponse trait and header support to the
-/// WASM-compatible AppResponse from the API crate.
+/// WASM-compatible AppResponse from the API crate. Bodies are encoded in the
+/// format negotiated from `Accept` (see [`codec`]).
 ///
 /// # Examples
 ///
-/// ```rust
+/// ```ignore
 /// use crate::response::AppResponse;
 ///
 /// // Simple JSON response
@@ -52,6 +54,16 @@ where
         self
     }
 
+    /// Adds a `Location` header pointing at the created resource
+    pub fn with_location(mut self, location: String) -> Self {
+        if let Ok(value) = HeaderValue::try_from(location) {
+            self.headers
+                .get_or_insert_with(HeaderMap::new)
+                .insert(header::LOCATION, value);
+        }
+        self
+    }
+
     // === Convenience constructors ===
 
     /// 200 OK with data
@@ -103,7 +115,7 @@ where
     fn into_response(self) -> Response {
         let status = convert_status(self.inner.status);
         let mut response = match self.inner.data {
-            Some(data) => (status, Json(data)).into_response(),
+            Some(data) => codec::respond(status, &data),
             None => status.into_response(),
         };
 
@@ -0,0 +1,387 @@
+use axum_diesel_api::{
+    CalendarComponent, CalendarFeedResponse, CalendarQuery, CreateCalendarFeedRequest,
+    TaskPriority, TaskResponse,
+};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::db::connection;
+use crate::db::models::NewCalendarFeed;
+use crate::db::repositories::{
+    CalendarFeedRepository, ProjectRepository, TaskFilter, TaskRepository,
+};
+use crate::error::AppError;
+use crate::services::TaskService;
+
+const PRODID: &str = "-//axum-diesel-project//Tasks//EN";
+
+/// Content lines are folded at 75 octets (RFC 5545 section 3.1)
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Escape a TEXT value (RFC 5545 section 3.3.11)
+fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn format_utc(at: &DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Append a content line, folding it without splitting UTF-8 characters
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for ch in line.chars() {
+        if width + ch.len_utf8() > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(ch);
+        width += ch.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// iCalendar priority: 1 is highest, 9 lowest
+fn ical_priority(priority: TaskPriority) -> u8 {
+    match priority {
+        TaskPriority::Urgent => 1,
+        TaskPriority::High => 3,
+        TaskPriority::Medium => 5,
+        TaskPriority::Low => 9,
+    }
+}
+
+/// Render tasks with a due date as an iCalendar document
+///
+/// Each task becomes a `VTODO` (or `VEVENT`) whose UID is the task ID, so
+/// calendar apps update entries in place. Events have no completed state in
+/// iCalendar, so completed tasks are left out of event calendars.
+pub fn render_calendar(name: &str, tasks: &[TaskResponse], component: CalendarComponent) -> String {
+    let mut out = String::new();
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, &format!("PRODID:{}", PRODID));
+    push_line(&mut out, "CALSCALE:GREGORIAN");
+    push_line(&mut out, &format!("X-WR-CALNAME:{}", escape_text(name)));
+
+    for task in tasks {
+        let Some(due_at) = task.due_at else {
+            continue;
+        };
+        if component == CalendarComponent::Event && task.completed {
+            continue;
+        }
+
+        let kind = match component {
+            CalendarComponent::Todo => "VTODO",
+            CalendarComponent::Event => "VEVENT",
+        };
+
+        push_line(&mut out, &format!("BEGIN:{}", kind));
+        push_line(&mut out, &format!("UID:{}", task.id));
+        push_line(
+            &mut out,
+            &format!("DTSTAMP:{}", format_utc(&task.updated_at)),
+        );
+        push_line(
+            &mut out,
+            &format!("CREATED:{}", format_utc(&task.created_at)),
+        );
+        push_line(
+            &mut out,
+            &format!("LAST-MODIFIED:{}", format_utc(&task.updated_at)),
+        );
+        push_line(&mut out, &format!("SUMMARY:{}", escape_text(&task.title)));
+        if let Some(ref description) = task.description {
+            push_line(
+                &mut out,
+                &format!("DESCRIPTION:{}", escape_text(description)),
+            );
+        }
+
+        match component {
+            CalendarComponent::Todo => {
+                push_line(&mut out, &format!("DUE:{}", format_utc(&due_at)));
+                if task.completed {
+                    push_line(&mut out, "STATUS:COMPLETED");
+                    if let Some(ref completed_at) = task.completed_at {
+                        push_line(&mut out, &format!("COMPLETED:{}", format_utc(completed_at)));
+                    }
+                } else {
+                    push_line(&mut out, "STATUS:NEEDS-ACTION");
+                }
+            }
+            CalendarComponent::Event => {
+                push_line(&mut out, &format!("DTSTART:{}", format_utc(&due_at)));
+                push_line(&mut out, "STATUS:CONFIRMED");
+                push_line(&mut out, "TRANSP:TRANSPARENT");
+            }
+        }
+
+        push_line(
+            &mut out,
+            &format!("PRIORITY:{}", ical_priority(task.priority)),
+        );
+        if !task.tags.is_empty() {
+            let categories: Vec<String> = task.tags.iter().map(|t| escape_text(&t.name)).collect();
+            push_line(&mut out, &format!("CATEGORIES:{}", categories.join(",")));
+        }
+        push_line(&mut out, &format!("END:{}", kind));
+    }
+
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+pub struct CalendarService;
+
+impl CalendarService {
+    /// Render the tasks with a due date, optionally limited to one project
+    pub fn export(query: CalendarQuery) -> Result<String, AppError> {
+        Self::render(query.project_id, query.component)
+    }
+
+    /// Render the calendar behind a feed token
+    pub fn export_feed(token: &str) -> Result<String, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let feed = CalendarFeedRepository::find_by_token(&mut conn, token)
+            .map_err(|e| e.not_found_as("Calendar feed not found"))?;
+        drop(conn);
+
+        Self::render(feed.project_id, feed.component())
+    }
+
+    /// List all calendar feeds
+    pub fn get_feeds() -> Result<Vec<CalendarFeedResponse>, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let feeds = CalendarFeedRepository::find_all(&mut conn)?;
+        Ok(feeds.into_iter().map(Into::into).collect())
+    }
+
+    /// Create a feed with a fresh secret token, whose URL is returned this once
+    pub fn create_feed(req: CreateCalendarFeedRequest) -> Result<CalendarFeedResponse, AppError> {
+        if req.name.trim().is_empty() {
+            return Err(AppError::validation("Feed name cannot be empty"));
+        }
+
+        if req.name.trim().len() > 100 {
+            return Err(AppError::validation(
+                "Feed name must be at most 100 characters",
+            ));
+        }
+
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        if let Some(project_id) = req.project_id {
+            ProjectRepository::find_by_id(&mut conn, project_id)
+                .map_err(|e| e.not_found_as("Project not found"))?;
+        }
+
+        // Two v4 UUIDs give 244 random bits
+        let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+
+        let new_feed = NewCalendarFeed {
+            name: req.name.trim().to_string(),
+            token,
+            project_id: req.project_id,
+            component: req.component.as_str().to_string(),
+        };
+
+        let feed = CalendarFeedRepository::create(&mut conn, new_feed)?;
+        Ok(feed.into_created_response())
+    }
+
+    /// Delete a feed, revoking its URL
+    pub fn delete_feed(id: Uuid) -> Result<(), AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        CalendarFeedRepository::delete(&mut conn, id)
+            .map_err(|e| e.not_found_as("Calendar feed not found"))
+    }
+
+    fn render(project_id: Option<Uuid>, component: CalendarComponent) -> Result<String, AppError> {
+        let mut conn = connection::get_read_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let name = match project_id {
+            Some(project_id) => {
+                ProjectRepository::find_by_id(&mut conn, project_id)
+                    .map_err(|e| e.not_found_as("Project not found"))?
+                    .name
+            }
+            None => "Tasks".to_string(),
+        };
+
+        let filter = TaskFilter {
+            project_id,
+            has_due_at: true,
+            ..Default::default()
+        };
+        let tasks = TaskRepository::find_filtered(&mut conn, &filter, true)?;
+        let tasks = TaskService::to_responses(&mut conn, tasks)?;
+
+        Ok(render_calendar(&name, &tasks, component))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::CalendarFeed;
+    use axum_diesel_api::TagResponse;
+    use chrono::TimeZone;
+    use ical::parser::ical::component::IcalCalendar;
+    use ical::property::Property;
+
+    fn task(id: u128, completed: bool) -> TaskResponse {
+        let at = |h| Utc.with_ymd_and_hms(2026, 3, 1, h, 0, 0).unwrap();
+        TaskResponse {
+            id: Uuid::from_u128(id),
+            completed,
+            created_at: at(8),
+            updated_at: at(9),
+            due_at: Some(at(17)),
+            priority: TaskPriority::High,
+            completed_at: completed.then(|| at(10)),
+            ..TaskResponse::fixture(&format!("Task {}", id))
+        }
+    }
+
+    /// Parse with the `ical` crate after checking line endings and folding
+    fn parse_strict(ics: &str) -> IcalCalendar {
+        assert!(ics.ends_with("\r\n"));
+        for line in ics.split_terminator("\r\n") {
+            assert!(!line.contains('\n'), "bare LF in {:?}", line);
+            assert!(line.len() <= MAX_LINE_OCTETS, "line too long: {:?}", line);
+        }
+
+        let mut parser = ical::IcalParser::new(ics.as_bytes());
+        let calendar = parser
+            .next()
+            .expect("one calendar")
+            .expect("valid iCalendar");
+        assert!(parser.next().is_none());
+        calendar
+    }
+
+    fn value<'a>(properties: &'a [Property], name: &str) -> Option<&'a str> {
+        properties
+            .iter()
+            .find(|p| p.name == name)
+            .and_then(|p| p.value.as_deref())
+    }
+
+    #[test]
+    fn test_url_is_only_in_the_created_response() {
+        let feed = CalendarFeed {
+            id: Uuid::new_v4(),
+            name: "Alice".to_string(),
+            token: "s3cret".to_string(),
+            project_id: None,
+            component: "todo".to_string(),
+            created_at: Utc::now(),
+        };
+
+        let created = feed.clone().into_created_response();
+        assert_eq!(created.url.as_deref(), Some("/feeds/s3cret/tasks.ics"));
+        let listed = serde_json::to_value(CalendarFeedResponse::from(feed)).unwrap();
+        assert!(listed.get("url").is_none());
+    }
+
+    #[test]
+    fn test_render_todos() {
+        let mut open = task(1, false);
+        open.tags = vec![TagResponse {
+            id: Uuid::from_u128(9),
+            name: "work".to_string(),
+            created_at: open.created_at,
+            updated_at: open.updated_at,
+        }];
+        let mut undated = task(3, false);
+        undated.due_at = None;
+
+        let ics = render_calendar(
+            "Tasks",
+            &[open, task(2, true), undated],
+            CalendarComponent::Todo,
+        );
+        let calendar = parse_strict(&ics);
+
+        assert_eq!(value(&calendar.properties, "VERSION"), Some("2.0"));
+        assert_eq!(calendar.todos.len(), 2);
+
+        let first = &calendar.todos[0].properties;
+        assert_eq!(
+            value(first, "UID"),
+            Some(Uuid::from_u128(1).to_string().as_str())
+        );
+        assert_eq!(value(first, "DTSTAMP"), Some("20260301T090000Z"));
+        assert_eq!(value(first, "DUE"), Some("20260301T170000Z"));
+        assert_eq!(value(first, "STATUS"), Some("NEEDS-ACTION"));
+        assert_eq!(value(first, "PRIORITY"), Some("3"));
+        assert_eq!(value(first, "CATEGORIES"), Some("work"));
+
+        let second = &calendar.todos[1].properties;
+        assert_eq!(value(second, "STATUS"), Some("COMPLETED"));
+        assert_eq!(value(second, "COMPLETED"), Some("20260301T100000Z"));
+    }
+
+    #[test]
+    fn test_render_events_skip_completed_tasks() {
+        let ics = render_calendar(
+            "Tasks",
+            &[task(1, false), task(2, true)],
+            CalendarComponent::Event,
+        );
+        let calendar = parse_strict(&ics);
+
+        assert!(calendar.todos.is_empty());
+        assert_eq!(calendar.events.len(), 1);
+        assert_eq!(
+            value(&calendar.events[0].properties, "DTSTART"),
+            Some("20260301T170000Z")
+        );
+    }
+
+    #[test]
+    fn test_escapes_and_folds_long_text() {
+        let mut long = task(1, false);
+        long.title = "Call Zoë; bring notes, \\ slides".to_string();
+        long.description = Some(format!("Line one\n{}", "é".repeat(80)));
+
+        let ics = render_calendar("Tasks", &[long], CalendarComponent::Todo);
+        let calendar = parse_strict(&ics);
+
+        let properties = &calendar.todos[0].properties;
+        assert_eq!(
+            value(properties, "SUMMARY"),
+            Some("Call Zoë\\; bring notes\\, \\\\ slides")
+        );
+        assert_eq!(
+            value(properties, "DESCRIPTION"),
+            Some(format!("Line one\\n{}", "é".repeat(80)).as_str())
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use axum_diesel_api::{ScheduleResponse, TaskResponse};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use uuid::Uuid;
+
+use crate::db::repositories::{DependencyRepository, TaskFilter, TaskRepository};
+use crate::db::{connection, retry};
+use crate::error::AppError;
+use crate::services::TaskService;
+
+/// Order `ids` so that every blocker precedes the tasks it blocks
+///
+/// `edges` are `(task, blocker)` pairs. Ties keep the input order. Returns
+/// `None` if the edges contain a cycle.
+fn topological_order(ids: &[Uuid], edges: &[(Uuid, Uuid)]) -> Option<Vec<Uuid>> {
+    let index: HashMap<Uuid, usize> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+    let mut blocked: Vec<Vec<usize>> = vec![Vec::new(); ids.len()];
+    let mut pending = vec![0usize; ids.len()];
+
+    for (task, blocker) in edges {
+        if let (Some(&t), Some(&b)) = (index.get(task), index.get(blocker)) {
+            blocked[b].push(t);
+            pending[t] += 1;
+        }
+    }
+
+    let mut ready: BinaryHeap<Reverse<usize>> = (0..ids.len())
+        .filter(|&i| pending[i] == 0)
+        .map(Reverse)
+        .collect();
+    let mut order = Vec::with_capacity(ids.len());
+
+    while let Some(Reverse(i)) = ready.pop() {
+        order.push(ids[i]);
+        for &t in &blocked[i] {
+            pending[t] -= 1;
+            if pending[t] == 0 {
+                ready.push(Reverse(t));
+            }
+        }
+    }
+
+    (order.len() == ids.len()).then_some(order)
+}
+
+/// Longest chain of open tasks through the dependency graph
+///
+/// `order` must be topologically sorted. Completed tasks do not add to a
+/// chain's length and are left out of the returned path.
+fn critical_path(order: &[Uuid], edges: &[(Uuid, Uuid)], open: &HashMap<Uuid, bool>) -> Vec<Uuid> {
+    let mut blockers: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for (task, blocker) in edges {
+        blockers.entry(*task).or_default().push(*blocker);
+    }
+
+    let mut length: HashMap<Uuid, usize> = HashMap::new();
+    let mut previous: HashMap<Uuid, Uuid> = HashMap::new();
+    let mut end: Option<(Uuid, usize)> = None;
+
+    for id in order {
+        let best = blockers
+            .get(id)
+            .into_iter()
+            .flatten()
+            .filter_map(|b| length.get(b).map(|len| (*b, *len)))
+            .max_by_key(|(_, len)| *len);
+
+        let own = usize::from(open.get(id).copied().unwrap_or(false));
+        let total = best.map_or(0, |(_, len)| len) + own;
+        if let Some((b, _)) = best {
+            previous.insert(*id, b);
+        }
+        length.insert(*id, total);
+
+        if total > 0 && end.is_none_or(|(_, len)| total > len) {
+            end = Some((*id, total));
+        }
+    }
+
+    let mut path = Vec::new();
+    let mut cursor = end.map(|(id, _)| id);
+    while let Some(id) = cursor {
+        if open.get(&id).copied().unwrap_or(false) {
+            path.push(id);
+        }
+        cursor = previous.get(&id).copied();
+    }
+    path.reverse();
+    path
+}
+
+pub struct DependencyService;
+
+impl DependencyService {
+    /// Get the tasks blocking a task
+    pub fn get_blockers(id: Uuid) -> Result<Vec<TaskResponse>, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        // Check if task exists
+        TaskRepository::find_by_id(&mut conn, id)?;
+
+        let tasks = DependencyRepository::find_blockers(&mut conn, id)?;
+        TaskService::to_responses(&mut conn, tasks)
+    }
+
+    /// Mark a task as blocked by another one
+    pub fn add(id: Uuid, blocked_by_id: Uuid) -> Result<Vec<TaskResponse>, AppError> {
+        if id == blocked_by_id {
+            return Err(AppError::validation("A task cannot block itself"));
+        }
+
+        // Serializable, so that two requests adding the two halves of a
+        // cycle cannot both pass the cycle check
+        retry::serializable(|conn| {
+            // Check if both tasks exist
+            TaskRepository::find_by_id(conn, id)?;
+            TaskRepository::find_by_id(conn, blocked_by_id)
+                .map_err(|e| e.not_found_as("Blocking task not found"))?;
+
+            if DependencyRepository::would_create_cycle(conn, id, blocked_by_id)? {
+                return Err(AppError::validation(
+                    "Dependency would create a cycle: the blocking task already depends on this task",
+                ));
+            }
+
+            DependencyRepository::add(conn, id, blocked_by_id)?;
+
+            let tasks = DependencyRepository::find_blockers(conn, id)?;
+            TaskService::to_responses(conn, tasks)
+        })
+    }
+
+    /// Remove a dependency between two tasks
+    pub fn remove(id: Uuid, blocked_by_id: Uuid) -> Result<Vec<TaskResponse>, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        // Check if task exists
+        TaskRepository::find_by_id(&mut conn, id)?;
+
+        DependencyRepository::remove(&mut conn, id, blocked_by_id)?;
+
+        let tasks = DependencyRepository::find_blockers(&mut conn, id)?;
+        TaskService::to_responses(&mut conn, tasks)
+    }
+
+    /// Order a set of tasks by their dependencies and find the critical path
+    ///
+    /// Without IDs the schedule covers every open, unarchived task.
+    /// Dependencies on tasks outside the set are ignored.
+    pub fn schedule(ids: Vec<Uuid>) -> Result<ScheduleResponse, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let mut tasks = if ids.is_empty() {
+            TaskRepository::find_filtered(
+                &mut conn,
+                &TaskFilter {
+                    completed: Some(false),
+                    ..Default::default()
+                },
+                true,
+            )?
+        } else {
+            let found = TaskRepository::find_by_ids(&mut conn, &ids)?;
+            if let Some(missing) = ids.iter().find(|id| !found.iter().any(|t| t.id == **id)) {
+                return Err(AppError::not_found(format!("Task {} not found", missing)));
+            }
+            found
+        };
+        tasks.sort_by_key(|t| t.created_at);
+
+        let task_ids: Vec<Uuid> = tasks.iter().map(|t| t.id).collect();
+        let edges = DependencyRepository::find_edges_among(&mut conn, &task_ids)?;
+
+        let order = topological_order(&task_ids, &edges)
+            .ok_or_else(|| AppError::internal("Dependency graph contains a cycle"))?;
+        let open: HashMap<Uuid, bool> = tasks.iter().map(|t| (t.id, !t.completed)).collect();
+        let critical_path = critical_path(&order, &edges, &open);
+
+        let mut by_id: HashMap<Uuid, TaskResponse> = TaskService::to_responses(&mut conn, tasks)?
+            .into_iter()
+            .map(|t| (t.id, t))
+            .collect();
+
+        Ok(ScheduleResponse {
+            order: order.iter().filter_map(|id| by_id.remove(id)).collect(),
+            critical_path,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(n: u128) -> Vec<Uuid> {
+        (1..=n).map(Uuid::from_u128).collect()
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let ids = ids(4);
+        // 1 is blocked by 3, 3 is blocked by 4
+        let edges = vec![(ids[0], ids[2]), (ids[2], ids[3])];
+        let order = topological_order(&ids, &edges).unwrap();
+        assert_eq!(order, vec![ids[1], ids[3], ids[2], ids[0]]);
+    }
+
+    #[test]
+    fn test_topological_order_detects_cycle() {
+        let ids = ids(2);
+        let edges = vec![(ids[0], ids[1]), (ids[1], ids[0])];
+        assert!(topological_order(&ids, &edges).is_none());
+    }
+
+    #[test]
+    fn test_critical_path() {
+        let ids = ids(5);
+        // Chains: 4 -> 3 -> 1 and 5 -> 2 (blocker -> blocked)
+        let edges = vec![(ids[0], ids[2]), (ids[2], ids[3]), (ids[1], ids[4])];
+        let order = topological_order(&ids, &edges).unwrap();
+        let open: HashMap<Uuid, bool> = ids.iter().map(|id| (*id, true)).collect();
+        assert_eq!(
+            critical_path(&order, &edges, &open),
+            vec![ids[3], ids[2], ids[0]]
+        );
+    }
+
+    #[test]
+    fn test_critical_path_skips_completed_tasks() {
+        let ids = ids(3);
+        let edges = vec![(ids[0], ids[1]), (ids[1], ids[2])];
+        let order = topological_order(&ids, &edges).unwrap();
+        let mut open: HashMap<Uuid, bool> = ids.iter().map(|id| (*id, true)).collect();
+        open.insert(ids[2], false);
+        assert_eq!(critical_path(&order, &edges, &open), vec![ids[1], ids[0]]);
+
+        let done: HashMap<Uuid, bool> = ids.iter().map(|id| (*id, false)).collect();
+        assert!(critical_path(&order, &edges, &done).is_empty());
+    }
+}
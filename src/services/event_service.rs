@@ -0,0 +1,326 @@
+use axum_diesel_api::{TaskEvent, TaskEventKind, TaskResponse};
+use diesel::pg::PgConnection;
+use diesel::{Connection, RunQueryDsl};
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::db::connection;
+use crate::db::repositories::TaskRepository;
+use crate::error::AppError;
+use crate::services::TaskService;
+
+/// Events kept in memory for `Last-Event-ID` resume
+const REPLAY_CAPACITY: usize = 1000;
+/// How often the listener collects pending notifications
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// How long the listener stays idle before checking its connection
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+const MIN_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// A message sent to stream subscribers
+#[derive(Debug, Clone)]
+pub enum Signal {
+    Event(Arc<TaskEvent>),
+    /// Events may have been missed: clients should reload their tasks
+    Reset,
+}
+
+/// A new subscriber's view of the stream
+pub struct Subscription {
+    /// Signals to send before the live ones
+    pub backlog: Vec<Signal>,
+    pub receiver: broadcast::Receiver<Signal>,
+}
+
+/// Fans events out to subscribers and keeps the latest ones for replay
+///
+/// Publishing and subscribing share a lock, so a subscriber sees each event
+/// exactly once, either in its backlog or live.
+pub struct EventHub {
+    sender: broadcast::Sender<Signal>,
+    replay: Mutex<VecDeque<Arc<TaskEvent>>>,
+    capacity: usize,
+}
+
+impl EventHub {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            sender,
+            replay: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    pub fn publish(&self, event: TaskEvent) {
+        let event = Arc::new(event);
+        let mut replay = self.replay.lock().expect("replay buffer poisoned");
+        if replay.len() == self.capacity {
+            replay.pop_front();
+        }
+        replay.push_back(event.clone());
+        // Sending only fails when nobody is subscribed
+        let _ = self.sender.send(Signal::Event(event));
+    }
+
+    /// Forget the buffered events after some were lost
+    pub fn reset(&self) {
+        let mut replay = self.replay.lock().expect("replay buffer poisoned");
+        replay.clear();
+        let _ = self.sender.send(Signal::Reset);
+    }
+
+    /// Subscribe, replaying the events buffered after `last_event_id`
+    ///
+    /// An ID that is no longer buffered (or never was) replays a reset.
+    pub fn subscribe(&self, last_event_id: Option<u64>) -> Subscription {
+        let replay = self.replay.lock().expect("replay buffer poisoned");
+        let backlog = match last_event_id {
+            None => Vec::new(),
+            Some(id) => match replay.iter().position(|event| event.id == id) {
+                Some(index) => replay
+                    .iter()
+                    .skip(index + 1)
+                    .cloned()
+                    .map(Signal::Event)
+                    .collect(),
+                None => vec![Signal::Reset],
+            },
+        };
+
+        Subscription {
+            backlog,
+            receiver: self.sender.subscribe(),
+        }
+    }
+}
+
+static HUB: LazyLock<EventHub> = LazyLock::new(|| EventHub::new(REPLAY_CAPACITY));
+static LISTENING: AtomicBool = AtomicBool::new(false);
+
+/// Payload sent on the `task_changes` channel by the change triggers
+#[derive(Debug, Deserialize)]
+struct Notification {
+    event_id: u64,
+    op: TaskEventKind,
+    task_id: Uuid,
+}
+
+pub struct EventService;
+
+impl EventService {
+    /// Postgres channel the change triggers notify on
+    pub const CHANNEL: &'static str = "task_changes";
+
+    /// Subscribe to task changes, resuming after `last_event_id`
+    pub fn subscribe(last_event_id: Option<u64>) -> Result<Subscription, AppError> {
+        if !LISTENING.load(Ordering::Relaxed) {
+            return Err(AppError::service_unavailable(
+                "Task events are only streamed by the local server",
+            ));
+        }
+        Ok(HUB.subscribe(last_event_id))
+    }
+
+    /// Start the thread turning change notifications into events
+    ///
+    /// It listens on a dedicated connection and reconnects with backoff;
+    /// subscribers get a reset after a reconnect.
+    pub fn spawn_listener() -> anyhow::Result<()> {
+        let url = std::env::var("DATABASE_URL")?;
+        thread::Builder::new()
+            .name("task-events".to_string())
+            .spawn(move || Self::listen(&url))?;
+        LISTENING.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn listen(url: &str) {
+        let mut delay = MIN_RECONNECT_DELAY;
+        let mut reconnecting = false;
+        loop {
+            let connected = PgConnection::establish(url)
+                .map_err(|e| e.to_string())
+                .and_then(|mut conn| {
+                    diesel::sql_query(format!("LISTEN {}", Self::CHANNEL))
+                        .execute(&mut conn)
+                        .map(|_| conn)
+                        .map_err(|e| e.to_string())
+                });
+            let mut conn = match connected {
+                Ok(conn) => conn,
+                Err(e) => {
+                    tracing::warn!("Task event listener cannot connect: {}", e);
+                    thread::sleep(delay);
+                    delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                    continue;
+                }
+            };
+
+            delay = MIN_RECONNECT_DELAY;
+            if reconnecting {
+                HUB.reset();
+            }
+            reconnecting = true;
+            tracing::info!("Listening for task changes");
+
+            if let Err(e) = Self::poll(&mut conn) {
+                tracing::warn!("Task event listener lost its connection: {}", e);
+            }
+        }
+    }
+
+    /// Publish notifications in batches until the connection fails
+    fn poll(conn: &mut PgConnection) -> Result<(), diesel::result::Error> {
+        let mut last_activity = Instant::now();
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            let mut batch = Vec::new();
+            for notification in conn.notifications_iter() {
+                let notification = notification?;
+                match serde_json::from_str::<Notification>(&notification.payload) {
+                    Ok(notification) => batch.push(notification),
+                    Err(e) => tracing::warn!("Ignoring task change notification: {}", e),
+                }
+            }
+
+            if batch.is_empty() {
+                if last_activity.elapsed() >= PING_INTERVAL {
+                    diesel::sql_query("SELECT 1").execute(conn)?;
+                    last_activity = Instant::now();
+                }
+                continue;
+            }
+            last_activity = Instant::now();
+
+            match Self::load_events(batch) {
+                Ok(events) => events.into_iter().for_each(|event| HUB.publish(event)),
+                Err(e) => {
+                    tracing::warn!("Failed to load task events: {}", e);
+                    HUB.reset();
+                }
+            }
+        }
+    }
+
+    /// Build the events of a batch of notifications, in notification order
+    ///
+    /// Created and updated events carry the current task and are dropped
+    /// when it has been deleted since; deleted events carry the snapshot
+    /// taken by the trigger.
+    fn load_events(batch: Vec<Notification>) -> Result<Vec<TaskEvent>, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let (deleted, written): (Vec<&Notification>, Vec<&Notification>) =
+            batch.iter().partition(|n| n.op == TaskEventKind::Deleted);
+
+        let task_ids: Vec<Uuid> = written.iter().map(|n| n.task_id).collect();
+        let tasks = TaskRepository::find_by_ids(&mut conn, &task_ids)?;
+        let current: HashMap<Uuid, TaskResponse> = TaskService::to_responses(&mut conn, tasks)?
+            .into_iter()
+            .map(|task| (task.id, task))
+            .collect();
+
+        let event_ids: Vec<i64> = deleted.iter().map(|n| n.event_id as i64).collect();
+        let mut snapshots: HashMap<u64, TaskResponse> = HashMap::new();
+        for deletion in TaskRepository::find_deletions(&mut conn, &event_ids)? {
+            let event_id = deletion.event_id as u64;
+            match deletion.into_response() {
+                Ok(task) => {
+                    snapshots.insert(event_id, task);
+                }
+                Err(e) => tracing::warn!("Ignoring task deletion {}: {}", event_id, e),
+            }
+        }
+
+        Ok(batch
+            .into_iter()
+            .filter_map(|n| {
+                let task = match n.op {
+                    TaskEventKind::Deleted => snapshots.remove(&n.event_id)?,
+                    _ => current.get(&n.task_id)?.clone(),
+                };
+                Some(TaskEvent {
+                    id: n.event_id,
+                    kind: n.op,
+                    task,
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: u64) -> TaskEvent {
+        TaskEvent {
+            id,
+            kind: TaskEventKind::Updated,
+            task: TaskResponse::fixture("Task"),
+        }
+    }
+
+    fn ids(signals: &[Signal]) -> Vec<Option<u64>> {
+        signals
+            .iter()
+            .map(|signal| match signal {
+                Signal::Event(event) => Some(event.id),
+                Signal::Reset => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_subscribe_replays_after_last_event_id() {
+        let hub = EventHub::new(3);
+        for id in [4, 7, 9, 12] {
+            hub.publish(event(id));
+        }
+
+        assert!(hub.subscribe(None).backlog.is_empty());
+        assert_eq!(ids(&hub.subscribe(Some(7)).backlog), [Some(9), Some(12)]);
+        assert!(hub.subscribe(Some(12)).backlog.is_empty());
+        // Evicted or unknown IDs cannot be resumed
+        assert_eq!(ids(&hub.subscribe(Some(4)).backlog), [None]);
+        assert_eq!(ids(&hub.subscribe(Some(8)).backlog), [None]);
+    }
+
+    #[test]
+    fn test_live_events_follow_backlog() {
+        let hub = EventHub::new(3);
+        hub.publish(event(1));
+        let mut subscription = hub.subscribe(Some(1));
+        hub.publish(event(2));
+        hub.reset();
+
+        assert!(subscription.backlog.is_empty());
+        let live = [
+            subscription.receiver.try_recv().unwrap(),
+            subscription.receiver.try_recv().unwrap(),
+        ];
+        assert_eq!(ids(&live), [Some(2), None]);
+        assert_eq!(ids(&hub.subscribe(Some(2)).backlog), [None]);
+    }
+
+    #[test]
+    fn test_notification_payload() {
+        let notification: Notification = serde_json::from_str(
+            r#"{"event_id" : 3, "op" : "deleted", "task_id" : "943d888d-7c79-4139-a4ef-90caf5240eae"}"#,
+        )
+        .unwrap();
+        assert_eq!(notification.event_id, 3);
+        assert_eq!(notification.op, TaskEventKind::Deleted);
+    }
+}
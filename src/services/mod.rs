@@ -1,3 +1,23 @@
+pub mod calendar_service;
+pub mod dependency_service;
+pub mod event_service;
+pub mod project_service;
+pub mod rank;
+pub mod recurrence;
+pub mod room_service;
+pub mod status_service;
+pub mod tag_service;
 pub mod task_service;
+pub mod transfer_service;
+pub mod webhook_service;
 
+pub use calendar_service::CalendarService;
+pub use dependency_service::DependencyService;
+pub use event_service::EventService;
+pub use project_service::ProjectService;
+pub use room_service::RoomService;
+pub use status_service::StatusService;
+pub use tag_service::TagService;
 pub use task_service::TaskService;
+pub use transfer_service::TransferService;
+pub use webhook_service::WebhookService;
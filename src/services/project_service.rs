@@ -0,0 +1,197 @@
+use axum_diesel_api::{
+    CreateProjectRequest, CreateTaskRequest, ListTasksQuery, ProjectResponse, SparseTask,
+    TaskResponse, UpdateProjectRequest,
+};
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::db::connection;
+use crate::db::models::{NewProject, UpdateProject};
+use crate::db::repositories::ProjectRepository;
+use crate::error::AppError;
+use crate::services::TaskService;
+
+fn validate_name(name: &str) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::validation("Project name cannot be empty"));
+    }
+
+    if name.len() > 255 {
+        return Err(AppError::validation(
+            "Project name must be less than 255 characters",
+        ));
+    }
+
+    Ok(())
+}
+
+pub struct ProjectService;
+
+impl ProjectService {
+    /// Get all projects with their task counts
+    pub fn get_all(include_archived: bool) -> Result<Vec<ProjectResponse>, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let projects = ProjectRepository::find_all(&mut conn, include_archived)?;
+        Ok(projects
+            .into_iter()
+            .map(|(project, counts)| project.with_counts(counts))
+            .collect())
+    }
+
+    /// Get a project by ID with its task counts
+    pub fn get_by_id(id: Uuid) -> Result<ProjectResponse, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let project = ProjectRepository::find_by_id(&mut conn, id)?;
+        let counts = ProjectRepository::count_tasks(&mut conn, id)?;
+        Ok(project.with_counts(counts))
+    }
+
+    /// Create a new project
+    pub fn create(req: CreateProjectRequest) -> Result<ProjectResponse, AppError> {
+        validate_name(&req.name)?;
+
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let new_project = NewProject {
+            name: req.name.trim().to_string(),
+            description: req
+                .description
+                .map(|d| d.trim().to_string())
+                .filter(|d| !d.is_empty()),
+        };
+
+        let project = ProjectRepository::create(&mut conn, new_project)?;
+        Ok(project.with_counts(Default::default()))
+    }
+
+    /// Update an existing project
+    pub fn update(id: Uuid, req: UpdateProjectRequest) -> Result<ProjectResponse, AppError> {
+        if let Some(ref name) = req.name {
+            validate_name(name)?;
+        }
+
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        // Check if project exists
+        let mut project = ProjectRepository::find_by_id(&mut conn, id)?;
+
+        let update_project = UpdateProject {
+            name: req.name.map(|n| n.trim().to_string()),
+            description: req
+                .description
+                .map(|d| d.trim().to_string())
+                .filter(|d| !d.is_empty()),
+        };
+
+        // Diesel rejects empty changesets
+        if update_project.name.is_some() || update_project.description.is_some() {
+            project = ProjectRepository::update(&mut conn, id, update_project)?;
+        }
+
+        let counts = ProjectRepository::count_tasks(&mut conn, id)?;
+        Ok(project.with_counts(counts))
+    }
+
+    /// Delete a project and all of its tasks
+    pub fn delete(id: Uuid) -> Result<(), AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        // Check if project exists
+        ProjectRepository::find_by_id(&mut conn, id)?;
+
+        ProjectRepository::delete(&mut conn, id)?;
+        Ok(())
+    }
+
+    /// Delete the projects archived more than `older_than_days` days ago, with their tasks
+    ///
+    /// Returns the number of projects deleted.
+    pub fn purge_archived(older_than_days: u32) -> Result<usize, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let cutoff = Utc::now() - chrono::Duration::days(older_than_days.into());
+        let ids = ProjectRepository::find_archived_before(&mut conn, cutoff)?;
+        for &id in &ids {
+            ProjectRepository::delete(&mut conn, id)?;
+        }
+        Ok(ids.len())
+    }
+
+    /// Archive a project and its tasks (no-op if already archived)
+    pub fn archive(id: Uuid) -> Result<ProjectResponse, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let mut project = ProjectRepository::find_by_id(&mut conn, id)?;
+        if project.archived_at.is_none() {
+            project = ProjectRepository::archive(&mut conn, id)?;
+        }
+
+        let counts = ProjectRepository::count_tasks(&mut conn, id)?;
+        Ok(project.with_counts(counts))
+    }
+
+    /// Unarchive a project and the tasks archived with it (no-op if not archived)
+    pub fn unarchive(id: Uuid) -> Result<ProjectResponse, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let mut project = ProjectRepository::find_by_id(&mut conn, id)?;
+        if project.archived_at.is_some() {
+            project = ProjectRepository::unarchive(&mut conn, id)?;
+        }
+
+        let counts = ProjectRepository::count_tasks(&mut conn, id)?;
+        Ok(project.with_counts(counts))
+    }
+
+    /// List the tasks of a project
+    pub fn get_tasks(
+        tasks: &TaskService,
+        id: Uuid,
+        query: ListTasksQuery,
+    ) -> Result<Vec<SparseTask>, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        // Check if project exists
+        let project = ProjectRepository::find_by_id(&mut conn, id)?;
+        drop(conn);
+
+        tasks.get_all(ListTasksQuery {
+            project_id: Some(id),
+            // Tasks of an archived project are all archived
+            include_archived: query.include_archived || project.archived_at.is_some(),
+            ..query
+        })
+    }
+
+    /// Create a task inside a project
+    pub fn create_task(
+        tasks: &TaskService,
+        id: Uuid,
+        req: CreateTaskRequest,
+    ) -> Result<TaskResponse, AppError> {
+        tasks.create(CreateTaskRequest {
+            project_id: Some(id),
+            ..req
+        })
+    }
+}
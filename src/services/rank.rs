@@ -0,0 +1,114 @@
+//! Lexicographic ranks for manual task ordering
+//!
+//! A rank is a string over `0-9a-z` that never ends in `0`. Between any two
+//! distinct ranks there is always another one, so moving a task only rewrites
+//! that task's rank. Ranks grow longer when a spot is repeatedly split; the
+//! rebalancing job rewrites them evenly spaced when they get too long.
+
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Ranks longer than this trigger a rebalance
+pub const MAX_RANK_LEN: usize = 24;
+
+fn digit(c: u8) -> usize {
+    DIGITS
+        .iter()
+        .position(|d| *d == c)
+        .expect("rank contains a character outside 0-9a-z")
+}
+
+/// Midpoint between `lo` and `hi` (`None` = past the last rank), with `lo < hi`
+fn midpoint(lo: &[u8], hi: Option<&[u8]>) -> Vec<u8> {
+    if let Some(hi) = hi {
+        // Skip the common prefix, treating `lo` as padded with zeros
+        let n = hi
+            .iter()
+            .enumerate()
+            .take_while(|(i, c)| lo.get(*i).copied().unwrap_or(DIGITS[0]) == **c)
+            .count();
+        if n > 0 {
+            let mut rank = hi[..n].to_vec();
+            rank.extend(midpoint(lo.get(n..).unwrap_or_default(), Some(&hi[n..])));
+            return rank;
+        }
+    }
+
+    let digit_lo = lo.first().map_or(0, |c| digit(*c));
+    let digit_hi = hi.map_or(DIGITS.len(), |h| digit(h[0]));
+
+    if digit_hi - digit_lo > 1 {
+        vec![DIGITS[(digit_lo + digit_hi).div_ceil(2)]]
+    } else if let Some(hi) = hi.filter(|h| h.len() > 1) {
+        hi[..1].to_vec()
+    } else {
+        let mut rank = vec![DIGITS[digit_lo]];
+        rank.extend(midpoint(lo.get(1..).unwrap_or_default(), None));
+        rank
+    }
+}
+
+/// A rank strictly between `before` and `after`
+///
+/// `None` stands for the start or the end of the list. Returns `None` when
+/// `before` is not lower than `after` (e.g. two tasks share a rank), which
+/// only a rebalance can fix.
+pub fn rank_between(before: Option<&str>, after: Option<&str>) -> Option<String> {
+    let lo = before.unwrap_or_default().as_bytes();
+    let hi = after.map(str::as_bytes);
+
+    if hi.is_some_and(|hi| lo >= hi) {
+        return None;
+    }
+
+    String::from_utf8(midpoint(lo, hi)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_between_bounds() {
+        assert_eq!(rank_between(None, None).as_deref(), Some("i"));
+        let first = rank_between(None, Some("00000001i")).unwrap();
+        assert!(first.as_str() < "00000001i");
+        let last = rank_between(Some("00000002i"), None).unwrap();
+        assert!(last.as_str() > "00000002i");
+    }
+
+    #[test]
+    fn test_rank_between_neighbours() {
+        for (lo, hi) in [
+            ("a", "b"),
+            ("a", "a1"),
+            ("az", "b"),
+            ("00000001i", "00000002i"),
+        ] {
+            let mid = rank_between(Some(lo), Some(hi)).unwrap();
+            assert!(
+                lo < mid.as_str() && mid.as_str() < hi,
+                "{} < {} < {}",
+                lo,
+                mid,
+                hi
+            );
+            assert!(!mid.ends_with('0'));
+        }
+    }
+
+    #[test]
+    fn test_rank_between_repeated_splits() {
+        let (lo, mut hi) = ("a".to_string(), "b".to_string());
+        for _ in 0..50 {
+            let mid = rank_between(Some(&lo), Some(&hi)).unwrap();
+            assert!(lo < mid && mid < hi);
+            hi = mid;
+        }
+    }
+
+    #[test]
+    fn test_rank_between_rejects_unordered() {
+        assert!(rank_between(Some("b"), Some("a")).is_none());
+        assert!(rank_between(Some("a"), Some("a")).is_none());
+    }
+}
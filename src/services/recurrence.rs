@@ -0,0 +1,572 @@
+//! Recurrence rules for repeating tasks
+//!
+//! Supports the commonly used subset of iCalendar (RFC 5545) RRULEs: `FREQ`
+//! (`DAILY`, `WEEKLY`, `MONTHLY`, `YEARLY`), `INTERVAL`, `COUNT`, `UNTIL`,
+//! `BYDAY`, `BYMONTHDAY` and `BYMONTH`. Occurrences keep the wall-clock time
+//! of the series start in the series time zone, so a 09:00 chore stays at
+//! 09:00 across DST changes.
+
+use chrono::{
+    DateTime, Datelike, Days, Months, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc,
+    Weekday,
+};
+use chrono_tz::Tz;
+use std::str::FromStr;
+
+/// Upper bound on the periods scanned, so rules that never match terminate
+const MAX_PERIODS: u32 = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// Last occurrence allowed by `UNTIL`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Until {
+    /// `UNTIL=20261231T000000Z`
+    Instant(DateTime<Utc>),
+    /// `UNTIL=20261231` or `UNTIL=20261231T090000`, in the series time zone
+    Local(NaiveDateTime),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<Until>,
+    /// Weekdays with an optional ordinal (`1MO`, `-1FR`); 0 means every one
+    pub by_day: Vec<(i32, Weekday)>,
+    pub by_month_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+}
+
+fn parse_weekday(s: &str) -> Option<Weekday> {
+    match s {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn parse_until(s: &str) -> Option<Until> {
+    if let Some(utc) = s.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Until::Instant(naive.and_utc()));
+    }
+
+    if let Ok(naive) = NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%S") {
+        return Some(Until::Local(naive));
+    }
+
+    // A date covers the whole day
+    let date = NaiveDate::parse_from_str(s, "%Y%m%d").ok()?;
+    Some(Until::Local(
+        date.and_time(NaiveTime::from_hms_opt(23, 59, 59)?),
+    ))
+}
+
+fn parse_list<T>(value: &str, parse: impl Fn(&str) -> Option<T>) -> Option<Vec<T>> {
+    value.split(',').map(|item| parse(item.trim())).collect()
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s
+            .get(..6)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("RRULE:"))
+            .map_or(s, |_| &s[6..]);
+
+        let mut freq = None;
+        let mut rule = RecurrenceRule {
+            freq: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+        };
+        let mut seen = Vec::new();
+
+        for part in s.split(';').filter(|p| !p.trim().is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid RRULE part '{}'", part))?;
+            let key = key.trim().to_ascii_uppercase();
+            let value = value.trim().to_ascii_uppercase();
+
+            if seen.contains(&key) {
+                return Err(format!("Duplicate RRULE part '{}'", key));
+            }
+            seen.push(key.clone());
+
+            let invalid = || format!("Invalid {} value '{}'", key, value);
+            match key.as_str() {
+                "FREQ" => {
+                    freq = Some(match value.as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(format!("Unsupported FREQ '{}'", value)),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value.parse().ok().filter(|i| *i > 0).ok_or_else(invalid)?
+                }
+                "COUNT" => {
+                    rule.count = Some(value.parse().ok().filter(|c| *c > 0).ok_or_else(invalid)?)
+                }
+                "UNTIL" => rule.until = Some(parse_until(&value).ok_or_else(invalid)?),
+                "BYDAY" => {
+                    rule.by_day = parse_list(&value, |item| {
+                        let split = item.len().checked_sub(2)?;
+                        let weekday = parse_weekday(item.get(split..)?)?;
+                        let ordinal = match &item[..split] {
+                            "" => 0,
+                            n => n
+                                .parse()
+                                .ok()
+                                .filter(|n: &i32| (1..=5).contains(&n.abs()))?,
+                        };
+                        Some((ordinal, weekday))
+                    })
+                    .ok_or_else(invalid)?
+                }
+                "BYMONTHDAY" => {
+                    rule.by_month_day = parse_list(&value, |item| {
+                        item.parse().ok().filter(|d: &i32| *d != 0 && d.abs() <= 31)
+                    })
+                    .ok_or_else(invalid)?
+                }
+                "BYMONTH" => {
+                    rule.by_month = parse_list(&value, |item| {
+                        item.parse().ok().filter(|m| (1..=12).contains(m))
+                    })
+                    .ok_or_else(invalid)?
+                }
+                "WKST" if value == "MO" => {}
+                _ => return Err(format!("Unsupported RRULE part '{}'", key)),
+            }
+        }
+
+        rule.freq = freq.ok_or("RRULE must have a FREQ")?;
+
+        if rule.count.is_some() && rule.until.is_some() {
+            return Err("RRULE cannot have both COUNT and UNTIL".to_string());
+        }
+
+        let has_ordinals = rule.by_day.iter().any(|(n, _)| *n != 0);
+        match rule.freq {
+            Frequency::Daily | Frequency::Weekly if has_ordinals => {
+                return Err("BYDAY ordinals need FREQ=MONTHLY or YEARLY".to_string());
+            }
+            Frequency::Weekly if !rule.by_month_day.is_empty() => {
+                return Err("BYMONTHDAY cannot be used with FREQ=WEEKLY".to_string());
+            }
+            Frequency::Yearly if has_ordinals && rule.by_month.is_empty() => {
+                return Err("Yearly BYDAY ordinals need a BYMONTH".to_string());
+            }
+            _ => {}
+        }
+
+        Ok(rule)
+    }
+}
+
+/// Convert a wall-clock time to an instant, following RFC 5545 for DST edges
+///
+/// Ambiguous times (clocks going back) use the first occurrence; times in a
+/// gap (clocks going forward) use the offset in effect before the gap.
+pub fn resolve_local(tz: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    if let Some(at) = tz.from_local_datetime(&local).earliest() {
+        return at.with_timezone(&Utc);
+    }
+
+    let before = local - chrono::Duration::hours(3);
+    let offset = tz
+        .from_local_datetime(&before)
+        .earliest()
+        .map_or(0, |at| at.offset().fix().local_minus_utc());
+    (local - chrono::Duration::seconds(offset.into())).and_utc()
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|first| first.checked_add_months(Months::new(1)))
+        .and_then(|next| next.pred_opt())
+        .map_or(28, |last| last.day())
+}
+
+impl RecurrenceRule {
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        (self.by_month.is_empty() || self.by_month.contains(&date.month()))
+            && (self.by_day.is_empty() || self.by_day.iter().any(|(_, w)| *w == date.weekday()))
+            && (self.by_month_day.is_empty()
+                || self
+                    .month_days(date.year(), date.month(), 0)
+                    .contains(&date.day()))
+    }
+
+    /// Days of a month selected by `BYMONTHDAY`/`BYDAY`, or `default_day`
+    fn month_days(&self, year: i32, month: u32, default_day: u32) -> Vec<u32> {
+        let len = days_in_month(year, month);
+
+        if self.by_month_day.is_empty() && self.by_day.is_empty() {
+            return if default_day <= len {
+                vec![default_day]
+            } else {
+                Vec::new()
+            };
+        }
+
+        let by_month_day: Vec<u32> = self
+            .by_month_day
+            .iter()
+            .filter_map(|d| {
+                let day = if *d > 0 { *d } else { len as i32 + 1 + d };
+                (1..=len as i32).contains(&day).then_some(day as u32)
+            })
+            .collect();
+
+        let mut by_day = Vec::new();
+        for (ordinal, weekday) in &self.by_day {
+            let matching: Vec<u32> = (1..=len)
+                .filter(|d| {
+                    NaiveDate::from_ymd_opt(year, month, *d).map(|date| date.weekday())
+                        == Some(*weekday)
+                })
+                .collect();
+            match *ordinal {
+                0 => by_day.extend(matching),
+                n if n > 0 => by_day.extend(matching.get(n as usize - 1)),
+                n => by_day.extend(
+                    matching
+                        .len()
+                        .checked_sub(n.unsigned_abs() as usize)
+                        .map(|i| matching[i]),
+                ),
+            }
+        }
+
+        // When both are given they narrow each other down
+        let mut days = match (self.by_month_day.is_empty(), self.by_day.is_empty()) {
+            (false, false) => by_month_day
+                .into_iter()
+                .filter(|d| by_day.contains(d))
+                .collect(),
+            (true, _) => by_day,
+            (false, true) => by_month_day,
+        };
+        days.sort_unstable();
+        days.dedup();
+        days
+    }
+
+    /// Candidate dates of the `period`-th period of the series, in order
+    fn period_dates(&self, start: NaiveDate, period: u32) -> Option<Vec<NaiveDate>> {
+        let step = period.checked_mul(self.interval)?;
+
+        let dates = match self.freq {
+            Frequency::Daily => {
+                let date = start.checked_add_days(Days::new(step.into()))?;
+                if self.matches_day(date) {
+                    vec![date]
+                } else {
+                    Vec::new()
+                }
+            }
+            Frequency::Weekly => {
+                let monday = start
+                    .checked_sub_days(Days::new(start.weekday().num_days_from_monday().into()))?
+                    .checked_add_days(Days::new(u64::from(step) * 7))?;
+                let mut weekdays: Vec<u32> = if self.by_day.is_empty() {
+                    vec![start.weekday().num_days_from_monday()]
+                } else {
+                    self.by_day
+                        .iter()
+                        .map(|(_, w)| w.num_days_from_monday())
+                        .collect()
+                };
+                weekdays.sort_unstable();
+                weekdays.dedup();
+                weekdays
+                    .into_iter()
+                    .filter_map(|offset| monday.checked_add_days(Days::new(offset.into())))
+                    .filter(|date| {
+                        self.by_month.is_empty() || self.by_month.contains(&date.month())
+                    })
+                    .collect()
+            }
+            Frequency::Monthly => {
+                let first = start.with_day(1)?.checked_add_months(Months::new(step))?;
+                if !self.by_month.is_empty() && !self.by_month.contains(&first.month()) {
+                    return Some(Vec::new());
+                }
+                self.month_days(first.year(), first.month(), start.day())
+                    .into_iter()
+                    .filter_map(|d| first.with_day(d))
+                    .collect()
+            }
+            Frequency::Yearly => {
+                let year = start.year().checked_add(i32::try_from(step).ok()?)?;
+                let months = if self.by_month.is_empty() {
+                    vec![start.month()]
+                } else {
+                    let mut months = self.by_month.clone();
+                    months.sort_unstable();
+                    months.dedup();
+                    months
+                };
+                let mut dates = Vec::new();
+                for month in months {
+                    for day in self.month_days(year, month, start.day()) {
+                        dates.extend(NaiveDate::from_ymd_opt(year, month, day));
+                    }
+                }
+                dates
+            }
+        };
+
+        Some(dates)
+    }
+
+    /// Up to `limit` occurrences strictly after `after`
+    ///
+    /// The series starts at `start`, whose wall-clock time in `tz` every
+    /// occurrence keeps. `COUNT` is counted from `start`.
+    pub fn occurrences_after(
+        &self,
+        start: DateTime<Utc>,
+        tz: Tz,
+        after: DateTime<Utc>,
+        limit: usize,
+    ) -> Vec<DateTime<Utc>> {
+        let local_start = start.with_timezone(&tz).naive_local();
+        let time = local_start.time();
+        let mut emitted = 0;
+        let mut found = Vec::new();
+
+        for period in 0..MAX_PERIODS {
+            let Some(dates) = self.period_dates(local_start.date(), period) else {
+                break;
+            };
+
+            for date in dates {
+                let local = date.and_time(time);
+                if local < local_start {
+                    continue;
+                }
+
+                let at = resolve_local(tz, local);
+                let past_until = match self.until {
+                    Some(Until::Instant(until)) => at > until,
+                    Some(Until::Local(until)) => local > until,
+                    None => false,
+                };
+                emitted += 1;
+                if past_until || self.count.is_some_and(|count| emitted > count) {
+                    return found;
+                }
+
+                if at > after {
+                    found.push(at);
+                    if found.len() >= limit {
+                        return found;
+                    }
+                }
+            }
+        }
+
+        found
+    }
+
+    /// The first occurrence strictly after `after`, if the series has one
+    pub fn next_after(
+        &self,
+        start: DateTime<Utc>,
+        tz: Tz,
+        after: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        self.occurrences_after(start, tz, after, 1).pop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn expand(rule: &str, start: &str, tz: Tz, limit: usize) -> Vec<String> {
+        let rule: RecurrenceRule = rule.parse().unwrap();
+        let start = utc(start);
+        rule.occurrences_after(start, tz, start - chrono::Duration::seconds(1), limit)
+            .into_iter()
+            .map(|at| at.with_timezone(&tz).to_rfc3339())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_rules() {
+        assert!("FREQ=DAILY".parse::<RecurrenceRule>().is_ok());
+        assert!(
+            "RRULE:freq=weekly;byday=mo,we"
+                .parse::<RecurrenceRule>()
+                .is_ok()
+        );
+        assert!("INTERVAL=2".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=HOURLY".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=DAILY;INTERVAL=0".parse::<RecurrenceRule>().is_err());
+        assert!(
+            "FREQ=DAILY;COUNT=2;UNTIL=20260101"
+                .parse::<RecurrenceRule>()
+                .is_err()
+        );
+        assert!("FREQ=WEEKLY;BYDAY=1MO".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=MONTHLY;BYSETPOS=1".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=DAILY;FREQ=WEEKLY".parse::<RecurrenceRule>().is_err());
+    }
+
+    #[test]
+    fn test_every_90_days() {
+        let got = expand(
+            "FREQ=DAILY;INTERVAL=90;COUNT=3",
+            "2026-01-05T09:00:00Z",
+            Tz::UTC,
+            10,
+        );
+        assert_eq!(
+            got,
+            [
+                "2026-01-05T09:00:00+00:00",
+                "2026-04-05T09:00:00+00:00",
+                "2026-07-04T09:00:00+00:00",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_weekly_by_day() {
+        // 2026-02-02 is a Monday
+        let got = expand(
+            "FREQ=WEEKLY;BYDAY=FR,MO",
+            "2026-02-02T08:00:00Z",
+            Tz::UTC,
+            4,
+        );
+        assert_eq!(
+            got,
+            [
+                "2026-02-02T08:00:00+00:00",
+                "2026-02-06T08:00:00+00:00",
+                "2026-02-09T08:00:00+00:00",
+                "2026-02-13T08:00:00+00:00",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_monthly_skips_short_months_and_supports_last_weekday() {
+        let got = expand("FREQ=MONTHLY", "2026-01-31T12:00:00Z", Tz::UTC, 3);
+        assert_eq!(
+            got,
+            [
+                "2026-01-31T12:00:00+00:00",
+                "2026-03-31T12:00:00+00:00",
+                "2026-05-31T12:00:00+00:00",
+            ]
+        );
+
+        let got = expand(
+            "FREQ=MONTHLY;BYDAY=-1FR",
+            "2026-01-30T12:00:00Z",
+            Tz::UTC,
+            2,
+        );
+        assert_eq!(
+            got,
+            ["2026-01-30T12:00:00+00:00", "2026-02-27T12:00:00+00:00"]
+        );
+    }
+
+    #[test]
+    fn test_yearly_leap_day() {
+        let got = expand("FREQ=YEARLY", "2024-02-29T00:00:00Z", Tz::UTC, 2);
+        assert_eq!(
+            got,
+            ["2024-02-29T00:00:00+00:00", "2028-02-29T00:00:00+00:00"]
+        );
+    }
+
+    #[test]
+    fn test_until_is_inclusive() {
+        let got = expand(
+            "FREQ=DAILY;UNTIL=20260103",
+            "2026-01-01T10:00:00Z",
+            Tz::UTC,
+            10,
+        );
+        assert_eq!(got.len(), 3);
+    }
+
+    #[test]
+    fn test_keeps_wall_clock_time_across_dst() {
+        // Clocks in Paris go forward on 2026-03-29
+        let got = expand(
+            "FREQ=WEEKLY",
+            "2026-03-22T08:00:00Z",
+            "Europe/Paris".parse().unwrap(),
+            2,
+        );
+        assert_eq!(
+            got,
+            ["2026-03-22T09:00:00+01:00", "2026-03-29T09:00:00+02:00"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_local_dst_edges() {
+        let tz: Tz = "Europe/Paris".parse().unwrap();
+
+        // 02:30 does not exist on 2026-03-29: use the offset from before the gap
+        let gap = NaiveDate::from_ymd_opt(2026, 3, 29)
+            .unwrap()
+            .and_hms_opt(2, 30, 0)
+            .unwrap();
+        assert_eq!(resolve_local(tz, gap), utc("2026-03-29T01:30:00Z"));
+
+        // 02:30 happens twice on 2026-10-25: use the first one
+        let ambiguous = NaiveDate::from_ymd_opt(2026, 10, 25)
+            .unwrap()
+            .and_hms_opt(2, 30, 0)
+            .unwrap();
+        assert_eq!(resolve_local(tz, ambiguous), utc("2026-10-25T00:30:00Z"));
+    }
+
+    #[test]
+    fn test_rule_that_never_matches_terminates() {
+        let got = expand(
+            "FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30",
+            "2026-01-01T00:00:00Z",
+            Tz::UTC,
+            1,
+        );
+        assert!(got.is_empty());
+    }
+}
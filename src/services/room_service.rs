@@ -0,0 +1,377 @@
+use axum_diesel_api::{
+    ChildrenPolicy, CreateTaskRequest, PresenceMember, ServerMessage, TaskResponse,
+    UpdateTaskRequest,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{LazyLock, Mutex};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::db::connection;
+use crate::db::models::Task;
+use crate::db::repositories::ProjectRepository;
+use crate::db::store::TaskStore;
+use crate::error::AppError;
+use crate::services::TaskService;
+
+/// Presence messages a slow connection may fall behind by
+const ROOM_CHANNEL_CAPACITY: usize = 64;
+
+/// Project whose tasks a room shares (`None` for tasks without a project)
+pub type RoomKey = Option<Uuid>;
+
+struct Member {
+    user: String,
+    editing: BTreeSet<Uuid>,
+}
+
+struct Room {
+    members: BTreeMap<Uuid, Member>,
+    sender: broadcast::Sender<ServerMessage>,
+}
+
+impl Room {
+    fn roster(&self) -> Vec<PresenceMember> {
+        self.members
+            .iter()
+            .map(|(connection_id, member)| PresenceMember {
+                connection_id: *connection_id,
+                user: member.user.clone(),
+                editing: member.editing.iter().copied().collect(),
+            })
+            .collect()
+    }
+
+    fn announce(&self) {
+        // Sending only fails when nobody is listening
+        let _ = self.sender.send(ServerMessage::Presence {
+            members: self.roster(),
+        });
+    }
+}
+
+/// The connections of this server instance, grouped by project
+#[derive(Default)]
+pub struct Rooms {
+    rooms: HashMap<RoomKey, Room>,
+}
+
+impl Rooms {
+    /// Add a connection to a room and tell the other members
+    ///
+    /// Returns the room's members, including the new one, and its channel.
+    pub fn join(
+        &mut self,
+        key: RoomKey,
+        connection_id: Uuid,
+        user: String,
+    ) -> (Vec<PresenceMember>, broadcast::Receiver<ServerMessage>) {
+        let room = self.rooms.entry(key).or_insert_with(|| Room {
+            members: BTreeMap::new(),
+            sender: broadcast::channel(ROOM_CHANNEL_CAPACITY).0,
+        });
+        room.members.insert(
+            connection_id,
+            Member {
+                user,
+                editing: BTreeSet::new(),
+            },
+        );
+        room.announce();
+        // Subscribe after the announcement: the new member gets the roster as a reply
+        (room.roster(), room.sender.subscribe())
+    }
+
+    /// Remove a connection from a room, dropping the room once empty
+    pub fn leave(&mut self, key: RoomKey, connection_id: Uuid) {
+        let Some(room) = self.rooms.get_mut(&key) else {
+            return;
+        };
+        if room.members.remove(&connection_id).is_none() {
+            return;
+        }
+        if room.members.is_empty() {
+            self.rooms.remove(&key);
+        } else {
+            room.announce();
+        }
+    }
+
+    /// Record that a member started or stopped editing a task
+    pub fn set_editing(&mut self, key: RoomKey, connection_id: Uuid, task_id: Uuid, editing: bool) {
+        let Some(room) = self.rooms.get_mut(&key) else {
+            return;
+        };
+        let Some(member) = room.members.get_mut(&connection_id) else {
+            return;
+        };
+        let changed = if editing {
+            member.editing.insert(task_id)
+        } else {
+            member.editing.remove(&task_id)
+        };
+        if changed {
+            room.announce();
+        }
+    }
+}
+
+static ROOMS: LazyLock<Mutex<Rooms>> = LazyLock::new(Mutex::default);
+
+fn rooms() -> std::sync::MutexGuard<'static, Rooms> {
+    ROOMS.lock().expect("rooms poisoned")
+}
+
+pub struct RoomService;
+
+impl RoomService {
+    /// Join a project's room under a display name
+    pub fn join(
+        key: RoomKey,
+        connection_id: Uuid,
+        user: &str,
+    ) -> Result<(Vec<PresenceMember>, broadcast::Receiver<ServerMessage>), AppError> {
+        let user = user.trim();
+        if user.is_empty() {
+            return Err(AppError::validation("User name cannot be empty"));
+        }
+        if user.chars().count() > 100 {
+            return Err(AppError::validation(
+                "User name must be at most 100 characters",
+            ));
+        }
+
+        if let Some(project_id) = key {
+            let mut conn = connection::get_connection().map_err(|e| {
+                AppError::service_unavailable(format!("Failed to get connection: {}", e))
+            })?;
+            // Check if project exists
+            ProjectRepository::find_by_id(&mut conn, project_id)?;
+        }
+
+        Ok(rooms().join(key, connection_id, user.to_string()))
+    }
+
+    /// Leave a room
+    pub fn leave(key: RoomKey, connection_id: Uuid) {
+        rooms().leave(key, connection_id);
+    }
+
+    /// Tell a room what a member is editing
+    pub fn set_editing(key: RoomKey, connection_id: Uuid, task_id: Uuid, editing: bool) {
+        rooms().set_editing(key, connection_id, task_id, editing);
+    }
+
+    /// Create a task in the room's project
+    pub fn create_task(
+        tasks: &TaskService,
+        key: RoomKey,
+        mut req: CreateTaskRequest,
+    ) -> Result<TaskResponse, AppError> {
+        if req
+            .project_id
+            .is_some_and(|project_id| Some(project_id) != key)
+        {
+            return Err(AppError::validation(
+                "Tasks can only be created in the room's project",
+            ));
+        }
+        req.project_id = key;
+        let parent_id = req.parent_id;
+        tasks.create_checked(req, |store| match parent_id {
+            Some(parent_id) => Self::check_parent_in_room(store, key, parent_id),
+            None => Ok(()),
+        })
+    }
+
+    /// Update a task of the room's project, keeping it there
+    pub fn update_task(
+        tasks: &TaskService,
+        key: RoomKey,
+        task_id: Uuid,
+        req: UpdateTaskRequest,
+    ) -> Result<TaskResponse, AppError> {
+        if req.project_id.is_some_and(|project_id| project_id != key) {
+            return Err(AppError::validation(
+                "Tasks cannot be moved out of the room's project",
+            ));
+        }
+        let parent_id = req.parent_id.flatten();
+        tasks.update_checked(task_id, req, |store, task| {
+            Self::check_in_room(key, task)?;
+            match parent_id {
+                Some(parent_id) => Self::check_parent_in_room(store, key, parent_id),
+                None => Ok(()),
+            }
+        })
+    }
+
+    /// Delete a task of the room's project
+    pub fn delete_task(
+        tasks: &TaskService,
+        key: RoomKey,
+        task_id: Uuid,
+        children: ChildrenPolicy,
+    ) -> Result<(), AppError> {
+        tasks.delete_checked(task_id, children, |task| Self::check_in_room(key, task))
+    }
+
+    /// Treat tasks of other projects as missing
+    fn check_in_room(key: RoomKey, task: &Task) -> Result<(), AppError> {
+        if task.project_id != key {
+            return Err(AppError::not_found("Task not found"));
+        }
+        Ok(())
+    }
+
+    fn check_parent_in_room(
+        store: &dyn TaskStore,
+        key: RoomKey,
+        parent_id: Uuid,
+    ) -> Result<(), AppError> {
+        let parent = store
+            .find_task(parent_id)
+            .map_err(|e| e.not_found_as("Parent task not found"))?;
+        if parent.project_id != key {
+            return Err(AppError::not_found("Parent task not found"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{NewTask, Priority};
+    use crate::db::store::MemoryTaskStore;
+    use axum_diesel_api::TaskPriority;
+    use std::sync::Arc;
+
+    fn users(message: ServerMessage) -> Vec<(String, Vec<Uuid>)> {
+        let ServerMessage::Presence { members } = message else {
+            panic!("expected presence, got {:?}", message);
+        };
+        members.into_iter().map(|m| (m.user, m.editing)).collect()
+    }
+
+    #[test]
+    fn test_presence_is_scoped_per_room() {
+        let mut rooms = Rooms::default();
+        let project = Some(Uuid::new_v4());
+        let (alice, bob, carol) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+
+        let (members, mut alice_rx) = rooms.join(project, alice, "alice".to_string());
+        assert_eq!(members.len(), 1);
+        let (_, mut carol_rx) = rooms.join(None, carol, "carol".to_string());
+        let (members, _bob_rx) = rooms.join(project, bob, "bob".to_string());
+        assert_eq!(members.len(), 2);
+
+        let task_id = Uuid::new_v4();
+        rooms.set_editing(project, bob, task_id, true);
+        rooms.set_editing(project, bob, task_id, true);
+
+        assert_eq!(
+            users(alice_rx.try_recv().unwrap()),
+            [("alice".into(), vec![]), ("bob".into(), vec![])]
+        );
+        assert_eq!(
+            users(alice_rx.try_recv().unwrap()),
+            [("alice".into(), vec![]), ("bob".into(), vec![task_id])]
+        );
+        // Repeating a state announces nothing
+        assert!(alice_rx.try_recv().is_err());
+
+        rooms.leave(project, bob);
+        assert_eq!(
+            users(alice_rx.try_recv().unwrap()),
+            [("alice".into(), vec![])]
+        );
+
+        // The other room saw nothing
+        assert!(carol_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_empty_rooms_are_dropped() {
+        let mut rooms = Rooms::default();
+        let connection_id = Uuid::new_v4();
+        rooms.join(None, connection_id, "alice".to_string());
+        rooms.leave(None, connection_id);
+        assert!(rooms.rooms.is_empty());
+    }
+
+    #[test]
+    fn test_room_writes_stay_in_the_room() {
+        let store = Arc::new(MemoryTaskStore::new());
+        let tasks = TaskService::new(store.clone());
+        let project = store.insert_project("Home", false);
+        let request = |title: &str| CreateTaskRequest {
+            title: title.to_string(),
+            description: None,
+            completed: false,
+            due_at: None,
+            priority: TaskPriority::default(),
+            project_id: None,
+            parent_id: None,
+            status: None,
+            recurrence: None,
+            recurrence_timezone: None,
+        };
+        // Project workflows are not seeded here, so the project's task is
+        // inserted directly
+        let parent = store
+            .create_task(NewTask {
+                title: "parent".to_string(),
+                description: None,
+                completed: false,
+                due_at: None,
+                priority: Priority::Medium,
+                project_id: Some(project.id),
+                parent_id: None,
+                position: "a".to_string(),
+                status_id: store.find_workflow(None).unwrap()[0].id,
+                recurrence: None,
+                recurrence_timezone: None,
+                recurrence_start: None,
+            })
+            .unwrap();
+
+        // A parent elsewhere does not pull the new task out of its room
+        let result = RoomService::create_task(
+            &tasks,
+            None,
+            CreateTaskRequest {
+                parent_id: Some(parent.id),
+                ..request("child")
+            },
+        );
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+
+        let loose = RoomService::create_task(&tasks, None, request("loose")).unwrap();
+        let result = RoomService::update_task(
+            &tasks,
+            None,
+            loose.id,
+            UpdateTaskRequest {
+                project_id: Some(Some(project.id)),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+        let result = RoomService::update_task(
+            &tasks,
+            None,
+            loose.id,
+            UpdateTaskRequest {
+                parent_id: Some(Some(parent.id)),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+
+        let result = RoomService::delete_task(&tasks, None, parent.id, ChildrenPolicy::Cascade);
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        RoomService::delete_task(&tasks, Some(project.id), parent.id, ChildrenPolicy::Cascade)
+            .unwrap();
+    }
+}
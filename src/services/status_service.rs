@@ -0,0 +1,317 @@
+use axum_diesel_api::{CreateStatusRequest, StatusResponse, UpdateStatusRequest};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::db::connection::{self, DbConnection};
+use crate::db::error::RepositoryError;
+use crate::db::models::{NewTaskStatus, StatusTransition, TaskStatus, UpdateTaskStatus};
+use crate::db::repositories::{ProjectRepository, StatusRepository};
+use crate::db::store::TaskStore;
+use crate::error::AppError;
+
+fn validate_name(name: &str) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::validation("Status name cannot be empty"));
+    }
+
+    if name.trim().len() > 50 {
+        return Err(AppError::validation(
+            "Status name must be at most 50 characters",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Default status of a workflow for new or reset tasks
+///
+/// That is the first open column (any column if none is open), or the first
+/// done column when `done` is set. `workflow` must be in column order.
+pub(crate) fn default_status(workflow: &[TaskStatus], done: bool) -> Option<&TaskStatus> {
+    if done {
+        workflow.iter().find(|s| s.is_done)
+    } else {
+        workflow.iter().find(|s| !s.is_done).or(workflow.first())
+    }
+}
+
+/// Load a workflow and pick its default status, see [`default_status`]
+pub(crate) fn load_default_status(
+    store: &dyn TaskStore,
+    project_id: Option<Uuid>,
+    done: bool,
+) -> Result<TaskStatus, AppError> {
+    let workflow = store.find_workflow(project_id)?;
+    default_status(&workflow, done).cloned().ok_or_else(|| {
+        AppError::validation(if done {
+            "The workflow has no done status"
+        } else {
+            "The workflow has no statuses"
+        })
+    })
+}
+
+/// Find a status of a workflow by name
+pub(crate) fn load_status_by_name(
+    store: &dyn TaskStore,
+    project_id: Option<Uuid>,
+    name: &str,
+) -> Result<TaskStatus, AppError> {
+    store
+        .find_status_by_name(project_id, name.trim())
+        .map_err(|e| match e {
+            RepositoryError::NotFound => {
+                AppError::validation(format!("Unknown status '{}'", name.trim()))
+            }
+            other => other.into(),
+        })
+}
+
+pub struct StatusService;
+
+impl StatusService {
+    /// Get the default workflow used by tasks without a project
+    pub fn get_default() -> Result<Vec<StatusResponse>, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let statuses = StatusRepository::find_workflow(&mut conn, None)?;
+        Self::with_transitions(&mut conn, statuses)
+    }
+
+    /// Get the workflow of a project
+    pub fn get_all(project_id: Uuid) -> Result<Vec<StatusResponse>, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        // Check if project exists
+        ProjectRepository::find_by_id(&mut conn, project_id)?;
+
+        let statuses = StatusRepository::find_workflow(&mut conn, Some(project_id))?;
+        Self::with_transitions(&mut conn, statuses)
+    }
+
+    /// Add a status to a project's workflow
+    pub fn create(project_id: Uuid, req: CreateStatusRequest) -> Result<StatusResponse, AppError> {
+        validate_name(&req.name)?;
+
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        // Check if project exists
+        ProjectRepository::find_by_id(&mut conn, project_id)?;
+
+        let position = match req.position {
+            Some(position) => position,
+            None => {
+                StatusRepository::max_position(&mut conn, Some(project_id))?.map_or(0, |p| p + 1)
+            }
+        };
+
+        let new_status = NewTaskStatus {
+            project_id: Some(project_id),
+            name: req.name.trim().to_string(),
+            position,
+            is_done: req.done,
+        };
+
+        let status = StatusRepository::create(&mut conn, new_status)?;
+        Ok(status.with_transitions(Vec::new()))
+    }
+
+    /// Rename, move or change the done flag of a status
+    ///
+    /// Changing the done flag updates `completed` on every task in the status.
+    pub fn update(
+        project_id: Uuid,
+        status_id: Uuid,
+        req: UpdateStatusRequest,
+    ) -> Result<StatusResponse, AppError> {
+        if let Some(ref name) = req.name {
+            validate_name(name)?;
+        }
+
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let mut status = Self::find_in_project(&mut conn, project_id, status_id)?;
+
+        let update_status = UpdateTaskStatus {
+            name: req.name.map(|n| n.trim().to_string()),
+            position: req.position,
+            is_done: req.done,
+        };
+
+        // Diesel rejects empty changesets
+        if update_status.name.is_some()
+            || update_status.position.is_some()
+            || update_status.is_done.is_some()
+        {
+            status = StatusRepository::update(&mut conn, status_id, update_status)?;
+        }
+
+        Ok(Self::with_transitions(&mut conn, vec![status])?
+            .pop()
+            .expect("one status in, one response out"))
+    }
+
+    /// Remove a status from a project's workflow
+    pub fn delete(project_id: Uuid, status_id: Uuid) -> Result<(), AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        Self::find_in_project(&mut conn, project_id, status_id)?;
+
+        let task_count = StatusRepository::count_tasks(&mut conn, status_id)?;
+        if task_count > 0 {
+            return Err(AppError::conflict(format!(
+                "Status is used by {} task(s)",
+                task_count
+            )));
+        }
+
+        StatusRepository::delete(&mut conn, status_id)?;
+        Ok(())
+    }
+
+    /// Allow tasks to move from one status to another
+    pub fn add_transition(
+        project_id: Uuid,
+        from_status_id: Uuid,
+        to_status_id: Uuid,
+    ) -> Result<StatusResponse, AppError> {
+        if from_status_id == to_status_id {
+            return Err(AppError::validation("A status cannot transition to itself"));
+        }
+
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let from = Self::find_in_project(&mut conn, project_id, from_status_id)?;
+        Self::find_in_project(&mut conn, project_id, to_status_id)?;
+
+        StatusRepository::add_transition(
+            &mut conn,
+            StatusTransition {
+                from_status_id,
+                to_status_id,
+            },
+        )?;
+
+        Ok(Self::with_transitions(&mut conn, vec![from])?
+            .pop()
+            .expect("one status in, one response out"))
+    }
+
+    /// Forbid tasks to move from one status to another
+    pub fn remove_transition(
+        project_id: Uuid,
+        from_status_id: Uuid,
+        to_status_id: Uuid,
+    ) -> Result<StatusResponse, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let from = Self::find_in_project(&mut conn, project_id, from_status_id)?;
+
+        StatusRepository::remove_transition(&mut conn, from_status_id, to_status_id)?;
+        Ok(Self::with_transitions(&mut conn, vec![from])?
+            .pop()
+            .expect("one status in, one response out"))
+    }
+
+    /// Load a status, treating statuses of other workflows as missing
+    fn find_in_project(
+        conn: &mut DbConnection,
+        project_id: Uuid,
+        status_id: Uuid,
+    ) -> Result<TaskStatus, AppError> {
+        // Check if project exists
+        ProjectRepository::find_by_id(conn, project_id)?;
+
+        let status = StatusRepository::find_by_id(conn, status_id)
+            .map_err(|e| e.not_found_as("Status not found"))?;
+        if status.project_id != Some(project_id) {
+            return Err(AppError::not_found("Status not found"));
+        }
+        Ok(status)
+    }
+
+    /// Load the outgoing transitions of a batch of statuses and build their responses
+    fn with_transitions(
+        conn: &mut DbConnection,
+        statuses: Vec<TaskStatus>,
+    ) -> Result<Vec<StatusResponse>, AppError> {
+        let ids: Vec<Uuid> = statuses.iter().map(|s| s.id).collect();
+        let mut targets: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        for transition in StatusRepository::find_transitions(conn, &ids)? {
+            targets
+                .entry(transition.from_status_id)
+                .or_default()
+                .push(transition.to_status_id);
+        }
+
+        Ok(statuses
+            .into_iter()
+            .map(|status| {
+                let transitions = targets.remove(&status.id).unwrap_or_default();
+                status.with_transitions(transitions)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn status(name: &str, is_done: bool) -> TaskStatus {
+        TaskStatus {
+            id: Uuid::new_v4(),
+            project_id: None,
+            name: name.to_string(),
+            position: 0,
+            is_done,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_default_status() {
+        let workflow = vec![
+            status("todo", false),
+            status("doing", false),
+            status("done", true),
+            status("wontfix", true),
+        ];
+
+        assert_eq!(default_status(&workflow, false).unwrap().name, "todo");
+        assert_eq!(default_status(&workflow, true).unwrap().name, "done");
+    }
+
+    #[test]
+    fn test_default_status_without_open_or_done_columns() {
+        let only_done = vec![status("done", true)];
+        assert_eq!(default_status(&only_done, false).unwrap().name, "done");
+
+        let only_open = vec![status("todo", false)];
+        assert!(default_status(&only_open, true).is_none());
+        assert!(default_status(&[], false).is_none());
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("review").is_ok());
+        assert!(validate_name("  ").is_err());
+        assert!(validate_name(&"x".repeat(51)).is_err());
+    }
+}
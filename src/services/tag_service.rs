@@ -0,0 +1,116 @@
+use axum_diesel_api::{CreateTagRequest, TagResponse, UpdateTagRequest};
+use uuid::Uuid;
+
+use crate::db::connection;
+use crate::db::models::{NewTag, UpdateTag};
+use crate::db::repositories::TagRepository;
+use crate::error::AppError;
+
+/// Maximum length of a tag name (matches the `tags.name` column)
+const MAX_TAG_NAME_LEN: usize = 50;
+
+/// Tag names are case-insensitive: store and compare them trimmed and lowercased
+pub fn normalize_tag_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+pub(crate) fn validate_tag_name(name: &str) -> Result<(), AppError> {
+    if name.is_empty() {
+        return Err(AppError::validation("Tag name cannot be empty"));
+    }
+
+    if name.chars().count() > MAX_TAG_NAME_LEN {
+        return Err(AppError::validation(format!(
+            "Tag name must be at most {} characters",
+            MAX_TAG_NAME_LEN
+        )));
+    }
+
+    Ok(())
+}
+
+pub struct TagService;
+
+impl TagService {
+    /// Get all tags
+    pub fn get_all() -> Result<Vec<TagResponse>, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let tags = TagRepository::find_all(&mut conn)?;
+        Ok(tags.into_iter().map(TagResponse::from).collect())
+    }
+
+    /// Get a tag by ID
+    pub fn get_by_id(id: Uuid) -> Result<TagResponse, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let tag = TagRepository::find_by_id(&mut conn, id)?;
+        Ok(TagResponse::from(tag))
+    }
+
+    /// Create a new tag
+    pub fn create(req: CreateTagRequest) -> Result<TagResponse, AppError> {
+        let name = normalize_tag_name(&req.name);
+        validate_tag_name(&name)?;
+
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let tag = TagRepository::create(&mut conn, NewTag { name })?;
+        Ok(TagResponse::from(tag))
+    }
+
+    /// Rename an existing tag
+    pub fn update(id: Uuid, req: UpdateTagRequest) -> Result<TagResponse, AppError> {
+        let name = req.name.as_deref().map(normalize_tag_name);
+        if let Some(ref name) = name {
+            validate_tag_name(name)?;
+        }
+
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let tag = match name {
+            Some(name) => TagRepository::update(&mut conn, id, UpdateTag { name: Some(name) })?,
+            // Nothing to change: Diesel rejects empty changesets
+            None => TagRepository::find_by_id(&mut conn, id)?,
+        };
+        Ok(TagResponse::from(tag))
+    }
+
+    /// Delete a tag
+    pub fn delete(id: Uuid) -> Result<(), AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        // Check if tag exists
+        TagRepository::find_by_id(&mut conn, id)?;
+
+        TagRepository::delete(&mut conn, id)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_tag_name() {
+        assert_eq!(normalize_tag_name("  Urgent "), "urgent");
+    }
+
+    #[test]
+    fn test_validate_tag_name() {
+        assert!(validate_tag_name("work").is_ok());
+        assert!(validate_tag_name("").is_err());
+        assert!(validate_tag_name(&"x".repeat(MAX_TAG_NAME_LEN + 1)).is_err());
+    }
+}
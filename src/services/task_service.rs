@@ -1,34 +1,251 @@
-use axum_diesel_api::{CreateTaskRequest, TaskResponse, UpdateTaskRequest};
+use axum_diesel_api::{
+    ChildrenPolicy, CreateTaskRequest, ListTasksQuery, MoveTaskRequest, OccurrencesResponse,
+    SparseTask, TagMatch, TaskField, TaskFields, TaskResponse, TaskTreeNode, UpdateTaskRequest,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::db::connection;
-use crate::db::models::{NewTask, UpdateTask};
-use crate::db::repositories::TaskRepository;
+use crate::db::connection::DbConnection;
+use crate::db::error::RepositoryError;
+use crate::db::models::{NewTask, Task, TaskStatus, UpdateTask};
+use crate::db::repositories::TaskFilter;
+use crate::db::retry::retry_transient;
+use crate::db::store::{IsolationLevel, MemoryTaskStore, PgTaskStore, TaskStore};
 use crate::error::AppError;
+use crate::services::rank::{self, MAX_RANK_LEN};
+use crate::services::recurrence::RecurrenceRule;
+use crate::services::status_service::{load_default_status, load_status_by_name};
+use crate::services::tag_service::normalize_tag_name;
 
-pub struct TaskService;
+/// Due dates outside this range are almost certainly client bugs (zeroed or
+/// millisecond-vs-second timestamps) rather than real deadlines
+const MIN_DUE_YEAR: i32 = 1970;
+const MAX_DUE_YEAR: i32 = 2100;
+
+/// Occurrences previewed by default, and at most, for a recurring task
+const DEFAULT_OCCURRENCES: usize = 5;
+const MAX_OCCURRENCES: usize = 100;
+
+fn validate_due_at(due_at: &DateTime<Utc>) -> Result<(), AppError> {
+    let min = Utc.with_ymd_and_hms(MIN_DUE_YEAR, 1, 1, 0, 0, 0).unwrap();
+    let max = Utc.with_ymd_and_hms(MAX_DUE_YEAR, 1, 1, 0, 0, 0).unwrap();
+
+    if *due_at < min || *due_at >= max {
+        return Err(AppError::validation(format!(
+            "Due date must be between {} and {}",
+            MIN_DUE_YEAR, MAX_DUE_YEAR
+        )));
+    }
+
+    Ok(())
+}
+
+/// Validate an RRULE and time zone, returning the stored forms of both
+fn parse_recurrence(rule: &str, timezone: &str) -> Result<(RecurrenceRule, Tz), AppError> {
+    let parsed = rule
+        .parse::<RecurrenceRule>()
+        .map_err(|e| AppError::validation(format!("Invalid recurrence: {}", e)))?;
+    let tz = timezone
+        .trim()
+        .parse::<Tz>()
+        .map_err(|_| AppError::validation(format!("Unknown time zone '{}'", timezone.trim())))?;
+    Ok((parsed, tz))
+}
+
+/// Recurrence columns for a rule starting at the task's due date
+fn recurrence_columns(
+    rule: &str,
+    timezone: &str,
+    due_at: Option<DateTime<Utc>>,
+) -> Result<(String, String, DateTime<Utc>), AppError> {
+    let (_, tz) = parse_recurrence(rule, timezone)?;
+    let due_at = due_at.ok_or_else(|| AppError::validation("Recurring tasks need a due date"))?;
+
+    let rule = rule.trim();
+    let rule = rule
+        .get(..6)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("RRULE:"))
+        .map_or(rule, |_| &rule[6..]);
+    Ok((rule.to_ascii_uppercase(), tz.name().to_string(), due_at))
+}
+
+/// Ensure a project exists and still accepts tasks
+fn ensure_active_project(store: &dyn TaskStore, project_id: Uuid) -> Result<(), AppError> {
+    let project = store
+        .find_project(project_id)
+        .map_err(|e| e.not_found_as("Project not found"))?;
+
+    if project.archived_at.is_some() {
+        return Err(AppError::validation(
+            "Cannot add tasks to an archived project",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check the fields of an update that need no stored task
+fn validate_update(req: &UpdateTaskRequest) -> Result<(), AppError> {
+    if let Some(ref title) = req.title {
+        if title.trim().is_empty() {
+            return Err(AppError::validation("Title cannot be empty"));
+        }
+
+        if title.len() > 255 {
+            return Err(AppError::validation(
+                "Title must be less than 255 characters",
+            ));
+        }
+    }
+
+    if let Some(Some(ref due_at)) = req.due_at {
+        validate_due_at(due_at)?;
+    }
+    Ok(())
+}
+
+/// Whether an update only sets the title, description, priority or a new
+/// due date, which no rule checks against the current task
+fn is_plain_update(req: &UpdateTaskRequest) -> bool {
+    req.completed.is_none()
+        && req.status.is_none()
+        && req.project_id.is_none()
+        && req.parent_id.is_none()
+        && req.recurrence.is_none()
+        && req.recurrence_timezone.is_none()
+        // Recurring tasks must keep a due date
+        && !matches!(req.due_at, Some(None))
+}
+
+/// Columns an update takes straight from the request
+fn plain_update(req: &UpdateTaskRequest) -> UpdateTask {
+    UpdateTask {
+        title: req.title.as_ref().map(|t| t.trim().to_string()),
+        description: req
+            .description
+            .as_ref()
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty()),
+        due_at: req.due_at,
+        priority: req.priority.map(Into::into),
+        ..Default::default()
+    }
+}
+
+/// Assemble a nested tree from `(task, depth)` pairs ordered parents-first
+///
+/// The first entry is the root. Returns `None` for an empty input.
+fn build_tree(nodes: Vec<(TaskResponse, i32)>) -> Option<TaskTreeNode> {
+    let mut children: HashMap<Uuid, Vec<TaskTreeNode>> = HashMap::new();
+    let mut root = None;
+
+    // Walk deepest-first so every child is complete before its parent is built
+    for (task, depth) in nodes.into_iter().rev() {
+        let kids = children.remove(&task.id).unwrap_or_default();
+        let completion_percent = (!kids.is_empty()).then(|| {
+            let done = kids.iter().filter(|k| k.task.completed).count();
+            done as f64 * 100.0 / kids.len() as f64
+        });
+        let parent_id = task.parent_id;
+        let node = TaskTreeNode {
+            task,
+            depth,
+            completion_percent,
+            children: kids,
+        };
+
+        match parent_id {
+            Some(parent_id) if depth > 0 => children.entry(parent_id).or_default().insert(0, node),
+            _ => root = Some(node),
+        }
+    }
+
+    root
+}
+
+/// Task use cases, on the [`TaskStore`] the service was built with
+///
+/// Cheap to clone: clones share the store.
+#[derive(Clone)]
+pub struct TaskService {
+    store: Arc<dyn TaskStore>,
+}
 
 impl TaskService {
-    /// Get all tasks
-    pub fn get_all() -> Result<Vec<TaskResponse>, AppError> {
-        let mut conn = connection::get_connection()
-            .map_err(|e| AppError::internal(format!("Failed to get connection: {}", e)))?;
+    pub fn new(store: Arc<dyn TaskStore>) -> Self {
+        Self { store }
+    }
 
-        let tasks = TaskRepository::find_all(&mut conn)?;
-        Ok(tasks.into_iter().map(TaskResponse::from).collect())
+    /// Service on the PostgreSQL database
+    pub fn postgres() -> Self {
+        Self::new(Arc::new(PgTaskStore::new()))
     }
 
-    /// Get a task by ID
-    pub fn get_by_id(id: Uuid) -> Result<TaskResponse, AppError> {
-        let mut conn = connection::get_connection()
-            .map_err(|e| AppError::internal(format!("Failed to get connection: {}", e)))?;
+    /// Service on an empty in-memory store
+    pub fn in_memory() -> Self {
+        Self::new(Arc::new(MemoryTaskStore::new()))
+    }
+
+    /// Service on the SQLite database at `path`, created and migrated if needed
+    #[cfg(feature = "sqlite")]
+    pub fn sqlite(path: &str) -> Result<Self, AppError> {
+        let store = crate::db::store::SqliteTaskStore::open(path)?;
+        Ok(Self::new(Arc::new(store)))
+    }
+
+    /// Get all tasks, optionally filtered by tags, due date and priority
+    ///
+    /// Only the selected `fields` are loaded where that saves work.
+    pub fn get_all(&self, query: ListTasksQuery) -> Result<Vec<SparseTask>, AppError> {
+        let mut tags: Vec<String> = query.tag.iter().map(|t| normalize_tag_name(t)).collect();
+        tags.retain(|t| !t.is_empty());
+        tags.sort();
+        tags.dedup();
+
+        let filter = TaskFilter {
+            tags,
+            match_all_tags: query.tag_match == TagMatch::All,
+            overdue: query.overdue,
+            due_before: query.due_before,
+            priority: query.priority.map(Into::into),
+            project_id: query.project_id,
+            include_archived: query.include_archived,
+            completed: None,
+            status: query.status.map(|s| s.trim().to_string()),
+            has_due_at: false,
+        };
+
+        let fields = query.fields;
+        let with_description = fields.contains(TaskField::Description);
+        retry_transient(|| {
+            let tasks = self.store.find_tasks(&filter, with_description)?;
+            Self::to_sparse_responses(&*self.store, tasks, fields)
+        })
+    }
 
-        let task = TaskRepository::find_by_id(&mut conn, id)?;
-        Ok(TaskResponse::from(task))
+    /// Get a task by ID
+    pub fn get_by_id(&self, id: Uuid, fields: TaskFields) -> Result<SparseTask, AppError> {
+        retry_transient(|| {
+            let task = self.store.find_task(id)?;
+            Ok(Self::to_sparse_responses(&*self.store, vec![task], fields)?
+                .pop()
+                .expect("one task in, one response out"))
+        })
     }
 
     /// Create a new task
-    pub fn create(req: CreateTaskRequest) -> Result<TaskResponse, AppError> {
+    pub fn create(&self, req: CreateTaskRequest) -> Result<TaskResponse, AppError> {
+        Self::create_in(&*self.store, req)
+    }
+
+    /// Create a new task in a given store, e.g. inside a transaction
+    pub(crate) fn create_in(
+        store: &dyn TaskStore,
+        req: CreateTaskRequest,
+    ) -> Result<TaskResponse, AppError> {
         // Validation
         if req.title.trim().is_empty() {
             return Err(AppError::validation("Title cannot be empty"));
@@ -40,8 +257,45 @@ impl TaskService {
             ));
         }
 
-        let mut conn = connection::get_connection()
-            .map_err(|e| AppError::internal(format!("Failed to get connection: {}", e)))?;
+        if let Some(ref due_at) = req.due_at {
+            validate_due_at(due_at)?;
+        }
+
+        // Subtasks default to their parent's project
+        let mut project_id = req.project_id;
+        if let Some(parent_id) = req.parent_id {
+            let parent = store
+                .find_task(parent_id)
+                .map_err(|e| e.not_found_as("Parent task not found"))?;
+            project_id = project_id.or(parent.project_id);
+        }
+
+        if let Some(project_id) = project_id {
+            ensure_active_project(store, project_id)?;
+        }
+
+        // An explicit status wins over `completed`, which then follows the status
+        let status = match req.status {
+            Some(ref name) => load_status_by_name(store, project_id, name)?,
+            None => load_default_status(store, project_id, req.completed)?,
+        };
+
+        let (recurrence, recurrence_timezone, recurrence_start) = match req.recurrence {
+            Some(ref rule) => {
+                let timezone = req.recurrence_timezone.as_deref().unwrap_or("UTC");
+                let (rule, timezone, start) = recurrence_columns(rule, timezone, req.due_at)?;
+                (Some(rule), Some(timezone), Some(start))
+            }
+            None if req.recurrence_timezone.is_some() => {
+                return Err(AppError::validation(
+                    "recurrence_timezone needs a recurrence",
+                ));
+            }
+            None => (None, None, None),
+        };
+
+        // New tasks go to the end of the manual order
+        let position = Self::next_position(store)?;
 
         let new_task = NewTask {
             title: req.title.trim().to_string(),
@@ -49,56 +303,842 @@ impl TaskService {
                 .description
                 .map(|d| d.trim().to_string())
                 .filter(|d| !d.is_empty()),
-            completed: req.completed,
+            completed: status.is_done,
+            due_at: req.due_at,
+            priority: req.priority.into(),
+            project_id,
+            parent_id: req.parent_id,
+            position,
+            status_id: status.id,
+            recurrence,
+            recurrence_timezone,
+            recurrence_start,
         };
 
-        let task = TaskRepository::create(&mut conn, new_task)?;
-        Ok(TaskResponse::from(task))
+        let task = store.create_task(new_task)?;
+        Ok(task.into_response(status.name, Vec::new()))
+    }
+
+    /// Create a new task if `check` passes, in the same transaction
+    ///
+    /// Serializable, so that what `check` reads cannot change before the insert.
+    pub(crate) fn create_checked(
+        &self,
+        req: CreateTaskRequest,
+        check: impl Fn(&dyn TaskStore) -> Result<(), AppError>,
+    ) -> Result<TaskResponse, AppError> {
+        self.store
+            .in_transaction_at(IsolationLevel::Serializable, |store| {
+                check(store)?;
+                Self::create_in(store, req.clone())
+            })
     }
 
     /// Update an existing task
-    pub fn update(id: Uuid, req: UpdateTaskRequest) -> Result<TaskResponse, AppError> {
-        // Validate title if provided
-        if let Some(ref title) = req.title {
-            if title.trim().is_empty() {
-                return Err(AppError::validation("Title cannot be empty"));
+    pub fn update(&self, id: Uuid, req: UpdateTaskRequest) -> Result<TaskResponse, AppError> {
+        validate_update(&req)?;
+
+        // Nothing to check against the current task: a single UPDATE, which
+        // is not found if it matches no row
+        if is_plain_update(&req) {
+            let task = self.store.update_task(id, plain_update(&req))?;
+            return Self::to_response(&*self.store, task);
+        }
+
+        self.update_in_transaction(id, req, &|_, _| Ok(()))
+    }
+
+    /// Update an existing task if `check` accepts it, in the same transaction
+    pub(crate) fn update_checked(
+        &self,
+        id: Uuid,
+        req: UpdateTaskRequest,
+        check: impl Fn(&dyn TaskStore, &Task) -> Result<(), AppError>,
+    ) -> Result<TaskResponse, AppError> {
+        validate_update(&req)?;
+        self.update_in_transaction(id, req, &check)
+    }
+
+    fn update_in_transaction(
+        &self,
+        id: Uuid,
+        req: UpdateTaskRequest,
+        check: &dyn Fn(&dyn TaskStore, &Task) -> Result<(), AppError>,
+    ) -> Result<TaskResponse, AppError> {
+        // One unit of work: if the task changes or vanishes between the checks
+        // and the write, the transaction fails and runs again. Completing a
+        // recurring task schedules its next occurrence in the same one.
+        // Reparenting is serializable, so that two requests moving two tasks
+        // under each other cannot both pass the cycle check.
+        let isolation = match req.parent_id {
+            Some(Some(_)) => IsolationLevel::Serializable,
+            _ => IsolationLevel::RepeatableRead,
+        };
+        let task = self.store.in_transaction_at(isolation, |store| {
+            Self::update_in(store, id, req.clone(), check)
+        })?;
+        Self::to_response(&*self.store, task)
+    }
+
+    /// Check and apply an update in a given store, inside a transaction
+    fn update_in(
+        store: &dyn TaskStore,
+        id: Uuid,
+        req: UpdateTaskRequest,
+        check: &dyn Fn(&dyn TaskStore, &Task) -> Result<(), AppError>,
+    ) -> Result<Task, AppError> {
+        let task = store.find_task(id)?;
+        check(store, &task)?;
+
+        // Unarchiving a project restores its tasks by project, so an archived
+        // task moved elsewhere would stay hidden
+        if task.archived_at.is_some() && req.project_id.is_some_and(|p| p != task.project_id) {
+            return Err(AppError::validation(
+                "Cannot move an archived task to another project",
+            ));
+        }
+
+        if let Some(Some(project_id)) = req.project_id {
+            ensure_active_project(store, project_id)?;
+        }
+
+        let status = Self::resolve_status_change(store, &task, &req)?;
+
+        if status.as_ref().is_some_and(|s| s.is_done) && !task.completed {
+            let open_blockers = store.count_open_blockers(id)?;
+            if open_blockers > 0 {
+                return Err(AppError::conflict(format!(
+                    "Task is blocked by {} open task(s)",
+                    open_blockers
+                )));
             }
+        }
 
-            if title.len() > 255 {
+        if let Some(Some(parent_id)) = req.parent_id {
+            store
+                .find_task(parent_id)
+                .map_err(|e| e.not_found_as("Parent task not found"))?;
+
+            if store.is_self_or_ancestor(id, parent_id)? {
                 return Err(AppError::validation(
-                    "Title must be less than 255 characters",
+                    "A task cannot be moved under itself or one of its subtasks",
                 ));
             }
         }
 
-        let mut conn = connection::get_connection()
-            .map_err(|e| AppError::internal(format!("Failed to get connection: {}", e)))?;
+        let completing = status.as_ref().is_some_and(|s| s.is_done) && !task.completed;
+        let due_at = req.due_at.unwrap_or(task.due_at);
 
-        // Check if task exists
-        TaskRepository::find_by_id(&mut conn, id)?;
+        let mut update_task = UpdateTask {
+            completed: status.as_ref().map(|s| s.is_done),
+            project_id: req.project_id,
+            parent_id: req.parent_id,
+            status_id: status.as_ref().map(|s| s.id),
+            ..plain_update(&req)
+        };
 
-        let update_task = UpdateTask {
-            title: req.title.map(|t| t.trim().to_string()),
-            description: req
-                .description
-                .map(|d| d.trim().to_string())
-                .filter(|d| !d.is_empty()),
-            completed: req.completed,
+        match (req.recurrence, req.recurrence_timezone) {
+            (Some(None), _) => {
+                update_task.recurrence = Some(None);
+                update_task.recurrence_timezone = Some(None);
+                update_task.recurrence_start = Some(None);
+            }
+            (Some(Some(rule)), timezone) => {
+                let timezone = timezone
+                    .or(task.recurrence_timezone)
+                    .unwrap_or_else(|| "UTC".to_string());
+                let (rule, timezone, start) = recurrence_columns(&rule, &timezone, due_at)?;
+                update_task.recurrence = Some(Some(rule));
+                update_task.recurrence_timezone = Some(Some(timezone));
+                update_task.recurrence_start = Some(Some(start));
+            }
+            (None, Some(timezone)) => {
+                let rule = task.recurrence.as_deref().ok_or_else(|| {
+                    AppError::validation("recurrence_timezone needs a recurrence")
+                })?;
+                let (_, timezone, _) = recurrence_columns(rule, &timezone, due_at)?;
+                update_task.recurrence_timezone = Some(Some(timezone));
+            }
+            (None, None) => {
+                if task.recurrence.is_some() && due_at.is_none() {
+                    return Err(AppError::validation("Recurring tasks need a due date"));
+                }
+            }
+        }
+
+        let task = store.update_task(id, update_task)?;
+        if completing && task.recurrence.is_some() {
+            return Self::spawn_next_occurrence(store, task);
+        }
+        Ok(task)
+    }
+
+    /// Preview the occurrences of a recurring task after its current due date
+    pub fn get_occurrences(
+        &self,
+        id: Uuid,
+        count: Option<usize>,
+    ) -> Result<OccurrencesResponse, AppError> {
+        let count = count.unwrap_or(DEFAULT_OCCURRENCES);
+        if !(1..=MAX_OCCURRENCES).contains(&count) {
+            return Err(AppError::validation(format!(
+                "count must be between 1 and {}",
+                MAX_OCCURRENCES
+            )));
+        }
+
+        let task = self.store.find_task(id)?;
+        let (Some(recurrence), Some(timezone), Some(start), Some(due_at)) = (
+            task.recurrence,
+            task.recurrence_timezone,
+            task.recurrence_start,
+            task.due_at,
+        ) else {
+            return Err(AppError::validation("Task does not recur"));
         };
 
-        let task = TaskRepository::update(&mut conn, id, update_task)?;
-        Ok(TaskResponse::from(task))
+        let (rule, tz) = parse_recurrence(&recurrence, &timezone)?;
+        let occurrences = rule
+            .occurrences_after(start, tz, due_at, count)
+            .into_iter()
+            .map(|at| at.with_timezone(&tz).fixed_offset())
+            .collect();
+
+        Ok(OccurrencesResponse {
+            recurrence,
+            timezone,
+            occurrences,
+        })
     }
 
-    /// Delete a task
-    pub fn delete(id: Uuid) -> Result<(), AppError> {
-        let mut conn = connection::get_connection()
-            .map_err(|e| AppError::internal(format!("Failed to get connection: {}", e)))?;
+    /// Complete the current occurrence of a series and create the next one
+    ///
+    /// The recurrence moves to the new task, so reopening and completing the
+    /// old one again does not create a duplicate. Returns the completed task.
+    fn spawn_next_occurrence(store: &dyn TaskStore, task: Task) -> Result<Task, AppError> {
+        let (Some(recurrence), Some(timezone), Some(start), Some(due_at)) = (
+            task.recurrence.clone(),
+            task.recurrence_timezone.clone(),
+            task.recurrence_start,
+            task.due_at,
+        ) else {
+            return Ok(task);
+        };
+
+        let completed = store.update_task(
+            task.id,
+            UpdateTask {
+                recurrence: Some(None),
+                recurrence_timezone: Some(None),
+                recurrence_start: Some(None),
+                ..Default::default()
+            },
+        )?;
 
-        // Check if task exists
-        TaskRepository::find_by_id(&mut conn, id)?;
+        let (rule, tz) = parse_recurrence(&recurrence, &timezone)?;
+        let Some(next_due) = rule
+            .next_after(start, tz, due_at)
+            .filter(|next| validate_due_at(next).is_ok())
+        else {
+            // The series is over
+            return Ok(completed);
+        };
+
+        let status = load_default_status(store, task.project_id, false)?;
+        let position = Self::next_position(store)?;
+        let next = store.create_task(NewTask {
+            title: task.title.clone(),
+            description: task.description.clone(),
+            completed: status.is_done,
+            due_at: Some(next_due),
+            priority: task.priority,
+            project_id: task.project_id,
+            parent_id: task.parent_id,
+            position,
+            status_id: status.id,
+            recurrence: Some(recurrence),
+            recurrence_timezone: Some(timezone),
+            recurrence_start: Some(start),
+        })?;
+
+        for tag in store.find_tags(std::slice::from_ref(&task))?.concat() {
+            store.attach_tag(next.id, tag.id)?;
+        }
 
-        TaskRepository::delete(&mut conn, id)?;
+        Ok(completed)
+    }
+
+    /// Rank placing a new task at the end of the manual order
+    fn next_position(store: &dyn TaskStore) -> Result<String, AppError> {
+        let last = store.max_position()?;
+        rank::rank_between(last.as_deref(), None)
+            .ok_or_else(|| AppError::internal("Failed to compute task position"))
+    }
+
+    /// Work out the status an update moves a task to, if any
+    ///
+    /// `status` must be an allowed transition from the current status. A
+    /// `completed` flip moves to the workflow's default done or open status
+    /// under the same rule. Changing project maps the status to the one with
+    /// the same name in the new workflow, without transition checks.
+    fn resolve_status_change(
+        store: &dyn TaskStore,
+        task: &Task,
+        req: &UpdateTaskRequest,
+    ) -> Result<Option<TaskStatus>, AppError> {
+        let project_id = req.project_id.unwrap_or(task.project_id);
+        let project_changed = project_id != task.project_id;
+
+        let target = if let Some(ref name) = req.status {
+            let status = load_status_by_name(store, project_id, name)?;
+            if req.completed.is_some_and(|c| c != status.is_done) {
+                return Err(AppError::validation(format!(
+                    "Status '{}' does not match completed={}",
+                    status.name, !status.is_done
+                )));
+            }
+            status
+        } else if let Some(completed) = req.completed.filter(|c| *c != task.completed) {
+            load_default_status(store, project_id, completed)?
+        } else if project_changed {
+            let current = store.find_status(task.status_id)?;
+            match store.find_status_by_name(project_id, &current.name) {
+                Ok(status) => status,
+                Err(_) => load_default_status(store, project_id, task.completed)?,
+            }
+        } else {
+            return Ok(None);
+        };
+
+        if target.id != task.status_id
+            && !project_changed
+            && !store.is_transition_allowed(task.status_id, target.id)?
+        {
+            let current = store.find_status(task.status_id)?;
+            return Err(AppError::conflict(format!(
+                "Moving from '{}' to '{}' is not allowed",
+                current.name, target.name
+            )));
+        }
+
+        Ok(Some(target))
+    }
+
+    /// Move a task before and/or after other tasks in the manual order
+    pub fn move_task(&self, id: Uuid, req: MoveTaskRequest) -> Result<TaskResponse, AppError> {
+        if req.before.is_none() && req.after.is_none() {
+            return Err(AppError::validation(
+                "Either before or after must be provided",
+            ));
+        }
+
+        if req.before == Some(id) || req.after == Some(id) {
+            return Err(AppError::validation(
+                "A task cannot be moved next to itself",
+            ));
+        }
+
+        // The rank is computed from the neighbours it is written between; the
+        // write is not found if the task is gone
+        let task = self
+            .store
+            .in_transaction_at(IsolationLevel::RepeatableRead, |store| {
+                let position = match Self::rank_for_move(store, id, &req)? {
+                    Some(position) => position,
+                    None => {
+                        // Anchors share a rank: spread ranks out and retry
+                        store.rebalance_positions()?;
+                        Self::rank_for_move(store, id, &req)?
+                            .ok_or_else(|| AppError::internal("Failed to compute task position"))?
+                    }
+                };
+                Ok(store.set_position(id, &position)?)
+            })?;
+        Self::to_response(&*self.store, task)
+    }
+
+    /// Rewrite ranks evenly when they have grown past `MAX_RANK_LEN`
+    ///
+    /// Returns the number of tasks whose rank changed.
+    pub fn rebalance_positions_if_needed(&self) -> Result<usize, AppError> {
+        let longest = self.store.max_position_len()?.unwrap_or(0);
+        if (longest as usize) <= MAX_RANK_LEN {
+            return Ok(0);
+        }
+
+        Ok(self.store.rebalance_positions()?)
+    }
+
+    /// Rank between the move anchors, or `None` if there is no room
+    fn rank_for_move(
+        store: &dyn TaskStore,
+        id: Uuid,
+        req: &MoveTaskRequest,
+    ) -> Result<Option<String>, AppError> {
+        let anchor = |anchor_id: Uuid| {
+            store
+                .find_task(anchor_id)
+                .map(|task| task.position)
+                .map_err(|e| e.not_found_as("Anchor task not found"))
+        };
+
+        let lo = req.after.map(anchor).transpose()?;
+        let hi = req.before.map(anchor).transpose()?;
+
+        let (lo, hi) = match (lo, hi) {
+            (Some(lo), None) => {
+                let hi = store.next_position(&lo, id)?;
+                (Some(lo), hi)
+            }
+            (None, Some(hi)) => {
+                let lo = store.previous_position(&hi, id)?;
+                (lo, Some(hi))
+            }
+            (Some(lo), Some(hi)) if lo > hi => {
+                return Err(AppError::validation(
+                    "The after task must come before the before task",
+                ));
+            }
+            bounds => bounds,
+        };
+
+        Ok(rank::rank_between(lo.as_deref(), hi.as_deref()))
+    }
+
+    /// Delete a task, cascading to or re-parenting its subtasks
+    ///
+    /// Not found if the task does not exist when the delete runs.
+    pub fn delete(&self, id: Uuid, children: ChildrenPolicy) -> Result<(), AppError> {
+        self.store.delete_task(id, children)?;
         Ok(())
     }
+
+    /// Delete a task if `check` accepts it, in the same transaction
+    pub(crate) fn delete_checked(
+        &self,
+        id: Uuid,
+        children: ChildrenPolicy,
+        check: impl Fn(&Task) -> Result<(), AppError>,
+    ) -> Result<(), AppError> {
+        self.store
+            .in_transaction_at(IsolationLevel::RepeatableRead, |store| {
+                check(&store.find_task(id)?)?;
+                Ok(store.delete_task(id, children)?)
+            })
+    }
+
+    /// Get the direct subtasks of a task
+    pub fn get_subtasks(&self, id: Uuid, fields: TaskFields) -> Result<Vec<SparseTask>, AppError> {
+        retry_transient(|| {
+            // Check if task exists
+            self.store.find_task(id)?;
+
+            let with_description = fields.contains(TaskField::Description);
+            let tasks = self.store.find_children(id, with_description)?;
+            Self::to_sparse_responses(&*self.store, tasks, fields)
+        })
+    }
+
+    /// Create a subtask under a task
+    pub fn create_subtask(
+        &self,
+        id: Uuid,
+        req: CreateTaskRequest,
+    ) -> Result<TaskResponse, AppError> {
+        self.create(CreateTaskRequest {
+            parent_id: Some(id),
+            ..req
+        })
+    }
+
+    /// Get a task with its whole subtask hierarchy
+    pub fn get_tree(&self, id: Uuid) -> Result<TaskTreeNode, AppError> {
+        retry_transient(|| {
+            let (tasks, depths): (Vec<_>, Vec<_>) = self
+                .store
+                .find_subtree(id)?
+                .into_iter()
+                .map(|node| (node.task, node.depth))
+                .unzip();
+
+            let tasks = Self::build_responses(&*self.store, tasks, TaskFields::all())?;
+            build_tree(tasks.into_iter().zip(depths).collect())
+                .ok_or_else(|| AppError::not_found("Resource not found"))
+        })
+    }
+
+    /// Attach a tag to a task
+    pub fn add_tag(&self, id: Uuid, tag_id: Uuid) -> Result<TaskResponse, AppError> {
+        let task = self.store.in_transaction(|store| {
+            match store.attach_tag(id, tag_id) {
+                // The task or the tag is missing: the lookup tells which
+                Err(RepositoryError::NotFound) => {
+                    store.find_task(id)?;
+                    Err(AppError::not_found("Tag not found"))
+                }
+                result => {
+                    result?;
+                    Ok(store.find_task(id)?)
+                }
+            }
+        })?;
+        Self::to_response(&*self.store, task)
+    }
+
+    /// Detach a tag from a task
+    pub fn remove_tag(&self, id: Uuid, tag_id: Uuid) -> Result<TaskResponse, AppError> {
+        let task = self.store.in_transaction(|store| {
+            match store.detach_tag(id, tag_id) {
+                // The task is missing or does not have the tag
+                Err(RepositoryError::NotFound) => {
+                    store.find_task(id)?;
+                    Err(AppError::not_found("Tag is not attached to the task"))
+                }
+                result => {
+                    result?;
+                    Ok(store.find_task(id)?)
+                }
+            }
+        })?;
+        Self::to_response(&*self.store, task)
+    }
+
+    /// Load tags and status names for a batch of tasks and build their responses
+    pub(crate) fn to_responses(
+        conn: &mut DbConnection,
+        tasks: Vec<Task>,
+    ) -> Result<Vec<TaskResponse>, AppError> {
+        Self::build_responses(
+            &PgTaskStore::with_connection(conn),
+            tasks,
+            TaskFields::all(),
+        )
+    }
+
+    /// Build responses limited to `fields`, skipping the queries for unselected tags and status
+    fn to_sparse_responses(
+        store: &dyn TaskStore,
+        tasks: Vec<Task>,
+        fields: TaskFields,
+    ) -> Result<Vec<SparseTask>, AppError> {
+        Ok(Self::build_responses(store, tasks, fields)?
+            .into_iter()
+            .map(|task| SparseTask { task, fields })
+            .collect())
+    }
+
+    /// Build responses, loading tags and status names only when `fields` has them
+    fn build_responses(
+        store: &dyn TaskStore,
+        tasks: Vec<Task>,
+        fields: TaskFields,
+    ) -> Result<Vec<TaskResponse>, AppError> {
+        let mut tags = if fields.contains(TaskField::Tags) {
+            store.find_tags(&tasks)?
+        } else {
+            Vec::new()
+        };
+        tags.resize_with(tasks.len(), Vec::new);
+
+        let mut names: HashMap<Uuid, String> = HashMap::new();
+        if fields.contains(TaskField::Status) {
+            let mut status_ids: Vec<Uuid> = tasks.iter().map(|t| t.status_id).collect();
+            status_ids.sort();
+            status_ids.dedup();
+            names = store
+                .find_statuses(&status_ids)?
+                .into_iter()
+                .map(|s| (s.id, s.name))
+                .collect();
+        }
+
+        Ok(tasks
+            .into_iter()
+            .zip(tags)
+            .map(|(task, tags)| {
+                let status = names.get(&task.status_id).cloned().unwrap_or_default();
+                task.into_response(status, tags)
+            })
+            .collect())
+    }
+
+    /// Load tags and status name for a single task and build its response
+    fn to_response(store: &dyn TaskStore, task: Task) -> Result<TaskResponse, AppError> {
+        let tags = store.find_tags(std::slice::from_ref(&task))?.concat();
+        let status = store.find_status(task.status_id)?;
+        Ok(task.into_response(status.name, tags))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum_diesel_api::TaskPriority;
+
+    fn service() -> (Arc<MemoryTaskStore<'static>>, TaskService) {
+        let store = Arc::new(MemoryTaskStore::new());
+        (store.clone(), TaskService::new(store))
+    }
+
+    fn create_request(title: &str) -> CreateTaskRequest {
+        CreateTaskRequest {
+            title: title.to_string(),
+            description: None,
+            completed: false,
+            due_at: None,
+            priority: TaskPriority::default(),
+            project_id: None,
+            parent_id: None,
+            status: None,
+            recurrence: None,
+            recurrence_timezone: None,
+        }
+    }
+
+    fn create(service: &TaskService, title: &str) -> TaskResponse {
+        service.create(create_request(title)).unwrap()
+    }
+
+    fn task(id: u128, parent: Option<u128>, completed: bool) -> TaskResponse {
+        TaskResponse {
+            id: Uuid::from_u128(id),
+            completed,
+            parent_id: parent.map(Uuid::from_u128),
+            position: format!("{:08x}i", id),
+            status: if completed { "done" } else { "todo" }.to_string(),
+            ..TaskResponse::fixture(&format!("task {}", id))
+        }
+    }
+
+    #[test]
+    fn test_build_tree() {
+        let tree = build_tree(vec![
+            (task(1, None, false), 0),
+            (task(2, Some(1), true), 1),
+            (task(3, Some(1), false), 1),
+            (task(4, Some(2), true), 2),
+        ])
+        .unwrap();
+
+        assert_eq!(tree.task.id, Uuid::from_u128(1));
+        assert_eq!(tree.completion_percent, Some(50.0));
+        assert_eq!(tree.children.len(), 2);
+        assert_eq!(tree.children[0].task.id, Uuid::from_u128(2));
+        assert_eq!(tree.children[0].completion_percent, Some(100.0));
+        assert_eq!(tree.children[0].children[0].depth, 2);
+        assert_eq!(tree.children[1].completion_percent, None);
+    }
+
+    #[test]
+    fn test_build_tree_of_subtask_ignores_its_parent() {
+        let tree = build_tree(vec![(task(2, Some(1), false), 0)]).unwrap();
+        assert_eq!(tree.task.id, Uuid::from_u128(2));
+        assert!(build_tree(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn test_validate_due_at() {
+        assert!(validate_due_at(&Utc::now()).is_ok());
+        assert!(validate_due_at(&DateTime::<Utc>::UNIX_EPOCH).is_ok());
+        assert!(validate_due_at(&Utc.with_ymd_and_hms(1969, 12, 31, 0, 0, 0).unwrap()).is_err());
+        assert!(validate_due_at(&Utc.with_ymd_and_hms(2100, 1, 1, 0, 0, 0).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_created_tasks_are_listed_in_manual_order() {
+        let (_, service) = service();
+        let first = create(&service, "  first  ");
+        let second = create(&service, "second");
+
+        assert_eq!(first.title, "first");
+        assert_eq!(first.status, "todo");
+        assert!(first.position < second.position);
+
+        let ids: Vec<Uuid> = service
+            .get_all(ListTasksQuery::default())
+            .unwrap()
+            .iter()
+            .map(|sparse| sparse.task.id)
+            .collect();
+        assert_eq!(ids, [first.id, second.id]);
+    }
+
+    #[test]
+    fn test_update_rejects_disallowed_transitions() {
+        let (_, service) = service();
+        let task = create(&service, "task");
+
+        let to = |status: &str| UpdateTaskRequest {
+            status: Some(status.to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            service.update(task.id, to("review")),
+            Err(AppError::Conflict(_))
+        ));
+
+        let task = service.update(task.id, to("in_progress")).unwrap();
+        assert_eq!(task.status, "in_progress");
+        assert!(!task.completed);
+    }
+
+    #[test]
+    fn test_open_blockers_prevent_completion() {
+        let (store, service) = service();
+        let blocker = create(&service, "blocker");
+        let task = create(&service, "task");
+        store.insert_dependency(task.id, blocker.id);
+
+        let complete = || UpdateTaskRequest {
+            completed: Some(true),
+            ..Default::default()
+        };
+        assert!(matches!(
+            service.update(task.id, complete()),
+            Err(AppError::Conflict(_))
+        ));
+
+        service.update(blocker.id, complete()).unwrap();
+        let task = service.update(task.id, complete()).unwrap();
+        assert!(task.completed);
+        assert!(task.completed_at.is_some());
+    }
+
+    #[test]
+    fn test_completing_a_recurring_task_schedules_the_next_one() {
+        let (_, service) = service();
+        let due_at = Utc.with_ymd_and_hms(2030, 1, 6, 9, 0, 0).unwrap();
+        let task = service
+            .create(CreateTaskRequest {
+                due_at: Some(due_at),
+                recurrence: Some("FREQ=WEEKLY".to_string()),
+                ..create_request("standup")
+            })
+            .unwrap();
+
+        let update = UpdateTaskRequest {
+            completed: Some(true),
+            ..Default::default()
+        };
+        service.update(task.id, update).unwrap();
+
+        let open: Vec<SparseTask> = service
+            .get_all(ListTasksQuery::default())
+            .unwrap()
+            .into_iter()
+            .filter(|sparse| !sparse.task.completed)
+            .collect();
+        assert_eq!(open.len(), 1);
+        assert_ne!(open[0].task.id, task.id);
+        assert_eq!(
+            open[0].task.due_at,
+            Some(due_at + chrono::Duration::weeks(1))
+        );
+    }
+
+    #[test]
+    fn test_move_task_between_neighbours() {
+        let (_, service) = service();
+        let a = create(&service, "a");
+        let b = create(&service, "b");
+        let c = create(&service, "c");
+
+        let moved = service
+            .move_task(
+                c.id,
+                MoveTaskRequest {
+                    before: Some(b.id),
+                    after: Some(a.id),
+                },
+            )
+            .unwrap();
+        assert!(a.position < moved.position && moved.position < b.position);
+        assert!(matches!(
+            service.move_task(
+                c.id,
+                MoveTaskRequest {
+                    before: None,
+                    after: None,
+                }
+            ),
+            Err(AppError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_archived_projects_take_no_tasks() {
+        let (store, service) = service();
+        let project = store.insert_project("old", true);
+
+        let result = service.create(CreateTaskRequest {
+            project_id: Some(project.id),
+            ..create_request("task")
+        });
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+    }
+
+    #[test]
+    fn test_tags_are_attached_and_detached() {
+        let (store, service) = service();
+        let task = create(&service, "task");
+        let tag = store.insert_tag("home");
+
+        let tagged = service.add_tag(task.id, tag.id).unwrap();
+        assert_eq!(tagged.tags.len(), 1);
+        assert_eq!(tagged.tags[0].name, "home");
+        assert!(service.remove_tag(task.id, tag.id).unwrap().tags.is_empty());
+
+        assert!(matches!(
+            service.remove_tag(task.id, tag.id),
+            Err(AppError::NotFound(msg)) if msg == "Tag is not attached to the task"
+        ));
+        assert!(matches!(
+            service.add_tag(task.id, Uuid::new_v4()),
+            Err(AppError::NotFound(msg)) if msg == "Tag not found"
+        ));
+        assert!(matches!(
+            service.add_tag(Uuid::new_v4(), tag.id),
+            Err(AppError::NotFound(msg)) if msg == "Resource not found"
+        ));
+    }
+
+    #[test]
+    fn test_writes_to_missing_tasks_are_not_found() {
+        let (_, service) = service();
+        let missing = Uuid::new_v4();
+        let rename = UpdateTaskRequest {
+            title: Some("renamed".to_string()),
+            ..Default::default()
+        };
+        assert!(is_plain_update(&rename));
+
+        assert!(matches!(
+            service.update(missing, rename),
+            Err(AppError::NotFound(_))
+        ));
+        assert!(matches!(
+            service.update(
+                missing,
+                UpdateTaskRequest {
+                    completed: Some(true),
+                    ..Default::default()
+                }
+            ),
+            Err(AppError::NotFound(_))
+        ));
+        assert!(matches!(
+            service.delete(missing, ChildrenPolicy::Cascade),
+            Err(AppError::NotFound(_))
+        ));
+
+        let task = create(&service, "task");
+        let moved = service.move_task(
+            missing,
+            MoveTaskRequest {
+                before: None,
+                after: Some(task.id),
+            },
+        );
+        assert!(matches!(moved, Err(AppError::NotFound(_))));
+    }
 }
@@ -0,0 +1,518 @@
+use axum_diesel_api::{
+    CreateTaskRequest, ExportTasksQuery, ImportReport, ImportedRow, RejectedRow, TaskFileFormat,
+    TaskPriority, TaskRecord,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use diesel::{Connection, RunQueryDsl};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::db::connection::{self, DbConnection};
+use crate::db::repositories::{ProjectRepository, TagRepository, TaskFilter, TaskRepository};
+use crate::db::store::PgTaskStore;
+use crate::error::AppError;
+use crate::services::TaskService;
+use crate::services::tag_service::{normalize_tag_name, validate_tag_name};
+
+/// Tasks loaded per query while exporting: the most an export holds in memory
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+/// Largest accepted import, in rows and in bytes
+pub const MAX_IMPORT_ROWS: usize = 10_000;
+pub const MAX_IMPORT_BYTES: usize = 16 * 1024 * 1024;
+
+/// CSV columns, in export order
+const CSV_COLUMNS: [&str; 15] = [
+    "id",
+    "title",
+    "description",
+    "status",
+    "completed",
+    "priority",
+    "due_at",
+    "project_id",
+    "parent_id",
+    "recurrence",
+    "recurrence_timezone",
+    "tags",
+    "created_at",
+    "updated_at",
+    "completed_at",
+];
+
+fn format_datetime(at: Option<DateTime<Utc>>) -> String {
+    at.map(|at| at.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+        .unwrap_or_default()
+}
+
+fn format_uuid(id: Option<Uuid>) -> String {
+    id.map(|id| id.to_string()).unwrap_or_default()
+}
+
+/// Cells of a CSV row, in `CSV_COLUMNS` order
+fn csv_row(record: TaskRecord) -> [String; 15] {
+    [
+        format_uuid(record.id),
+        record.title,
+        record.description.unwrap_or_default(),
+        record.status.unwrap_or_default(),
+        record.completed.to_string(),
+        priority_name(record.priority).to_string(),
+        format_datetime(record.due_at),
+        format_uuid(record.project_id),
+        format_uuid(record.parent_id),
+        record.recurrence.unwrap_or_default(),
+        record.recurrence_timezone.unwrap_or_default(),
+        record.tags.join(","),
+        format_datetime(record.created_at),
+        format_datetime(record.updated_at),
+        format_datetime(record.completed_at),
+    ]
+}
+
+fn priority_name(priority: TaskPriority) -> &'static str {
+    match priority {
+        TaskPriority::Low => "low",
+        TaskPriority::Medium => "medium",
+        TaskPriority::High => "high",
+        TaskPriority::Urgent => "urgent",
+    }
+}
+
+fn parse_priority(value: &str) -> Result<TaskPriority, AppError> {
+    match value.to_ascii_lowercase().as_str() {
+        "low" => Ok(TaskPriority::Low),
+        "medium" => Ok(TaskPriority::Medium),
+        "high" => Ok(TaskPriority::High),
+        "urgent" => Ok(TaskPriority::Urgent),
+        _ => Err(AppError::invalid_input(format!(
+            "Invalid priority '{}'",
+            value
+        ))),
+    }
+}
+
+/// Build a record from a CSV row; empty cells count as missing
+fn parse_csv_row(
+    columns: &HashMap<String, usize>,
+    row: &csv::StringRecord,
+) -> Result<TaskRecord, AppError> {
+    let cell = |name: &str| {
+        columns
+            .get(name)
+            .and_then(|&i| row.get(i))
+            .filter(|value| !value.trim().is_empty())
+    };
+    let uuid = |name: &str| {
+        cell(name)
+            .map(|value| {
+                value.trim().parse::<Uuid>().map_err(|_| {
+                    AppError::invalid_input(format!("Invalid UUID in {}: '{}'", name, value))
+                })
+            })
+            .transpose()
+    };
+    let datetime = |name: &str| {
+        cell(name)
+            .map(|value| {
+                DateTime::parse_from_rfc3339(value.trim())
+                    .map(|at| at.with_timezone(&Utc))
+                    .map_err(|_| {
+                        AppError::invalid_input(format!("Invalid date in {}: '{}'", name, value))
+                    })
+            })
+            .transpose()
+    };
+
+    let completed = match cell("completed").map(|value| value.trim().to_ascii_lowercase()) {
+        None => false,
+        Some(value) if value == "true" => true,
+        Some(value) if value == "false" => false,
+        Some(value) => {
+            return Err(AppError::invalid_input(format!(
+                "Invalid completed flag '{}'",
+                value
+            )));
+        }
+    };
+
+    Ok(TaskRecord {
+        id: uuid("id")?,
+        title: cell("title").unwrap_or_default().to_string(),
+        description: cell("description").map(str::to_string),
+        status: cell("status").map(str::to_string),
+        completed,
+        priority: cell("priority")
+            .map(|value| parse_priority(value.trim()))
+            .transpose()?
+            .unwrap_or_default(),
+        due_at: datetime("due_at")?,
+        project_id: uuid("project_id")?,
+        parent_id: uuid("parent_id")?,
+        recurrence: cell("recurrence").map(str::to_string),
+        recurrence_timezone: cell("recurrence_timezone").map(str::to_string),
+        tags: cell("tags")
+            .map(|tags| tags.split(',').map(str::to_string).collect())
+            .unwrap_or_default(),
+        created_at: datetime("created_at")?,
+        updated_at: datetime("updated_at")?,
+        completed_at: datetime("completed_at")?,
+    })
+}
+
+/// An import row: its number and either the parsed record or why it is invalid
+type ImportRow = (usize, Result<TaskRecord, AppError>);
+
+/// Split an import body into numbered rows
+///
+/// Problems with the file as a whole (missing header, too many rows) fail
+/// the import; problems with a single row are kept for the report.
+fn parse_rows(format: TaskFileFormat, body: &[u8]) -> Result<Vec<ImportRow>, AppError> {
+    let rows: Vec<_> = match format {
+        TaskFileFormat::Ndjson => {
+            let body = std::str::from_utf8(body)
+                .map_err(|_| AppError::invalid_input("NDJSON must be UTF-8"))?;
+            body.lines()
+                .enumerate()
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(|(i, line)| {
+                    let record = serde_json::from_str::<TaskRecord>(line)
+                        .map_err(|e| AppError::invalid_input(format!("Invalid JSON: {}", e)));
+                    (i + 1, record)
+                })
+                .collect()
+        }
+        TaskFileFormat::Csv => {
+            let mut reader = csv::Reader::from_reader(body);
+            let mut columns = HashMap::new();
+            for (i, name) in reader
+                .headers()
+                .map_err(|e| AppError::invalid_input(format!("Invalid CSV header: {}", e)))?
+                .iter()
+                .enumerate()
+            {
+                let name = name.trim().to_ascii_lowercase();
+                if !CSV_COLUMNS.contains(&name.as_str()) {
+                    return Err(AppError::validation(format!(
+                        "Unknown CSV column '{}'",
+                        name
+                    )));
+                }
+                columns.insert(name, i);
+            }
+            if !columns.contains_key("title") {
+                return Err(AppError::validation("CSV header needs a title column"));
+            }
+
+            reader
+                .records()
+                .enumerate()
+                .map(|(i, row)| {
+                    let record = row
+                        .map_err(|e| AppError::invalid_input(format!("Invalid CSV row: {}", e)))
+                        .and_then(|row| parse_csv_row(&columns, &row));
+                    (i + 1, record)
+                })
+                .collect()
+        }
+    };
+
+    if rows.len() > MAX_IMPORT_ROWS {
+        return Err(AppError::validation(format!(
+            "Imports are limited to {} rows",
+            MAX_IMPORT_ROWS
+        )));
+    }
+
+    Ok(rows)
+}
+
+/// Ends the import transaction: committed, undone for a dry run, or failed
+enum Rollback {
+    DryRun(ImportReport),
+    Failed(AppError),
+}
+
+impl From<diesel::result::Error> for Rollback {
+    fn from(err: diesel::result::Error) -> Self {
+        Rollback::Failed(err.into())
+    }
+}
+
+/// A validated export, ready to stream
+pub struct TaskExport {
+    format: TaskFileFormat,
+    filter: TaskFilter,
+}
+
+impl TaskExport {
+    pub fn format(&self) -> TaskFileFormat {
+        self.format
+    }
+
+    /// Write the export a page at a time
+    ///
+    /// Pages are read in one repeatable-read transaction, so the file is a
+    /// consistent snapshot. `write` returns `false` once nobody is reading,
+    /// which ends the export early.
+    pub fn run(self, mut write: impl FnMut(Vec<u8>) -> bool) -> Result<(), AppError> {
+        let mut conn = connection::get_read_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        conn.transaction::<_, AppError, _>(|conn| {
+            diesel::sql_query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+                .execute(conn)?;
+
+            if self.format == TaskFileFormat::Csv && !write(encode_csv([CSV_COLUMNS])?) {
+                return Ok(());
+            }
+
+            let mut cursor: Option<(String, Uuid)> = None;
+            loop {
+                let after = cursor
+                    .as_ref()
+                    .map(|(position, id)| (position.as_str(), *id));
+                let page = TaskRepository::find_page(conn, &self.filter, after, EXPORT_PAGE_SIZE)?;
+                let Some(last) = page.last() else {
+                    return Ok(());
+                };
+                cursor = Some((last.position.clone(), last.id));
+                let more = page.len() as i64 == EXPORT_PAGE_SIZE;
+
+                let records = TaskService::to_responses(conn, page)?
+                    .into_iter()
+                    .map(TaskRecord::from);
+                let chunk = match self.format {
+                    TaskFileFormat::Csv => encode_csv(records.map(csv_row))?,
+                    TaskFileFormat::Ndjson => encode_ndjson(records)?,
+                };
+
+                if !write(chunk) || !more {
+                    return Ok(());
+                }
+            }
+        })
+    }
+}
+
+fn encode_csv<R, C>(rows: impl IntoIterator<Item = R>) -> Result<Vec<u8>, AppError>
+where
+    R: IntoIterator<Item = C>,
+    C: AsRef<[u8]>,
+{
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for row in rows {
+        writer
+            .write_record(row)
+            .map_err(|e| AppError::internal(format!("Failed to write CSV: {}", e)))?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| AppError::internal(format!("Failed to write CSV: {}", e)))
+}
+
+fn encode_ndjson(records: impl IntoIterator<Item = TaskRecord>) -> Result<Vec<u8>, AppError> {
+    let mut out = Vec::new();
+    for record in records {
+        serde_json::to_writer(&mut out, &record)
+            .map_err(|e| AppError::internal(format!("Failed to write JSON: {}", e)))?;
+        out.push(b'\n');
+    }
+    Ok(out)
+}
+
+pub struct TransferService;
+
+impl TransferService {
+    /// Check an export request, so errors are reported before streaming starts
+    pub fn prepare_export(query: ExportTasksQuery) -> Result<TaskExport, AppError> {
+        if let Some(project_id) = query.project_id {
+            let mut conn = connection::get_connection().map_err(|e| {
+                AppError::service_unavailable(format!("Failed to get connection: {}", e))
+            })?;
+
+            ProjectRepository::find_by_id(&mut conn, project_id)
+                .map_err(|e| e.not_found_as("Project not found"))?;
+        }
+
+        Ok(TaskExport {
+            format: query.format,
+            filter: TaskFilter {
+                project_id: query.project_id,
+                include_archived: query.include_archived,
+                ..Default::default()
+            },
+        })
+    }
+
+    /// Import tasks, validating each row with the same rules as `POST /tasks`
+    ///
+    /// Valid rows are saved even when others are rejected, unless `dry_run`
+    /// is set, in which case the whole import is rolled back.
+    pub fn import(
+        format: TaskFileFormat,
+        dry_run: bool,
+        body: &[u8],
+    ) -> Result<ImportReport, AppError> {
+        let rows = parse_rows(format, body)?;
+
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let outcome = conn.transaction::<_, Rollback, _>(|conn| {
+            let mut report = ImportReport {
+                dry_run,
+                accepted: Vec::new(),
+                rejected: Vec::new(),
+            };
+            // File IDs of imported rows, so later rows can use them as parents
+            let mut imported: HashMap<Uuid, Uuid> = HashMap::new();
+
+            for (row, record) in rows {
+                // Each row runs in a savepoint: a rejected row leaves no trace
+                let result = record.and_then(|record| {
+                    let file_id = record.id;
+                    conn.transaction(|conn| Self::import_record(conn, &imported, record))
+                        .map(|id| (file_id, id))
+                });
+
+                match result {
+                    Ok((file_id, id)) => {
+                        if let Some(file_id) = file_id {
+                            imported.insert(file_id, id);
+                        }
+                        report.accepted.push(ImportedRow {
+                            row,
+                            id: (!dry_run).then_some(id),
+                        });
+                    }
+                    Err(e) => report.rejected.push(RejectedRow {
+                        row,
+                        error: e.to_error_response(),
+                    }),
+                }
+            }
+
+            if dry_run {
+                Err(Rollback::DryRun(report))
+            } else {
+                Ok(report)
+            }
+        });
+
+        match outcome {
+            Ok(report) | Err(Rollback::DryRun(report)) => Ok(report),
+            Err(Rollback::Failed(e)) => Err(e),
+        }
+    }
+
+    /// Create the task of one import row and attach its tags
+    fn import_record(
+        conn: &mut DbConnection,
+        imported: &HashMap<Uuid, Uuid>,
+        record: TaskRecord,
+    ) -> Result<Uuid, AppError> {
+        let mut tags = Vec::new();
+        for name in &record.tags {
+            let name = normalize_tag_name(name);
+            if !name.is_empty() {
+                validate_tag_name(&name)?;
+                tags.push(name);
+            }
+        }
+
+        let task = TaskService::create_in(
+            &PgTaskStore::with_connection(conn),
+            CreateTaskRequest {
+                title: record.title,
+                description: record.description,
+                completed: record.completed,
+                due_at: record.due_at,
+                priority: record.priority,
+                project_id: record.project_id,
+                parent_id: record
+                    .parent_id
+                    .map(|id| imported.get(&id).copied().unwrap_or(id)),
+                status: record.status,
+                recurrence: record.recurrence,
+                recurrence_timezone: record.recurrence_timezone,
+            },
+        )?;
+
+        for name in tags {
+            let tag = TagRepository::find_or_create(conn, &name)?;
+            TagRepository::attach(conn, task.id, tag.id)?;
+        }
+
+        Ok(task.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> TaskRecord {
+        TaskRecord {
+            id: Some(Uuid::from_u128(1)),
+            title: "Pay rent, \"now\"".to_string(),
+            description: Some("line one\nline two".to_string()),
+            status: Some("todo".to_string()),
+            completed: false,
+            priority: TaskPriority::High,
+            due_at: Some("2026-03-01T09:00:00Z".parse().unwrap()),
+            project_id: None,
+            parent_id: Some(Uuid::from_u128(2)),
+            recurrence: Some("FREQ=MONTHLY".to_string()),
+            recurrence_timezone: Some("Europe/Paris".to_string()),
+            tags: vec!["bills".to_string(), "home".to_string()],
+            created_at: Some("2026-01-01T00:00:00Z".parse().unwrap()),
+            updated_at: Some("2026-01-02T00:00:00Z".parse().unwrap()),
+            completed_at: None,
+        }
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let csv = encode_csv([CSV_COLUMNS.map(str::to_string), csv_row(record())]).unwrap();
+        let rows = parse_rows(TaskFileFormat::Csv, &csv).unwrap();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].0, 1);
+        assert_eq!(rows[0].1.as_ref().unwrap(), &record());
+    }
+
+    #[test]
+    fn test_ndjson_round_trip_skips_blank_lines() {
+        let mut body = b"\n".to_vec();
+        body.extend(encode_ndjson([record(), record()]).unwrap());
+        let rows = parse_rows(TaskFileFormat::Ndjson, &body).unwrap();
+
+        assert_eq!(rows.iter().map(|(row, _)| *row).collect::<Vec<_>>(), [2, 3]);
+        assert_eq!(rows[1].1.as_ref().unwrap(), &record());
+    }
+
+    #[test]
+    fn test_bad_rows_are_reported_individually() {
+        let body = b"title,priority,due_at\nok,low,\nbad,extreme,\nlate,,tomorrow\n\"unterminated";
+        let rows = parse_rows(TaskFileFormat::Csv, body).unwrap();
+
+        assert!(rows[0].1.is_ok());
+        assert!(rows[1].1.is_err());
+        assert!(rows[2].1.is_err());
+        assert_eq!(rows[0].1.as_ref().unwrap().priority, TaskPriority::Low);
+
+        let rows = parse_rows(TaskFileFormat::Ndjson, b"{\"title\": \"a\"}\n{oops}\n").unwrap();
+        assert!(rows[0].1.is_ok());
+        assert!(rows[1].1.is_err());
+    }
+
+    #[test]
+    fn test_csv_header_is_checked() {
+        assert!(parse_rows(TaskFileFormat::Csv, b"description\nx\n").is_err());
+        assert!(parse_rows(TaskFileFormat::Csv, b"title,colour\nx,red\n").is_err());
+        assert!(parse_rows(TaskFileFormat::Csv, b"Title\nx\n").is_ok());
+    }
+}
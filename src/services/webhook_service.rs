@@ -0,0 +1,629 @@
+use axum_diesel_api::{
+    CreateWebhookRequest, DeliveryStatus, ListDeliveriesQuery, UpdateWebhookRequest,
+    WebhookDeliveryResponse, WebhookEvent, WebhookResponse,
+};
+use chrono::{DateTime, Utc};
+use diesel::Connection;
+use hmac::{Hmac, KeyInit, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::db::connection::{self, DbConnection};
+use crate::db::models::{
+    NewDeliveryAttempt, NewWebhook, NewWebhookDelivery, UpdateWebhook, Webhook, WebhookDelivery,
+};
+use crate::db::repositories::{OutboxRepository, WebhookRepository};
+use crate::error::AppError;
+
+/// Attempts before a delivery is dead-lettered
+pub const MAX_ATTEMPTS: i32 = 8;
+/// Delay after the first failed attempt, doubled after each following one
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(30);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(6 * 3600);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Outbox events queued for delivery per pass
+const QUEUE_BATCH: i64 = 500;
+/// Deliveries sent per pass
+const SEND_BATCH: i64 = 20;
+/// How long claimed deliveries are hidden from other dispatchers
+const CLAIM_LEASE: Duration = Duration::from_secs(300);
+/// How long delivered events are kept for the delivery logs
+const OUTBOX_RETENTION_DAYS: i64 = 30;
+
+fn validate_url(url: &str) -> Result<(), AppError> {
+    let uri: ureq::http::Uri = url
+        .parse()
+        .map_err(|_| AppError::validation(format!("Invalid webhook URL '{}'", url)))?;
+
+    if !matches!(uri.scheme_str(), Some("http" | "https")) || uri.host().is_none() {
+        return Err(AppError::validation(
+            "Webhook URL must be an absolute http or https URL",
+        ));
+    }
+
+    if url.len() > 2048 {
+        return Err(AppError::validation(
+            "Webhook URL must be at most 2048 characters",
+        ));
+    }
+
+    Ok(())
+}
+
+fn validate_description(description: &str) -> Result<(), AppError> {
+    if description.len() > 500 {
+        return Err(AppError::validation(
+            "Webhook description must be at most 500 characters",
+        ));
+    }
+    Ok(())
+}
+
+/// Signature of a request body: `sha256=` and the hex HMAC of `{timestamp}.{body}`
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Delay before the next attempt, after `attempts` failed ones
+fn retry_delay(attempts: i32) -> Duration {
+    let doublings = attempts.clamp(1, 30) as u32 - 1;
+    BASE_RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(doublings))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// State of a delivery after an attempt, and when it is due again
+fn next_state(
+    succeeded: bool,
+    attempts: i32,
+    now: DateTime<Utc>,
+) -> (DeliveryStatus, DateTime<Utc>) {
+    if succeeded {
+        (DeliveryStatus::Delivered, now)
+    } else if attempts >= MAX_ATTEMPTS {
+        (DeliveryStatus::Dead, now)
+    } else {
+        let delay = chrono::Duration::from_std(retry_delay(attempts)).unwrap_or_default();
+        (DeliveryStatus::Pending, now + delay)
+    }
+}
+
+/// State of a delivery that could not be sent, e.g. because its event is
+/// invalid (dead at once) or the database was briefly unavailable (retried)
+fn state_after_error(
+    error: &AppError,
+    attempts: i32,
+    now: DateTime<Utc>,
+) -> (DeliveryStatus, DateTime<Utc>) {
+    if error.may_succeed_later() {
+        next_state(false, attempts, now)
+    } else {
+        (DeliveryStatus::Dead, now)
+    }
+}
+
+/// Result of one request to a webhook
+#[derive(Debug)]
+pub struct AttemptOutcome {
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub duration: Duration,
+}
+
+impl AttemptOutcome {
+    pub fn succeeded(&self) -> bool {
+        self.status_code
+            .is_some_and(|code| (200..300).contains(&code))
+    }
+}
+
+/// POST an event to a webhook URL, signed with its secret
+pub fn deliver(
+    agent: &ureq::Agent,
+    url: &str,
+    secret: &str,
+    delivery_id: Uuid,
+    event: &WebhookEvent,
+) -> AttemptOutcome {
+    let body = serde_json::to_vec(event).expect("webhook events serialize to JSON");
+    let timestamp = Utc::now().timestamp();
+    let started = Instant::now();
+
+    let result = agent
+        .post(url)
+        .header("content-type", "application/json")
+        .header("x-webhook-event-id", event.id.to_string())
+        .header("x-webhook-delivery-id", delivery_id.to_string())
+        .header("x-webhook-timestamp", timestamp.to_string())
+        .header("x-webhook-signature", sign(secret, timestamp, &body))
+        .send(&body[..]);
+
+    let duration = started.elapsed();
+    match result {
+        Ok(response) => {
+            let status = response.status();
+            AttemptOutcome {
+                status_code: Some(status.as_u16()),
+                error: (!status.is_success()).then(|| format!("HTTP {}", status)),
+                duration,
+            }
+        }
+        Err(e) => AttemptOutcome {
+            status_code: None,
+            error: Some(e.to_string()),
+            duration,
+        },
+    }
+}
+
+/// HTTP client for deliveries: non-2xx responses are outcomes, not errors
+pub fn http_agent() -> ureq::Agent {
+    ureq::Agent::config_builder()
+        .timeout_global(Some(REQUEST_TIMEOUT))
+        .http_status_as_error(false)
+        .build()
+        .into()
+}
+
+/// Work done by one dispatcher pass
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DispatchStats {
+    /// Outbox events queued for delivery
+    pub queued: usize,
+    pub delivered: usize,
+    /// Failed attempts that will be retried
+    pub retried: usize,
+    /// Deliveries that ran out of attempts
+    pub dead: usize,
+}
+
+pub struct WebhookService;
+
+impl WebhookService {
+    /// List all webhooks
+    pub fn get_all() -> Result<Vec<WebhookResponse>, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let webhooks = WebhookRepository::find_all(&mut conn)?;
+        Ok(webhooks.into_iter().map(Into::into).collect())
+    }
+
+    /// Get a webhook by ID
+    pub fn get_by_id(id: Uuid) -> Result<WebhookResponse, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        Ok(Self::find(&mut conn, id)?.into())
+    }
+
+    /// Register a webhook with a fresh signing secret, returned this once
+    pub fn create(req: CreateWebhookRequest) -> Result<WebhookResponse, AppError> {
+        let url = req.url.trim();
+        validate_url(url)?;
+        if let Some(ref description) = req.description {
+            validate_description(description)?;
+        }
+
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        // Two v4 UUIDs give 244 random bits
+        let secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+
+        let new_webhook = NewWebhook {
+            url: url.to_string(),
+            description: req.description,
+            secret,
+            active: req.active,
+        };
+
+        let webhook = WebhookRepository::create(&mut conn, new_webhook)?;
+        Ok(webhook.into_created_response())
+    }
+
+    /// Change a webhook's URL, description or active flag
+    pub fn update(id: Uuid, req: UpdateWebhookRequest) -> Result<WebhookResponse, AppError> {
+        let url = req.url.as_deref().map(str::trim);
+        if let Some(url) = url {
+            validate_url(url)?;
+        }
+        if let Some(Some(ref description)) = req.description {
+            validate_description(description)?;
+        }
+
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let mut webhook = Self::find(&mut conn, id)?;
+
+        let update_webhook = UpdateWebhook {
+            url: url.map(str::to_string),
+            description: req.description,
+            active: req.active,
+        };
+
+        // Diesel rejects empty changesets
+        if update_webhook.url.is_some()
+            || update_webhook.description.is_some()
+            || update_webhook.active.is_some()
+        {
+            webhook = WebhookRepository::update(&mut conn, id, update_webhook)?;
+        }
+
+        Ok(webhook.into())
+    }
+
+    /// Delete a webhook and its delivery logs
+    pub fn delete(id: Uuid) -> Result<(), AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        Self::find(&mut conn, id)?;
+
+        WebhookRepository::delete(&mut conn, id)?;
+        Ok(())
+    }
+
+    /// List a webhook's latest deliveries with their attempts
+    pub fn get_deliveries(
+        id: Uuid,
+        query: ListDeliveriesQuery,
+    ) -> Result<Vec<WebhookDeliveryResponse>, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        Self::find(&mut conn, id)?;
+
+        let deliveries = WebhookRepository::find_deliveries(&mut conn, id, query.status)?;
+        Self::with_attempts(&mut conn, deliveries)
+    }
+
+    /// Send a delivered or dead delivery again, with a fresh set of attempts
+    pub fn retry_delivery(
+        id: Uuid,
+        delivery_id: Uuid,
+    ) -> Result<WebhookDeliveryResponse, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let (delivery, _) = WebhookRepository::find_delivery(&mut conn, delivery_id)
+            .map_err(|e| e.not_found_as("Delivery not found"))?;
+        if delivery.webhook_id != id {
+            return Err(AppError::not_found("Delivery not found"));
+        }
+
+        if delivery.status() == DeliveryStatus::Pending {
+            return Err(AppError::conflict("Delivery is already pending"));
+        }
+
+        WebhookRepository::requeue(&mut conn, delivery_id)?;
+        let delivery = WebhookRepository::find_delivery(&mut conn, delivery_id)?;
+        Ok(Self::with_attempts(&mut conn, vec![delivery])?
+            .pop()
+            .expect("one delivery in, one response out"))
+    }
+
+    /// Queue new outbox events for every active webhook and send due deliveries
+    pub fn dispatch(agent: &ureq::Agent) -> Result<DispatchStats, AppError> {
+        let mut conn = connection::get_connection().map_err(|e| {
+            AppError::service_unavailable(format!("Failed to get connection: {}", e))
+        })?;
+
+        let mut stats = DispatchStats {
+            queued: Self::queue_events(&mut conn)?,
+            ..Default::default()
+        };
+
+        let lease_until = Utc::now() + chrono::Duration::from_std(CLAIM_LEASE).unwrap_or_default();
+        let deliveries = WebhookRepository::claim_due(&mut conn, SEND_BATCH, lease_until)?;
+        let mut webhooks: HashMap<Uuid, Webhook> = HashMap::new();
+
+        // A delivery that fails to go out is recorded and the batch goes on,
+        // so that one bad event cannot hold back the others
+        for delivery in deliveries {
+            let attempts = delivery.attempts + 1;
+            let (outcome, (status, next_attempt_at)) =
+                match Self::send(&mut conn, agent, &mut webhooks, &delivery) {
+                    Ok(outcome) => {
+                        let state = next_state(outcome.succeeded(), attempts, Utc::now());
+                        (outcome, state)
+                    }
+                    Err(e) => {
+                        tracing::warn!("Webhook delivery {} was not sent: {}", delivery.id, e);
+                        let state = state_after_error(&e, attempts, Utc::now());
+                        let outcome = AttemptOutcome {
+                            status_code: None,
+                            error: Some(e.to_string()),
+                            duration: Duration::ZERO,
+                        };
+                        (outcome, state)
+                    }
+                };
+            match status {
+                DeliveryStatus::Delivered => stats.delivered += 1,
+                DeliveryStatus::Pending => stats.retried += 1,
+                DeliveryStatus::Dead => {
+                    tracing::warn!(
+                        "Webhook delivery {} is dead after {} attempts",
+                        delivery.id,
+                        attempts
+                    );
+                    stats.dead += 1;
+                }
+            }
+
+            let attempt = NewDeliveryAttempt {
+                delivery_id: delivery.id,
+                status_code: outcome.status_code.map(i32::from),
+                error: outcome.error,
+                duration_ms: outcome.duration.as_millis().min(i32::MAX as u128) as i32,
+            };
+            if let Err(e) =
+                WebhookRepository::record_attempt(&mut conn, attempt, status, next_attempt_at)
+            {
+                // Sent again once its lease runs out
+                tracing::warn!(
+                    "Failed to record the attempt of webhook delivery {}: {}",
+                    delivery.id,
+                    e
+                );
+            }
+        }
+
+        let cutoff = Utc::now() - chrono::Duration::days(OUTBOX_RETENTION_DAYS);
+        OutboxRepository::prune(&mut conn, cutoff)?;
+
+        Ok(stats)
+    }
+
+    /// Build the event of a delivery and POST it to its webhook
+    fn send(
+        conn: &mut DbConnection,
+        agent: &ureq::Agent,
+        webhooks: &mut HashMap<Uuid, Webhook>,
+        delivery: &WebhookDelivery,
+    ) -> Result<AttemptOutcome, AppError> {
+        if !webhooks.contains_key(&delivery.webhook_id) {
+            let webhook = WebhookRepository::find_by_id(conn, delivery.webhook_id)?;
+            webhooks.insert(webhook.id, webhook);
+        }
+        let webhook = &webhooks[&delivery.webhook_id];
+        let event = OutboxRepository::find_by_id(conn, delivery.outbox_id)?
+            .to_webhook_event()
+            .map_err(|e| AppError::internal(format!("Invalid outbox event: {}", e)))?;
+
+        Ok(deliver(
+            agent,
+            &webhook.url,
+            &webhook.secret,
+            delivery.id,
+            &event,
+        ))
+    }
+
+    /// Turn undispatched outbox events into one delivery per active webhook
+    fn queue_events(conn: &mut DbConnection) -> Result<usize, AppError> {
+        conn.transaction(|conn| {
+            let events = OutboxRepository::lock_undispatched(conn, QUEUE_BATCH)?;
+            if events.is_empty() {
+                return Ok(0);
+            }
+
+            let webhooks = WebhookRepository::find_active(conn)?;
+            let deliveries: Vec<NewWebhookDelivery> = events
+                .iter()
+                .flat_map(|event| {
+                    webhooks.iter().map(|webhook| NewWebhookDelivery {
+                        webhook_id: webhook.id,
+                        outbox_id: event.id,
+                    })
+                })
+                .collect();
+            // Stay well below the bind parameter limit
+            for chunk in deliveries.chunks(1000) {
+                WebhookRepository::queue_deliveries(conn, chunk)?;
+            }
+
+            let event_ids: Vec<Uuid> = events.iter().map(|event| event.id).collect();
+            OutboxRepository::mark_dispatched(conn, &event_ids)?;
+            Ok(events.len())
+        })
+    }
+
+    fn find(conn: &mut DbConnection, id: Uuid) -> Result<Webhook, AppError> {
+        WebhookRepository::find_by_id(conn, id).map_err(|e| e.not_found_as("Webhook not found"))
+    }
+
+    /// Load the attempt logs of a batch of deliveries and build their responses
+    fn with_attempts(
+        conn: &mut DbConnection,
+        deliveries: Vec<(WebhookDelivery, String)>,
+    ) -> Result<Vec<WebhookDeliveryResponse>, AppError> {
+        let (deliveries, kinds): (Vec<WebhookDelivery>, Vec<String>) =
+            deliveries.into_iter().unzip();
+        let attempts = WebhookRepository::find_attempts(conn, &deliveries)?;
+
+        Ok(deliveries
+            .into_iter()
+            .zip(kinds)
+            .zip(attempts)
+            .map(|((delivery, kind), attempts)| delivery.into_response(&kind, attempts))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::{Router, body::Bytes, routing::post};
+    use axum_diesel_api::{TaskEventKind, TaskPriority, TaskResponse};
+    use std::sync::{Arc, Mutex};
+
+    type Received = Arc<Mutex<Vec<(HeaderMap, Bytes)>>>;
+
+    /// Start a local HTTP stub: `/ok` answers 204, `/fail` answers 500
+    async fn stub() -> (String, Received) {
+        let received = Received::default();
+        let record = |status: StatusCode| {
+            move |State(received): State<Received>, headers: HeaderMap, body: Bytes| async move {
+                received.lock().unwrap().push((headers, body));
+                status
+            }
+        };
+        let app = Router::new()
+            .route("/ok", post(record(StatusCode::NO_CONTENT)))
+            .route("/fail", post(record(StatusCode::INTERNAL_SERVER_ERROR)))
+            .with_state(received.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (base, received)
+    }
+
+    fn event() -> WebhookEvent {
+        WebhookEvent {
+            id: Uuid::new_v4(),
+            kind: TaskEventKind::Created,
+            occurred_at: Utc::now(),
+            task: TaskResponse {
+                priority: TaskPriority::High,
+                ..TaskResponse::fixture("Ship it")
+            },
+        }
+    }
+
+    async fn deliver_to(url: String, event: WebhookEvent) -> AttemptOutcome {
+        tokio::task::spawn_blocking(move || {
+            deliver(&http_agent(), &url, "s3cret", Uuid::new_v4(), &event)
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_deliver_signs_the_body() {
+        let (base, received) = stub().await;
+        let event = event();
+
+        let outcome = deliver_to(format!("{}/ok", base), event.clone()).await;
+        assert!(outcome.succeeded());
+        assert_eq!(outcome.status_code, Some(204));
+        assert_eq!(outcome.error, None);
+
+        let (headers, body) = received.lock().unwrap().pop().unwrap();
+        let header = |name: &str| headers[name].to_str().unwrap().to_string();
+        let timestamp: i64 = header("x-webhook-timestamp").parse().unwrap();
+        assert_eq!(
+            header("x-webhook-signature"),
+            sign("s3cret", timestamp, &body)
+        );
+        assert_eq!(header("x-webhook-event-id"), event.id.to_string());
+        assert_eq!(header("content-type"), "application/json");
+
+        let sent: WebhookEvent = serde_json::from_slice(&body).unwrap();
+        assert_eq!(sent.task.title, "Ship it");
+    }
+
+    #[tokio::test]
+    async fn test_deliver_reports_failures() {
+        let (base, received) = stub().await;
+
+        let outcome = deliver_to(format!("{}/fail", base), event()).await;
+        assert!(!outcome.succeeded());
+        assert_eq!(outcome.status_code, Some(500));
+        assert_eq!(received.lock().unwrap().len(), 1);
+
+        // Nothing listens on port 9 (discard) in the test environment
+        let outcome = deliver_to("http://127.0.0.1:9/".to_string(), event()).await;
+        assert!(!outcome.succeeded());
+        assert_eq!(outcome.status_code, None);
+        assert!(outcome.error.is_some());
+    }
+
+    #[test]
+    fn test_sign() {
+        // HMAC-SHA256 of "1700000000.{}" keyed with "key"
+        assert_eq!(
+            sign("key", 1_700_000_000, b"{}"),
+            "sha256=9d713ed406bb7076d4123f0dc2c39d2df5c654ed4b0cd56b52c8b4c940bd63ae"
+        );
+    }
+
+    #[test]
+    fn test_backoff_and_dead_letter() {
+        let now = Utc::now();
+        assert_eq!(retry_delay(1), Duration::from_secs(30));
+        assert_eq!(retry_delay(2), Duration::from_secs(60));
+        assert_eq!(retry_delay(5), Duration::from_secs(480));
+        assert_eq!(retry_delay(30), MAX_RETRY_DELAY);
+
+        assert_eq!(next_state(true, 3, now), (DeliveryStatus::Delivered, now));
+        assert_eq!(
+            next_state(false, 1, now),
+            (DeliveryStatus::Pending, now + chrono::Duration::seconds(30))
+        );
+        assert_eq!(next_state(false, MAX_ATTEMPTS, now).0, DeliveryStatus::Dead);
+    }
+
+    #[test]
+    fn test_unsendable_deliveries_are_retried_or_dead_lettered() {
+        let now = Utc::now();
+        let (status, next) = state_after_error(&AppError::transient("deadlock"), 1, now);
+        assert_eq!(status, DeliveryStatus::Pending);
+        assert!(next > now);
+        let (status, _) = state_after_error(&AppError::service_unavailable("down"), 1, now);
+        assert_eq!(status, DeliveryStatus::Pending);
+
+        let invalid = AppError::internal("Invalid outbox event: missing task");
+        assert_eq!(
+            state_after_error(&invalid, 1, now),
+            (DeliveryStatus::Dead, now)
+        );
+    }
+
+    #[test]
+    fn test_secret_is_only_in_the_created_response() {
+        let webhook = Webhook {
+            id: Uuid::new_v4(),
+            url: "https://example.com/hook".to_string(),
+            description: None,
+            secret: "s3cret".to_string(),
+            active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        let created = webhook.clone().into_created_response();
+        assert_eq!(created.secret.as_deref(), Some("s3cret"));
+        let listed = serde_json::to_value(WebhookResponse::from(webhook)).unwrap();
+        assert!(listed.get("secret").is_none());
+    }
+
+    #[test]
+    fn test_validate_url() {
+        assert!(validate_url("https://example.com/hooks").is_ok());
+        assert!(validate_url("http://localhost:8080").is_ok());
+        assert!(validate_url("ftp://example.com").is_err());
+        assert!(validate_url("/relative").is_err());
+        assert!(validate_url("not a url").is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode, header};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::support::TestApp;
+
+#[tokio::test]
+async fn test_invalid_task_bodies() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+
+    let cases = [
+        json!({ "title": "   " }),
+        json!({ "title": "x".repeat(256) }),
+        json!({ "title": "Old", "due_at": "1969-12-31T00:00:00Z" }),
+        json!({ "title": "Odd", "due_at": "2030-01-01T00:00:00Z", "recurrence": "FREQ=SOMETIMES" }),
+        json!({ "title": "Zone", "recurrence_timezone": "Europe/Paris" }),
+        json!({ "title": "Status", "status": "blocked" }),
+    ];
+    for body in cases {
+        app.post("/tasks", body)
+            .await
+            .error(StatusCode::BAD_REQUEST, "VALIDATION_ERROR");
+    }
+
+    let task = app.create_titled("Valid").await;
+    app.put(&format!("/tasks/{}", task.id), json!({ "title": "" }))
+        .await
+        .error(StatusCode::BAD_REQUEST, "VALIDATION_ERROR");
+    assert!(app.get("/tasks").await.tasks().len() == 1);
+}
+
+#[tokio::test]
+async fn test_malformed_requests() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+
+    let broken = Request::post("/tasks")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from("{\"title\": "))
+        .unwrap();
+    app.send(broken)
+        .await
+        .error(StatusCode::BAD_REQUEST, "INVALID_INPUT");
+
+    app.post("/tasks", json!({ "description": "no title" }))
+        .await
+        .error(StatusCode::BAD_REQUEST, "INVALID_INPUT");
+    app.post("/tasks", json!({ "title": "Odd", "priority": "extreme" }))
+        .await
+        .error(StatusCode::BAD_REQUEST, "INVALID_INPUT");
+
+    app.get("/tasks/not-a-uuid")
+        .await
+        .expect_status(StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_unknown_resources() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let id = Uuid::new_v4();
+
+    app.get(&format!("/tasks/{}", id))
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+    app.put(&format!("/tasks/{}", id), json!({ "title": "Ghost" }))
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+    app.post(
+        &format!("/tasks/{}/move", id),
+        json!({ "after": Uuid::new_v4() }),
+    )
+    .await
+    .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+    app.get(&format!("/tasks/{}/occurrences", id))
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+    app.post("/tasks", json!({ "title": "Orphan", "parent_id": id }))
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+
+    app.get("/nowhere")
+        .await
+        .expect_status(StatusCode::NOT_FOUND);
+    app.request(axum::http::Method::PATCH, "/tasks", None)
+        .await
+        .expect_status(StatusCode::METHOD_NOT_ALLOWED);
+}
+
+#[tokio::test]
+async fn test_content_negotiation_errors() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+
+    let plain = Request::post("/tasks")
+        .header(header::CONTENT_TYPE, "text/plain")
+        .body(Body::from("title"))
+        .unwrap();
+    app.send(plain)
+        .await
+        .error(StatusCode::UNSUPPORTED_MEDIA_TYPE, "UNSUPPORTED_MEDIA_TYPE");
+
+    let xml = Request::get("/tasks")
+        .header(header::ACCEPT, "application/xml")
+        .body(Body::empty())
+        .unwrap();
+    app.send(xml)
+        .await
+        .error(StatusCode::NOT_ACCEPTABLE, "NOT_ACCEPTABLE");
+}
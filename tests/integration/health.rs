@@ -0,0 +1,18 @@
+use axum::http::StatusCode;
+use serde_json::{Value, json};
+
+use crate::support::TestApp;
+
+#[tokio::test]
+async fn test_health() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+
+    let body: Value = app
+        .get("/health")
+        .await
+        .expect_status(StatusCode::OK)
+        .json();
+    assert_eq!(body, json!({ "status": "ok" }));
+}
@@ -1,20 +0,0 @@
-// Integration tests are placed in the tests/ directory
-// Each file in tests/ is compiled as a separate crate
-
-// Note: Full integration tests require running the application
-// These tests serve as examples for implementing your own test suite
-
-#[cfg(test)]
-mod tests {
-    #[tokio::test]
-    async fn test_health_endpoint_example() {
-        // Example test structure
-        // In a real implementation, you would:
-        // 1. Start the application server
-        // 2. Make HTTP requests to test endpoints
-        // 3. Assert on responses
-
-        // For now, this is a placeholder to demonstrate test structure
-        assert!(true, "Health endpoint test placeholder");
-    }
-}
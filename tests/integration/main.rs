@@ -0,0 +1,16 @@
+//! HTTP integration tests: the real router against a test database
+//!
+//! Set `TEST_DATABASE_URL` (or `DATABASE_URL`) to a database the tests may
+//! create schemas in; without one the tests pass without running.
+
+mod support;
+
+mod errors;
+mod health;
+mod read_replica;
+mod task_crud;
+mod task_dependencies;
+mod task_hierarchy;
+mod task_listing;
+mod task_transfer;
+mod transactions;
@@ -0,0 +1,96 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode, header};
+use axum_diesel_project::db::connection::{self, READ_PRIMARY_COOKIE, READ_PRIMARY_HEADER};
+use diesel::r2d2::{ConnectionManager, Pool};
+use std::time::Duration;
+
+use crate::support::TestApp;
+
+#[tokio::test]
+async fn test_reads_go_to_the_replica_unless_pinned_to_the_primary() {
+    let Some(mut app) = TestApp::start().await else {
+        return;
+    };
+    app.use_stale_replica();
+
+    // Writes read their result back from the primary
+    let task = app.create_titled("fresh").await;
+    app.put(
+        &format!("/tasks/{}", task.id),
+        serde_json::json!({ "title": "renamed" }),
+    )
+    .await
+    .expect_status(StatusCode::OK);
+
+    // Plain reads see the replica, which has not caught up
+    assert!(app.get("/tasks").await.tasks().is_empty());
+    app.get(&format!("/tasks/{}", task.id))
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+
+    let pinned = Request::get("/tasks")
+        .header(READ_PRIMARY_HEADER, "true")
+        .body(Body::empty())
+        .unwrap();
+    let tasks = app.send(pinned).await.expect_status(StatusCode::OK).tasks();
+    assert_eq!(tasks[0].title, "renamed");
+}
+
+#[tokio::test]
+async fn test_writes_pin_the_client_to_the_primary_for_a_while() {
+    let Some(mut app) = TestApp::start().await else {
+        return;
+    };
+    app.use_stale_replica();
+
+    let created = app
+        .post("/tasks", serde_json::json!({ "title": "fresh" }))
+        .await
+        .expect_status(StatusCode::CREATED);
+    let cookie = created
+        .header(header::SET_COOKIE)
+        .expect("writes set the read-your-writes cookie")
+        .split(';')
+        .next()
+        .unwrap()
+        .to_string();
+    assert!(cookie.starts_with(READ_PRIMARY_COOKIE));
+
+    // The next request of the client reads what it wrote
+    let next = Request::get("/tasks")
+        .header(header::COOKIE, &cookie)
+        .body(Body::empty())
+        .unwrap();
+    let tasks = app.send(next).await.expect_status(StatusCode::OK).tasks();
+    assert_eq!(tasks[0].title, "fresh");
+
+    // Other clients, and this one once the window is over, read the replica
+    assert!(app.get("/tasks").await.tasks().is_empty());
+    let expired = Request::get("/tasks")
+        .header(header::COOKIE, format!("{}=1", READ_PRIMARY_COOKIE))
+        .body(Body::empty())
+        .unwrap();
+    assert!(app.send(expired).await.tasks().is_empty());
+}
+
+#[tokio::test]
+async fn test_unreachable_replica_falls_back_to_the_primary() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    app.create_titled("kept").await;
+
+    let unreachable = Pool::builder()
+        .connection_timeout(Duration::from_millis(100))
+        .build_unchecked(ConnectionManager::new("postgres://127.0.0.1:1/nowhere"));
+    connection::set_read_pool(Some(unreachable));
+
+    for _ in 0..2 {
+        let tasks = app
+            .get("/tasks")
+            .await
+            .expect_status(StatusCode::OK)
+            .tasks();
+        assert_eq!(tasks[0].title, "kept");
+    }
+}
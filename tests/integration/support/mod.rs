@@ -0,0 +1,262 @@
+//! Test harness: the real router on a throwaway schema of the test database
+//!
+//! Every test gets a fresh schema with the migrations applied, and the
+//! application pool points at it until the test ends. The pool is global, so
+//! tests take turns.
+
+use axum::Router;
+use axum::body::{Body, Bytes};
+use axum::http::{HeaderMap, Method, Request, StatusCode, header};
+use axum_diesel_api::{ErrorResponse, TaskResponse};
+use axum_diesel_project::app::build_router;
+use axum_diesel_project::db::connection::{self, DbPool};
+use diesel::prelude::*;
+use diesel::r2d2::{self, ConnectionManager, CustomizeConnection, Pool};
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tokio::sync::{Mutex, MutexGuard};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/// Held by the running test, as all tests share the application pool
+static TURN: Mutex<()> = Mutex::const_new(());
+
+/// Points every pool connection at the schema of the test
+#[derive(Debug)]
+struct SearchPath(String);
+
+impl CustomizeConnection<PgConnection, r2d2::Error> for SearchPath {
+    fn on_acquire(&self, conn: &mut PgConnection) -> Result<(), r2d2::Error> {
+        diesel::sql_query(format!("SET search_path TO {}", self.0))
+            .execute(conn)
+            .map(|_| ())
+            .map_err(r2d2::Error::QueryError)
+    }
+}
+
+/// The application, on a schema of its own
+pub struct TestApp {
+    router: Router,
+    url: String,
+    /// The schema of the test, then any other schema it created
+    schemas: Vec<String>,
+    admin: PgConnection,
+    _turn: MutexGuard<'static, ()>,
+}
+
+impl TestApp {
+    /// Migrate a new schema and build the router on it
+    ///
+    /// The database comes from `TEST_DATABASE_URL`, or else `DATABASE_URL`.
+    /// When neither is set, returns `None` and the test passes without
+    /// running, except on CI (`CI` set), where the test fails instead.
+    pub async fn start() -> Option<TestApp> {
+        let Some(url) = ["TEST_DATABASE_URL", "DATABASE_URL"]
+            .into_iter()
+            .find_map(|name| std::env::var(name).ok())
+        else {
+            assert!(
+                std::env::var_os("CI").is_none(),
+                "CI is set but TEST_DATABASE_URL and DATABASE_URL are not: integration tests would not run"
+            );
+            eprintln!("skipped: set TEST_DATABASE_URL or DATABASE_URL to run integration tests");
+            return None;
+        };
+        let turn = TURN.lock().await;
+
+        let schema = format!("test_{}", Uuid::new_v4().simple());
+        let mut admin = PgConnection::establish(&url).expect("test database is reachable");
+        connection::set_pool(migrated_pool(&mut admin, &url, &schema));
+        connection::set_read_pool(None);
+
+        Some(TestApp {
+            router: build_router(),
+            url,
+            schemas: vec![schema],
+            admin,
+            _turn: turn,
+        })
+    }
+
+    /// Serve reads from an empty schema, like a replica that is far behind
+    pub fn use_stale_replica(&mut self) {
+        let schema = format!("{}_replica", self.schemas[0]);
+        connection::set_read_pool(Some(migrated_pool(&mut self.admin, &self.url, &schema)));
+        self.schemas.push(schema);
+    }
+
+    /// Send a request through the router and read the whole response
+    pub async fn send(&self, request: Request<Body>) -> TestResponse {
+        let response = self
+            .router
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("the router is infallible");
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("read the response body");
+
+        TestResponse {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    /// Send a request with an optional JSON body
+    pub async fn request(&self, method: Method, uri: &str, body: Option<Value>) -> TestResponse {
+        let request = Request::builder().method(method).uri(uri);
+        let request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        };
+        self.send(request.unwrap()).await
+    }
+
+    pub async fn get(&self, uri: &str) -> TestResponse {
+        self.request(Method::GET, uri, None).await
+    }
+
+    pub async fn post(&self, uri: &str, body: Value) -> TestResponse {
+        self.request(Method::POST, uri, Some(body)).await
+    }
+
+    pub async fn put(&self, uri: &str, body: Value) -> TestResponse {
+        self.request(Method::PUT, uri, Some(body)).await
+    }
+
+    /// `PUT` without a body, for link endpoints
+    pub async fn put_empty(&self, uri: &str) -> TestResponse {
+        self.request(Method::PUT, uri, None).await
+    }
+
+    pub async fn delete(&self, uri: &str) -> TestResponse {
+        self.request(Method::DELETE, uri, None).await
+    }
+
+    /// Create a task from a JSON body that must be valid
+    pub async fn create_task(&self, body: Value) -> TaskResponse {
+        self.post("/tasks", body)
+            .await
+            .expect_status(StatusCode::CREATED)
+            .task()
+    }
+
+    /// Create a task with just a title
+    pub async fn create_titled(&self, title: &str) -> TaskResponse {
+        self.create_task(serde_json::json!({ "title": title }))
+            .await
+    }
+
+    /// Create a project and return its ID
+    pub async fn create_project(&self, name: &str) -> Uuid {
+        let project: Value = self
+            .post("/projects", serde_json::json!({ "name": name }))
+            .await
+            .expect_status(StatusCode::CREATED)
+            .json();
+        project["id"].as_str().unwrap().parse().unwrap()
+    }
+
+    /// Create a tag and return its ID
+    pub async fn create_tag(&self, name: &str) -> Uuid {
+        let tag: Value = self
+            .post("/tags", serde_json::json!({ "name": name }))
+            .await
+            .expect_status(StatusCode::CREATED)
+            .json();
+        tag["id"].as_str().unwrap().parse().unwrap()
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        connection::set_read_pool(None);
+        for schema in &self.schemas {
+            let dropped = diesel::sql_query(format!("DROP SCHEMA {} CASCADE", schema))
+                .execute(&mut self.admin);
+            if let Err(e) = dropped {
+                eprintln!("failed to drop test schema {}: {}", schema, e);
+            }
+        }
+    }
+}
+
+/// Create `schema` with the migrations applied, and a pool on it
+fn migrated_pool(admin: &mut PgConnection, url: &str, schema: &str) -> DbPool {
+    diesel::sql_query(format!("CREATE SCHEMA {schema}"))
+        .execute(admin)
+        .expect("create the test schema");
+
+    let mut conn = PgConnection::establish(url).expect("test database is reachable");
+    diesel::sql_query(format!("SET search_path TO {schema}"))
+        .execute(&mut conn)
+        .expect("switch to the test schema");
+    conn.run_pending_migrations(MIGRATIONS)
+        .expect("migrations apply");
+
+    Pool::builder()
+        .max_size(4)
+        .min_idle(Some(0))
+        .connection_customizer(Box::new(SearchPath(schema.to_string())))
+        .build(ConnectionManager::new(url))
+        .expect("build the test pool")
+}
+
+/// A buffered response
+#[derive(Debug)]
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl TestResponse {
+    /// Check the status, showing the body when it differs
+    #[track_caller]
+    pub fn expect_status(self, status: StatusCode) -> Self {
+        assert_eq!(self.status, status, "unexpected response: {}", self.text());
+        self
+    }
+
+    pub fn text(&self) -> &str {
+        std::str::from_utf8(&self.body).expect("UTF-8 body")
+    }
+
+    pub fn header(&self, name: header::HeaderName) -> Option<&str> {
+        self.headers.get(name).map(|v| v.to_str().unwrap())
+    }
+
+    /// Decode a JSON body
+    #[track_caller]
+    pub fn json<T: DeserializeOwned>(&self) -> T {
+        serde_json::from_slice(&self.body)
+            .unwrap_or_else(|e| panic!("undecodable body ({}): {}", e, self.text()))
+    }
+
+    #[track_caller]
+    pub fn task(&self) -> TaskResponse {
+        self.json()
+    }
+
+    #[track_caller]
+    pub fn tasks(&self) -> Vec<TaskResponse> {
+        self.json()
+    }
+
+    /// Decode an error body, checking its status and code
+    #[track_caller]
+    pub fn error(self, status: StatusCode, code: &str) -> ErrorResponse {
+        let error: ErrorResponse = self.expect_status(status).json();
+        assert_eq!(error.error, code, "unexpected error: {}", error.message);
+        error
+    }
+}
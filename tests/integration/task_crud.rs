@@ -0,0 +1,264 @@
+use axum::http::{StatusCode, header};
+use axum_diesel_api::TaskPriority;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use crate::support::TestApp;
+
+#[tokio::test]
+async fn test_create_and_get_task() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+
+    let response = app
+        .post(
+            "/tasks",
+            json!({
+                "title": "  Write report  ",
+                "description": "  quarterly  ",
+                "priority": "high",
+                "due_at": "2030-01-01T09:00:00Z"
+            }),
+        )
+        .await
+        .expect_status(StatusCode::CREATED);
+    let created = response.task();
+    assert_eq!(
+        response.header(header::LOCATION),
+        Some(format!("/tasks/{}", created.id).as_str())
+    );
+    assert_eq!(created.title, "Write report");
+    assert_eq!(created.description.as_deref(), Some("quarterly"));
+    assert_eq!(created.priority, TaskPriority::High);
+    assert_eq!(created.status, "todo");
+    assert!(!created.completed);
+    assert!(created.completed_at.is_none());
+
+    let fetched = app
+        .get(&format!("/tasks/{}", created.id))
+        .await
+        .expect_status(StatusCode::OK)
+        .task();
+    assert_eq!(fetched.id, created.id);
+    assert_eq!(fetched.due_at, created.due_at);
+}
+
+#[tokio::test]
+async fn test_get_task_with_selected_fields() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let task = app.create_titled("Sparse").await;
+
+    let body: Value = app
+        .get(&format!("/tasks/{}?fields=title,status", task.id))
+        .await
+        .expect_status(StatusCode::OK)
+        .json();
+    assert_eq!(
+        body,
+        json!({ "id": task.id, "title": "Sparse", "status": "todo" })
+    );
+}
+
+#[tokio::test]
+async fn test_update_task() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let task = app
+        .create_task(json!({ "title": "Draft", "due_at": "2030-01-01T00:00:00Z" }))
+        .await;
+
+    let updated = app
+        .put(
+            &format!("/tasks/{}", task.id),
+            json!({ "title": "Final", "priority": "urgent", "due_at": null }),
+        )
+        .await
+        .expect_status(StatusCode::OK)
+        .task();
+    assert_eq!(updated.title, "Final");
+    assert_eq!(updated.priority, TaskPriority::Urgent);
+    assert!(updated.due_at.is_none());
+    assert!(updated.updated_at >= task.updated_at);
+
+    let completed = app
+        .put(&format!("/tasks/{}", task.id), json!({ "completed": true }))
+        .await
+        .expect_status(StatusCode::OK)
+        .task();
+    assert!(completed.completed);
+    assert_eq!(completed.status, "done");
+    assert!(completed.completed_at.is_some());
+}
+
+#[tokio::test]
+async fn test_update_follows_the_workflow() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let task = app.create_titled("Flow").await;
+    let uri = format!("/tasks/{}", task.id);
+
+    app.put(&uri, json!({ "status": "review" }))
+        .await
+        .error(StatusCode::CONFLICT, "CONFLICT");
+    app.put(&uri, json!({ "status": "unknown" }))
+        .await
+        .error(StatusCode::BAD_REQUEST, "VALIDATION_ERROR");
+
+    let task = app
+        .put(&uri, json!({ "status": "in_progress" }))
+        .await
+        .expect_status(StatusCode::OK)
+        .task();
+    assert_eq!(task.status, "in_progress");
+}
+
+#[tokio::test]
+async fn test_delete_task() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let task = app.create_titled("Doomed").await;
+    let uri = format!("/tasks/{}", task.id);
+
+    let response = app.delete(&uri).await.expect_status(StatusCode::NO_CONTENT);
+    assert!(response.body.is_empty());
+
+    app.get(&uri)
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+    app.delete(&uri)
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+    app.delete(&format!("{}?children=cascade", uri))
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+}
+
+#[tokio::test]
+async fn test_move_task() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let a = app.create_titled("a").await;
+    let b = app.create_titled("b").await;
+    let c = app.create_titled("c").await;
+
+    let moved = app
+        .post(
+            &format!("/tasks/{}/move", c.id),
+            json!({ "after": a.id, "before": b.id }),
+        )
+        .await
+        .expect_status(StatusCode::OK)
+        .task();
+    assert!(a.position < moved.position && moved.position < b.position);
+
+    let titles: Vec<String> = app
+        .get("/tasks")
+        .await
+        .tasks()
+        .into_iter()
+        .map(|t| t.title)
+        .collect();
+    assert_eq!(titles, ["a", "c", "b"]);
+
+    let uri = format!("/tasks/{}/move", a.id);
+    app.post(&uri, json!({}))
+        .await
+        .error(StatusCode::BAD_REQUEST, "VALIDATION_ERROR");
+    app.post(&uri, json!({ "before": a.id }))
+        .await
+        .error(StatusCode::BAD_REQUEST, "VALIDATION_ERROR");
+    app.post(&uri, json!({ "before": Uuid::new_v4() }))
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+}
+
+#[tokio::test]
+async fn test_recurring_task() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let task = app
+        .create_task(json!({
+            "title": "Standup",
+            "due_at": "2030-01-07T09:00:00Z",
+            "recurrence": "RRULE:freq=weekly;byday=MO",
+            "recurrence_timezone": "Europe/Paris"
+        }))
+        .await;
+    assert_eq!(task.recurrence.as_deref(), Some("FREQ=WEEKLY;BYDAY=MO"));
+    assert_eq!(task.recurrence_timezone.as_deref(), Some("Europe/Paris"));
+
+    let preview: Value = app
+        .get(&format!("/tasks/{}/occurrences?count=2", task.id))
+        .await
+        .expect_status(StatusCode::OK)
+        .json();
+    assert_eq!(
+        preview["occurrences"],
+        json!(["2030-01-14T10:00:00+01:00", "2030-01-21T10:00:00+01:00"])
+    );
+    app.get(&format!("/tasks/{}/occurrences?count=0", task.id))
+        .await
+        .error(StatusCode::BAD_REQUEST, "VALIDATION_ERROR");
+
+    // Completing an occurrence schedules the next one
+    app.put(&format!("/tasks/{}", task.id), json!({ "completed": true }))
+        .await
+        .expect_status(StatusCode::OK);
+    let open = app.get("/tasks?status=todo").await.tasks();
+    assert_eq!(open.len(), 1);
+    assert_ne!(open[0].id, task.id);
+    assert_eq!(
+        open[0].due_at.unwrap().to_rfc3339(),
+        "2030-01-14T09:00:00+00:00"
+    );
+
+    let plain = app.create_titled("Once").await;
+    app.get(&format!("/tasks/{}/occurrences", plain.id))
+        .await
+        .error(StatusCode::BAD_REQUEST, "VALIDATION_ERROR");
+    app.post(
+        "/tasks",
+        json!({ "title": "No due date", "recurrence": "FREQ=DAILY" }),
+    )
+    .await
+    .error(StatusCode::BAD_REQUEST, "VALIDATION_ERROR");
+}
+
+#[tokio::test]
+async fn test_tag_and_untag_task() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let task = app.create_titled("Tagged").await;
+    let tag_id = app.create_tag("home").await;
+    let uri = format!("/tasks/{}/tags/{}", task.id, tag_id);
+
+    let tagged = app
+        .put_empty(&uri)
+        .await
+        .expect_status(StatusCode::OK)
+        .task();
+    assert_eq!(tagged.tags.len(), 1);
+    assert_eq!(tagged.tags[0].name, "home");
+
+    let untagged = app.delete(&uri).await.expect_status(StatusCode::OK).task();
+    assert!(untagged.tags.is_empty());
+    app.delete(&uri)
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+
+    app.put_empty(&format!("/tasks/{}/tags/{}", task.id, Uuid::new_v4()))
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+    app.put_empty(&format!("/tasks/{}/tags/{}", Uuid::new_v4(), tag_id))
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+}
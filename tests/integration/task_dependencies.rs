@@ -0,0 +1,131 @@
+use axum::http::StatusCode;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use crate::support::TestApp;
+
+#[tokio::test]
+async fn test_add_list_and_remove_dependencies() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let task = app.create_titled("ship").await;
+    let blocker = app.create_titled("test").await;
+    let uri = format!("/tasks/{}/dependencies/{}", task.id, blocker.id);
+
+    let blockers = app
+        .put_empty(&uri)
+        .await
+        .expect_status(StatusCode::OK)
+        .tasks();
+    assert_eq!(blockers.len(), 1);
+    assert_eq!(blockers[0].id, blocker.id);
+
+    let listed = app
+        .get(&format!("/tasks/{}/dependencies", task.id))
+        .await
+        .expect_status(StatusCode::OK)
+        .tasks();
+    assert_eq!(listed.len(), 1);
+
+    let remaining = app.delete(&uri).await.expect_status(StatusCode::OK).tasks();
+    assert!(remaining.is_empty());
+}
+
+#[tokio::test]
+async fn test_invalid_dependencies() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let a = app.create_titled("a").await;
+    let b = app.create_titled("b").await;
+    app.put_empty(&format!("/tasks/{}/dependencies/{}", a.id, b.id))
+        .await
+        .expect_status(StatusCode::OK);
+
+    app.put_empty(&format!("/tasks/{}/dependencies/{}", a.id, a.id))
+        .await
+        .error(StatusCode::BAD_REQUEST, "VALIDATION_ERROR");
+    app.put_empty(&format!("/tasks/{}/dependencies/{}", b.id, a.id))
+        .await
+        .error(StatusCode::BAD_REQUEST, "VALIDATION_ERROR");
+    app.put_empty(&format!("/tasks/{}/dependencies/{}", a.id, Uuid::new_v4()))
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+    app.get(&format!("/tasks/{}/dependencies", Uuid::new_v4()))
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+}
+
+#[tokio::test]
+async fn test_blocked_tasks_cannot_be_completed() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let task = app.create_titled("ship").await;
+    let blocker = app.create_titled("test").await;
+    app.put_empty(&format!("/tasks/{}/dependencies/{}", task.id, blocker.id))
+        .await
+        .expect_status(StatusCode::OK);
+
+    let complete = json!({ "completed": true });
+    app.put(&format!("/tasks/{}", task.id), complete.clone())
+        .await
+        .error(StatusCode::CONFLICT, "CONFLICT");
+
+    app.put(&format!("/tasks/{}", blocker.id), complete.clone())
+        .await
+        .expect_status(StatusCode::OK);
+    let task = app
+        .put(&format!("/tasks/{}", task.id), complete)
+        .await
+        .expect_status(StatusCode::OK)
+        .task();
+    assert!(task.completed);
+}
+
+#[tokio::test]
+async fn test_schedule() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let deploy = app.create_titled("deploy").await;
+    let build = app.create_titled("build").await;
+    let test = app.create_titled("test").await;
+    let docs = app.create_titled("docs").await;
+    for (task, blocker) in [(deploy.id, test.id), (test.id, build.id)] {
+        app.put_empty(&format!("/tasks/{}/dependencies/{}", task, blocker))
+            .await
+            .expect_status(StatusCode::OK);
+    }
+
+    let schedule: Value = app
+        .get("/tasks/schedule")
+        .await
+        .expect_status(StatusCode::OK)
+        .json();
+    let order: Vec<&str> = schedule["order"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|task| task["title"].as_str().unwrap())
+        .collect();
+    assert_eq!(order.len(), 4);
+    let index = |title| order.iter().position(|t| *t == title).unwrap();
+    assert!(index("build") < index("test") && index("test") < index("deploy"));
+    assert_eq!(
+        schedule["critical_path"],
+        json!([build.id, test.id, deploy.id])
+    );
+
+    let subset: Value = app
+        .get(&format!("/tasks/schedule?id={}&id={}", docs.id, deploy.id))
+        .await
+        .expect_status(StatusCode::OK)
+        .json();
+    assert_eq!(subset["order"].as_array().unwrap().len(), 2);
+
+    app.get(&format!("/tasks/schedule?id={}", Uuid::new_v4()))
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+}
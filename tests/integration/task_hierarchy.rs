@@ -0,0 +1,180 @@
+use axum::http::StatusCode;
+use axum_diesel_project::db::connection;
+use diesel::RunQueryDsl;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use crate::support::TestApp;
+
+#[tokio::test]
+async fn test_create_and_list_subtasks() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let project = app.create_project("Home").await;
+    let parent = app
+        .create_task(json!({ "title": "parent", "project_id": project }))
+        .await;
+    let uri = format!("/tasks/{}/subtasks", parent.id);
+
+    let response = app
+        .post(&uri, json!({ "title": "child" }))
+        .await
+        .expect_status(StatusCode::CREATED);
+    let child = response.task();
+    assert_eq!(child.parent_id, Some(parent.id));
+    // Subtasks default to the project of their parent
+    assert_eq!(child.project_id, Some(project));
+
+    let subtasks = app.get(&uri).await.expect_status(StatusCode::OK).tasks();
+    assert_eq!(subtasks.len(), 1);
+    assert_eq!(subtasks[0].id, child.id);
+
+    let missing = format!("/tasks/{}/subtasks", Uuid::new_v4());
+    app.get(&missing)
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+    app.post(&missing, json!({ "title": "orphan" }))
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+}
+
+#[tokio::test]
+async fn test_task_tree() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let root = app.create_titled("root").await;
+    let done = app
+        .create_task(json!({ "title": "done", "parent_id": root.id, "completed": true }))
+        .await;
+    let open = app
+        .create_task(json!({ "title": "open", "parent_id": root.id }))
+        .await;
+    app.create_task(json!({ "title": "leaf", "parent_id": open.id }))
+        .await;
+
+    let tree: Value = app
+        .get(&format!("/tasks/{}/tree", root.id))
+        .await
+        .expect_status(StatusCode::OK)
+        .json();
+    assert_eq!(tree["id"], json!(root.id));
+    assert_eq!(tree["depth"], 0);
+    assert_eq!(tree["completion_percent"], 50.0);
+    let children = tree["children"].as_array().unwrap();
+    assert_eq!(children[0]["id"], json!(done.id));
+    assert_eq!(children[1]["id"], json!(open.id));
+    assert_eq!(children[1]["children"][0]["title"], "leaf");
+    assert_eq!(children[1]["children"][0]["depth"], 2);
+
+    app.get(&format!("/tasks/{}/tree", Uuid::new_v4()))
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+}
+
+#[tokio::test]
+async fn test_reparent_task() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let root = app.create_titled("root").await;
+    let child = app
+        .create_task(json!({ "title": "child", "parent_id": root.id }))
+        .await;
+    let other = app.create_titled("other").await;
+
+    let moved = app
+        .put(
+            &format!("/tasks/{}", child.id),
+            json!({ "parent_id": other.id }),
+        )
+        .await
+        .expect_status(StatusCode::OK)
+        .task();
+    assert_eq!(moved.parent_id, Some(other.id));
+
+    // A task cannot end up under itself
+    app.put(
+        &format!("/tasks/{}", other.id),
+        json!({ "parent_id": child.id }),
+    )
+    .await
+    .error(StatusCode::BAD_REQUEST, "VALIDATION_ERROR");
+    app.put(
+        &format!("/tasks/{}", other.id),
+        json!({ "parent_id": Uuid::new_v4() }),
+    )
+    .await
+    .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+
+    let detached = app
+        .put(
+            &format!("/tasks/{}", child.id),
+            json!({ "parent_id": null }),
+        )
+        .await
+        .expect_status(StatusCode::OK)
+        .task();
+    assert!(detached.parent_id.is_none());
+}
+
+#[tokio::test]
+async fn test_delete_task_with_subtasks() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let root = app.create_titled("root").await;
+    let child = app
+        .create_task(json!({ "title": "child", "parent_id": root.id }))
+        .await;
+    let grandchild = app
+        .create_task(json!({ "title": "grandchild", "parent_id": child.id }))
+        .await;
+
+    // By default subtasks move up to the parent of the deleted task
+    app.delete(&format!("/tasks/{}", child.id))
+        .await
+        .expect_status(StatusCode::NO_CONTENT);
+    let grandchild = app.get(&format!("/tasks/{}", grandchild.id)).await.task();
+    assert_eq!(grandchild.parent_id, Some(root.id));
+
+    app.delete(&format!("/tasks/{}?children=cascade", root.id))
+        .await
+        .expect_status(StatusCode::NO_CONTENT);
+    app.get(&format!("/tasks/{}", grandchild.id))
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+    assert!(app.get("/tasks").await.tasks().is_empty());
+}
+
+#[tokio::test]
+async fn test_parent_cycle_does_not_hang_tree_or_delete() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let a = app.create_titled("a").await;
+    let b = app
+        .create_task(json!({ "title": "b", "parent_id": a.id }))
+        .await;
+    // A cycle the service would refuse to create
+    let mut conn = connection::get_connection().unwrap();
+    diesel::sql_query("UPDATE tasks SET parent_id = $1 WHERE id = $2")
+        .bind::<diesel::sql_types::Uuid, _>(b.id)
+        .bind::<diesel::sql_types::Uuid, _>(a.id)
+        .execute(&mut conn)
+        .unwrap();
+
+    let tree: Value = app
+        .get(&format!("/tasks/{}/tree", a.id))
+        .await
+        .expect_status(StatusCode::OK)
+        .json();
+    assert_eq!(tree["children"][0]["id"], json!(b.id));
+    assert_eq!(tree["children"][0]["children"], json!([]));
+
+    app.delete(&format!("/tasks/{}?children=cascade", a.id))
+        .await
+        .expect_status(StatusCode::NO_CONTENT);
+    assert!(app.get("/tasks").await.tasks().is_empty());
+}
@@ -0,0 +1,249 @@
+use axum::http::StatusCode;
+use axum_diesel_api::TaskResponse;
+use axum_diesel_project::db::connection;
+use axum_diesel_project::db::schema::outbox;
+use diesel::prelude::*;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use crate::support::TestApp;
+
+/// Titles of the tasks listed at `uri`, in order
+async fn titles(app: &TestApp, uri: &str) -> Vec<String> {
+    app.get(uri)
+        .await
+        .expect_status(StatusCode::OK)
+        .tasks()
+        .into_iter()
+        .map(|task| task.title)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_list_tasks_in_manual_order() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    assert!(titles(&app, "/tasks").await.is_empty());
+
+    for title in ["first", "second", "third"] {
+        app.create_titled(title).await;
+    }
+    assert_eq!(titles(&app, "/tasks").await, ["first", "second", "third"]);
+}
+
+#[tokio::test]
+async fn test_list_tasks_with_selected_fields() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let task = app.create_titled("Sparse").await;
+
+    let body: Value = app
+        .get("/tasks?fields=completed")
+        .await
+        .expect_status(StatusCode::OK)
+        .json();
+    assert_eq!(body, json!([{ "id": task.id, "completed": false }]));
+
+    app.get("/tasks?fields=colour")
+        .await
+        .expect_status(StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_filter_tasks_by_tag() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let home = app.create_tag("home").await;
+    let urgent = app.create_tag("urgent").await;
+    let both = app.create_titled("both").await;
+    let only_home = app.create_titled("home only").await;
+    app.create_titled("untagged").await;
+    for (task, tag) in [(both.id, home), (both.id, urgent), (only_home.id, home)] {
+        app.put_empty(&format!("/tasks/{}/tags/{}", task, tag))
+            .await
+            .expect_status(StatusCode::OK);
+    }
+
+    assert_eq!(titles(&app, "/tasks?tag=HOME").await, ["both", "home only"]);
+    assert_eq!(
+        titles(&app, "/tasks?tag=home&tag=urgent").await,
+        ["both", "home only"]
+    );
+    assert_eq!(
+        titles(&app, "/tasks?tag=home&tag=urgent&match=all").await,
+        ["both"]
+    );
+    assert!(titles(&app, "/tasks?tag=missing").await.is_empty());
+}
+
+#[tokio::test]
+async fn test_filter_tasks_by_due_date_priority_and_status() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    app.create_task(json!({ "title": "late", "due_at": "2000-01-01T00:00:00Z" }))
+        .await;
+    app.create_task(json!({
+        "title": "soon",
+        "due_at": "2030-01-01T00:00:00Z",
+        "priority": "urgent"
+    }))
+    .await;
+    app.create_task(json!({ "title": "done", "completed": true }))
+        .await;
+
+    assert_eq!(titles(&app, "/tasks?overdue=true").await, ["late"]);
+    assert_eq!(
+        titles(&app, "/tasks?due_before=2031-01-01T00:00:00Z").await,
+        ["late", "soon"]
+    );
+    assert_eq!(titles(&app, "/tasks?priority=urgent").await, ["soon"]);
+    assert_eq!(titles(&app, "/tasks?status=done").await, ["done"]);
+
+    app.get("/tasks?priority=extreme")
+        .await
+        .expect_status(StatusCode::BAD_REQUEST);
+    app.get("/tasks?due_before=tomorrow")
+        .await
+        .expect_status(StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_project_tasks() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let project = app.create_project("Launch").await;
+    app.create_titled("elsewhere").await;
+
+    let response = app
+        .post(
+            &format!("/projects/{}/tasks", project),
+            json!({ "title": "inside" }),
+        )
+        .await
+        .expect_status(StatusCode::CREATED);
+    assert_eq!(response.task().project_id, Some(project));
+
+    assert_eq!(
+        titles(&app, &format!("/projects/{}/tasks", project)).await,
+        ["inside"]
+    );
+    assert_eq!(
+        titles(&app, &format!("/tasks?project_id={}", project)).await,
+        ["inside"]
+    );
+
+    app.get(&format!("/projects/{}/tasks", Uuid::new_v4()))
+        .await
+        .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+    app.post(
+        "/tasks",
+        json!({ "title": "lost", "project_id": Uuid::new_v4() }),
+    )
+    .await
+    .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+}
+
+#[tokio::test]
+async fn test_archived_project_tasks() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let project = app.create_project("Old").await;
+    let task = app
+        .create_task(json!({ "title": "archived", "project_id": project }))
+        .await;
+    app.create_titled("active").await;
+
+    app.post(&format!("/projects/{}/archive", project), json!({}))
+        .await
+        .expect_status(StatusCode::OK);
+
+    assert_eq!(titles(&app, "/tasks").await, ["active"]);
+    assert_eq!(
+        titles(&app, "/tasks?include_archived=true").await,
+        ["archived", "active"]
+    );
+    let archived = app.get(&format!("/tasks/{}", task.id)).await.task();
+    assert!(archived.archived_at.is_some());
+
+    app.post("/tasks", json!({ "title": "late", "project_id": project }))
+        .await
+        .error(StatusCode::BAD_REQUEST, "VALIDATION_ERROR");
+
+    // Archived tasks stay in their project until it is unarchived
+    let other = app.create_project("New").await;
+    for project_id in [json!(other), Value::Null] {
+        app.put(
+            &format!("/tasks/{}", task.id),
+            json!({ "project_id": project_id }),
+        )
+        .await
+        .error(StatusCode::BAD_REQUEST, "VALIDATION_ERROR");
+    }
+    app.post(&format!("/projects/{}/unarchive", project), json!({}))
+        .await
+        .expect_status(StatusCode::OK);
+    assert_eq!(titles(&app, "/tasks").await, ["archived", "active"]);
+}
+
+/// Payloads of the `updated` events recorded for a task, oldest first
+fn updated_events(task: &TaskResponse) -> Vec<TaskResponse> {
+    let mut conn = connection::get_connection().unwrap();
+    outbox::table
+        .filter(outbox::task_id.eq(task.id))
+        .filter(outbox::kind.eq("updated"))
+        .order(outbox::created_at)
+        .select(outbox::payload)
+        .load::<Value>(&mut conn)
+        .unwrap()
+        .into_iter()
+        .map(|payload| serde_json::from_value(payload).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_bulk_changes_record_task_events() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let project = app.create_project("Events").await;
+    let task = app
+        .create_task(json!({ "title": "watched", "project_id": project }))
+        .await;
+
+    app.post(&format!("/projects/{}/archive", project), json!({}))
+        .await
+        .expect_status(StatusCode::OK);
+    app.post(&format!("/projects/{}/unarchive", project), json!({}))
+        .await
+        .expect_status(StatusCode::OK);
+    let statuses: Vec<Value> = app
+        .get(&format!("/projects/{}/statuses", project))
+        .await
+        .json();
+    let status = statuses
+        .iter()
+        .find(|status| status["name"] == json!(task.status))
+        .unwrap();
+    app.put(
+        &format!(
+            "/projects/{}/statuses/{}",
+            project,
+            status["id"].as_str().unwrap()
+        ),
+        json!({ "done": true }),
+    )
+    .await
+    .expect_status(StatusCode::OK);
+
+    let events = updated_events(&task);
+    assert_eq!(events.len(), 3);
+    assert!(events[0].archived_at.is_some());
+    assert!(events[1].archived_at.is_none());
+    assert!(events[2].completed);
+}
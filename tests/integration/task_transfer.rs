@@ -0,0 +1,130 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode, header};
+use axum_diesel_api::TaskRecord;
+use serde_json::Value;
+
+use crate::support::TestApp;
+
+fn import(uri: &str, body: &str) -> Request<Body> {
+    Request::post(uri)
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_import_ndjson() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let file = concat!(
+        r#"{"id":"00000000-0000-0000-0000-000000000001","title":"parent","tags":["Home"]}"#,
+        "\n",
+        r#"{"title":"child","parent_id":"00000000-0000-0000-0000-000000000001"}"#,
+        "\n",
+        r#"{"title":""}"#,
+        "\n",
+    );
+
+    let report: Value = app
+        .send(import("/tasks/import?format=ndjson", file))
+        .await
+        .expect_status(StatusCode::OK)
+        .json();
+    assert_eq!(report["dry_run"], false);
+    assert_eq!(report["accepted"].as_array().unwrap().len(), 2);
+    assert_eq!(report["rejected"][0]["row"], 3);
+    assert_eq!(report["rejected"][0]["error"], "VALIDATION_ERROR");
+
+    let tasks = app.get("/tasks").await.tasks();
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[0].tags[0].name, "home");
+    // `parent_id` resolved to the task created from the first row
+    assert_eq!(tasks[1].parent_id, Some(tasks[0].id));
+}
+
+#[tokio::test]
+async fn test_import_csv_dry_run() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let file = "title,priority\nfirst,high\nsecond,extreme\n";
+
+    let report: Value = app
+        .send(import("/tasks/import?format=csv&dry_run=true", file))
+        .await
+        .expect_status(StatusCode::OK)
+        .json();
+    assert_eq!(report["dry_run"], true);
+    assert_eq!(report["accepted"][0]["row"], 1);
+    assert_eq!(report["accepted"][0]["id"], Value::Null);
+    assert_eq!(report["rejected"][0]["row"], 2);
+    assert!(app.get("/tasks").await.tasks().is_empty());
+
+    app.send(import("/tasks/import?format=xml", file))
+        .await
+        .expect_status(StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_export() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let first = app.create_titled("first").await;
+    app.create_titled("second").await;
+
+    let response = app
+        .get("/tasks/export?format=ndjson")
+        .await
+        .expect_status(StatusCode::OK);
+    assert_eq!(
+        response.header(header::CONTENT_TYPE),
+        Some("application/x-ndjson")
+    );
+    let records: Vec<TaskRecord> = response
+        .text()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].id, Some(first.id));
+    assert_eq!(records[1].title, "second");
+
+    let response = app
+        .get("/tasks/export?format=csv")
+        .await
+        .expect_status(StatusCode::OK);
+    let mut lines = response.text().lines();
+    assert!(lines.next().unwrap().starts_with("id,title,"));
+    assert_eq!(lines.count(), 2);
+
+    app.get(&format!(
+        "/tasks/export?project_id={}",
+        uuid::Uuid::new_v4()
+    ))
+    .await
+    .error(StatusCode::NOT_FOUND, "NOT_FOUND");
+}
+
+#[tokio::test]
+async fn test_calendar_export() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let task = app
+        .create_task(serde_json::json!({ "title": "Dentist", "due_at": "2030-03-01T10:00:00Z" }))
+        .await;
+    app.create_titled("No due date").await;
+
+    let response = app.get("/tasks.ics").await.expect_status(StatusCode::OK);
+    assert!(
+        response
+            .header(header::CONTENT_TYPE)
+            .unwrap()
+            .starts_with("text/calendar")
+    );
+    let ics = response.text();
+    assert_eq!(ics.matches("BEGIN:VTODO").count(), 1);
+    assert!(ics.contains(&format!("UID:{}", task.id)));
+    assert!(ics.contains("SUMMARY:Dentist"));
+}
@@ -0,0 +1,113 @@
+use axum_diesel_api::ChildrenPolicy;
+use axum_diesel_project::db::models::UpdateTask;
+use axum_diesel_project::db::store::{IsolationLevel, PgTaskStore, TaskStore};
+use axum_diesel_project::error::AppError;
+use uuid::Uuid;
+
+use crate::support::TestApp;
+
+fn rename(title: &str) -> UpdateTask {
+    UpdateTask {
+        title: Some(title.to_string()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_repeatable_read_runs_again_after_a_concurrent_change() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let task = app.create_titled("mine").await;
+    let store = PgTaskStore::new();
+    let store: &dyn TaskStore = &store;
+
+    let mut attempts = 0;
+    let title = store
+        .in_transaction_at(IsolationLevel::RepeatableRead, |tx| {
+            attempts += 1;
+            let current = tx.find_task(task.id)?;
+            if attempts == 1 {
+                // Another request renames the task after this one read it
+                PgTaskStore::new().update_task(task.id, rename("theirs"))?;
+            }
+            let updated =
+                tx.update_task(task.id, rename(&format!("{}, then mine", current.title)))?;
+            Ok(updated.title)
+        })
+        .unwrap();
+
+    assert_eq!(attempts, 2);
+    assert_eq!(title, "theirs, then mine");
+}
+
+#[tokio::test]
+async fn test_update_of_a_task_deleted_meanwhile_is_not_found() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let task = app.create_titled("doomed").await;
+    let store = PgTaskStore::new();
+    let store: &dyn TaskStore = &store;
+
+    let mut attempts = 0;
+    let result: Result<(), AppError> =
+        store.in_transaction_at(IsolationLevel::RepeatableRead, |tx| {
+            attempts += 1;
+            tx.find_task(task.id)?;
+            if attempts == 1 {
+                PgTaskStore::new().delete_task(task.id, ChildrenPolicy::Cascade)?;
+            }
+            tx.update_task(task.id, rename("too late"))?;
+            Ok(())
+        });
+
+    // The second run no longer finds the task
+    assert_eq!(attempts, 2);
+    assert!(matches!(result, Err(AppError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn test_concurrent_reparenting_cannot_build_a_cycle() {
+    let Some(app) = TestApp::start().await else {
+        return;
+    };
+    let a = app.create_titled("a").await;
+    let b = app.create_titled("b").await;
+    let store = PgTaskStore::new();
+    let store: &dyn TaskStore = &store;
+    let reparent = |tx: &dyn TaskStore, id: Uuid, parent_id: Uuid| {
+        if tx.is_self_or_ancestor(id, parent_id)? {
+            return Err(AppError::validation("cycle"));
+        }
+        tx.update_task(
+            id,
+            UpdateTask {
+                parent_id: Some(Some(parent_id)),
+                ..Default::default()
+            },
+        )?;
+        Ok(())
+    };
+
+    // Each request checks for a cycle before the other one writes
+    let mut attempts = 0;
+    let result = store.in_transaction_at(IsolationLevel::Serializable, |tx| {
+        attempts += 1;
+        let cycle = tx.is_self_or_ancestor(a.id, b.id)?;
+        if attempts == 1 {
+            let other = PgTaskStore::new();
+            let other: &dyn TaskStore = &other;
+            other.in_transaction_at(IsolationLevel::Serializable, |tx| reparent(tx, b.id, a.id))?;
+        }
+        if cycle {
+            return Err(AppError::validation("cycle"));
+        }
+        reparent(tx, a.id, b.id)
+    });
+
+    assert_eq!(attempts, 2);
+    assert!(matches!(result, Err(AppError::ValidationError(_))));
+    assert_eq!(store.find_task(b.id).unwrap().parent_id, Some(a.id));
+    assert_eq!(store.find_task(a.id).unwrap().parent_id, None);
+}